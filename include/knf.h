@@ -0,0 +1,115 @@
+/*
+ * C API of the Rust kaldi-native-fbank port (build with `--features ffi`).
+ *
+ * Fallible functions return -1 or NULL; knf_last_error() then describes the
+ * failure on the calling thread.
+ */
+#ifndef KNF_H_
+#define KNF_H_
+
+#include <stdbool.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct KnfFrameOptions {
+  float samp_freq;
+  float frame_shift_ms;
+  float frame_length_ms;
+  float dither;
+  float preemph_coeff;
+  bool remove_dc_offset;
+  /* NUL-terminated window name ("povey", "hamming", ...); NULL keeps the default. */
+  const char *window_type;
+  bool round_to_power_of_two;
+  float blackman_coeff;
+  bool snip_edges;
+} KnfFrameOptions;
+
+typedef struct KnfMelOptions {
+  int32_t num_bins;
+  float low_freq;
+  float high_freq;
+  float vtln_low;
+  float vtln_high;
+  bool debug_mel;
+  bool htk_mode;
+} KnfMelOptions;
+
+typedef struct KnfFbankOptions {
+  KnfFrameOptions frame_opts;
+  KnfMelOptions mel_opts;
+  bool use_energy;
+  bool raw_energy;
+  bool htk_compat;
+  float energy_floor;
+  bool use_log_fbank;
+  bool use_power;
+} KnfFbankOptions;
+
+typedef struct KnfMfccOptions {
+  KnfFrameOptions frame_opts;
+  KnfMelOptions mel_opts;
+  int32_t num_ceps;
+  bool use_energy;
+  float energy_floor;
+  bool raw_energy;
+  float cepstral_lifter;
+  bool htk_compat;
+} KnfMfccOptions;
+
+typedef struct KnfWhisperOptions {
+  KnfFrameOptions frame_opts;
+  int32_t dim;
+} KnfWhisperOptions;
+
+typedef struct KnfOnlineFeature KnfOnlineFeature;
+
+const char *knf_last_error(void);
+
+KnfFbankOptions knf_fbank_options_default(void);
+KnfMfccOptions knf_mfcc_options_default(void);
+KnfWhisperOptions knf_whisper_options_default(void);
+
+/* Streaming extraction */
+KnfOnlineFeature *knf_online_fbank_create(const KnfFbankOptions *opts);
+KnfOnlineFeature *knf_online_mfcc_create(const KnfMfccOptions *opts);
+/* Whisper frames are linear mel power energies, not log-mels. */
+KnfOnlineFeature *knf_online_whisper_fbank_create(const KnfWhisperOptions *opts);
+void knf_online_feature_destroy(KnfOnlineFeature *feature);
+
+int32_t knf_online_feature_accept_waveform(KnfOnlineFeature *feature,
+                                           float sampling_rate,
+                                           const float *samples, int32_t n);
+void knf_online_feature_input_finished(KnfOnlineFeature *feature);
+void knf_online_feature_reset(KnfOnlineFeature *feature);
+int32_t knf_online_feature_dim(const KnfOnlineFeature *feature);
+int32_t knf_online_feature_num_frames_ready(const KnfOnlineFeature *feature);
+bool knf_online_feature_is_last_frame(const KnfOnlineFeature *feature,
+                                      int32_t frame);
+/* dim values, or NULL if the frame is not ready; valid until the next
+ * modifying call on the same feature. */
+const float *knf_online_feature_get_frame(const KnfOnlineFeature *feature,
+                                          int32_t frame);
+
+/* One-shot extraction into a row-major num_frames x dim matrix, released
+ * with knf_features_free. */
+int32_t knf_compute_fbank(const KnfFbankOptions *opts, float sampling_rate,
+                          const float *samples, int32_t n, float **out,
+                          int32_t *num_frames, int32_t *dim);
+int32_t knf_compute_mfcc(const KnfMfccOptions *opts, float sampling_rate,
+                         const float *samples, int32_t n, float **out,
+                         int32_t *num_frames, int32_t *dim);
+int32_t knf_compute_whisper_fbank(const KnfWhisperOptions *opts,
+                                  float sampling_rate, const float *samples,
+                                  int32_t n, float **out, int32_t *num_frames,
+                                  int32_t *dim);
+void knf_features_free(float *data, int32_t num_frames, int32_t dim);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* KNF_H_ */
@@ -0,0 +1,106 @@
+use crate::error::KnfError;
+use crate::window::{first_sample_of_frame, num_frames, FrameOptions};
+use std::ops::Range;
+
+/// Conversions between sample, time and frame indices for a given framing.
+///
+/// Each (possibly subsampled) output frame owns a non-overlapping span of
+/// `frame_shift * subsampling_factor` samples centred on its first analysis
+/// window, so spans tile the signal and are suitable for timestamps. The full
+/// analysis window, which overlaps neighbouring frames, is available through
+/// [`frame_window`](Self::frame_window).
+#[derive(Clone, Debug)]
+pub struct FrameAligner {
+    pub frame_opts: FrameOptions,
+    pub subsampling_factor: usize,
+}
+
+impl FrameAligner {
+    pub fn new(frame_opts: FrameOptions, subsampling_factor: usize) -> Result<Self, KnfError> {
+        frame_opts.validate()?;
+        if subsampling_factor == 0 {
+            return Err(KnfError::invalid("subsampling_factor must be at least 1"));
+        }
+        Ok(Self {
+            frame_opts,
+            subsampling_factor,
+        })
+    }
+
+    /// Number of samples between consecutive output frames.
+    pub fn frame_shift(&self) -> usize {
+        self.frame_opts.window_shift() * self.subsampling_factor
+    }
+
+    /// Number of output frames for a signal of `num_samples` samples.
+    pub fn num_frames(&self, num_samples: usize, flush: bool) -> usize {
+        num_frames(num_samples, &self.frame_opts, flush).div_ceil(self.subsampling_factor)
+    }
+
+    /// Samples read by the analysis windows of an output frame. The start is
+    /// negative for the leading frames when `snip_edges` is false.
+    pub fn frame_window(&self, frame: usize) -> Range<isize> {
+        let first = frame * self.subsampling_factor;
+        let last = first + self.subsampling_factor - 1;
+        let window_size = self.frame_opts.window_size() as isize;
+        first_sample_of_frame(first, &self.frame_opts)
+            ..first_sample_of_frame(last, &self.frame_opts) + window_size
+    }
+
+    /// Span of samples owned by an output frame.
+    pub fn frame_span(&self, frame: usize) -> Range<isize> {
+        let start = self.span_origin() + (frame * self.frame_shift()) as isize;
+        start..start + self.frame_shift() as isize
+    }
+
+    /// Output frame whose span contains `sample`, clamped to the first frame.
+    pub fn sample_to_frame(&self, sample: usize) -> usize {
+        let offset = sample as isize - self.span_origin();
+        if offset < 0 {
+            0
+        } else {
+            offset as usize / self.frame_shift()
+        }
+    }
+
+    /// Output frames whose spans overlap the sample range.
+    pub fn samples_to_frames(&self, samples: Range<usize>) -> Range<usize> {
+        if samples.is_empty() {
+            let frame = self.sample_to_frame(samples.start);
+            return frame..frame;
+        }
+        self.sample_to_frame(samples.start)..self.sample_to_frame(samples.end - 1) + 1
+    }
+
+    /// Span of an output frame in seconds.
+    pub fn frame_to_time(&self, frame: usize) -> Range<f64> {
+        let span = self.frame_span(frame);
+        self.sample_to_time(span.start)..self.sample_to_time(span.end)
+    }
+
+    /// Output frame whose span contains time `seconds`.
+    pub fn time_to_frame(&self, seconds: f64) -> usize {
+        self.sample_to_frame(self.time_to_sample(seconds))
+    }
+
+    /// Output frames whose spans overlap the time range.
+    pub fn times_to_frames(&self, seconds: Range<f64>) -> Range<usize> {
+        self.samples_to_frames(self.time_to_sample(seconds.start)..self.time_to_sample(seconds.end))
+    }
+
+    fn span_origin(&self) -> isize {
+        let center =
+            first_sample_of_frame(0, &self.frame_opts) + self.frame_opts.window_size() as isize / 2;
+        center - self.frame_opts.window_shift() as isize / 2
+    }
+
+    fn sample_to_time(&self, sample: isize) -> f64 {
+        sample as f64 / self.frame_opts.samp_freq as f64
+    }
+
+    fn time_to_sample(&self, seconds: f64) -> usize {
+        (seconds * self.frame_opts.samp_freq as f64)
+            .round()
+            .max(0.0) as usize
+    }
+}
@@ -0,0 +1,170 @@
+//! Waveform perturbations for data augmentation, applied before feature
+//! extraction.
+
+use crate::error::KnfError;
+use crate::pcm::PcmScale;
+use rand::Rng;
+
+/// What happens to samples that a gain pushes past `max_amplitude`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ClipMode {
+    /// Leave them as they are.
+    None,
+    /// Clamp them to `±max_amplitude`.
+    Clip,
+    /// Scale the whole signal down so its peak is `max_amplitude`, which keeps
+    /// the waveform undistorted.
+    #[default]
+    Limit,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct GainOptions {
+    /// Largest allowed sample magnitude; the default suits
+    /// [`PcmScale::Kaldi`], see [`for_scale`](Self::for_scale).
+    pub max_amplitude: f32,
+    pub clip: ClipMode,
+}
+
+impl Default for GainOptions {
+    fn default() -> Self {
+        Self::for_scale(PcmScale::Kaldi)
+    }
+}
+
+impl GainOptions {
+    /// Limits samples to the range of 16-bit PCM in `scale`.
+    pub fn for_scale(scale: PcmScale) -> Self {
+        Self {
+            max_amplitude: i16::MAX as f32 * scale.factor(),
+            clip: ClipMode::Limit,
+        }
+    }
+}
+
+/// A gain applied to a whole waveform.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GainPerturbation {
+    /// A fixed gain in dB.
+    Fixed(f32),
+    /// A gain drawn uniformly from `[min_db, max_db]`.
+    Uniform { min_db: f32, max_db: f32 },
+    /// A gain moving linearly in dB from the first to the last sample.
+    Ramp { start_db: f32, end_db: f32 },
+    /// A time-varying gain through `num_points` evenly spaced values drawn
+    /// uniformly from `[min_db, max_db]`, interpolated linearly in dB.
+    RandomRamp {
+        min_db: f32,
+        max_db: f32,
+        num_points: usize,
+    },
+}
+
+impl GainPerturbation {
+    pub fn validate(&self) -> Result<(), KnfError> {
+        let values = match *self {
+            Self::Fixed(db) => vec![db],
+            Self::Ramp { start_db, end_db } => vec![start_db, end_db],
+            Self::Uniform { min_db, max_db } | Self::RandomRamp { min_db, max_db, .. } => {
+                if min_db > max_db {
+                    return Err(KnfError::invalid(format!(
+                        "Gain range is empty: [{}, {}] dB",
+                        min_db, max_db
+                    )));
+                }
+                vec![min_db, max_db]
+            }
+        };
+        if values.iter().any(|db| !db.is_finite()) {
+            return Err(KnfError::invalid("Gains must be finite"));
+        }
+        if let Self::RandomRamp { num_points, .. } = *self {
+            if num_points < 2 {
+                return Err(KnfError::invalid("A gain ramp needs at least 2 points"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the gain to `wave` in place, drawing random gains from `rng`,
+    /// and returns the gain curve in dB (one value for a constant gain, else
+    /// the ramp's points).
+    pub fn apply<R: Rng + ?Sized>(
+        &self,
+        wave: &mut [f32],
+        opts: &GainOptions,
+        rng: &mut R,
+    ) -> Result<Vec<f32>, KnfError> {
+        self.validate()?;
+        let points = match *self {
+            Self::Fixed(db) => vec![db],
+            Self::Uniform { min_db, max_db } => vec![rng.gen_range(min_db..=max_db)],
+            Self::Ramp { start_db, end_db } => vec![start_db, end_db],
+            Self::RandomRamp {
+                min_db,
+                max_db,
+                num_points,
+            } => (0..num_points)
+                .map(|_| rng.gen_range(min_db..=max_db))
+                .collect(),
+        };
+        apply_gain_curve(wave, &points, opts);
+        Ok(points)
+    }
+}
+
+/// Scales `wave` by `gain_db`.
+pub fn apply_gain_db(wave: &mut [f32], gain_db: f32, opts: &GainOptions) {
+    apply_gain_curve(wave, &[gain_db], opts);
+}
+
+/// Scales `wave` by a gain interpolated linearly in dB through evenly spaced
+/// `points_db`, the first at the first sample and the last at the last one.
+pub fn apply_gain_curve(wave: &mut [f32], points_db: &[f32], opts: &GainOptions) {
+    let n = wave.len();
+    match points_db {
+        [] => {}
+        [db] => {
+            let gain = db_to_amplitude(*db);
+            wave.iter_mut().for_each(|x| *x *= gain);
+        }
+        _ => {
+            let segments = (points_db.len() - 1) as f32;
+            for (i, x) in wave.iter_mut().enumerate() {
+                let pos = if n > 1 {
+                    i as f32 * segments / (n - 1) as f32
+                } else {
+                    0.0
+                };
+                let j = (pos as usize).min(points_db.len() - 2);
+                let frac = pos - j as f32;
+                let db = points_db[j] + frac * (points_db[j + 1] - points_db[j]);
+                *x *= db_to_amplitude(db);
+            }
+        }
+    }
+
+    let max = opts.max_amplitude;
+    match opts.clip {
+        ClipMode::None => {}
+        ClipMode::Clip => wave.iter_mut().for_each(|x| *x = x.clamp(-max, max)),
+        ClipMode::Limit => {
+            let peak = wave.iter().fold(0.0f32, |m, x| m.max(x.abs()));
+            if peak > max {
+                let scale = max / peak;
+                wave.iter_mut().for_each(|x| *x *= scale);
+            }
+        }
+    }
+}
+
+fn db_to_amplitude(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
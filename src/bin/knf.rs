@@ -0,0 +1,470 @@
+//! `knf`: Kaldi-style feature extraction from the command line.
+//!
+//! ```text
+//! knf compute-fbank-feats --num-mel-bins=80 scp:wav.scp ark:feats.ark
+//! knf compute-mfcc-feats --dither=0 utt.wav utt.npy
+//! ```
+//!
+//! The frame, mel, energy and HTK options of Kaldi are supported, with one
+//! `--vtln-warp` factor for all utterances. Per-speaker warping
+//! (`--vtln-map`, `--utt2spk`), `--min-duration` and `--write-utt2dur` are not.
+
+use clap::{Args, Parser, Subcommand};
+use kaldi_native_fbank::error::KnfError;
+use kaldi_native_fbank::fbank::{FbankComputer, FbankOptions};
+use kaldi_native_fbank::mel::{MelBanks, MelOptions};
+use kaldi_native_fbank::mfcc::{MfccComputer, MfccOptions};
+use kaldi_native_fbank::online::{Computer, FeatureComputer, OnlineFeature};
+use kaldi_native_fbank::pcm::{ChannelPolicy, PcmScale};
+use kaldi_native_fbank::resample::resample_waveform;
+use kaldi_native_fbank::wav::read_wave_channels;
+use kaldi_native_fbank::window::{DitherMode, FrameOptions, WindowType};
+use npyz::WriterBuilder;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(name = "knf", version, about = "Kaldi-compatible feature extraction")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Compute log mel filterbank features, like Kaldi's compute-fbank-feats.
+    ComputeFbankFeats {
+        #[command(flatten)]
+        fbank: FbankArgs,
+        #[command(flatten)]
+        common: CommonArgs,
+    },
+    /// Compute MFCC features, like Kaldi's compute-mfcc-feats.
+    ComputeMfccFeats {
+        #[command(flatten)]
+        mfcc: MfccArgs,
+        #[command(flatten)]
+        common: CommonArgs,
+    },
+}
+
+/// Options shared by every subcommand. Unset flags keep the crate defaults.
+#[derive(Args)]
+struct CommonArgs {
+    /// A wav file, or `scp:<file>` with `<utterance-id> <wav-path>` lines.
+    wav_rspecifier: String,
+    /// `ark:<file>` (binary), `ark,t:<file>` (text), `npy:<dir>` (one
+    /// `<utterance-id>.npy` per utterance) or a `.npy` file for a single wav.
+    /// `ark:-` and `ark,t:-` write to stdout.
+    feats_wspecifier: String,
+
+    /// Channel to extract from multi-channel files; by default channels are averaged.
+    #[arg(long)]
+    channel: Option<usize>,
+    /// Resample input with a higher sampling rate than --sample-frequency.
+    #[arg(long, default_value_t = false, action = clap::ArgAction::Set)]
+    allow_downsample: bool,
+    /// Resample input with a lower sampling rate than --sample-frequency.
+    #[arg(long, default_value_t = false, action = clap::ArgAction::Set)]
+    allow_upsample: bool,
+    /// Subtract the per-utterance mean of each feature dimension.
+    #[arg(long, default_value_t = false, action = clap::ArgAction::Set)]
+    subtract_mean: bool,
+
+    #[arg(long)]
+    sample_frequency: Option<f32>,
+    /// Frame shift in milliseconds.
+    #[arg(long)]
+    frame_shift: Option<f32>,
+    /// Frame length in milliseconds.
+    #[arg(long)]
+    frame_length: Option<f32>,
+    #[arg(long)]
+    dither: Option<f32>,
+    #[arg(long)]
+    preemphasis_coefficient: Option<f32>,
+    #[arg(long)]
+    remove_dc_offset: Option<bool>,
+    /// hamming, hanning, povey, rectangular, sine or blackman.
+    #[arg(long)]
+    window_type: Option<WindowType>,
+    #[arg(long)]
+    round_to_power_of_two: Option<bool>,
+    #[arg(long)]
+    blackman_coeff: Option<f32>,
+    #[arg(long)]
+    snip_edges: Option<bool>,
+
+    #[arg(long)]
+    num_mel_bins: Option<usize>,
+    #[arg(long)]
+    low_freq: Option<f32>,
+    /// High cutoff in Hz; values <= 0 are relative to the Nyquist frequency.
+    #[arg(long)]
+    high_freq: Option<f32>,
+    /// VTLN warp factor applied to every utterance.
+    #[arg(long, default_value_t = 1.0)]
+    vtln_warp: f32,
+    #[arg(long)]
+    vtln_low: Option<f32>,
+    #[arg(long)]
+    vtln_high: Option<f32>,
+    #[arg(long)]
+    htk_mode: Option<bool>,
+    #[arg(long)]
+    debug_mel: Option<bool>,
+
+    #[arg(long)]
+    use_energy: Option<bool>,
+    #[arg(long)]
+    raw_energy: Option<bool>,
+    #[arg(long)]
+    htk_compat: Option<bool>,
+    #[arg(long)]
+    energy_floor: Option<f32>,
+}
+
+#[derive(Args)]
+struct FbankArgs {
+    #[arg(long)]
+    use_log_fbank: Option<bool>,
+    #[arg(long)]
+    use_power: Option<bool>,
+}
+
+#[derive(Args)]
+struct MfccArgs {
+    #[arg(long)]
+    num_ceps: Option<usize>,
+    #[arg(long)]
+    cepstral_lifter: Option<f32>,
+}
+
+fn set<T>(target: &mut T, value: Option<T>) {
+    if let Some(v) = value {
+        *target = v;
+    }
+}
+
+impl CommonArgs {
+    fn apply_frame(&self, opts: &mut FrameOptions) {
+        set(&mut opts.samp_freq, self.sample_frequency);
+        set(&mut opts.frame_shift_ms, self.frame_shift);
+        set(&mut opts.frame_length_ms, self.frame_length);
+        set(&mut opts.dither, self.dither);
+        // Kaldi's compute-*-feats dither with Gaussian noise
+        opts.dither_mode = DitherMode::Gaussian;
+        set(&mut opts.preemph_coeff, self.preemphasis_coefficient);
+        set(&mut opts.remove_dc_offset, self.remove_dc_offset);
+        set(&mut opts.window_type, self.window_type);
+        set(&mut opts.round_to_power_of_two, self.round_to_power_of_two);
+        set(&mut opts.blackman_coeff, self.blackman_coeff);
+        set(&mut opts.snip_edges, self.snip_edges);
+    }
+
+    fn apply_mel(&self, opts: &mut MelOptions) {
+        set(&mut opts.num_bins, self.num_mel_bins);
+        set(&mut opts.low_freq, self.low_freq);
+        set(&mut opts.high_freq, self.high_freq);
+        set(&mut opts.vtln_low, self.vtln_low);
+        set(&mut opts.vtln_high, self.vtln_high);
+        set(&mut opts.htk_mode, self.htk_mode);
+        set(&mut opts.debug_mel, self.debug_mel);
+    }
+
+    fn fbank_options(&self, args: &FbankArgs) -> FbankOptions {
+        let mut opts = FbankOptions::default();
+        self.apply_frame(&mut opts.frame_opts);
+        self.apply_mel(&mut opts.mel_opts);
+        set(&mut opts.use_energy, self.use_energy);
+        set(&mut opts.raw_energy, self.raw_energy);
+        set(&mut opts.htk_compat, self.htk_compat);
+        set(&mut opts.energy_floor, self.energy_floor);
+        set(&mut opts.use_log_fbank, args.use_log_fbank);
+        set(&mut opts.use_power, args.use_power);
+        opts
+    }
+
+    fn mfcc_options(&self, args: &MfccArgs) -> MfccOptions {
+        let mut opts = MfccOptions::default();
+        self.apply_frame(&mut opts.frame_opts);
+        self.apply_mel(&mut opts.mel_opts);
+        set(&mut opts.use_energy, self.use_energy);
+        set(&mut opts.raw_energy, self.raw_energy);
+        set(&mut opts.htk_compat, self.htk_compat);
+        set(&mut opts.energy_floor, self.energy_floor);
+        set(&mut opts.num_ceps, args.num_ceps);
+        set(&mut opts.cepstral_lifter, args.cepstral_lifter);
+        opts
+    }
+}
+
+/// Where extracted features are written.
+enum FeatureWriter {
+    Ark { out: Box<dyn Write>, binary: bool },
+    NpyDir(PathBuf),
+    NpyFile(PathBuf, bool),
+}
+
+impl FeatureWriter {
+    fn open(wspecifier: &str) -> Result<Self, Box<dyn Error>> {
+        let ark = |path: &str, binary: bool| -> Result<Self, Box<dyn Error>> {
+            let out: Box<dyn Write> = if path == "-" {
+                Box::new(BufWriter::new(std::io::stdout()))
+            } else {
+                let file = File::create(path).map_err(|e| format!("{}: {}", path, e))?;
+                Box::new(BufWriter::new(file))
+            };
+            Ok(Self::Ark { out, binary })
+        };
+
+        if let Some(path) = wspecifier.strip_prefix("ark:") {
+            ark(path, true)
+        } else if let Some(path) = wspecifier.strip_prefix("ark,t:") {
+            ark(path, false)
+        } else if let Some(dir) = wspecifier.strip_prefix("npy:") {
+            std::fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir, e))?;
+            Ok(Self::NpyDir(PathBuf::from(dir)))
+        } else if wspecifier.ends_with(".npy") {
+            Ok(Self::NpyFile(PathBuf::from(wspecifier), false))
+        } else {
+            Err(format!(
+                "Unsupported wspecifier {:?}; expected ark:, ark,t:, npy: or a .npy file",
+                wspecifier
+            )
+            .into())
+        }
+    }
+
+    fn write(
+        &mut self,
+        key: &str,
+        features: &[Vec<f32>],
+        dim: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        match self {
+            Self::Ark { out, binary } => {
+                let result = if *binary {
+                    write_binary_matrix(out, key, features, dim)
+                } else {
+                    write_text_matrix(out, key, features)
+                };
+                Ok(result?)
+            }
+            Self::NpyDir(dir) => write_npy(&dir.join(format!("{}.npy", key)), features, dim),
+            Self::NpyFile(path, written) => {
+                if *written {
+                    return Err(
+                        format!("{} can only hold a single utterance", path.display()).into(),
+                    );
+                }
+                *written = true;
+                write_npy(path, features, dim)
+            }
+        }
+    }
+
+    fn finish(self) -> Result<(), Box<dyn Error>> {
+        match self {
+            Self::Ark { mut out, .. } => Ok(out.flush()?),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Kaldi binary matrix: `<key> \0B FM <rows> <cols> <data>` with sizes as
+/// length-prefixed little-endian i32.
+fn write_binary_matrix(
+    out: &mut dyn Write,
+    key: &str,
+    features: &[Vec<f32>],
+    dim: usize,
+) -> std::io::Result<()> {
+    write!(out, "{} \0BFM ", key)?;
+    for size in [features.len(), dim] {
+        out.write_all(&[4])?;
+        out.write_all(&(size as i32).to_le_bytes())?;
+    }
+    for x in features.iter().flatten() {
+        out.write_all(&x.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn write_text_matrix(out: &mut dyn Write, key: &str, features: &[Vec<f32>]) -> std::io::Result<()> {
+    write!(out, "{}  [", key)?;
+    if features.is_empty() {
+        return writeln!(out, " ]");
+    }
+    for (t, frame) in features.iter().enumerate() {
+        write!(out, "\n ")?;
+        for x in frame {
+            write!(out, " {}", x)?;
+        }
+        if t + 1 == features.len() {
+            writeln!(out, " ]")?;
+        }
+    }
+    Ok(())
+}
+
+fn write_npy(path: &Path, features: &[Vec<f32>], dim: usize) -> Result<(), Box<dyn Error>> {
+    let err = |e: std::io::Error| format!("{}: {}", path.display(), e);
+    let file = BufWriter::new(File::create(path).map_err(err)?);
+    let mut writer = npyz::WriteOptions::new()
+        .default_dtype()
+        .shape(&[features.len() as u64, dim as u64])
+        .writer(file)
+        .begin_nd()
+        .map_err(err)?;
+    writer
+        .extend(features.iter().flatten().copied())
+        .map_err(err)?;
+    Ok(writer.finish().map_err(err)?)
+}
+
+/// `(utterance-id, wav-path)` pairs for a wav file or `scp:` list.
+fn read_rspecifier(rspecifier: &str) -> Result<Vec<(String, PathBuf)>, Box<dyn Error>> {
+    let Some(scp) = rspecifier.strip_prefix("scp:") else {
+        let path = PathBuf::from(rspecifier);
+        let key = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .ok_or(format!("Invalid wav path {:?}", rspecifier))?;
+        return Ok(vec![(key, path)]);
+    };
+
+    let file = File::open(scp).map_err(|e| format!("{}: {}", scp, e))?;
+    let mut entries = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("{}: {}", scp, e))?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (key, path) = line.split_once(char::is_whitespace).ok_or(format!(
+            "{}:{}: expected `<utterance-id> <wav-path>`",
+            scp,
+            i + 1
+        ))?;
+        let path = path.trim();
+        if path.ends_with('|') {
+            return Err(format!("{}:{}: piped commands are not supported", scp, i + 1).into());
+        }
+        entries.push((key.to_string(), PathBuf::from(path)));
+    }
+    Ok(entries)
+}
+
+fn extract(
+    path: &Path,
+    args: &CommonArgs,
+    make_computer: &dyn Fn() -> Result<FeatureComputer, KnfError>,
+) -> Result<Vec<Vec<f32>>, Box<dyn Error>> {
+    let policy = match args.channel {
+        Some(c) => ChannelPolicy::Select(c),
+        None => ChannelPolicy::Average,
+    };
+    let (mut channels, rate) = read_wave_channels(path, policy, PcmScale::Kaldi)?;
+    let mut wave = channels.remove(0);
+
+    let computer = make_computer()?;
+    let samp_freq = computer.frame_opts().samp_freq;
+    if (rate as f32 - samp_freq).abs() > 1.0 {
+        let allowed = if (rate as f32) > samp_freq {
+            args.allow_downsample
+        } else {
+            args.allow_upsample
+        };
+        if !allowed {
+            return Err(format!(
+                "Sampling rate {} does not match --sample-frequency={}; use --allow-downsample or --allow-upsample",
+                rate, samp_freq
+            ).into());
+        }
+        wave = resample_waveform(&wave, rate, samp_freq as u32)?;
+    }
+
+    let mut feature = OnlineFeature::new(computer);
+    feature.set_vtln_warp(args.vtln_warp)?;
+    feature.accept_waveform(samp_freq, &wave)?;
+    feature.input_finished();
+    let mut features = std::mem::take(&mut feature.features);
+
+    if args.subtract_mean && !features.is_empty() {
+        let dim = features[0].len();
+        let mut mean = vec![0.0f32; dim];
+        for frame in &features {
+            for (m, x) in mean.iter_mut().zip(frame) {
+                *m += x;
+            }
+        }
+        let n = features.len() as f32;
+        for frame in &mut features {
+            for (x, m) in frame.iter_mut().zip(&mean) {
+                *x -= m / n;
+            }
+        }
+    }
+    Ok(features)
+}
+
+fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    let (common, make_computer): (_, Box<dyn Fn() -> Result<FeatureComputer, KnfError>>) =
+        match &cli.command {
+            Command::ComputeFbankFeats { fbank, common } => {
+                let opts = common.fbank_options(fbank);
+                MelBanks::new(&opts.mel_opts, &opts.frame_opts, common.vtln_warp)?;
+                (
+                    common,
+                    Box::new(move || Ok(FeatureComputer::Fbank(FbankComputer::new(opts.clone())?))),
+                )
+            }
+            Command::ComputeMfccFeats { mfcc, common } => {
+                let opts = common.mfcc_options(mfcc);
+                MelBanks::new(&opts.mel_opts, &opts.frame_opts, common.vtln_warp)?;
+                (
+                    common,
+                    Box::new(move || Ok(FeatureComputer::Mfcc(MfccComputer::new(opts.clone())?))),
+                )
+            }
+        };
+
+    let dim = make_computer()?.dim();
+    let utterances = read_rspecifier(&common.wav_rspecifier)?;
+    let mut writer = FeatureWriter::open(&common.feats_wspecifier)?;
+    let mut num_failed = 0;
+    for (key, path) in &utterances {
+        match extract(path, common, &make_computer) {
+            Ok(features) => writer.write(key, &features, dim)?,
+            Err(e) => {
+                eprintln!("knf: skipping {}: {}", key, e);
+                num_failed += 1;
+            }
+        }
+    }
+    writer.finish()?;
+
+    eprintln!(
+        "knf: processed {} utterances, {} failed",
+        utterances.len() - num_failed,
+        num_failed
+    );
+    if num_failed == utterances.len() && !utterances.is_empty() {
+        return Err("No utterances were processed".into());
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("knf: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
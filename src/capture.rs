@@ -0,0 +1,186 @@
+//! Microphone capture via cpal, feeding device audio into [`OnlineFeature`].
+
+use crate::error::KnfError;
+use crate::online::{Computer, OnlineFeature};
+use crate::pcm::{apply_channel_policy, ChannelPolicy, PcmScale};
+use crate::resample::LinearResample;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SampleFormat, SizedSample};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::time::Duration;
+
+/// Turns interleaved device audio in [-1, 1] into mono samples at the feature
+/// sampling rate: channels are averaged, then resampled and scaled.
+pub struct CaptureConverter {
+    num_channels: usize,
+    resampler: Option<LinearResample>,
+    gain: f32,
+}
+
+impl CaptureConverter {
+    pub fn new(
+        device_rate: u32,
+        num_channels: usize,
+        samp_freq: f32,
+        scale: PcmScale,
+    ) -> Result<Self, KnfError> {
+        if num_channels == 0 {
+            return Err(KnfError::invalid("num_channels must be at least 1"));
+        }
+        if samp_freq.fract() != 0.0 || samp_freq <= 0.0 {
+            return Err(KnfError::invalid(format!(
+                "Cannot resample to {} Hz",
+                samp_freq
+            )));
+        }
+        let target_rate = samp_freq as u32;
+        let resampler = if device_rate == target_rate {
+            None
+        } else {
+            let cutoff = 0.99 * 0.5 * device_rate.min(target_rate) as f32;
+            Some(LinearResample::new(device_rate, target_rate, cutoff, 6)?)
+        };
+        Ok(Self {
+            num_channels,
+            resampler,
+            gain: 32768.0 * scale.factor(),
+        })
+    }
+
+    /// Converts the next chunk; chunks must hold whole multi-channel frames.
+    pub fn process(&mut self, interleaved: &[f32]) -> Result<Vec<f32>, KnfError> {
+        let mono = apply_channel_policy(interleaved, self.num_channels, ChannelPolicy::Average)?
+            .pop()
+            .unwrap_or_default();
+        let mut out = match self.resampler.as_mut() {
+            Some(resampler) => resampler.resample(&mono),
+            None => mono,
+        };
+        for x in out.iter_mut() {
+            *x *= self.gain;
+        }
+        Ok(out)
+    }
+
+    /// Output held back by the resampler at the end of the signal.
+    pub fn flush(&mut self) -> Vec<f32> {
+        let mut out = self
+            .resampler
+            .as_mut()
+            .map(|r| r.flush())
+            .unwrap_or_default();
+        for x in out.iter_mut() {
+            *x *= self.gain;
+        }
+        out
+    }
+}
+
+/// A running input stream on an audio device.
+///
+/// Audio is captured on cpal's thread and converted when read, so call
+/// [`feed`](Self::feed) regularly. Capture stops when the value is dropped.
+pub struct Microphone {
+    _stream: cpal::Stream,
+    receiver: Receiver<Vec<f32>>,
+    converter: CaptureConverter,
+    samp_freq: f32,
+}
+
+impl Microphone {
+    /// Opens the default input device, producing audio at `samp_freq` in the
+    /// given `scale` (Kaldi features expect [`PcmScale::Kaldi`]).
+    pub fn open_default(samp_freq: f32, scale: PcmScale) -> Result<Self, KnfError> {
+        let device = cpal::default_host()
+            .default_input_device()
+            .ok_or_else(|| KnfError::AudioDevice("No input device available".to_string()))?;
+        Self::open(&device, samp_freq, scale)
+    }
+
+    pub fn open(device: &cpal::Device, samp_freq: f32, scale: PcmScale) -> Result<Self, KnfError> {
+        let supported = device.default_input_config().map_err(device_error)?;
+        let config = supported.config();
+        let converter = CaptureConverter::new(
+            config.sample_rate.0,
+            config.channels as usize,
+            samp_freq,
+            scale,
+        )?;
+
+        let (sender, receiver) = channel();
+        let stream = match supported.sample_format() {
+            SampleFormat::F32 => build_stream::<f32>(device, &config, sender),
+            SampleFormat::I16 => build_stream::<i16>(device, &config, sender),
+            SampleFormat::U16 => build_stream::<u16>(device, &config, sender),
+            SampleFormat::I32 => build_stream::<i32>(device, &config, sender),
+            format => {
+                return Err(KnfError::AudioDevice(format!(
+                    "Unsupported sample format {}",
+                    format
+                )))
+            }
+        }?;
+        stream.play().map_err(device_error)?;
+
+        Ok(Self {
+            _stream: stream,
+            receiver,
+            converter,
+            samp_freq,
+        })
+    }
+
+    /// Converted audio captured so far, waiting up to `timeout` for the first
+    /// chunk. Empty if nothing arrived in time.
+    pub fn read(&mut self, timeout: Duration) -> Result<Vec<f32>, KnfError> {
+        let mut samples = match self.receiver.recv_timeout(timeout) {
+            Ok(chunk) => self.converter.process(&chunk)?,
+            Err(RecvTimeoutError::Timeout) => return Ok(Vec::new()),
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(KnfError::AudioDevice("Input stream closed".to_string()))
+            }
+        };
+        while let Ok(chunk) = self.receiver.try_recv() {
+            samples.extend(self.converter.process(&chunk)?);
+        }
+        Ok(samples)
+    }
+
+    /// Moves captured audio into `feature`; returns the number of samples fed.
+    pub fn feed<C: Computer>(
+        &mut self,
+        feature: &mut OnlineFeature<C>,
+        timeout: Duration,
+    ) -> Result<usize, KnfError> {
+        let samples = self.read(timeout)?;
+        feature.accept_waveform(self.samp_freq, &samples)?;
+        Ok(samples.len())
+    }
+}
+
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    sender: Sender<Vec<f32>>,
+) -> Result<cpal::Stream, KnfError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                let chunk = data.iter().map(|&s| f32::from_sample_(s)).collect();
+                // The receiver is gone only while the Microphone is being dropped.
+                let _ = sender.send(chunk);
+            },
+            |err| log::error!("Audio input stream error: {}", err),
+            None,
+        )
+        .map_err(device_error)
+}
+
+fn device_error(error: impl std::fmt::Display) -> KnfError {
+    KnfError::AudioDevice(error.to_string())
+}
@@ -0,0 +1,179 @@
+//! Real and complex cepstra with liftering, following MATLAB's `rceps`,
+//! `cceps` and `icceps`: echoes show up as peaks at their delay in
+//! quefrency, and low-quefrency liftering gives a smooth spectral envelope.
+
+use crate::cfft::{Cfft, Complex};
+use crate::error::KnfError;
+use std::f64::consts::PI;
+
+/// Magnitudes are floored here before taking the logarithm.
+pub(crate) const MAGNITUDE_FLOOR: f32 = 1e-10;
+
+/// Complex cepstrum and what was factored out of the signal before the
+/// inverse transform.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ComplexCepstrum {
+    pub values: Vec<f32>,
+    /// Circular delay in samples whose linear phase was removed.
+    pub delay: isize,
+    /// The signal was negated to make its DC gain positive.
+    pub negated: bool,
+}
+
+fn spectrum(x: &[f32]) -> Result<Vec<Complex<f64>>, KnfError> {
+    if x.is_empty() {
+        return Err(KnfError::invalid("Cepstrum input must be non-empty"));
+    }
+    let mut data: Vec<Complex<f64>> = x.iter().map(|&v| Complex::new(v as f64, 0.0)).collect();
+    Cfft::new(x.len(), false).compute(&mut data);
+    Ok(data)
+}
+
+/// Real part of the normalized inverse FFT of `data`.
+fn inverse_real(mut data: Vec<Complex<f64>>) -> Vec<f32> {
+    let n = data.len();
+    Cfft::new(n, true).compute(&mut data);
+    data.iter().map(|c| (c.re / n as f64) as f32).collect()
+}
+
+fn log_magnitude(c: &Complex<f64>) -> f64 {
+    c.norm().max(MAGNITUDE_FLOOR as f64).ln()
+}
+
+/// Unwraps radian phases in place so consecutive values never differ by
+/// more than pi, like `numpy.unwrap`.
+pub fn unwrap_phase(phase: &mut [f32]) {
+    let mut unwrapped: Vec<f64> = phase.iter().map(|&p| p as f64).collect();
+    unwrap(&mut unwrapped);
+    for (p, u) in phase.iter_mut().zip(unwrapped) {
+        *p = u as f32;
+    }
+}
+
+fn unwrap(phase: &mut [f64]) {
+    let mut offset = 0.0;
+    for i in 1..phase.len() {
+        let raw = phase[i] + offset;
+        let jump = raw - phase[i - 1];
+        let wrapped = (jump + PI).rem_euclid(2.0 * PI) - PI;
+        let correction = if wrapped == -PI && jump > 0.0 {
+            PI - jump
+        } else {
+            wrapped - jump
+        };
+        offset += correction;
+        phase[i] = raw + correction;
+    }
+}
+
+/// Real cepstrum `ifft(ln |fft(x)|)` over `x.len()` points; quefrency `q`
+/// and `-q` share the value at index `q` and `len - q`.
+pub fn real_cepstrum(x: &[f32]) -> Result<Vec<f32>, KnfError> {
+    let log_mag = spectrum(x)?
+        .iter()
+        .map(|c| Complex::new(log_magnitude(c), 0.0))
+        .collect();
+    Ok(inverse_real(log_mag))
+}
+
+/// Phase at bin `k` of `n` of a circular delay of `delay` samples.
+fn linear_phase(n: usize, delay: isize, k: usize) -> f64 {
+    -2.0 * PI * delay as f64 * k as f64 / n as f64
+}
+
+/// Complex cepstrum `ifft(ln |X| + j arg X)`. The phase is unwrapped over
+/// bins `0..=len / 2` and mirrored, after removing the linear trend of a
+/// whole-sample delay, which is reported as `delay`. For even lengths and a
+/// positive DC gain the values match MATLAB's `cceps`, whose `nd` is
+/// `-delay`.
+pub fn complex_cepstrum(x: &[f32]) -> Result<ComplexCepstrum, KnfError> {
+    let mut spec = spectrum(x)?;
+    let negated = spec[0].re < 0.0;
+    if negated {
+        spec.iter_mut().for_each(|c| *c = -*c);
+    }
+    let n = spec.len();
+    let half = n / 2;
+    let mut phase: Vec<f64> = spec[..=half].iter().map(|c| c.arg()).collect();
+    unwrap(&mut phase);
+    let delay = if half > 0 {
+        (-phase[half] * n as f64 / (2.0 * PI * half as f64)).round() as isize
+    } else {
+        0
+    };
+    let mut log_spec: Vec<Complex<f64>> = spec[..=half]
+        .iter()
+        .zip(&phase)
+        .enumerate()
+        .map(|(k, (c, &p))| Complex::new(log_magnitude(c), p - linear_phase(n, delay, k)))
+        .collect();
+    for k in half + 1..n {
+        log_spec.push(log_spec[n - k].conj());
+    }
+    Ok(ComplexCepstrum {
+        values: inverse_real(log_spec),
+        delay,
+        negated,
+    })
+}
+
+/// Signal of a complex cepstrum, restoring its delay: the inverse of
+/// [`complex_cepstrum`] up to rounding.
+pub fn inverse_complex_cepstrum(cepstrum: &ComplexCepstrum) -> Vec<f32> {
+    let n = cepstrum.values.len();
+    if n == 0 {
+        return Vec::new();
+    }
+    let mut log_spec: Vec<Complex<f64>> = cepstrum
+        .values
+        .iter()
+        .map(|&v| Complex::new(v as f64, 0.0))
+        .collect();
+    Cfft::new(n, false).compute(&mut log_spec);
+    let spec = log_spec
+        .iter()
+        .enumerate()
+        .map(|(k, c)| Complex::from_polar(c.re.exp(), c.im + linear_phase(n, cepstrum.delay, k)))
+        .collect();
+    let mut signal = inverse_real(spec);
+    if cepstrum.negated {
+        signal.iter_mut().for_each(|x| *x = -*x);
+    }
+    signal
+}
+
+/// Keeps quefrencies `|q| < cutoff` (both ends of the cepstrum) and zeroes
+/// the rest, leaving the slowly varying part of the log spectrum.
+pub fn lifter_low(cepstrum: &mut [f32], cutoff: usize) {
+    let n = cepstrum.len();
+    for (i, c) in cepstrum.iter_mut().enumerate() {
+        if i.min(n - i) >= cutoff {
+            *c = 0.0;
+        }
+    }
+}
+
+/// Zeroes quefrencies `|q| < cutoff`, the complement of [`lifter_low`],
+/// e.g. to keep the excitation or echo peaks.
+pub fn lifter_high(cepstrum: &mut [f32], cutoff: usize) {
+    let n = cepstrum.len();
+    for (i, c) in cepstrum.iter_mut().enumerate() {
+        if i.min(n - i) < cutoff {
+            *c = 0.0;
+        }
+    }
+}
+
+/// Natural-log magnitude envelope of `frame` at bins `0..=len / 2`: the
+/// spectrum of its real cepstrum liftered to quefrencies below `cutoff`.
+pub fn spectral_envelope(frame: &[f32], cutoff: usize) -> Result<Vec<f32>, KnfError> {
+    let mut cepstrum = real_cepstrum(frame)?;
+    lifter_low(&mut cepstrum, cutoff);
+    let n = cepstrum.len();
+    let mut spec: Vec<Complex<f64>> = cepstrum
+        .iter()
+        .map(|&v| Complex::new(v as f64, 0.0))
+        .collect();
+    Cfft::new(n, false).compute(&mut spec);
+    Ok(spec[..=n / 2].iter().map(|c| c.re as f32).collect())
+}
@@ -0,0 +1,47 @@
+use rustfft::num_traits::Zero;
+use rustfft::{Fft, FftNum, FftPlanner};
+use std::sync::Arc;
+
+pub use rustfft::num_complex::Complex;
+
+/// A wrapper around `rustfft` for complex-to-complex transforms, the
+/// counterpart of [`Rfft`](crate::rfft::Rfft) for complex signals.
+pub struct Cfft<T: FftNum = f32> {
+    n: usize,
+    fft: Arc<dyn Fft<T>>,
+    scratch: Vec<Complex<T>>, // Scratch buffer for FFT computation
+}
+
+impl<T: FftNum> Cfft<T> {
+    pub fn new(n: usize, inverse: bool) -> Self {
+        let mut planner = FftPlanner::<T>::new();
+        let fft = if !inverse {
+            planner.plan_fft_forward(n)
+        } else {
+            planner.plan_fft_inverse(n)
+        };
+        let scratch_len = fft.get_inplace_scratch_len();
+
+        Self {
+            n,
+            fft,
+            scratch: vec![Complex::zero(); scratch_len],
+        }
+    }
+
+    /// Transforms the first `n` values of `data` in place.
+    ///
+    /// Like [`Rfft`](crate::rfft::Rfft), the inverse transform is
+    /// unnormalized: a forward/inverse round trip scales the signal by `n`.
+    pub fn compute(&mut self, data: &mut [Complex<T>]) {
+        let n = self.n;
+        if data.len() < n {
+            panic!("Data length {} too small for FFT size {}", data.len(), n);
+        }
+        if n == 0 {
+            return;
+        }
+        self.fft
+            .process_with_scratch(&mut data[..n], &mut self.scratch);
+    }
+}
@@ -0,0 +1,189 @@
+use crate::error::KnfError;
+use crate::online::{Computer, OnlineFeature};
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+struct State {
+    frames: VecDeque<Vec<f32>>,
+    /// No more frames will be sent (input finished and flushed, or producer dropped).
+    closed: bool,
+    /// The consumer was dropped; nothing will drain the queue any more.
+    consumer_gone: bool,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Creates a bounded producer/consumer pair around `feature`.
+///
+/// The producer only computes frames while fewer than `max_pending` frames are
+/// waiting for the consumer; further audio is buffered uncomputed until the
+/// consumer catches up, and [`FeatureProducer::is_backpressured`] reports the
+/// condition to the audio source.
+pub fn feature_channel<C: Computer>(
+    feature: OnlineFeature<C>,
+    max_pending: usize,
+) -> (FeatureProducer<C>, FeatureConsumer) {
+    assert!(max_pending > 0, "max_pending must be at least 1");
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            frames: VecDeque::new(),
+            closed: false,
+            consumer_gone: false,
+        }),
+        changed: Condvar::new(),
+    });
+
+    let mut producer = FeatureProducer {
+        feature,
+        shared: shared.clone(),
+        max_pending,
+        next_frame: 0,
+    };
+    producer.pump();
+
+    (producer, FeatureConsumer { shared })
+}
+
+pub struct FeatureProducer<C: Computer> {
+    feature: OnlineFeature<C>,
+    shared: Arc<Shared>,
+    max_pending: usize,
+    next_frame: usize,
+}
+
+impl<C: Computer> FeatureProducer<C> {
+    /// Buffers audio and sends as many frames as the queue has room for.
+    /// Returns the number of frames sent.
+    pub fn accept_waveform(
+        &mut self,
+        sampling_rate: f32,
+        waveform: &[f32],
+    ) -> Result<usize, KnfError> {
+        self.feature.accept_waveform(sampling_rate, waveform)?;
+        Ok(self.pump())
+    }
+
+    pub fn input_finished(&mut self) -> usize {
+        self.feature.input_finished();
+        self.pump()
+    }
+
+    /// Whether the consumer is lagging: the queue is full and computation is paused.
+    pub fn is_backpressured(&self) -> bool {
+        self.shared.lock().frames.len() >= self.max_pending
+    }
+
+    /// Computes and sends frames into whatever room the consumer has freed.
+    /// Returns the number of frames sent.
+    pub fn pump(&mut self) -> usize {
+        let room = self.max_pending - self.shared.lock().frames.len().min(self.max_pending);
+        self.feature.set_frame_limit(Some(self.next_frame + room));
+
+        let ready = self.feature.num_frames_ready();
+        let sent = ready - self.next_frame;
+        let mut state = self.shared.lock();
+        state.frames.extend(self.feature.pop_frames(sent));
+        self.next_frame = ready;
+
+        if self.feature.is_input_finished() && self.feature.num_frames_pending() == 0 {
+            state.closed = true;
+        }
+        drop(state);
+        self.shared.changed.notify_all();
+        sent
+    }
+
+    /// Whether the consumer was dropped, so no more frames can be sent.
+    pub fn is_consumer_gone(&self) -> bool {
+        self.shared.lock().consumer_gone
+    }
+
+    /// Blocks until the consumer has room, then pumps. Returns 0 without
+    /// waiting once the consumer was dropped.
+    pub fn wait_for_capacity(&mut self) -> usize {
+        let mut state = self.shared.lock();
+        while state.frames.len() >= self.max_pending {
+            if state.consumer_gone {
+                return 0;
+            }
+            state = self
+                .shared
+                .changed
+                .wait(state)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        drop(state);
+        self.pump()
+    }
+
+    pub fn feature(&self) -> &OnlineFeature<C> {
+        &self.feature
+    }
+}
+
+impl<C: Computer> Drop for FeatureProducer<C> {
+    fn drop(&mut self) {
+        self.shared.lock().closed = true;
+        self.shared.changed.notify_all();
+    }
+}
+
+pub struct FeatureConsumer {
+    shared: Arc<Shared>,
+}
+
+impl FeatureConsumer {
+    /// Blocks until a frame is available. Returns `None` once the producer has
+    /// finished (or was dropped) and all frames have been received.
+    pub fn recv(&self) -> Option<Vec<f32>> {
+        let mut state = self.shared.lock();
+        loop {
+            if let Some(frame) = state.frames.pop_front() {
+                drop(state);
+                self.shared.changed.notify_all();
+                return Some(frame);
+            }
+            if state.closed {
+                return None;
+            }
+            state = self
+                .shared
+                .changed
+                .wait(state)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    pub fn try_recv(&self) -> Option<Vec<f32>> {
+        let frame = self.shared.lock().frames.pop_front();
+        if frame.is_some() {
+            self.shared.changed.notify_all();
+        }
+        frame
+    }
+
+    /// Number of frames waiting to be received.
+    pub fn len(&self) -> usize {
+        self.shared.lock().frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Drop for FeatureConsumer {
+    fn drop(&mut self) {
+        self.shared.lock().consumer_gone = true;
+        self.shared.changed.notify_all();
+    }
+}
@@ -0,0 +1,115 @@
+use crate::error::KnfError;
+use crate::online::{Computer, ComputerState};
+use crate::window::FrameOptions;
+
+/// Runs several computers on the same frame and concatenates their outputs,
+/// like Kaldi's `append-feats`.
+///
+/// Frames are extracted with the options of the first computer; all computers
+/// must agree on sampling rate, frame shift, window size and padded window size.
+pub struct ComposedComputer {
+    computers: Vec<Box<dyn Computer>>,
+    frame_buf: Vec<f32>,
+    dim: usize,
+}
+
+impl ComposedComputer {
+    pub fn new(computers: Vec<Box<dyn Computer>>) -> Result<Self, KnfError> {
+        let first = computers
+            .first()
+            .ok_or(KnfError::invalid(
+                "ComposedComputer needs at least one computer",
+            ))?
+            .frame_opts();
+
+        for c in computers.iter().skip(1) {
+            let opts = c.frame_opts();
+            if opts.samp_freq != first.samp_freq
+                || opts.window_shift() != first.window_shift()
+                || opts.window_size() != first.window_size()
+                || opts.padded_window_size() != first.padded_window_size()
+            {
+                return Err(KnfError::invalid(
+                    "Composed computers must share the same framing",
+                ));
+            }
+        }
+
+        let dim = computers.iter().map(|c| c.dim()).sum();
+        Ok(Self {
+            frame_buf: vec![0.0; first.padded_window_size()],
+            computers,
+            dim,
+        })
+    }
+
+    pub fn computers(&self) -> &[Box<dyn Computer>] {
+        &self.computers
+    }
+}
+
+impl Computer for ComposedComputer {
+    fn dim(&self) -> usize {
+        self.dim
+    }
+
+    fn frame_opts(&self) -> &FrameOptions {
+        self.computers[0].frame_opts()
+    }
+
+    fn need_raw_energy(&self) -> bool {
+        self.computers.iter().any(|c| c.need_raw_energy())
+    }
+
+    fn compute(
+        &mut self,
+        raw_log_energy: f32,
+        vtln_warp: f32,
+        window: &mut [f32],
+        feature: &mut [f32],
+    ) {
+        // Computers transform the frame in place, so each gets its own copy.
+        self.frame_buf.resize(window.len(), 0.0);
+        let mut offset = 0;
+        for c in self.computers.iter_mut() {
+            let dim = c.dim();
+            self.frame_buf.copy_from_slice(window);
+            c.compute(
+                raw_log_energy,
+                vtln_warp,
+                &mut self.frame_buf,
+                &mut feature[offset..offset + dim],
+            );
+            offset += dim;
+        }
+    }
+
+    fn prepare_vtln_warp(&mut self, vtln_warp: f32) -> Result<(), KnfError> {
+        for c in self.computers.iter_mut() {
+            c.prepare_vtln_warp(vtln_warp)?;
+        }
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        for c in self.computers.iter_mut() {
+            c.reset();
+        }
+    }
+
+    fn save_state(&self) -> ComputerState {
+        ComputerState {
+            values: Vec::new(),
+            children: self.computers.iter().map(|c| c.save_state()).collect(),
+        }
+    }
+
+    fn restore_state(&mut self, state: &ComputerState) {
+        for (i, c) in self.computers.iter_mut().enumerate() {
+            match state.children.get(i) {
+                Some(state) => c.restore_state(state),
+                None => c.reset(),
+            }
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! Fast convolution with long impulse responses, e.g. room impulse responses
+//! for reverberation augmentation.
+
+use crate::error::KnfError;
+use crate::rfft::Rfft;
+use std::collections::VecDeque;
+
+/// Full linear convolution of `signal` with `ir` (`signal.len() + ir.len() - 1`
+/// samples) by FFT overlap-add.
+pub fn convolve(signal: &[f32], ir: &[f32]) -> Result<Vec<f32>, KnfError> {
+    let block_size = ir.len().clamp(256, 4096).next_power_of_two();
+    let mut convolver = Convolver::new(ir, block_size)?;
+    let mut out = convolver.accept_waveform(signal);
+    out.extend(convolver.flush());
+    Ok(out)
+}
+
+/// Reverberates `wave` with a room impulse response the way Kaldi and
+/// torchaudio recipes do: the RIR is scaled to unit energy, shifted so its
+/// direct path (largest peak) lines up with the dry signal, and the output
+/// is cut to the input length.
+pub fn reverberate(wave: &[f32], rir: &[f32]) -> Result<Vec<f32>, KnfError> {
+    let norm = rir.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        return Err(KnfError::invalid("Room impulse response is silent"));
+    }
+    let peak = rir.iter().enumerate().fold(
+        0,
+        |best, (i, x)| if x.abs() > rir[best].abs() { i } else { best },
+    );
+    let rir: Vec<f32> = rir[peak..].iter().map(|x| x / norm).collect();
+    let mut out = convolve(wave, &rir)?;
+    out.truncate(wave.len());
+    Ok(out)
+}
+
+/// Streaming convolution with a fixed impulse response, using uniformly
+/// partitioned overlap-add so long responses cost a few block-sized FFTs
+/// per block instead of one FFT of the response's length.
+///
+/// Output lags the input by up to `block_size - 1` samples; the output of
+/// all chunks followed by [`flush`](Self::flush) equals [`convolve`].
+pub struct Convolver {
+    block_size: usize,
+    ir_len: usize,
+    fft: Rfft,
+    ifft: Rfft,
+    // Packed spectra of the response partitions, scaled for the inverse FFT
+    partitions: Vec<Vec<f32>>,
+    // Spectra of the latest input blocks, newest first
+    history: VecDeque<Vec<f32>>,
+    pending: Vec<f32>,
+    overlap: Vec<f32>,
+    acc: Vec<f32>,
+    num_input: usize,
+    num_output: usize,
+}
+
+impl Convolver {
+    pub fn new(ir: &[f32], block_size: usize) -> Result<Self, KnfError> {
+        if ir.is_empty() {
+            return Err(KnfError::invalid("Impulse response is empty"));
+        }
+        if block_size == 0 {
+            return Err(KnfError::invalid("block_size must be positive"));
+        }
+        let n = 2 * block_size;
+        let mut fft = Rfft::new(n, false);
+        let scale = 1.0 / n as f32;
+        let partitions: Vec<Vec<f32>> = ir
+            .chunks(block_size)
+            .map(|part| {
+                let mut spectrum = vec![0.0; n];
+                for (s, x) in spectrum.iter_mut().zip(part) {
+                    *s = x * scale;
+                }
+                fft.compute(&mut spectrum);
+                spectrum
+            })
+            .collect();
+        Ok(Self {
+            block_size,
+            ir_len: ir.len(),
+            history: (0..partitions.len()).map(|_| vec![0.0; n]).collect(),
+            partitions,
+            fft,
+            ifft: Rfft::new(n, true),
+            pending: Vec::with_capacity(block_size),
+            overlap: vec![0.0; block_size],
+            acc: vec![0.0; n],
+            num_input: 0,
+            num_output: 0,
+        })
+    }
+
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Adds the next chunk of input; returns the output of every block it
+    /// completes.
+    pub fn accept_waveform(&mut self, input: &[f32]) -> Vec<f32> {
+        self.num_input += input.len();
+        let mut out = Vec::with_capacity(input.len() + self.block_size);
+        let mut input = input;
+        while !input.is_empty() {
+            let take = (self.block_size - self.pending.len()).min(input.len());
+            self.pending.extend_from_slice(&input[..take]);
+            input = &input[take..];
+            if self.pending.len() == self.block_size {
+                self.process_block(&mut out);
+            }
+        }
+        out
+    }
+
+    /// Returns the rest of the convolution, including the response's tail,
+    /// and resets for a new signal.
+    pub fn flush(&mut self) -> Vec<f32> {
+        let total = match self.num_input {
+            0 => 0,
+            n => n + self.ir_len - 1,
+        };
+        let mut out = Vec::new();
+        while self.num_output < total {
+            self.pending.resize(self.block_size, 0.0);
+            self.process_block(&mut out);
+        }
+        out.truncate(out.len() - (self.num_output - total));
+        self.reset();
+        out
+    }
+
+    pub fn reset(&mut self) {
+        for spectrum in self.history.iter_mut() {
+            spectrum.iter_mut().for_each(|x| *x = 0.0);
+        }
+        self.pending.clear();
+        self.overlap.iter_mut().for_each(|x| *x = 0.0);
+        self.num_input = 0;
+        self.num_output = 0;
+    }
+
+    /// Convolves the full pending block and appends `block_size` samples.
+    fn process_block(&mut self, out: &mut Vec<f32>) {
+        let b = self.block_size;
+        let mut spectrum = self.history.pop_back().unwrap_or_default();
+        spectrum[..b].copy_from_slice(&self.pending);
+        spectrum[b..].iter_mut().for_each(|x| *x = 0.0);
+        self.pending.clear();
+        self.fft.compute(&mut spectrum);
+        self.history.push_front(spectrum);
+
+        self.acc.iter_mut().for_each(|x| *x = 0.0);
+        for (x, h) in self.history.iter().zip(&self.partitions) {
+            multiply_accumulate(x, h, &mut self.acc);
+        }
+        self.ifft.compute(&mut self.acc);
+
+        out.extend(self.acc[..b].iter().zip(&self.overlap).map(|(a, o)| a + o));
+        self.overlap.copy_from_slice(&self.acc[b..]);
+        self.num_output += b;
+    }
+}
+
+/// `acc += x * h` for spectra in the packed layout of [`Rfft`] (even size).
+pub(crate) fn multiply_accumulate(x: &[f32], h: &[f32], acc: &mut [f32]) {
+    acc[0] += x[0] * h[0];
+    acc[1] += x[1] * h[1];
+    for ((a, x), h) in acc[2..]
+        .chunks_exact_mut(2)
+        .zip(x[2..].chunks_exact(2))
+        .zip(h[2..].chunks_exact(2))
+    {
+        a[0] += x[0] * h[0] - x[1] * h[1];
+        a[1] += x[0] * h[1] + x[1] * h[0];
+    }
+}
@@ -0,0 +1,113 @@
+//! Conversions between linear values and decibels, following librosa's
+//! `power_to_db`, `amplitude_to_db`, `db_to_power` and `db_to_amplitude`.
+//! All functions work in place on any slice, e.g. the data of a
+//! [`FeatureMatrix`](crate::FeatureMatrix).
+
+use crate::error::KnfError;
+
+/// Value that maps to 0 dB.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DbReference {
+    Value(f32),
+    /// The largest magnitude in the input, like `ref=np.max`.
+    Max,
+}
+
+impl Default for DbReference {
+    fn default() -> Self {
+        DbReference::Value(1.0)
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct DbOptions {
+    pub reference: DbReference,
+    /// Floor applied before the logarithm; `None` uses librosa's default of
+    /// 1e-10 for powers and 1e-5 for amplitudes.
+    pub amin: Option<f32>,
+    /// Floors the output at `max - top_db`.
+    pub top_db: Option<f32>,
+}
+
+impl Default for DbOptions {
+    fn default() -> Self {
+        Self {
+            reference: DbReference::default(),
+            amin: None,
+            top_db: Some(80.0),
+        }
+    }
+}
+
+impl DbOptions {
+    pub fn validate(&self) -> Result<(), KnfError> {
+        if let Some(amin) = self.amin {
+            if amin.is_nan() || amin <= 0.0 {
+                return Err(KnfError::invalid("amin must be positive"));
+            }
+        }
+        if let Some(top_db) = self.top_db {
+            if top_db.is_nan() || top_db < 0.0 {
+                return Err(KnfError::invalid("top_db must be non-negative"));
+            }
+        }
+        if let DbReference::Value(r) = self.reference {
+            if r.is_nan() || r < 0.0 {
+                return Err(KnfError::invalid("Reference value must be non-negative"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Converts powers to dB: `10 * log10(max(amin, |x|) / max(amin, ref))`.
+pub fn power_to_db(values: &mut [f32], opts: &DbOptions) -> Result<(), KnfError> {
+    opts.validate()?;
+    to_db(values, opts.amin.unwrap_or(1e-10), opts, 10.0);
+    Ok(())
+}
+
+/// Converts amplitudes to dB: `20 * log10(max(amin, |x|) / max(amin, ref))`,
+/// which equals [`power_to_db`] of the squares.
+pub fn amplitude_to_db(values: &mut [f32], opts: &DbOptions) -> Result<(), KnfError> {
+    opts.validate()?;
+    to_db(values, opts.amin.unwrap_or(1e-5), opts, 20.0);
+    Ok(())
+}
+
+pub(crate) fn to_db(values: &mut [f32], amin: f32, opts: &DbOptions, scale: f32) {
+    let reference = match opts.reference {
+        DbReference::Value(r) => r,
+        DbReference::Max => values.iter().fold(0.0f32, |m, x| m.max(x.abs())),
+    };
+    let offset = scale * reference.max(amin).log10();
+    for x in values.iter_mut() {
+        *x = scale * x.abs().max(amin).log10() - offset;
+    }
+    if let Some(top_db) = opts.top_db {
+        let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        for x in values.iter_mut() {
+            *x = x.max(max - top_db);
+        }
+    }
+}
+
+/// Inverse of [`power_to_db`] (without `top_db`): `reference * 10^(x / 10)`.
+pub fn db_to_power(values: &mut [f32], reference: f32) {
+    for x in values.iter_mut() {
+        *x = reference * 10f32.powf(*x / 10.0);
+    }
+}
+
+/// Inverse of [`amplitude_to_db`] (without `top_db`): `reference * 10^(x / 20)`.
+pub fn db_to_amplitude(values: &mut [f32], reference: f32) {
+    for x in values.iter_mut() {
+        *x = reference * 10f32.powf(*x / 20.0);
+    }
+}
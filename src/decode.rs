@@ -0,0 +1,113 @@
+use crate::error::KnfError;
+use crate::pcm::{apply_channel_policy, ChannelPolicy};
+use crate::pipeline::{OnlineFeaturePipeline, OnlinePipelineOptions};
+use crate::resample::resample_waveform;
+use std::fs::File;
+use std::path::Path;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+/// Decodes an audio file (MP3, FLAC, Ogg Vorbis, WAV) to mono samples in
+/// [-1, 1), averaging its channels. Returns the samples and the sampling rate.
+pub fn decode_audio_file<P: AsRef<Path>>(path: P) -> Result<(Vec<f32>, u32), KnfError> {
+    let path = path.as_ref();
+    let file = File::open(path).map_err(KnfError::io)?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+    let probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(KnfError::io)?;
+    let mut format = probed.format;
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or(KnfError::input("No audio track found"))?;
+    let track_id = track.id;
+    let sample_rate = track
+        .codec_params
+        .sample_rate
+        .ok_or(KnfError::input("Unknown sampling rate"))?;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(KnfError::io)?;
+
+    let mut samples = Vec::new();
+    let mut num_channels = 0;
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                break
+            }
+            Err(e) => return Err(KnfError::io(e)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // Corrupt packets are skipped, as symphonia recommends
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(e) => return Err(KnfError::io(e)),
+        };
+        let spec = *decoded.spec();
+        num_channels = spec.channels.count();
+        let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        buffer.copy_interleaved_ref(decoded);
+        samples.extend_from_slice(buffer.samples());
+    }
+
+    if num_channels == 0 {
+        return Ok((Vec::new(), sample_rate));
+    }
+    let mut mono = apply_channel_policy(&samples, num_channels, ChannelPolicy::Average)?;
+    Ok((mono.pop().unwrap_or_default(), sample_rate))
+}
+
+/// Decodes an audio file, downmixes it to mono, resamples it to
+/// `frame_opts.samp_freq` of the selected features and runs the pipeline.
+///
+/// Samples are scaled to the 16-bit integer range, as Kaldi features expect.
+pub fn extract_features_from_file<P: AsRef<Path>>(
+    path: P,
+    opts: &OnlinePipelineOptions,
+) -> Result<Vec<Vec<f32>>, KnfError> {
+    let mut pipeline = OnlineFeaturePipeline::new(opts.clone())?;
+    let (mut samples, sample_rate) = decode_audio_file(path)?;
+    for x in samples.iter_mut() {
+        *x *= 32768.0;
+    }
+
+    let target_rate = match opts.feature_type.as_str() {
+        "mfcc" => opts.mfcc_opts.frame_opts.samp_freq,
+        _ => opts.fbank_opts.frame_opts.samp_freq,
+    };
+    if target_rate.fract() != 0.0 || target_rate <= 0.0 {
+        return Err(KnfError::invalid(format!(
+            "Cannot resample to {} Hz",
+            target_rate
+        )));
+    }
+    let target_rate = target_rate as u32;
+
+    let samples = resample_waveform(&samples, sample_rate, target_rate)?;
+    pipeline.accept_waveform(target_rate as f32, &samples)?;
+    pipeline.input_finished();
+    Ok(pipeline.features)
+}
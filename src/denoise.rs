@@ -0,0 +1,359 @@
+use crate::error::KnfError;
+use crate::istft::{istft_compute, IstftOptions};
+use crate::stft::{stft_compute, StftOptions, StftResult};
+use crate::window::WindowType;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct WienerOptions {
+    /// Analysis/synthesis STFT; defaults to a 512-point Hann window with a hop
+    /// of 128.
+    pub stft: StftOptions,
+    /// Leading frames assumed to hold only noise, from which the noise power
+    /// spectrum is estimated.
+    pub noise_frames: usize,
+    /// Decision-directed smoothing of the a priori SNR (Ephraim & Malah use
+    /// 0.98); higher values reduce musical noise.
+    pub alpha: f32,
+    /// Lower bound on the gain, which keeps some residual noise rather than
+    /// gating it to silence.
+    pub min_gain: f32,
+}
+
+impl Default for WienerOptions {
+    fn default() -> Self {
+        Self {
+            stft: StftOptions {
+                n_fft: 512,
+                hop_length: 128,
+                win_length: 512,
+                window_type: WindowType::Hann,
+                ..Default::default()
+            },
+            noise_frames: 10,
+            alpha: 0.98,
+            min_gain: 0.1,
+        }
+    }
+}
+
+impl WienerOptions {
+    pub fn validate(&self) -> Result<(), KnfError> {
+        self.stft.validate()?;
+        if self.noise_frames == 0 {
+            return Err(KnfError::invalid("noise_frames must be positive"));
+        }
+        if !(0.0..1.0).contains(&self.alpha) {
+            return Err(KnfError::invalid("alpha must be in [0, 1)"));
+        }
+        if !(0.0..=1.0).contains(&self.min_gain) {
+            return Err(KnfError::invalid("min_gain must be in [0, 1]"));
+        }
+        Ok(())
+    }
+}
+
+/// Removes stationary noise from `wave` with [`WienerFilter`]; the output has
+/// the same length as the input.
+pub fn wiener_denoise(wave: &[f32], opts: &WienerOptions) -> Result<Vec<f32>, KnfError> {
+    let mut filter = WienerFilter::new(opts.clone())?;
+    if wave.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut stft = stft_compute(&opts.stft, wave)?;
+    filter.process(&mut stft)?;
+    let mut out = istft_compute(&IstftOptions::from(&opts.stft), &stft)?;
+    out.resize(wave.len(), 0.0);
+    Ok(out)
+}
+
+/// Frame-by-frame Wiener filter with the decision-directed a priori SNR
+/// estimate of Ephraim & Malah, for STFT frames of `opts.stft`.
+///
+/// Unless a noise spectrum is given with
+/// [`with_noise_psd`](Self::with_noise_psd), it is averaged over the first
+/// `noise_frames` frames, which are filtered with the estimate so far.
+pub struct WienerFilter {
+    opts: WienerOptions,
+    noise: Vec<f32>,
+    noise_count: usize,
+    noise_given: bool,
+    // Clean power estimate of the previous frame
+    prev_clean: Vec<f32>,
+}
+
+impl WienerFilter {
+    pub fn new(opts: WienerOptions) -> Result<Self, KnfError> {
+        opts.validate()?;
+        let bins = opts.stft.n_fft / 2 + 1;
+        Ok(Self {
+            opts,
+            noise: vec![0.0; bins],
+            noise_count: 0,
+            noise_given: false,
+            prev_clean: vec![0.0; bins],
+        })
+    }
+
+    /// Uses a known noise power spectrum (`n_fft / 2 + 1` bins) instead of
+    /// estimating it from the leading frames.
+    pub fn with_noise_psd(opts: WienerOptions, noise: Vec<f32>) -> Result<Self, KnfError> {
+        let mut filter = Self::new(opts)?;
+        KnfError::check_len("noise PSD bins", filter.noise.len(), noise.len())?;
+        filter.noise = noise;
+        filter.noise_given = true;
+        Ok(filter)
+    }
+
+    pub fn noise_psd(&self) -> &[f32] {
+        &self.noise
+    }
+
+    /// Filters one frame in place.
+    pub fn process_frame(&mut self, real: &mut [f32], imag: &mut [f32]) -> Result<(), KnfError> {
+        let bins = self.noise.len();
+        KnfError::check_len("STFT bins per frame", bins, real.len())?;
+        KnfError::check_len("STFT bins per frame", bins, imag.len())?;
+
+        let learning = !self.noise_given && self.noise_count < self.opts.noise_frames;
+        if learning {
+            self.noise_count += 1;
+        }
+        let alpha = self.opts.alpha;
+        for k in 0..bins {
+            let power = real[k] * real[k] + imag[k] * imag[k];
+            if learning {
+                self.noise[k] += (power - self.noise[k]) / self.noise_count as f32;
+            }
+            let noise = self.noise[k].max(1e-10);
+            let posterior = power / noise;
+            let prior =
+                alpha * self.prev_clean[k] / noise + (1.0 - alpha) * (posterior - 1.0).max(0.0);
+            let gain = (prior / (1.0 + prior)).max(self.opts.min_gain);
+            real[k] *= gain;
+            imag[k] *= gain;
+            self.prev_clean[k] = gain * gain * power;
+        }
+        Ok(())
+    }
+
+    /// Filters all frames of `stft` in place.
+    pub fn process(&mut self, stft: &mut StftResult) -> Result<(), KnfError> {
+        KnfError::check_len("n_fft", self.opts.stft.n_fft, stft.n_fft)?;
+        let bins = self.noise.len();
+        for (real, imag) in stft
+            .real
+            .chunks_exact_mut(bins)
+            .zip(stft.imag.chunks_exact_mut(bins))
+        {
+            self.process_frame(real, imag)?;
+        }
+        Ok(())
+    }
+
+    /// Forgets the previous frame and, unless it was given, the noise estimate.
+    pub fn reset(&mut self) {
+        if !self.noise_given {
+            self.noise.iter_mut().for_each(|x| *x = 0.0);
+            self.noise_count = 0;
+        }
+        self.prev_clean.iter_mut().for_each(|x| *x = 0.0);
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct SpectralGateOptions {
+    /// Analysis/synthesis STFT; defaults to a 512-point Hann window with a hop
+    /// of 128.
+    pub stft: StftOptions,
+    /// Leading frames assumed to hold only noise, from which the per-bin noise
+    /// level statistics are initialized.
+    pub noise_frames: usize,
+    /// A bin passes when its level exceeds the noise mean by this many
+    /// standard deviations (in dB), like noisereduce's `n_std_thresh_stationary`.
+    pub n_std_thresh: f32,
+    /// Fraction of a gated bin that is removed; 1 removes it entirely.
+    pub prop_decrease: f32,
+    /// After the leading frames, gated bins keep updating the noise statistics
+    /// with this smoothing factor so the threshold follows slowly changing
+    /// noise; 0 keeps them fixed.
+    pub adapt_rate: f32,
+    /// Half-width in bins of the triangular smoothing of the mask over frequency.
+    pub freq_smoothing: usize,
+    /// One-pole smoothing of the mask over time, in [0, 1).
+    pub time_smoothing: f32,
+}
+
+impl Default for SpectralGateOptions {
+    fn default() -> Self {
+        Self {
+            stft: WienerOptions::default().stft,
+            noise_frames: 10,
+            n_std_thresh: 1.5,
+            prop_decrease: 1.0,
+            adapt_rate: 0.01,
+            freq_smoothing: 1,
+            time_smoothing: 0.5,
+        }
+    }
+}
+
+impl SpectralGateOptions {
+    pub fn validate(&self) -> Result<(), KnfError> {
+        self.stft.validate()?;
+        if self.noise_frames == 0 {
+            return Err(KnfError::invalid("noise_frames must be positive"));
+        }
+        if !(0.0..=1.0).contains(&self.prop_decrease) {
+            return Err(KnfError::invalid("prop_decrease must be in [0, 1]"));
+        }
+        if !(0.0..=1.0).contains(&self.adapt_rate) {
+            return Err(KnfError::invalid("adapt_rate must be in [0, 1]"));
+        }
+        if !(0.0..1.0).contains(&self.time_smoothing) {
+            return Err(KnfError::invalid("time_smoothing must be in [0, 1)"));
+        }
+        Ok(())
+    }
+}
+
+/// Attenuates the bins of `wave` that stay below the noise threshold with
+/// [`SpectralGate`]; the output has the same length as the input, e.g. for
+/// cleaning audio before feature extraction.
+pub fn spectral_gate(wave: &[f32], opts: &SpectralGateOptions) -> Result<Vec<f32>, KnfError> {
+    let mut gate = SpectralGate::new(opts.clone())?;
+    if wave.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut stft = stft_compute(&opts.stft, wave)?;
+    gate.process(&mut stft)?;
+    let mut out = istft_compute(&IstftOptions::from(&opts.stft), &stft)?;
+    out.resize(wave.len(), 0.0);
+    Ok(out)
+}
+
+/// Frame-by-frame spectral noise gate in the style of noisereduce: a bin is
+/// kept when its level in dB exceeds `mean + n_std_thresh * std` of the noise
+/// in that bin, and the resulting mask is smoothed over frequency and time.
+pub struct SpectralGate {
+    opts: SpectralGateOptions,
+    // Per-bin mean and variance of the noise level in dB
+    mean: Vec<f32>,
+    var: Vec<f32>,
+    noise_count: usize,
+    level: Vec<f32>,
+    mask: Vec<f32>,
+    smoothed: Vec<f32>,
+}
+
+impl SpectralGate {
+    pub fn new(opts: SpectralGateOptions) -> Result<Self, KnfError> {
+        opts.validate()?;
+        let bins = opts.stft.n_fft / 2 + 1;
+        Ok(Self {
+            opts,
+            mean: vec![0.0; bins],
+            var: vec![0.0; bins],
+            noise_count: 0,
+            level: vec![0.0; bins],
+            mask: vec![0.0; bins],
+            smoothed: vec![0.0; bins],
+        })
+    }
+
+    /// Per-bin noise threshold in dB.
+    pub fn threshold(&self) -> Vec<f32> {
+        self.mean
+            .iter()
+            .zip(&self.var)
+            .map(|(m, v)| m + self.opts.n_std_thresh * v.sqrt())
+            .collect()
+    }
+
+    /// Gates one frame in place.
+    pub fn process_frame(&mut self, real: &mut [f32], imag: &mut [f32]) -> Result<(), KnfError> {
+        let bins = self.mean.len();
+        KnfError::check_len("STFT bins per frame", bins, real.len())?;
+        KnfError::check_len("STFT bins per frame", bins, imag.len())?;
+
+        let learning = self.noise_count < self.opts.noise_frames;
+        if learning {
+            self.noise_count += 1;
+        }
+        for k in 0..bins {
+            let level = 10.0 * (real[k] * real[k] + imag[k] * imag[k]).max(1e-10).log10();
+            self.level[k] = level;
+            if learning {
+                // Running mean and variance over the leading frames
+                let n = self.noise_count as f32;
+                let delta = level - self.mean[k];
+                self.mean[k] += delta / n;
+                self.var[k] += (delta * (level - self.mean[k]) - self.var[k]) / n;
+            }
+            let threshold = self.mean[k] + self.opts.n_std_thresh * self.var[k].sqrt();
+            self.mask[k] = if level > threshold { 1.0 } else { 0.0 };
+        }
+
+        if !learning && self.opts.adapt_rate > 0.0 {
+            let rate = self.opts.adapt_rate;
+            for k in 0..bins {
+                if self.mask[k] == 0.0 {
+                    let delta = self.level[k] - self.mean[k];
+                    self.mean[k] += rate * delta;
+                    self.var[k] += rate * (delta * delta - self.var[k]);
+                }
+            }
+        }
+
+        let width = self.opts.freq_smoothing as isize;
+        let smoothing = self.opts.time_smoothing;
+        for k in 0..bins {
+            // Triangular kernel over neighbouring bins
+            let (mut sum, mut norm) = (0.0, 0.0);
+            for d in -width..=width {
+                let j = k as isize + d;
+                if j >= 0 && (j as usize) < bins {
+                    let w = (width + 1 - d.abs()) as f32;
+                    sum += w * self.mask[j as usize];
+                    norm += w;
+                }
+            }
+            self.smoothed[k] = smoothing * self.smoothed[k] + (1.0 - smoothing) * sum / norm;
+            let gain = 1.0 - self.opts.prop_decrease * (1.0 - self.smoothed[k]);
+            real[k] *= gain;
+            imag[k] *= gain;
+        }
+        Ok(())
+    }
+
+    /// Gates all frames of `stft` in place.
+    pub fn process(&mut self, stft: &mut StftResult) -> Result<(), KnfError> {
+        KnfError::check_len("n_fft", self.opts.stft.n_fft, stft.n_fft)?;
+        let bins = self.mean.len();
+        for (real, imag) in stft
+            .real
+            .chunks_exact_mut(bins)
+            .zip(stft.imag.chunks_exact_mut(bins))
+        {
+            self.process_frame(real, imag)?;
+        }
+        Ok(())
+    }
+
+    /// Forgets the noise statistics and the mask history.
+    pub fn reset(&mut self) {
+        for v in [&mut self.mean, &mut self.var, &mut self.smoothed] {
+            v.iter_mut().for_each(|x| *x = 0.0);
+        }
+        self.noise_count = 0;
+    }
+}
@@ -0,0 +1,254 @@
+//! Level control applied to waveforms ahead of feature extraction.
+
+use crate::error::KnfError;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct AgcOptions {
+    pub samp_freq: f32,
+    /// Output RMS the gain steers towards, in sample units; the default is
+    /// -20 dBFS at [`PcmScale::Kaldi`](crate::PcmScale::Kaldi).
+    pub target_rms: f32,
+    /// Time over which the input level is measured.
+    pub window_ms: f32,
+    /// Time constant for lowering the gain when the level rises.
+    pub attack_ms: f32,
+    /// Time constant for raising the gain when the level falls.
+    pub release_ms: f32,
+    pub max_gain_db: f32,
+    /// Input below this RMS is treated as silence: the gain is held rather
+    /// than raised towards `max_gain_db`.
+    pub gate_rms: f32,
+}
+
+impl Default for AgcOptions {
+    fn default() -> Self {
+        Self {
+            samp_freq: 16000.0,
+            target_rms: 3276.8,
+            window_ms: 50.0,
+            attack_ms: 20.0,
+            release_ms: 500.0,
+            max_gain_db: 30.0,
+            gate_rms: 30.0,
+        }
+    }
+}
+
+impl AgcOptions {
+    pub fn validate(&self) -> Result<(), KnfError> {
+        if self.samp_freq <= 0.0 {
+            return Err(KnfError::invalid("samp_freq must be positive"));
+        }
+        if self.target_rms <= 0.0 {
+            return Err(KnfError::invalid("target_rms must be positive"));
+        }
+        if self.window_ms <= 0.0 || self.attack_ms <= 0.0 || self.release_ms <= 0.0 {
+            return Err(KnfError::invalid(
+                "AGC window, attack and release times must be positive",
+            ));
+        }
+        if self.gate_rms < 0.0 {
+            return Err(KnfError::invalid("gate_rms must be non-negative"));
+        }
+        Ok(())
+    }
+}
+
+/// One-pole smoothing coefficient for a time constant of `ms` milliseconds.
+pub(crate) fn time_coeff(ms: f32, samp_freq: f32) -> f32 {
+    1.0 - (-1000.0 / (ms * samp_freq)).exp()
+}
+
+/// Streaming automatic gain control: the input RMS is tracked over
+/// `window_ms` and the gain moves towards `target_rms / rms`, falling with the
+/// attack and rising with the release time constant, up to `max_gain_db`.
+#[derive(Clone, Debug)]
+pub struct Agc {
+    opts: AgcOptions,
+    window_coeff: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+    max_gain: f32,
+    power: f32,
+    gain: f32,
+}
+
+impl Agc {
+    pub fn new(opts: AgcOptions) -> Result<Self, KnfError> {
+        opts.validate()?;
+        Ok(Self {
+            window_coeff: time_coeff(opts.window_ms, opts.samp_freq),
+            attack_coeff: time_coeff(opts.attack_ms, opts.samp_freq),
+            release_coeff: time_coeff(opts.release_ms, opts.samp_freq),
+            max_gain: 10f32.powf(opts.max_gain_db / 20.0),
+            power: 0.0,
+            gain: 1.0,
+            opts,
+        })
+    }
+
+    /// Current linear gain.
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    pub fn process_sample(&mut self, x: f32) -> f32 {
+        self.power += self.window_coeff * (x * x - self.power);
+        let rms = self.power.sqrt();
+        if rms >= self.opts.gate_rms && rms > 0.0 {
+            let desired = (self.opts.target_rms / rms).min(self.max_gain);
+            let coeff = if desired < self.gain {
+                self.attack_coeff
+            } else {
+                self.release_coeff
+            };
+            self.gain += coeff * (desired - self.gain);
+        }
+        x * self.gain
+    }
+
+    /// Processes the next chunk of input.
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        input.iter().map(|&x| self.process_sample(x)).collect()
+    }
+
+    /// Starts a new signal at unit gain.
+    pub fn reset(&mut self) {
+        self.power = 0.0;
+        self.gain = 1.0;
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct CompressorOptions {
+    pub samp_freq: f32,
+    /// Sample value of 0 dBFS; the default suits [`PcmScale::Kaldi`](crate::PcmScale::Kaldi).
+    pub full_scale: f32,
+    pub threshold_db: f32,
+    /// Input dB above the threshold per output dB; `f32::INFINITY` makes a limiter.
+    pub ratio: f32,
+    /// Width in dB of the soft knee around the threshold; 0 for a hard knee.
+    pub knee_db: f32,
+    pub attack_ms: f32,
+    pub release_ms: f32,
+    /// Gain in dB added after compression.
+    pub makeup_db: f32,
+}
+
+impl Default for CompressorOptions {
+    fn default() -> Self {
+        Self {
+            samp_freq: 16000.0,
+            full_scale: 32768.0,
+            threshold_db: -20.0,
+            ratio: 4.0,
+            knee_db: 6.0,
+            attack_ms: 5.0,
+            release_ms: 100.0,
+            makeup_db: 0.0,
+        }
+    }
+}
+
+impl CompressorOptions {
+    pub fn validate(&self) -> Result<(), KnfError> {
+        if self.samp_freq <= 0.0 || self.full_scale <= 0.0 {
+            return Err(KnfError::invalid(
+                "samp_freq and full_scale must be positive",
+            ));
+        }
+        if self.ratio.is_nan() || self.ratio < 1.0 {
+            return Err(KnfError::invalid(format!(
+                "Compression ratio ({}) must be at least 1",
+                self.ratio
+            )));
+        }
+        if self.knee_db.is_nan() || self.knee_db < 0.0 {
+            return Err(KnfError::invalid("knee_db must be non-negative"));
+        }
+        if self.attack_ms <= 0.0 || self.release_ms <= 0.0 {
+            return Err(KnfError::invalid(
+                "Compressor attack and release times must be positive",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Streaming feed-forward compressor with a soft knee, after Giannoulis,
+/// Massberg & Reiss (2012): the gain reduction of each sample's level is
+/// smoothed in dB with the attack and release time constants.
+#[derive(Clone, Debug)]
+pub struct Compressor {
+    opts: CompressorOptions,
+    attack_coeff: f32,
+    release_coeff: f32,
+    // Smoothed gain change in dB (<= 0)
+    reduction_db: f32,
+}
+
+impl Compressor {
+    pub fn new(opts: CompressorOptions) -> Result<Self, KnfError> {
+        opts.validate()?;
+        Ok(Self {
+            attack_coeff: time_coeff(opts.attack_ms, opts.samp_freq),
+            release_coeff: time_coeff(opts.release_ms, opts.samp_freq),
+            reduction_db: 0.0,
+            opts,
+        })
+    }
+
+    /// Current gain reduction in dB, before the makeup gain.
+    pub fn gain_reduction_db(&self) -> f32 {
+        -self.reduction_db
+    }
+
+    /// Output level in dB of the static curve for an input level in dB.
+    pub fn static_curve(&self, level_db: f32) -> f32 {
+        let CompressorOptions {
+            threshold_db: t,
+            ratio: r,
+            knee_db: w,
+            ..
+        } = self.opts;
+        let over = level_db - t;
+        if 2.0 * over < -w {
+            level_db
+        } else if 2.0 * over.abs() <= w && w > 0.0 {
+            level_db + (1.0 / r - 1.0) * (over + w / 2.0).powi(2) / (2.0 * w)
+        } else {
+            t + over / r
+        }
+    }
+
+    pub fn process_sample(&mut self, x: f32) -> f32 {
+        let level_db = 20.0 * (x.abs() / self.opts.full_scale).max(1e-10).log10();
+        let target = self.static_curve(level_db) - level_db;
+        let coeff = if target < self.reduction_db {
+            self.attack_coeff
+        } else {
+            self.release_coeff
+        };
+        self.reduction_db += coeff * (target - self.reduction_db);
+        x * 10f32.powf((self.reduction_db + self.opts.makeup_db) / 20.0)
+    }
+
+    /// Processes the next chunk of input.
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        input.iter().map(|&x| self.process_sample(x)).collect()
+    }
+
+    pub fn reset(&mut self) {
+        self.reduction_db = 0.0;
+    }
+}
@@ -0,0 +1,231 @@
+//! Endpoint detection for streaming recognition, with rules in the style of
+//! Kaldi's online2 endpointing and sherpa: an utterance ends once enough
+//! trailing silence follows it, or once it grows too long.
+
+use crate::error::KnfError;
+use crate::online::{Computer, OnlineFeature};
+
+/// Fires when the utterance so far satisfies every condition.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EndpointRule {
+    /// Only fire once the utterance has at least one speech frame.
+    pub must_contain_speech: bool,
+    /// Seconds of non-speech at the end of the utterance.
+    pub min_trailing_silence: f32,
+    /// Seconds since the utterance started.
+    pub min_utterance_length: f32,
+}
+
+impl EndpointRule {
+    pub fn new(
+        must_contain_speech: bool,
+        min_trailing_silence: f32,
+        min_utterance_length: f32,
+    ) -> Self {
+        Self {
+            must_contain_speech,
+            min_trailing_silence,
+            min_utterance_length,
+        }
+    }
+
+    /// The rule's durations in frames, rounding up.
+    fn to_frames(&self, frame_shift_ms: f32) -> (bool, usize, usize) {
+        // The tolerance keeps e.g. 1.2 s at 10 ms from becoming 121 frames
+        let frames = |secs: f32| (secs * 1000.0 / frame_shift_ms - 1e-3).ceil().max(0.0) as usize;
+        (
+            self.must_contain_speech,
+            frames(self.min_trailing_silence),
+            frames(self.min_utterance_length),
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct EndpointOptions {
+    /// Should match `FrameOptions::frame_shift_ms` of the evaluated frames.
+    pub frame_shift_ms: f32,
+    /// Checked in order; the first rule that fires ends the utterance. The
+    /// defaults are sherpa's: 2.4 s of silence without speech, 1.2 s of
+    /// silence after speech, or 20 s of audio.
+    pub rules: Vec<EndpointRule>,
+}
+
+impl Default for EndpointOptions {
+    fn default() -> Self {
+        Self {
+            frame_shift_ms: 10.0,
+            rules: vec![
+                EndpointRule::new(false, 2.4, 0.0),
+                EndpointRule::new(true, 1.2, 0.0),
+                EndpointRule::new(false, 0.0, 20.0),
+            ],
+        }
+    }
+}
+
+impl EndpointOptions {
+    pub fn validate(&self) -> Result<(), KnfError> {
+        if self.frame_shift_ms <= 0.0 {
+            return Err(KnfError::invalid("frame_shift_ms must be positive"));
+        }
+        for rule in &self.rules {
+            if rule.min_trailing_silence.is_nan()
+                || rule.min_trailing_silence < 0.0
+                || rule.min_utterance_length.is_nan()
+                || rule.min_utterance_length < 0.0
+            {
+                return Err(KnfError::invalid(format!(
+                    "Endpoint rule durations must be non-negative: {:?}",
+                    rule
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An utterance ended by an [`Endpointer`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Endpoint {
+    /// Index into [`EndpointOptions::rules`] of the rule that fired.
+    pub rule: usize,
+    /// First frame of the utterance.
+    pub start_frame: usize,
+    /// One past the last frame of the utterance; the next one starts here.
+    pub end_frame: usize,
+    /// Non-speech frames at the end of the utterance.
+    pub trailing_silence_frames: usize,
+}
+
+/// Classifies frames as speech by their log energy: a single feature column,
+/// such as MFCC C0 with `use_energy`, or the mean over all columns, such as
+/// log-mel FBANK bins.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnergyVad {
+    pub threshold: f32,
+    pub index: Option<usize>,
+}
+
+impl EnergyVad {
+    pub fn is_speech(&self, frame: &[f32]) -> bool {
+        let energy = match self.index {
+            Some(i) => frame[i],
+            None => frame.iter().sum::<f32>() / frame.len().max(1) as f32,
+        };
+        energy > self.threshold
+    }
+}
+
+/// Tracks per-frame speech decisions and reports endpoints. Frames are
+/// numbered from the last [`reset`](Self::reset); after an endpoint the next
+/// utterance starts with the following frame.
+#[derive(Clone, Debug)]
+pub struct Endpointer {
+    opts: EndpointOptions,
+    // Rules as (must_contain_speech, min_trailing_silence, min_utterance_length) in frames
+    rules: Vec<(bool, usize, usize)>,
+    start_frame: usize,
+    next_frame: usize,
+    contains_speech: bool,
+    trailing_silence: usize,
+}
+
+impl Endpointer {
+    pub fn new(opts: EndpointOptions) -> Result<Self, KnfError> {
+        opts.validate()?;
+        Ok(Self {
+            rules: opts
+                .rules
+                .iter()
+                .map(|r| r.to_frames(opts.frame_shift_ms))
+                .collect(),
+            opts,
+            start_frame: 0,
+            next_frame: 0,
+            contains_speech: false,
+            trailing_silence: 0,
+        })
+    }
+
+    pub fn options(&self) -> &EndpointOptions {
+        &self.opts
+    }
+
+    /// Index of the next frame to evaluate.
+    pub fn num_frames(&self) -> usize {
+        self.next_frame
+    }
+
+    /// First frame of the current utterance.
+    pub fn utterance_start(&self) -> usize {
+        self.start_frame
+    }
+
+    pub fn accept_frame(&mut self, is_speech: bool) -> Option<Endpoint> {
+        if is_speech {
+            self.contains_speech = true;
+            self.trailing_silence = 0;
+        } else {
+            self.trailing_silence += 1;
+        }
+        self.next_frame += 1;
+
+        let length = self.next_frame - self.start_frame;
+        let rule = self
+            .rules
+            .iter()
+            .position(|&(need_speech, trailing, min_length)| {
+                (self.contains_speech || !need_speech)
+                    && self.trailing_silence >= trailing
+                    && length >= min_length
+            })?;
+        let endpoint = Endpoint {
+            rule,
+            start_frame: self.start_frame,
+            end_frame: self.next_frame,
+            trailing_silence_frames: self.trailing_silence,
+        };
+        self.start_frame = self.next_frame;
+        self.contains_speech = false;
+        self.trailing_silence = 0;
+        Some(endpoint)
+    }
+
+    /// Evaluates the frames of `feature` computed since the last call with
+    /// `is_speech`, stopping at the first endpoint so the caller can act on
+    /// it before calling again. Frames released from `feature` before they
+    /// were evaluated are skipped.
+    pub fn update<C, F>(&mut self, feature: &OnlineFeature<C>, mut is_speech: F) -> Option<Endpoint>
+    where
+        C: Computer,
+        F: FnMut(&[f32]) -> bool,
+    {
+        if self.next_frame < feature.first_frame() {
+            self.next_frame = feature.first_frame();
+            self.start_frame = self.start_frame.max(self.next_frame);
+        }
+        while let Some(frame) = feature.get_frame(self.next_frame) {
+            if let Some(endpoint) = self.accept_frame(is_speech(frame)) {
+                return Some(endpoint);
+            }
+        }
+        None
+    }
+
+    /// Starts over at frame 0, e.g. for a new stream or after
+    /// [`OnlineFeature::reset`].
+    pub fn reset(&mut self) {
+        self.start_frame = 0;
+        self.next_frame = 0;
+        self.contains_speech = false;
+        self.trailing_silence = 0;
+    }
+}
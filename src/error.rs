@@ -0,0 +1,73 @@
+use thiserror::Error;
+
+/// Error type of every fallible function in this crate.
+#[derive(Clone, Debug, PartialEq, Error)]
+pub enum KnfError {
+    /// An option value is out of range, inconsistent or unsupported.
+    #[error("invalid options: {0}")]
+    InvalidOptions(String),
+
+    /// An input buffer does not have the expected number of values.
+    #[error("size mismatch for {what}: expected {expected}, got {actual}")]
+    SizeMismatch {
+        what: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+
+    /// Input data is malformed, e.g. ragged frames or a non-integral number
+    /// of interleaved sample frames.
+    #[error("invalid input: {0}")]
+    InvalidInput(String),
+
+    /// A frame does not lie within the available samples.
+    #[error("frame {frame} is out of range of the available samples")]
+    FrameOutOfRange { frame: usize },
+
+    /// Audio was provided at a different sampling rate than configured.
+    #[error("sampling rate mismatch: expected {expected}, got {actual}")]
+    SamplingRateMismatch { expected: f32, actual: f32 },
+
+    /// A file or encoded buffer could not be read or decoded.
+    #[error("failed to read input: {0}")]
+    Io(String),
+
+    /// The GPU backend is unavailable or a GPU operation failed.
+    #[error("GPU backend error: {0}")]
+    Gpu(String),
+
+    /// An audio capture device could not be opened or failed while running.
+    #[error("audio device error: {0}")]
+    AudioDevice(String),
+}
+
+impl KnfError {
+    pub(crate) fn invalid(message: impl Into<String>) -> Self {
+        Self::InvalidOptions(message.into())
+    }
+
+    pub(crate) fn input(message: impl Into<String>) -> Self {
+        Self::InvalidInput(message.into())
+    }
+
+    /// Returns [`KnfError::SizeMismatch`] unless `actual == expected`.
+    pub(crate) fn check_len(
+        what: &'static str,
+        expected: usize,
+        actual: usize,
+    ) -> Result<(), Self> {
+        if actual == expected {
+            Ok(())
+        } else {
+            Err(Self::SizeMismatch {
+                what,
+                expected,
+                actual,
+            })
+        }
+    }
+
+    pub(crate) fn io(error: impl std::fmt::Display) -> Self {
+        Self::Io(error.to_string())
+    }
+}
@@ -0,0 +1,94 @@
+use crate::error::KnfError;
+use safetensors::tensor::{Dtype, SafeTensorError, SafeTensors, TensorView};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Serializes named feature matrices (lists of equal-length frames) to the
+/// safetensors format as `F32` tensors of shape `[num_frames, dim]`.
+///
+/// `metadata` is stored in the header's `__metadata__`, e.g. the options or
+/// fingerprint used to extract the features.
+pub fn features_to_safetensors<F: AsRef<[f32]>>(
+    tensors: &[(&str, &[F])],
+    metadata: Option<HashMap<String, String>>,
+) -> Result<Vec<u8>, KnfError> {
+    with_views(tensors, |views| safetensors::serialize(views, &metadata))
+}
+
+/// Like [`features_to_safetensors`], writing directly to a file.
+pub fn save_safetensors<F: AsRef<[f32]>, P: AsRef<Path>>(
+    path: P,
+    tensors: &[(&str, &[F])],
+    metadata: Option<HashMap<String, String>>,
+) -> Result<(), KnfError> {
+    with_views(tensors, |views| {
+        safetensors::serialize_to_file(views, &metadata, path.as_ref())
+    })
+}
+
+/// Reads the 2-D `F32` tensor `name` back into a list of frames.
+pub fn features_from_safetensors(bytes: &[u8], name: &str) -> Result<Vec<Vec<f32>>, KnfError> {
+    let tensors = SafeTensors::deserialize(bytes).map_err(KnfError::io)?;
+    let view = tensors.tensor(name).map_err(KnfError::io)?;
+    if view.dtype() != Dtype::F32 {
+        return Err(KnfError::input(format!(
+            "Tensor {} is {:?}, expected F32",
+            name,
+            view.dtype()
+        )));
+    }
+    let &[num_frames, dim] = view.shape() else {
+        return Err(KnfError::input(format!(
+            "Tensor {} has shape {:?}, expected [num_frames, dim]",
+            name,
+            view.shape()
+        )));
+    };
+    let values: Vec<f32> = view
+        .data()
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+    Ok((0..num_frames)
+        .map(|t| values[t * dim..(t + 1) * dim].to_vec())
+        .collect())
+}
+
+/// Row-major little-endian bytes and shape of a feature matrix.
+fn to_bytes<F: AsRef<[f32]>>(frames: &[F]) -> Result<(Vec<usize>, Vec<u8>), KnfError> {
+    let dim = frames.first().map_or(0, |f| f.as_ref().len());
+    let mut bytes = Vec::with_capacity(frames.len() * dim * 4);
+    for frame in frames {
+        let frame = frame.as_ref();
+        if frame.len() != dim {
+            return Err(KnfError::SizeMismatch {
+                what: "frame dimension",
+                expected: dim,
+                actual: frame.len(),
+            });
+        }
+        for x in frame {
+            bytes.extend_from_slice(&x.to_le_bytes());
+        }
+    }
+    Ok((vec![frames.len(), dim], bytes))
+}
+
+/// Encodes every matrix and hands the tensor views to `f`.
+fn with_views<F: AsRef<[f32]>, R>(
+    tensors: &[(&str, &[F])],
+    f: impl FnOnce(Vec<(&str, TensorView<'_>)>) -> Result<R, SafeTensorError>,
+) -> Result<R, KnfError> {
+    let buffers = tensors
+        .iter()
+        .map(|(name, frames)| Ok((*name, to_bytes(frames)?)))
+        .collect::<Result<Vec<_>, KnfError>>()?;
+    let views = buffers
+        .iter()
+        .map(|(name, (shape, bytes))| {
+            TensorView::new(Dtype::F32, shape.clone(), bytes).map(|view| (*name, view))
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(KnfError::io)?;
+    f(views).map_err(KnfError::io)
+}
@@ -1,9 +1,32 @@
-use crate::mel::{MelBanks, MelOptions};
+use crate::error::KnfError;
+use crate::mel::{MelBanksCache, MelOptions};
+use crate::online::{Computer, ComputerState};
 use crate::rfft::Rfft;
 use crate::utils::{compute_power_spectrum_inplace, inner_product, log_energy};
 use crate::window::FrameOptions;
+use std::collections::VecDeque;
+
+/// How [`FbankOptions::mel_floor`] keeps the log of mel energies finite.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum MelFloorMode {
+    /// `ln(max(x, floor))`, as Kaldi.
+    #[default]
+    Clamp,
+    /// `ln(x + floor)`, as e.g. lhotse and some TTS frontends.
+    Add,
+}
 
 #[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct FbankOptions {
     pub frame_opts: FrameOptions,
     pub mel_opts: MelOptions,
@@ -13,6 +36,13 @@ pub struct FbankOptions {
     pub energy_floor: f32,
     pub use_log_fbank: bool,
     pub use_power: bool,
+    pub mel_floor: f32,
+    pub mel_floor_mode: MelFloorMode,
+    /// Subtract the mean frame log-energy from the log features, for gain invariance.
+    pub subtract_mean_log_energy: bool,
+    /// Frames in the mean log-energy window; 0 uses all frames since the start
+    /// of the utterance (see [`FbankComputer::reset`]).
+    pub log_energy_norm_window: usize,
 }
 
 impl Default for FbankOptions {
@@ -26,22 +56,60 @@ impl Default for FbankOptions {
             energy_floor: 0.0,
             use_log_fbank: true,
             use_power: true,
+            mel_floor: 1e-20,
+            mel_floor_mode: MelFloorMode::Clamp,
+            subtract_mean_log_energy: false,
+            log_energy_norm_window: 0,
         }
     }
 }
 
+impl FbankOptions {
+    pub fn validate(&self) -> Result<(), KnfError> {
+        self.frame_opts.validate()?;
+        self.mel_opts.validate(&self.frame_opts)
+    }
+
+    /// sherpa-onnx / icefall zipformer transducers (lhotse `Fbank` defaults):
+    /// 80 bins up to Nyquist - 400 Hz, no dither, no energy, `snip_edges = false`.
+    pub fn sherpa_onnx_zipformer() -> Self {
+        let mut opts = Self::default();
+        opts.frame_opts.dither = 0.0;
+        opts.frame_opts.snip_edges = false;
+        opts.mel_opts.num_bins = 80;
+        opts.mel_opts.high_freq = -400.0;
+        opts.use_energy = false;
+        opts
+    }
+
+    /// WeNet models (`torchaudio.compliance.kaldi.fbank` at inference): 80 bins,
+    /// no dither, no energy, `snip_edges = true`.
+    pub fn wenet_80bin() -> Self {
+        let mut opts = Self::default();
+        opts.frame_opts.dither = 0.0;
+        opts.frame_opts.snip_edges = true;
+        opts.mel_opts.num_bins = 80;
+        opts.use_energy = false;
+        opts
+    }
+}
+
 pub struct FbankComputer {
     pub opts: FbankOptions,
     rfft: Rfft,
-    mel_banks: MelBanks,
+    mel_banks: MelBanksCache,
     log_energy_floor: f32,
+    energy_history: VecDeque<f32>,
+    energy_sum: f64,
+    energy_count: usize,
 }
 
 impl FbankComputer {
-    pub fn new(opts: FbankOptions) -> Result<Self, String> {
+    pub fn new(opts: FbankOptions) -> Result<Self, KnfError> {
+        opts.validate()?;
         let n_fft = opts.frame_opts.padded_window_size();
         let rfft = Rfft::new(n_fft, false);
-        let mel_banks = MelBanks::new(&opts.mel_opts, &opts.frame_opts, 1.0)?;
+        let mel_banks = MelBanksCache::new(&opts.mel_opts, &opts.frame_opts)?;
 
         let log_energy_floor = if opts.energy_floor > 0.0 {
             opts.energy_floor.ln()
@@ -54,9 +122,34 @@ impl FbankComputer {
             rfft,
             mel_banks,
             log_energy_floor,
+            energy_history: VecDeque::new(),
+            energy_sum: 0.0,
+            energy_count: 0,
         })
     }
 
+    /// Clears the mean log-energy statistics before a new utterance.
+    pub fn reset(&mut self) {
+        self.energy_history.clear();
+        self.energy_sum = 0.0;
+        self.energy_count = 0;
+    }
+
+    fn update_mean_log_energy(&mut self, frame_log_energy: f32) -> f32 {
+        self.energy_sum += frame_log_energy as f64;
+        self.energy_count += 1;
+        let window = self.opts.log_energy_norm_window;
+        if window > 0 {
+            self.energy_history.push_back(frame_log_energy);
+            if self.energy_history.len() > window {
+                let old = self.energy_history.pop_front().unwrap();
+                self.energy_sum -= old as f64;
+                self.energy_count -= 1;
+            }
+        }
+        (self.energy_sum / self.energy_count as f64) as f32
+    }
+
     pub fn dim(&self) -> usize {
         self.opts.mel_opts.num_bins
             + if self.opts.use_energy && !self.opts.htk_compat {
@@ -66,22 +159,34 @@ impl FbankComputer {
             }
     }
 
+    /// Builds the mel banks for `vtln_warp`; see [`MelBanksCache::prepare`].
+    pub fn prepare_vtln_warp(&mut self, vtln_warp: f32) -> Result<(), KnfError> {
+        self.mel_banks.prepare(vtln_warp).map(|_| ())
+    }
+
+    /// Computes one frame with the mel banks warped by `vtln_warp` (1.0 for
+    /// none). Warps not set up with [`prepare_vtln_warp`](Self::prepare_vtln_warp)
+    /// use the unwarped banks.
     pub fn compute(
         &mut self,
         mut signal_raw_log_energy: f32,
-        _vtln_warp: f32,
+        vtln_warp: f32,
         signal_frame: &mut [f32],
         feature: &mut [f32],
     ) {
-        // vtln_warp handled in mel creation usually, but dynamic warp would require regenerating banks or more complex logic.
-        // This port assumes static banks for now as per the simplified C code structure for `mel_banks_create` call in `new`.
-
         // 1. Calculate energy if needed and not raw
         if self.opts.use_energy && !self.opts.raw_energy {
             let energy = inner_product(signal_frame, signal_frame);
             signal_raw_log_energy = log_energy(energy);
         }
 
+        let mean_log_energy = if self.opts.subtract_mean_log_energy {
+            let energy = inner_product(signal_frame, signal_frame);
+            Some(self.update_mean_log_energy(log_energy(energy)))
+        } else {
+            None
+        };
+
         // 2. FFT
         self.rfft.compute(signal_frame);
 
@@ -102,20 +207,24 @@ impl FbankComputer {
         } else {
             0
         };
-        let fft_bins = self.mel_banks.num_fft_bins + 1; // use only the computed power bins (N/2 + 1)
-        self.mel_banks
-            .compute(&signal_frame[..fft_bins], &mut feature[mel_offset..]);
+        let mel_banks = self.mel_banks.get(vtln_warp);
+        let fft_bins = mel_banks.num_fft_bins + 1; // use only the computed power bins (N/2 + 1)
+        mel_banks.compute(&signal_frame[..fft_bins], &mut feature[mel_offset..]);
 
         // 6. Log
         if self.opts.use_log_fbank {
+            let floor = self.opts.mel_floor;
             for x in feature[mel_offset..].iter_mut() {
-                *x = log_energy(*x);
+                *x = match self.opts.mel_floor_mode {
+                    MelFloorMode::Clamp => x.max(floor).ln(),
+                    MelFloorMode::Add => (*x + floor).ln(),
+                };
             }
         }
 
         // 7. Energy appending
         if self.opts.use_energy {
-            if self.opts.energy_floor > 0.0 && signal_raw_log_energy < self.log_energy_floor {
+            if signal_raw_log_energy < self.log_energy_floor {
                 signal_raw_log_energy = self.log_energy_floor;
             }
             let energy_index = if self.opts.htk_compat {
@@ -125,5 +234,74 @@ impl FbankComputer {
             };
             feature[energy_index] = signal_raw_log_energy;
         }
+
+        // 8. Mean log-energy subtraction
+        if let Some(mean) = mean_log_energy {
+            if self.opts.use_log_fbank {
+                for x in feature[mel_offset..mel_offset + self.opts.mel_opts.num_bins].iter_mut() {
+                    *x -= mean;
+                }
+            }
+            if self.opts.use_energy {
+                let energy_index = if self.opts.htk_compat {
+                    self.opts.mel_opts.num_bins
+                } else {
+                    0
+                };
+                feature[energy_index] -= mean;
+            }
+        }
+    }
+}
+
+impl Computer for FbankComputer {
+    fn dim(&self) -> usize {
+        FbankComputer::dim(self)
+    }
+
+    fn frame_opts(&self) -> &FrameOptions {
+        &self.opts.frame_opts
+    }
+
+    fn need_raw_energy(&self) -> bool {
+        self.opts.use_energy && self.opts.raw_energy
+    }
+
+    fn compute(
+        &mut self,
+        raw_log_energy: f32,
+        vtln_warp: f32,
+        window: &mut [f32],
+        feature: &mut [f32],
+    ) {
+        FbankComputer::compute(self, raw_log_energy, vtln_warp, window, feature)
+    }
+
+    fn prepare_vtln_warp(&mut self, vtln_warp: f32) -> Result<(), KnfError> {
+        FbankComputer::prepare_vtln_warp(self, vtln_warp)
+    }
+
+    fn reset(&mut self) {
+        FbankComputer::reset(self)
+    }
+
+    /// `[sum, count, history...]` of the mean log-energy statistics.
+    fn save_state(&self) -> ComputerState {
+        let mut values = vec![self.energy_sum, self.energy_count as f64];
+        values.extend(self.energy_history.iter().map(|&e| e as f64));
+        ComputerState {
+            values,
+            children: Vec::new(),
+        }
+    }
+
+    fn restore_state(&mut self, state: &ComputerState) {
+        self.reset();
+        if let [sum, count, history @ ..] = state.values.as_slice() {
+            self.energy_sum = *sum;
+            self.energy_count = *count as usize;
+            self.energy_history
+                .extend(history.iter().map(|&e| e as f32));
+        }
     }
 }
@@ -0,0 +1,585 @@
+//! C API mirroring kaldi-native-fbank's `knf_*` functions; see `include/knf.h`.
+//!
+//! Functions that can fail return a negative value or `NULL` and record a
+//! message retrievable with [`knf_last_error`]. Panics never unwind into the
+//! caller: they are caught and reported the same way, after which the handle
+//! involved should be destroyed.
+
+use crate::error::KnfError;
+use crate::fbank::{FbankComputer, FbankOptions};
+use crate::mel::MelOptions;
+use crate::mfcc::{MfccComputer, MfccOptions};
+use crate::online::{Computer, FeatureComputer, OnlineFeature};
+use crate::whisper::{WhisperComputer, WhisperOptions};
+use crate::window::FrameOptions;
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = message);
+}
+
+/// Runs `f`, turning a panic into [`knf_last_error`] and `on_panic`.
+fn guard<T>(on_panic: T, f: impl FnOnce() -> T) -> T {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        set_last_error(format!("panic: {}", message));
+        on_panic
+    })
+}
+
+/// Message of the last failed call on this thread, or an empty string. Valid
+/// until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn knf_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ptr())
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct KnfFrameOptions {
+    pub samp_freq: f32,
+    pub frame_shift_ms: f32,
+    pub frame_length_ms: f32,
+    pub dither: f32,
+    pub preemph_coeff: f32,
+    pub remove_dc_offset: bool,
+    /// NUL-terminated window name; `NULL` keeps the default.
+    pub window_type: *const c_char,
+    pub round_to_power_of_two: bool,
+    pub blackman_coeff: f32,
+    pub snip_edges: bool,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct KnfMelOptions {
+    pub num_bins: i32,
+    pub low_freq: f32,
+    pub high_freq: f32,
+    pub vtln_low: f32,
+    pub vtln_high: f32,
+    pub debug_mel: bool,
+    pub htk_mode: bool,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct KnfFbankOptions {
+    pub frame_opts: KnfFrameOptions,
+    pub mel_opts: KnfMelOptions,
+    pub use_energy: bool,
+    pub raw_energy: bool,
+    pub htk_compat: bool,
+    pub energy_floor: f32,
+    pub use_log_fbank: bool,
+    pub use_power: bool,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct KnfMfccOptions {
+    pub frame_opts: KnfFrameOptions,
+    pub mel_opts: KnfMelOptions,
+    pub num_ceps: i32,
+    pub use_energy: bool,
+    pub energy_floor: f32,
+    pub raw_energy: bool,
+    pub cepstral_lifter: f32,
+    pub htk_compat: bool,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct KnfWhisperOptions {
+    pub frame_opts: KnfFrameOptions,
+    pub dim: i32,
+}
+
+impl From<&FrameOptions> for KnfFrameOptions {
+    fn from(o: &FrameOptions) -> Self {
+        Self {
+            samp_freq: o.samp_freq,
+            frame_shift_ms: o.frame_shift_ms,
+            frame_length_ms: o.frame_length_ms,
+            dither: o.dither,
+            preemph_coeff: o.preemph_coeff,
+            remove_dc_offset: o.remove_dc_offset,
+            window_type: ptr::null(),
+            round_to_power_of_two: o.round_to_power_of_two,
+            blackman_coeff: o.blackman_coeff,
+            snip_edges: o.snip_edges,
+        }
+    }
+}
+
+impl From<&MelOptions> for KnfMelOptions {
+    fn from(o: &MelOptions) -> Self {
+        Self {
+            num_bins: o.num_bins as i32,
+            low_freq: o.low_freq,
+            high_freq: o.high_freq,
+            vtln_low: o.vtln_low,
+            vtln_high: o.vtln_high,
+            debug_mel: o.debug_mel,
+            htk_mode: o.htk_mode,
+        }
+    }
+}
+
+fn to_usize(value: i32, name: &str) -> Result<usize, KnfError> {
+    usize::try_from(value)
+        .map_err(|_| KnfError::InvalidOptions(format!("{} must be non-negative", name)))
+}
+
+/// # Safety
+/// `window_type` must be `NULL` or a valid NUL-terminated string.
+unsafe fn frame_options(
+    c: &KnfFrameOptions,
+    defaults: FrameOptions,
+) -> Result<FrameOptions, KnfError> {
+    let window_type = if c.window_type.is_null() {
+        defaults.window_type
+    } else {
+        CStr::from_ptr(c.window_type)
+            .to_str()
+            .map_err(|e| KnfError::InvalidOptions(e.to_string()))?
+            .parse()?
+    };
+    Ok(FrameOptions {
+        samp_freq: c.samp_freq,
+        frame_shift_ms: c.frame_shift_ms,
+        frame_length_ms: c.frame_length_ms,
+        dither: c.dither,
+        preemph_coeff: c.preemph_coeff,
+        remove_dc_offset: c.remove_dc_offset,
+        window_type,
+        round_to_power_of_two: c.round_to_power_of_two,
+        blackman_coeff: c.blackman_coeff,
+        snip_edges: c.snip_edges,
+        ..defaults
+    })
+}
+
+fn mel_options(c: &KnfMelOptions) -> Result<MelOptions, KnfError> {
+    Ok(MelOptions {
+        num_bins: to_usize(c.num_bins, "num_bins")?,
+        low_freq: c.low_freq,
+        high_freq: c.high_freq,
+        vtln_low: c.vtln_low,
+        vtln_high: c.vtln_high,
+        debug_mel: c.debug_mel,
+        htk_mode: c.htk_mode,
+        ..Default::default()
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn knf_fbank_options_default() -> KnfFbankOptions {
+    let o = FbankOptions::default();
+    KnfFbankOptions {
+        frame_opts: (&o.frame_opts).into(),
+        mel_opts: (&o.mel_opts).into(),
+        use_energy: o.use_energy,
+        raw_energy: o.raw_energy,
+        htk_compat: o.htk_compat,
+        energy_floor: o.energy_floor,
+        use_log_fbank: o.use_log_fbank,
+        use_power: o.use_power,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn knf_mfcc_options_default() -> KnfMfccOptions {
+    let o = MfccOptions::default();
+    KnfMfccOptions {
+        frame_opts: (&o.frame_opts).into(),
+        mel_opts: (&o.mel_opts).into(),
+        num_ceps: o.num_ceps as i32,
+        use_energy: o.use_energy,
+        energy_floor: o.energy_floor,
+        raw_energy: o.raw_energy,
+        cepstral_lifter: o.cepstral_lifter,
+        htk_compat: o.htk_compat,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn knf_whisper_options_default() -> KnfWhisperOptions {
+    let o = WhisperOptions::default();
+    KnfWhisperOptions {
+        frame_opts: (&o.frame_opts).into(),
+        dim: o.dim as i32,
+    }
+}
+
+/// # Safety
+/// `opts` must be `NULL` or point to valid options.
+unsafe fn fbank_computer(opts: *const KnfFbankOptions) -> Result<FeatureComputer, KnfError> {
+    let c = opts
+        .as_ref()
+        .ok_or(KnfError::InvalidOptions("opts is NULL".to_string()))?;
+    let defaults = FbankOptions::default();
+    let opts = FbankOptions {
+        frame_opts: frame_options(&c.frame_opts, defaults.frame_opts.clone())?,
+        mel_opts: mel_options(&c.mel_opts)?,
+        use_energy: c.use_energy,
+        raw_energy: c.raw_energy,
+        htk_compat: c.htk_compat,
+        energy_floor: c.energy_floor,
+        use_log_fbank: c.use_log_fbank,
+        use_power: c.use_power,
+        ..defaults
+    };
+    Ok(FeatureComputer::Fbank(FbankComputer::new(opts)?))
+}
+
+/// # Safety
+/// `opts` must be `NULL` or point to valid options.
+unsafe fn mfcc_computer(opts: *const KnfMfccOptions) -> Result<FeatureComputer, KnfError> {
+    let c = opts
+        .as_ref()
+        .ok_or(KnfError::InvalidOptions("opts is NULL".to_string()))?;
+    let defaults = MfccOptions::default();
+    let opts = MfccOptions {
+        frame_opts: frame_options(&c.frame_opts, defaults.frame_opts.clone())?,
+        mel_opts: mel_options(&c.mel_opts)?,
+        num_ceps: to_usize(c.num_ceps, "num_ceps")?,
+        use_energy: c.use_energy,
+        energy_floor: c.energy_floor,
+        raw_energy: c.raw_energy,
+        cepstral_lifter: c.cepstral_lifter,
+        htk_compat: c.htk_compat,
+        ..defaults
+    };
+    Ok(FeatureComputer::Mfcc(MfccComputer::new(opts)?))
+}
+
+/// # Safety
+/// `opts` must be `NULL` or point to valid options.
+unsafe fn whisper_computer(opts: *const KnfWhisperOptions) -> Result<FeatureComputer, KnfError> {
+    let c = opts
+        .as_ref()
+        .ok_or(KnfError::InvalidOptions("opts is NULL".to_string()))?;
+    let defaults = WhisperOptions::default();
+    let opts = WhisperOptions {
+        frame_opts: frame_options(&c.frame_opts, defaults.frame_opts)?,
+        dim: to_usize(c.dim, "dim")?,
+    };
+    Ok(FeatureComputer::Whisper(WhisperComputer::new(opts)?))
+}
+
+/// Opaque streaming feature extractor.
+pub struct KnfOnlineFeature {
+    feature: OnlineFeature,
+    dim: usize,
+}
+
+fn into_handle(computer: Result<FeatureComputer, KnfError>) -> *mut KnfOnlineFeature {
+    match computer {
+        Ok(computer) => {
+            let dim = computer.dim();
+            Box::into_raw(Box::new(KnfOnlineFeature {
+                feature: OnlineFeature::new(computer),
+                dim,
+            }))
+        }
+        Err(e) => {
+            set_last_error(e.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Creates an online FBANK extractor; returns `NULL` on invalid options.
+///
+/// # Safety
+/// `opts` must be `NULL` or point to valid options.
+#[no_mangle]
+pub unsafe extern "C" fn knf_online_fbank_create(
+    opts: *const KnfFbankOptions,
+) -> *mut KnfOnlineFeature {
+    guard(ptr::null_mut(), || into_handle(fbank_computer(opts)))
+}
+
+/// Creates an online MFCC extractor; returns `NULL` on invalid options.
+///
+/// # Safety
+/// `opts` must be `NULL` or point to valid options.
+#[no_mangle]
+pub unsafe extern "C" fn knf_online_mfcc_create(
+    opts: *const KnfMfccOptions,
+) -> *mut KnfOnlineFeature {
+    guard(ptr::null_mut(), || into_handle(mfcc_computer(opts)))
+}
+
+/// Creates an online extractor of Whisper's linear mel power energies, like
+/// the C reference; returns `NULL` on invalid options. Apply Whisper's
+/// `log10`/clamp/normalize step to the frames to get its log-mel input.
+///
+/// # Safety
+/// `opts` must be `NULL` or point to valid options.
+#[no_mangle]
+pub unsafe extern "C" fn knf_online_whisper_fbank_create(
+    opts: *const KnfWhisperOptions,
+) -> *mut KnfOnlineFeature {
+    guard(ptr::null_mut(), || into_handle(whisper_computer(opts)))
+}
+
+/// # Safety
+/// `feature` must be `NULL` or a handle from a `knf_online_*_create` function
+/// that has not been destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn knf_online_feature_destroy(feature: *mut KnfOnlineFeature) {
+    if !feature.is_null() {
+        guard((), || drop(Box::from_raw(feature)));
+    }
+}
+
+/// Appends `n` samples; returns 0, or -1 on a sampling rate mismatch or error.
+///
+/// # Safety
+/// `feature` must be a live handle and `samples` must point to `n` floats.
+#[no_mangle]
+pub unsafe extern "C" fn knf_online_feature_accept_waveform(
+    feature: *mut KnfOnlineFeature,
+    sampling_rate: f32,
+    samples: *const f32,
+    n: i32,
+) -> i32 {
+    guard(-1, || {
+        let Some(f) = feature.as_mut() else {
+            set_last_error("feature is NULL".to_string());
+            return -1;
+        };
+        let samples = if n > 0 {
+            std::slice::from_raw_parts(samples, n as usize)
+        } else {
+            &[]
+        };
+        match f.feature.accept_waveform(sampling_rate, samples) {
+            Ok(()) => 0,
+            Err(e) => {
+                set_last_error(e.to_string());
+                -1
+            }
+        }
+    })
+}
+
+/// # Safety
+/// `feature` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn knf_online_feature_input_finished(feature: *mut KnfOnlineFeature) {
+    if let Some(f) = feature.as_mut() {
+        guard((), || f.feature.input_finished());
+    }
+}
+
+/// Clears all audio and frames so the handle can be reused for a new utterance.
+///
+/// # Safety
+/// `feature` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn knf_online_feature_reset(feature: *mut KnfOnlineFeature) {
+    if let Some(f) = feature.as_mut() {
+        guard((), || f.feature.reset());
+    }
+}
+
+/// # Safety
+/// `feature` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn knf_online_feature_dim(feature: *const KnfOnlineFeature) -> i32 {
+    guard(-1, || feature.as_ref().map_or(0, |f| f.dim as i32))
+}
+
+/// # Safety
+/// `feature` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn knf_online_feature_num_frames_ready(
+    feature: *const KnfOnlineFeature,
+) -> i32 {
+    guard(-1, || {
+        feature
+            .as_ref()
+            .map_or(0, |f| f.feature.num_frames_ready() as i32)
+    })
+}
+
+/// Whether `frame` is the final frame (only known after `input_finished`).
+///
+/// # Safety
+/// `feature` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn knf_online_feature_is_last_frame(
+    feature: *const KnfOnlineFeature,
+    frame: i32,
+) -> bool {
+    guard(false, || {
+        feature.as_ref().is_some_and(|f| {
+            f.feature.is_input_finished()
+                && frame >= 0
+                && frame as usize + 1 == f.feature.num_frames_ready()
+        })
+    })
+}
+
+/// Pointer to the `dim` values of `frame`, or `NULL` if it is not ready. Valid
+/// until the next call that modifies `feature`.
+///
+/// # Safety
+/// `feature` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn knf_online_feature_get_frame(
+    feature: *const KnfOnlineFeature,
+    frame: i32,
+) -> *const f32 {
+    guard(None, || {
+        let f = feature.as_ref()?;
+        let t = usize::try_from(frame).ok()?;
+        f.feature.get_frame(t).map(|v| v.as_ptr())
+    })
+    .unwrap_or(ptr::null())
+}
+
+/// Computes features of a whole waveform into a newly allocated row-major
+/// `num_frames * dim` matrix, to be released with [`knf_features_free`].
+///
+/// # Safety
+/// `samples` must point to `n` floats and `out`, `num_frames`, `dim` must be
+/// valid for writes.
+unsafe fn compute_all(
+    computer: impl FnOnce() -> Result<FeatureComputer, KnfError>,
+    sampling_rate: f32,
+    samples: *const f32,
+    n: i32,
+    out: *mut *mut f32,
+    num_frames: *mut i32,
+    dim: *mut i32,
+) -> i32 {
+    if out.is_null() || num_frames.is_null() || dim.is_null() {
+        set_last_error("output pointers must not be NULL".to_string());
+        return -1;
+    }
+    guard(-1, || {
+        let handle = into_handle(computer());
+        if handle.is_null() {
+            return -1;
+        }
+        let mut f = Box::from_raw(handle);
+        if knf_online_feature_accept_waveform(&mut *f, sampling_rate, samples, n) != 0 {
+            return -1;
+        }
+        f.feature.input_finished();
+
+        let data: Box<[f32]> = f.feature.features.iter().flatten().copied().collect();
+        *num_frames = f.feature.num_frames_ready() as i32;
+        *dim = f.dim as i32;
+        *out = Box::into_raw(data) as *mut f32;
+        0
+    })
+}
+
+/// One-shot FBANK extraction; returns 0, or -1 on error.
+///
+/// # Safety
+/// See [`knf_online_fbank_create`]; `samples` must point to `n` floats and
+/// `out`, `num_frames`, `dim` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn knf_compute_fbank(
+    opts: *const KnfFbankOptions,
+    sampling_rate: f32,
+    samples: *const f32,
+    n: i32,
+    out: *mut *mut f32,
+    num_frames: *mut i32,
+    dim: *mut i32,
+) -> i32 {
+    compute_all(
+        || fbank_computer(opts),
+        sampling_rate,
+        samples,
+        n,
+        out,
+        num_frames,
+        dim,
+    )
+}
+
+/// One-shot MFCC extraction; returns 0, or -1 on error.
+///
+/// # Safety
+/// See [`knf_compute_fbank`].
+#[no_mangle]
+pub unsafe extern "C" fn knf_compute_mfcc(
+    opts: *const KnfMfccOptions,
+    sampling_rate: f32,
+    samples: *const f32,
+    n: i32,
+    out: *mut *mut f32,
+    num_frames: *mut i32,
+    dim: *mut i32,
+) -> i32 {
+    compute_all(
+        || mfcc_computer(opts),
+        sampling_rate,
+        samples,
+        n,
+        out,
+        num_frames,
+        dim,
+    )
+}
+
+/// One-shot extraction of Whisper's linear mel power energies (see
+/// [`knf_online_whisper_fbank_create`]); returns 0, or -1 on error.
+///
+/// # Safety
+/// See [`knf_compute_fbank`].
+#[no_mangle]
+pub unsafe extern "C" fn knf_compute_whisper_fbank(
+    opts: *const KnfWhisperOptions,
+    sampling_rate: f32,
+    samples: *const f32,
+    n: i32,
+    out: *mut *mut f32,
+    num_frames: *mut i32,
+    dim: *mut i32,
+) -> i32 {
+    compute_all(
+        || whisper_computer(opts),
+        sampling_rate,
+        samples,
+        n,
+        out,
+        num_frames,
+        dim,
+    )
+}
+
+/// Releases a matrix from a `knf_compute_*` function.
+///
+/// # Safety
+/// `data` must be `NULL` or a matrix of `num_frames * dim` values returned by
+/// a `knf_compute_*` function, freed only once.
+#[no_mangle]
+pub unsafe extern "C" fn knf_features_free(data: *mut f32, num_frames: i32, dim: i32) {
+    if !data.is_null() {
+        let len = num_frames.max(0) as usize * dim.max(0) as usize;
+        guard((), || {
+            drop(Box::from_raw(ptr::slice_from_raw_parts_mut(data, len)))
+        });
+    }
+}
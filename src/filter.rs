@@ -0,0 +1,499 @@
+//! Linear filters applied to waveforms before framing, e.g. for band-limiting
+//! or equalization.
+
+use crate::convolve::multiply_accumulate;
+use crate::error::KnfError;
+use crate::rfft::Rfft;
+
+/// Filters `signal` with the FIR filter `taps` like `scipy.signal.lfilter(taps,
+/// 1, signal)`; the output has the same length as the input.
+pub fn fir_filter(taps: &[f32], signal: &[f32]) -> Result<Vec<f32>, KnfError> {
+    Ok(FirFilter::new(taps)?.process(signal))
+}
+
+/// Streaming FIR filter by FFT overlap-save. Taps come from any design
+/// method; `taps[0]` applies to the current sample.
+///
+/// Every call returns one output sample per input sample without added
+/// latency, and chunking does not change the output.
+pub struct FirFilter {
+    taps: Vec<f32>,
+    // Inputs preceding the next chunk, `taps.len() - 1` of them
+    history: Vec<f32>,
+    fft: Rfft,
+    ifft: Rfft,
+    // Packed spectrum of the taps, scaled for the inverse FFT
+    spectrum: Vec<f32>,
+    buf: Vec<f32>,
+    acc: Vec<f32>,
+}
+
+impl FirFilter {
+    pub fn new(taps: &[f32]) -> Result<Self, KnfError> {
+        if taps.is_empty() {
+            return Err(KnfError::invalid("FIR filter needs at least one tap"));
+        }
+        let n = (4 * taps.len()).next_power_of_two().max(64);
+        let mut fft = Rfft::new(n, false);
+        let mut spectrum = vec![0.0; n];
+        for (s, t) in spectrum.iter_mut().zip(taps) {
+            *s = t / n as f32;
+        }
+        fft.compute(&mut spectrum);
+        Ok(Self {
+            taps: taps.to_vec(),
+            history: vec![0.0; taps.len() - 1],
+            fft,
+            ifft: Rfft::new(n, true),
+            spectrum,
+            buf: vec![0.0; n],
+            acc: vec![0.0; n],
+        })
+    }
+
+    pub fn taps(&self) -> &[f32] {
+        &self.taps
+    }
+
+    /// Filters the next chunk of input.
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        let n = self.buf.len();
+        let m = self.history.len();
+        let mut out = Vec::with_capacity(input.len());
+        for chunk in input.chunks(n - m) {
+            let r = chunk.len();
+            self.buf[..m].copy_from_slice(&self.history);
+            self.buf[m..m + r].copy_from_slice(chunk);
+            self.buf[m + r..].iter_mut().for_each(|x| *x = 0.0);
+            // The last inputs become the history of the next chunk
+            self.history.copy_from_slice(&self.buf[r..r + m]);
+
+            self.fft.compute(&mut self.buf);
+            self.acc.iter_mut().for_each(|x| *x = 0.0);
+            multiply_accumulate(&self.buf, &self.spectrum, &mut self.acc);
+            self.ifft.compute(&mut self.acc);
+            // The first `m` outputs wrapped around and are discarded
+            out.extend_from_slice(&self.acc[m..m + r]);
+        }
+        out
+    }
+
+    /// Forgets past input, as if the signal had been silent.
+    pub fn reset(&mut self) {
+        self.history.iter_mut().for_each(|x| *x = 0.0);
+    }
+}
+
+/// Response shape of a [`Biquad`], after the Audio EQ Cookbook (R. Bristow-Johnson).
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BiquadType {
+    LowPass,
+    HighPass,
+    /// Band-pass with 0 dB gain at the center frequency.
+    BandPass,
+    Notch,
+    /// Boosts or cuts frequencies below the corner by `gain_db`.
+    LowShelf {
+        gain_db: f32,
+    },
+    /// Boosts or cuts frequencies above the corner by `gain_db`.
+    HighShelf {
+        gain_db: f32,
+    },
+}
+
+/// Second-order IIR section in transposed direct form II, with streaming state.
+///
+/// Coefficients and state are kept in `f64` so low corner frequencies (e.g.
+/// rumble filters at 16 kHz) stay stable.
+#[derive(Clone, Debug)]
+pub struct Biquad {
+    b: [f64; 3],
+    // a1, a2, normalized by a0
+    a: [f64; 2],
+    state: [f64; 2],
+}
+
+impl Biquad {
+    /// Designs a section with corner (or center) frequency `freq` Hz and
+    /// quality factor `q` (`1 / sqrt(2)` gives a Butterworth response).
+    pub fn new(kind: BiquadType, samp_freq: f32, freq: f32, q: f32) -> Result<Self, KnfError> {
+        if !(freq > 0.0 && freq < samp_freq / 2.0) {
+            return Err(KnfError::invalid(format!(
+                "Filter frequency {} Hz must be in (0, {}) Hz",
+                freq,
+                samp_freq / 2.0
+            )));
+        }
+        if !(q > 0.0 && q.is_finite()) {
+            return Err(KnfError::invalid("Filter Q must be positive"));
+        }
+        let w0 = 2.0 * std::f64::consts::PI * freq as f64 / samp_freq as f64;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * q as f64);
+
+        let (b, a) = match kind {
+            BiquadType::LowPass => (
+                [(1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0],
+                [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+            ),
+            BiquadType::HighPass => (
+                [(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0],
+                [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+            ),
+            BiquadType::BandPass => ([alpha, 0.0, -alpha], [1.0 + alpha, -2.0 * cos, 1.0 - alpha]),
+            BiquadType::Notch => (
+                [1.0, -2.0 * cos, 1.0],
+                [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+            ),
+            BiquadType::LowShelf { gain_db } | BiquadType::HighShelf { gain_db } => {
+                let amp = 10f64.powf(gain_db as f64 / 40.0);
+                let beta = 2.0 * amp.sqrt() * alpha;
+                let (p, m) = (amp + 1.0, amp - 1.0);
+                if matches!(kind, BiquadType::LowShelf { .. }) {
+                    (
+                        [
+                            amp * (p - m * cos + beta),
+                            2.0 * amp * (m - p * cos),
+                            amp * (p - m * cos - beta),
+                        ],
+                        [p + m * cos + beta, -2.0 * (m + p * cos), p + m * cos - beta],
+                    )
+                } else {
+                    (
+                        [
+                            amp * (p + m * cos + beta),
+                            -2.0 * amp * (m + p * cos),
+                            amp * (p + m * cos - beta),
+                        ],
+                        [p - m * cos + beta, 2.0 * (m - p * cos), p - m * cos - beta],
+                    )
+                }
+            }
+        };
+        Self::from_coefficients(b, a)
+    }
+
+    /// Section with transfer function `(b0 + b1 z^-1 + b2 z^-2) / (a0 + a1 z^-1 + a2 z^-2)`.
+    pub fn from_coefficients(b: [f64; 3], a: [f64; 3]) -> Result<Self, KnfError> {
+        if a[0] == 0.0 {
+            return Err(KnfError::invalid("Biquad a0 must be non-zero"));
+        }
+        Ok(Self {
+            b: b.map(|x| x / a[0]),
+            a: [a[1] / a[0], a[2] / a[0]],
+            state: [0.0; 2],
+        })
+    }
+
+    pub fn process_sample(&mut self, x: f32) -> f32 {
+        let x = x as f64;
+        let y = self.b[0] * x + self.state[0];
+        self.state[0] = self.b[1] * x - self.a[0] * y + self.state[1];
+        self.state[1] = self.b[2] * x - self.a[1] * y;
+        y as f32
+    }
+
+    /// Filters the next chunk of input.
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        input.iter().map(|&x| self.process_sample(x)).collect()
+    }
+
+    /// Gain in dB at `freq` Hz.
+    pub fn magnitude_db(&self, freq: f32, samp_freq: f32) -> f32 {
+        let w = 2.0 * std::f64::consts::PI * freq as f64 / samp_freq as f64;
+        // |c0 + c1 e^-jw + c2 e^-2jw|^2
+        let power = |c: [f64; 3]| {
+            let re = c[0] + c[1] * w.cos() + c[2] * (2.0 * w).cos();
+            let im = c[1] * w.sin() + c[2] * (2.0 * w).sin();
+            re * re + im * im
+        };
+        let a = [1.0, self.a[0], self.a[1]];
+        (10.0 * (power(self.b) / power(a)).log10()) as f32
+    }
+
+    pub fn reset(&mut self) {
+        self.state = [0.0; 2];
+    }
+}
+
+/// Digital section for the analog `(b0 s^2 + b1 s + b2) / (a0 s^2 + a1 s + a2)`
+/// by the bilinear transform.
+fn bilinear(b: [f64; 3], a: [f64; 3], samp_freq: f64) -> Result<Biquad, KnfError> {
+    let k = 2.0 * samp_freq;
+    let map = |c: [f64; 3]| {
+        [
+            c[0] * k * k + c[1] * k + c[2],
+            2.0 * (c[2] - c[0] * k * k),
+            c[0] * k * k - c[1] * k + c[2],
+        ]
+    };
+    Biquad::from_coefficients(map(b), map(a))
+}
+
+/// Filters `wave` with the A-weighting of [`BiquadCascade::a_weighting`].
+pub fn a_weight(wave: &[f32], samp_freq: f32) -> Result<Vec<f32>, KnfError> {
+    Ok(BiquadCascade::a_weighting(samp_freq)?.process(wave))
+}
+
+/// Biquads applied one after another, for higher-order filters.
+#[derive(Clone, Debug, Default)]
+pub struct BiquadCascade {
+    pub stages: Vec<Biquad>,
+}
+
+impl BiquadCascade {
+    pub fn new(stages: Vec<Biquad>) -> Self {
+        Self { stages }
+    }
+
+    /// Butterworth low-pass or high-pass of even `order` as `order / 2`
+    /// sections; other types are rejected.
+    pub fn butterworth(
+        kind: BiquadType,
+        order: usize,
+        samp_freq: f32,
+        freq: f32,
+    ) -> Result<Self, KnfError> {
+        if !matches!(kind, BiquadType::LowPass | BiquadType::HighPass) {
+            return Err(KnfError::invalid(
+                "Butterworth cascades are low-pass or high-pass",
+            ));
+        }
+        if order == 0 || order % 2 == 1 {
+            return Err(KnfError::invalid(format!(
+                "Butterworth order must be even and positive, got {}",
+                order
+            )));
+        }
+        let stages = (0..order / 2)
+            .map(|k| {
+                let theta = (2 * k + 1) as f32 * std::f32::consts::PI / (2 * order) as f32;
+                Biquad::new(kind, samp_freq, freq, 1.0 / (2.0 * theta.cos()))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { stages })
+    }
+
+    /// 300-3400 Hz band of narrowband telephony, as 4th-order Butterworth
+    /// high-pass and low-pass filters.
+    pub fn telephone_band(samp_freq: f32) -> Result<Self, KnfError> {
+        let mut cascade = Self::butterworth(BiquadType::HighPass, 4, samp_freq, 300.0)?;
+        cascade
+            .stages
+            .extend(Self::butterworth(BiquadType::LowPass, 4, samp_freq, 3400.0)?.stages);
+        Ok(cascade)
+    }
+
+    /// K-weighting of ITU-R BS.1770 (a high shelf modelling the head, then
+    /// the RLB high-pass), redesigned for `samp_freq` as libebur128 does; at
+    /// 48 kHz the coefficients are the standard's.
+    pub fn k_weighting(samp_freq: f32) -> Result<Self, KnfError> {
+        const SHELF_FREQ: f64 = 1_681.974_450_955_533;
+        if samp_freq as f64 <= 2.0 * SHELF_FREQ {
+            return Err(KnfError::invalid(format!(
+                "K-weighting needs a sampling rate above 3364 Hz, got {}",
+                samp_freq
+            )));
+        }
+        let fs = samp_freq as f64;
+
+        let k = (std::f64::consts::PI * SHELF_FREQ / fs).tan();
+        let q = 0.707_175_236_955_419_6;
+        let vh = 10f64.powf(3.999_843_853_973_347 / 20.0);
+        let vb = vh.powf(0.499_666_774_154_541_6);
+        let a0 = 1.0 + k / q + k * k;
+        let shelf = Biquad::from_coefficients(
+            [
+                (vh + vb * k / q + k * k) / a0,
+                2.0 * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+            ],
+            [1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        )?;
+
+        let k = (std::f64::consts::PI * 38.135_470_876_024_44 / fs).tan();
+        let q = 0.500_327_037_323_877_3;
+        let a0 = 1.0 + k / q + k * k;
+        let highpass = Biquad::from_coefficients(
+            [1.0, -2.0, 1.0],
+            [1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        )?;
+        Ok(Self::new(vec![shelf, highpass]))
+    }
+
+    /// A-weighting of IEC 61672-1 as three sections from the bilinear
+    /// transform of the analog response, normalized to 0 dB at 1 kHz. Towards
+    /// the Nyquist frequency the response falls below the standard's (about
+    /// 0.6 dB low at a sixth of the sampling rate).
+    pub fn a_weighting(samp_freq: f32) -> Result<Self, KnfError> {
+        if samp_freq <= 2000.0 {
+            return Err(KnfError::invalid(format!(
+                "A-weighting needs a sampling rate above 2000 Hz, got {}",
+                samp_freq
+            )));
+        }
+        let fs = samp_freq as f64;
+        let w = [20.598_997, 107.652_65, 737.862_23, 12_194.217]
+            .map(|f: f64| 2.0 * std::f64::consts::PI * f);
+        let mut cascade = Self::new(vec![
+            bilinear([1.0, 0.0, 0.0], [1.0, 2.0 * w[0], w[0] * w[0]], fs)?,
+            bilinear([1.0, 0.0, 0.0], [1.0, w[1] + w[2], w[1] * w[2]], fs)?,
+            bilinear([0.0, 0.0, 1.0], [1.0, 2.0 * w[3], w[3] * w[3]], fs)?,
+        ]);
+        let gain = 10f64.powf(-cascade.magnitude_db(1000.0, samp_freq) as f64 / 20.0);
+        cascade.stages[0].b.iter_mut().for_each(|b| *b *= gain);
+        Ok(cascade)
+    }
+
+    /// Gain in dB at `freq` Hz.
+    pub fn magnitude_db(&self, freq: f32, samp_freq: f32) -> f32 {
+        self.stages
+            .iter()
+            .map(|stage| stage.magnitude_db(freq, samp_freq))
+            .sum()
+    }
+
+    pub fn process_sample(&mut self, x: f32) -> f32 {
+        self.stages
+            .iter_mut()
+            .fold(x, |x, stage| stage.process_sample(x))
+    }
+
+    /// Filters the next chunk of input.
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        input.iter().map(|&x| self.process_sample(x)).collect()
+    }
+
+    pub fn reset(&mut self) {
+        self.stages.iter_mut().for_each(Biquad::reset);
+    }
+}
+
+/// First-order high-pass, `y[n] = a * (y[n-1] + x[n] - x[n-1])` with
+/// `a = 1 / (1 + 2 pi fc / fs)`, with streaming state; see
+/// [`FrameOptions::highpass_cutoff`](crate::FrameOptions::highpass_cutoff).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FirstOrderHighPass {
+    coeff: f64,
+    prev_x: f64,
+    prev_y: f64,
+}
+
+impl FirstOrderHighPass {
+    pub fn new(samp_freq: f32, cutoff: f32) -> Result<Self, KnfError> {
+        if !(cutoff > 0.0 && cutoff < samp_freq / 2.0) {
+            return Err(KnfError::invalid(format!(
+                "High-pass cutoff {} Hz must be in (0, {}) Hz",
+                cutoff,
+                samp_freq / 2.0
+            )));
+        }
+        let rate = 2.0 * std::f64::consts::PI * cutoff as f64 / samp_freq as f64;
+        Ok(Self {
+            coeff: 1.0 / (1.0 + rate),
+            prev_x: 0.0,
+            prev_y: 0.0,
+        })
+    }
+
+    pub fn process_sample(&mut self, x: f32) -> f32 {
+        let x = x as f64;
+        self.prev_y = self.coeff * (self.prev_y + x - self.prev_x);
+        self.prev_x = x;
+        self.prev_y as f32
+    }
+
+    /// Filters the next chunk of input.
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        input.iter().map(|&x| self.process_sample(x)).collect()
+    }
+
+    pub fn reset(&mut self) {
+        self.prev_x = 0.0;
+        self.prev_y = 0.0;
+    }
+}
+
+/// Streaming pre-emphasis `y[n] = x[n] - coeff * x[n-1]`, the waveform-domain
+/// counterpart of [`FrameOptions::preemph_coeff`](crate::FrameOptions::preemph_coeff).
+///
+/// Like Kaldi's in-frame pre-emphasis, the first sample of a signal is its
+/// own predecessor, so a single frame is filtered exactly as in
+/// [`extract_window`](crate::window::extract_window).
+#[derive(Clone, Debug, PartialEq)]
+pub struct PreEmphasis {
+    coeff: f32,
+    prev: Option<f32>,
+}
+
+impl PreEmphasis {
+    pub fn new(coeff: f32) -> Result<Self, KnfError> {
+        if !(0.0..=1.0).contains(&coeff) {
+            return Err(KnfError::invalid(format!(
+                "Pre-emphasis coefficient ({}) must be in [0, 1]",
+                coeff
+            )));
+        }
+        Ok(Self { coeff, prev: None })
+    }
+
+    pub fn process_sample(&mut self, x: f32) -> f32 {
+        let prev = self.prev.unwrap_or(x);
+        self.prev = Some(x);
+        x - self.coeff * prev
+    }
+
+    /// Filters the next chunk of input.
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        input.iter().map(|&x| self.process_sample(x)).collect()
+    }
+
+    /// Starts a new signal.
+    pub fn reset(&mut self) {
+        self.prev = None;
+    }
+}
+
+/// Streaming de-emphasis `y[n] = x[n] + coeff * y[n-1]`, which exactly undoes
+/// [`PreEmphasis`] with the same coefficient.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeEmphasis {
+    coeff: f32,
+    prev: Option<f32>,
+}
+
+impl DeEmphasis {
+    /// `coeff` must be below 1, as the first sample cannot be recovered otherwise.
+    pub fn new(coeff: f32) -> Result<Self, KnfError> {
+        if !(0.0..1.0).contains(&coeff) {
+            return Err(KnfError::invalid(format!(
+                "De-emphasis coefficient ({}) must be in [0, 1)",
+                coeff
+            )));
+        }
+        Ok(Self { coeff, prev: None })
+    }
+
+    pub fn process_sample(&mut self, x: f32) -> f32 {
+        let y = match self.prev {
+            Some(prev) => x + self.coeff * prev,
+            // Pre-emphasis scaled the first sample by 1 - coeff
+            None => x / (1.0 - self.coeff),
+        };
+        self.prev = Some(y);
+        y
+    }
+
+    /// Filters the next chunk of input.
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        input.iter().map(|&x| self.process_sample(x)).collect()
+    }
+
+    /// Starts a new signal.
+    pub fn reset(&mut self) {
+        self.prev = None;
+    }
+}
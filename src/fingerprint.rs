@@ -0,0 +1,51 @@
+use std::fmt::Debug;
+use xxhash_rust::xxh3::{xxh3_64, Xxh3Default};
+
+/// Default quantization step for feature fingerprints. Values closer than this
+/// hash identically, which absorbs platform-level floating point noise.
+pub const DEFAULT_QUANTIZATION: f32 = 1e-3;
+
+/// Fingerprint of an options struct (e.g. `FbankOptions`).
+///
+/// Computed from the `Debug` representation, so it covers every field and is
+/// stable for a given crate version, but may change when options are added.
+pub fn options_fingerprint<T: Debug>(opts: &T) -> u64 {
+    xxh3_64(format!("{:?}", opts).as_bytes())
+}
+
+/// Fingerprint of a feature matrix given as a list of frames.
+///
+/// Each value is quantized to a multiple of `quantization` before hashing; the
+/// number of frames and the frame dimensions are part of the hash.
+pub fn features_fingerprint<F: AsRef<[f32]>>(frames: &[F], quantization: f32) -> u64 {
+    assert!(quantization > 0.0, "quantization must be positive");
+
+    let mut hasher = Xxh3Default::new();
+    hasher.update(&(frames.len() as u64).to_le_bytes());
+    for frame in frames {
+        let frame = frame.as_ref();
+        hasher.update(&(frame.len() as u64).to_le_bytes());
+        for &x in frame {
+            let q = if x.is_nan() {
+                i64::MIN
+            } else {
+                (x as f64 / quantization as f64).round() as i64
+            };
+            hasher.update(&q.to_le_bytes());
+        }
+    }
+    hasher.digest()
+}
+
+/// Fingerprint of an utterance: its features combined with the options used to
+/// extract them. Suitable as a deduplication or cache key.
+pub fn utterance_fingerprint<F: AsRef<[f32]>, T: Debug>(
+    frames: &[F],
+    opts: &T,
+    quantization: f32,
+) -> u64 {
+    let mut hasher = Xxh3Default::new();
+    hasher.update(&options_fingerprint(opts).to_le_bytes());
+    hasher.update(&features_fingerprint(frames, quantization).to_le_bytes());
+    hasher.digest()
+}
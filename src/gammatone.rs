@@ -0,0 +1,358 @@
+//! Gammatone filterbank energies and GFCC (gammatone frequency cepstral
+//! coefficients), with filters spaced on the ERB-rate scale of Glasberg &
+//! Moore (1990).
+
+use crate::error::KnfError;
+use crate::mel::{trim_bin, MelBanks};
+use crate::mfcc::{dct_matrix, lifter_coeffs};
+use crate::online::Computer;
+use crate::rfft::Rfft;
+use crate::utils::{compute_power_spectrum_inplace, inner_product, log_energy};
+use crate::window::FrameOptions;
+
+/// Equivalent rectangular bandwidth in Hz of the auditory filter at `freq` Hz.
+pub fn erb_bandwidth(freq: f32) -> f32 {
+    24.7 + 0.108 * freq
+}
+
+/// Number of ERBs below `freq` Hz.
+pub fn hz_to_erb_rate(freq: f32) -> f32 {
+    21.4 * (1.0 + 0.004_37 * freq).log10()
+}
+
+pub fn erb_rate_to_hz(erb_rate: f32) -> f32 {
+    (10f32.powf(erb_rate / 21.4) - 1.0) / 0.004_37
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct GammatoneOptions {
+    pub num_bins: usize,
+    /// Center frequency of the lowest filter.
+    pub low_freq: f32,
+    /// Center frequency of the highest filter; non-positive values are
+    /// relative to the Nyquist frequency.
+    pub high_freq: f32,
+    /// Filter order; 4 models the human auditory filters.
+    pub order: u32,
+}
+
+impl Default for GammatoneOptions {
+    fn default() -> Self {
+        Self {
+            num_bins: 64,
+            low_freq: 50.0,
+            high_freq: 0.0,
+            order: 4,
+        }
+    }
+}
+
+impl GammatoneOptions {
+    pub fn validate(&self, frame_opts: &FrameOptions) -> Result<(), KnfError> {
+        if self.num_bins == 0 {
+            return Err(KnfError::invalid("num_bins must be positive"));
+        }
+        if self.order == 0 {
+            return Err(KnfError::invalid("Gammatone order must be positive"));
+        }
+        let nyquist = 0.5 * frame_opts.samp_freq;
+        let high_freq = self.high_freq(frame_opts);
+        if self.low_freq <= 0.0 || high_freq > nyquist || high_freq < self.low_freq {
+            return Err(KnfError::invalid(format!(
+                "Invalid frequency range for gammatone banks: low_freq {} and high_freq {} with nyquist {}",
+                self.low_freq, high_freq, nyquist
+            )));
+        }
+        Ok(())
+    }
+
+    fn high_freq(&self, frame_opts: &FrameOptions) -> f32 {
+        if self.high_freq > 0.0 {
+            self.high_freq
+        } else {
+            0.5 * frame_opts.samp_freq + self.high_freq
+        }
+    }
+}
+
+impl MelBanks {
+    /// Gammatone filters applied to the power spectrum: the squared magnitude
+    /// response `(1 + ((f - fc) / b)^2)^-order` with `b = 1.019 * ERB(fc)`,
+    /// peaking at 1.0, for centers evenly spaced in ERB rate.
+    ///
+    /// `edges_hz` holds `[fc - b, fc, fc + b]` for each filter and `edges_mel`
+    /// the same on the ERB-rate scale.
+    pub fn gammatone(opts: &GammatoneOptions, frame_opts: &FrameOptions) -> Result<Self, KnfError> {
+        opts.validate(frame_opts)?;
+        let n_fft = frame_opts.padded_window_size();
+        let num_fft_bins = n_fft / 2;
+        let bin_width = frame_opts.samp_freq / n_fft as f32;
+
+        let erb_low = hz_to_erb_rate(opts.low_freq);
+        let erb_high = hz_to_erb_rate(opts.high_freq(frame_opts));
+        let step = if opts.num_bins > 1 {
+            (erb_high - erb_low) / (opts.num_bins - 1) as f32
+        } else {
+            0.0
+        };
+
+        let mut bins = Vec::with_capacity(opts.num_bins);
+        let mut edges_hz = Vec::with_capacity(opts.num_bins);
+        let mut row = vec![0.0; num_fft_bins];
+        for bin in 0..opts.num_bins {
+            let center = erb_rate_to_hz(erb_low + bin as f32 * step);
+            let b = 1.019 * erb_bandwidth(center);
+            for (i, w) in row.iter_mut().enumerate() {
+                let x = (i as f32 * bin_width - center) / b;
+                *w = (1.0 + x * x).powi(-(opts.order as i32));
+            }
+            bins.push(trim_bin(&row));
+            edges_hz.push([center - b, center, center + b]);
+        }
+
+        Ok(Self {
+            num_bins: opts.num_bins,
+            num_fft_bins,
+            htk_mode: false,
+            bins,
+            edges_mel: edges_hz
+                .iter()
+                .map(|e| e.map(|f| hz_to_erb_rate(f.max(0.0))))
+                .collect(),
+            edges_hz,
+        })
+    }
+}
+
+/// Amplitude compression of gammatone energies.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Compression {
+    /// Natural log, floored like FBANK energies.
+    #[default]
+    Log,
+    /// Cube root, as GFCC of Shao & Wang (2008).
+    CubeRoot,
+    /// Linear energies.
+    None,
+}
+
+impl Compression {
+    /// Applies the compression in place.
+    fn apply(self, values: &mut [f32]) {
+        match self {
+            Self::Log => values.iter_mut().for_each(|x| *x = log_energy(*x)),
+            Self::CubeRoot => values.iter_mut().for_each(|x| *x = x.cbrt()),
+            Self::None => {}
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct GammatoneFbankOptions {
+    pub frame_opts: FrameOptions,
+    pub gammatone_opts: GammatoneOptions,
+    pub compression: Compression,
+}
+
+impl Default for GammatoneFbankOptions {
+    fn default() -> Self {
+        Self {
+            frame_opts: FrameOptions::default(),
+            gammatone_opts: GammatoneOptions::default(),
+            compression: Compression::Log,
+        }
+    }
+}
+
+impl GammatoneFbankOptions {
+    pub fn validate(&self) -> Result<(), KnfError> {
+        self.frame_opts.validate()?;
+        self.gammatone_opts.validate(&self.frame_opts)
+    }
+}
+
+/// Per-frame gammatone filterbank energies, the counterpart of
+/// [`FbankComputer`](crate::FbankComputer).
+pub struct GammatoneComputer {
+    pub opts: GammatoneFbankOptions,
+    rfft: Rfft,
+    banks: MelBanks,
+}
+
+impl GammatoneComputer {
+    pub fn new(opts: GammatoneFbankOptions) -> Result<Self, KnfError> {
+        opts.validate()?;
+        Ok(Self {
+            rfft: Rfft::new(opts.frame_opts.padded_window_size(), false),
+            banks: MelBanks::gammatone(&opts.gammatone_opts, &opts.frame_opts)?,
+            opts,
+        })
+    }
+
+    pub fn banks(&self) -> &MelBanks {
+        &self.banks
+    }
+
+    pub fn dim(&self) -> usize {
+        self.banks.num_bins
+    }
+
+    /// Computes one frame; `signal_frame` is used as FFT scratch space.
+    pub fn compute(&mut self, signal_frame: &mut [f32], feature: &mut [f32]) {
+        self.rfft.compute(signal_frame);
+        compute_power_spectrum_inplace(signal_frame);
+        let fft_bins = self.banks.num_fft_bins + 1;
+        self.banks.compute(&signal_frame[..fft_bins], feature);
+        self.opts.compression.apply(feature);
+    }
+}
+
+impl Computer for GammatoneComputer {
+    fn dim(&self) -> usize {
+        GammatoneComputer::dim(self)
+    }
+
+    fn frame_opts(&self) -> &FrameOptions {
+        &self.opts.frame_opts
+    }
+
+    fn need_raw_energy(&self) -> bool {
+        false
+    }
+
+    fn compute(
+        &mut self,
+        _raw_log_energy: f32,
+        _vtln_warp: f32,
+        window: &mut [f32],
+        feature: &mut [f32],
+    ) {
+        GammatoneComputer::compute(self, window, feature)
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct GfccOptions {
+    pub frame_opts: FrameOptions,
+    pub gammatone_opts: GammatoneOptions,
+    /// Applied to the filterbank energies before the DCT; GFCC as in Shao &
+    /// Wang (2008) uses [`Compression::CubeRoot`].
+    pub compression: Compression,
+    pub num_ceps: usize,
+    pub cepstral_lifter: f32,
+}
+
+impl Default for GfccOptions {
+    fn default() -> Self {
+        Self {
+            frame_opts: FrameOptions::default(),
+            gammatone_opts: GammatoneOptions::default(),
+            compression: Compression::CubeRoot,
+            num_ceps: 23,
+            cepstral_lifter: 0.0,
+        }
+    }
+}
+
+impl GfccOptions {
+    pub fn validate(&self) -> Result<(), KnfError> {
+        self.frame_opts.validate()?;
+        self.gammatone_opts.validate(&self.frame_opts)?;
+        if self.num_ceps == 0 || self.num_ceps > self.gammatone_opts.num_bins {
+            return Err(KnfError::invalid(format!(
+                "num_ceps ({}) must be in [1, num_bins ({})]",
+                self.num_ceps, self.gammatone_opts.num_bins
+            )));
+        }
+        if self.cepstral_lifter < 0.0 {
+            return Err(KnfError::invalid("cepstral_lifter must be non-negative"));
+        }
+        Ok(())
+    }
+}
+
+/// GFCC: the orthonormal DCT-II of compressed gammatone energies, computed
+/// like [`MfccComputer`](crate::MfccComputer) computes MFCC from log-mels.
+pub struct GfccComputer {
+    pub opts: GfccOptions,
+    fbank: GammatoneComputer,
+    energies: Vec<f32>,
+    dct_matrix: Vec<f32>, // flattened [num_ceps * num_bins]
+    lifter_coeffs: Vec<f32>,
+}
+
+impl GfccComputer {
+    pub fn new(opts: GfccOptions) -> Result<Self, KnfError> {
+        opts.validate()?;
+        let num_bins = opts.gammatone_opts.num_bins;
+        let fbank = GammatoneComputer::new(GammatoneFbankOptions {
+            frame_opts: opts.frame_opts.clone(),
+            gammatone_opts: opts.gammatone_opts.clone(),
+            compression: opts.compression,
+        })?;
+        Ok(Self {
+            fbank,
+            energies: vec![0.0; num_bins],
+            dct_matrix: dct_matrix(opts.num_ceps, num_bins),
+            lifter_coeffs: lifter_coeffs(opts.num_ceps, opts.cepstral_lifter),
+            opts,
+        })
+    }
+
+    pub fn dim(&self) -> usize {
+        self.opts.num_ceps
+    }
+
+    pub fn compute(&mut self, signal_frame: &mut [f32], feature: &mut [f32]) {
+        self.fbank.compute(signal_frame, &mut self.energies);
+        let num_bins = self.energies.len();
+        for (i, val) in feature.iter_mut().enumerate().take(self.opts.num_ceps) {
+            let row = &self.dct_matrix[i * num_bins..(i + 1) * num_bins];
+            *val = inner_product(row, &self.energies) * self.lifter_coeffs[i];
+        }
+    }
+}
+
+impl Computer for GfccComputer {
+    fn dim(&self) -> usize {
+        GfccComputer::dim(self)
+    }
+
+    fn frame_opts(&self) -> &FrameOptions {
+        &self.opts.frame_opts
+    }
+
+    fn need_raw_energy(&self) -> bool {
+        false
+    }
+
+    fn compute(
+        &mut self,
+        _raw_log_energy: f32,
+        _vtln_warp: f32,
+        window: &mut [f32],
+        feature: &mut [f32],
+    ) {
+        GfccComputer::compute(self, window, feature)
+    }
+}
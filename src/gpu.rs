@@ -0,0 +1,400 @@
+//! Optional wgpu backend for the power spectrum -> mel -> log stage of FBANK
+//! extraction over batches of frames. Framing, windowing and the FFT stay on
+//! the CPU; [`compute_features_gpu`] falls back to [`compute_features`] when
+//! no GPU is available.
+
+use crate::error::KnfError;
+use crate::fbank::{FbankOptions, MelFloorMode};
+use crate::mel::MelBanks;
+use crate::offline::{compute_features, FeatureMatrix};
+use crate::rfft::Rfft;
+use crate::utils::{inner_product, log_energy};
+use crate::window::{extract_window, highpass_filtered, num_frames, Window};
+use wgpu::util::DeviceExt;
+
+const SHADER: &str = r#"
+struct Params {
+    num_frames: u32,
+    n_fft: u32,
+    num_bins: u32,
+    num_fft_bins: u32,
+    use_power: u32,
+    use_log: u32,
+    floor_add: u32,
+    htk_mode: u32,
+    mel_floor: f32,
+    row_stride: u32,
+}
+
+@group(0) @binding(0) var<storage, read> spectra: array<f32>;
+// Per mel bin: first FFT bin, number of weights, offset into `weights`.
+@group(0) @binding(1) var<storage, read> ranges: array<u32>;
+@group(0) @binding(2) var<storage, read> weights: array<f32>;
+@group(0) @binding(3) var<storage, read_write> mels: array<f32>;
+@group(0) @binding(4) var<uniform> params: Params;
+
+// Power of FFT bin `k` in the packed Rfft layout.
+fn power(base: u32, k: u32) -> f32 {
+    let n = params.n_fft;
+    if (k == 0u) {
+        let re = spectra[base];
+        return re * re;
+    }
+    if (k == n / 2u) {
+        let re = spectra[base + 1u];
+        if (n % 2u == 0u) {
+            return re * re;
+        }
+        let im = spectra[base + n - 1u];
+        return re * re + im * im;
+    }
+    let re = spectra[base + 2u * k];
+    let im = spectra[base + 2u * k + 1u];
+    return re * re + im * im;
+}
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let idx = id.y * params.row_stride + id.x;
+    if (idx >= params.num_frames * params.num_bins) {
+        return;
+    }
+    let frame = idx / params.num_bins;
+    let bin = idx % params.num_bins;
+    let base = frame * params.n_fft;
+
+    let first = ranges[3u * bin];
+    let len = ranges[3u * bin + 1u];
+    let offset = ranges[3u * bin + 2u];
+    var acc = 0.0;
+    for (var i = 0u; i < len; i++) {
+        var p = power(base, first + i);
+        if (params.use_power == 0u) {
+            p = sqrt(p);
+        }
+        acc += weights[offset + i] * p;
+    }
+    if (params.htk_mode != 0u && acc < 1.0) {
+        acc = 1.0;
+    }
+    if (params.use_log != 0u) {
+        if (params.floor_add != 0u) {
+            acc = log(acc + params.mel_floor);
+        } else {
+            acc = log(max(acc, params.mel_floor));
+        }
+    }
+    mels[idx] = acc;
+}
+"#;
+
+const WORKGROUP_SIZE: u32 = 64;
+const MAX_WORKGROUPS_X: u32 = 65535;
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    num_frames: u32,
+    n_fft: u32,
+    num_bins: u32,
+    num_fft_bins: u32,
+    use_power: u32,
+    use_log: u32,
+    floor_add: u32,
+    htk_mode: u32,
+    mel_floor: f32,
+    row_stride: u32,
+    _pad: [u32; 2],
+}
+
+/// Mel integration (and log) of packed [`Rfft`] spectra on the GPU.
+pub struct GpuMelStage {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    ranges: wgpu::Buffer,
+    weights: wgpu::Buffer,
+    params: Params,
+    max_frames: usize,
+}
+
+impl GpuMelStage {
+    /// Builds the stage for the mel banks and output options of `opts`, or
+    /// [`KnfError::Gpu`] when no GPU adapter or device is available.
+    pub fn new(opts: &FbankOptions) -> Result<Self, KnfError> {
+        opts.validate()?;
+        let banks = MelBanks::new(&opts.mel_opts, &opts.frame_opts, 1.0)?;
+        let n_fft = opts.frame_opts.padded_window_size();
+
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+        let adapter =
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+                .ok_or(KnfError::Gpu("no GPU adapter available".to_string()))?;
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("knf"),
+                required_features: wgpu::Features::empty(),
+                required_limits: adapter.limits(),
+                memory_hints: wgpu::MemoryHints::Performance,
+            },
+            None,
+        ))
+        .map_err(|e| KnfError::Gpu(e.to_string()))?;
+
+        let mut ranges = Vec::with_capacity(3 * banks.num_bins);
+        let mut weights = Vec::new();
+        for (first, w) in &banks.bins {
+            ranges.extend([*first as u32, w.len() as u32, weights.len() as u32]);
+            weights.extend_from_slice(w);
+        }
+        // Zero-sized storage bindings are invalid
+        weights.push(0.0);
+
+        let ranges = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("knf mel ranges"),
+            contents: bytemuck::cast_slice(&ranges),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let weights = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("knf mel weights"),
+            contents: bytemuck::cast_slice(&weights),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("knf mel"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("knf mel"),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let limits = device.limits();
+        let max_binding = limits
+            .max_storage_buffer_binding_size
+            .min(limits.max_buffer_size.min(u32::MAX as u64) as u32)
+            as usize;
+        let bytes_per_frame = 4 * n_fft.max(banks.num_bins);
+        let max_frames = (max_binding / bytes_per_frame).max(1);
+
+        let params = Params {
+            num_frames: 0,
+            n_fft: n_fft as u32,
+            num_bins: banks.num_bins as u32,
+            num_fft_bins: banks.num_fft_bins as u32,
+            use_power: opts.use_power as u32,
+            use_log: opts.use_log_fbank as u32,
+            floor_add: (opts.mel_floor_mode == MelFloorMode::Add) as u32,
+            htk_mode: banks.htk_mode as u32,
+            mel_floor: opts.mel_floor,
+            row_stride: 0,
+            _pad: [0; 2],
+        };
+
+        Ok(Self {
+            device,
+            queue,
+            pipeline,
+            ranges,
+            weights,
+            params,
+            max_frames,
+        })
+    }
+
+    pub fn num_bins(&self) -> usize {
+        self.params.num_bins as usize
+    }
+
+    /// `spectra` holds `n_fft` packed FFT values per frame; the mel (log)
+    /// energies are written to `out`, `num_bins` values per frame.
+    pub fn compute(&self, spectra: &[f32], out: &mut [f32]) -> Result<(), KnfError> {
+        let n_fft = self.params.n_fft as usize;
+        let num_bins = self.num_bins();
+        if !spectra.len().is_multiple_of(n_fft) {
+            return Err(KnfError::input(format!(
+                "spectra length {} is not a multiple of n_fft {}",
+                spectra.len(),
+                n_fft
+            )));
+        }
+        let frames = spectra.len() / n_fft;
+        if out.len() != frames * num_bins {
+            return Err(KnfError::SizeMismatch {
+                what: "mel output",
+                expected: frames * num_bins,
+                actual: out.len(),
+            });
+        }
+
+        for (spectra, out) in spectra
+            .chunks(self.max_frames * n_fft)
+            .zip(out.chunks_mut(self.max_frames * num_bins))
+        {
+            self.compute_chunk(spectra, out)?;
+        }
+        Ok(())
+    }
+
+    fn compute_chunk(&self, spectra: &[f32], out: &mut [f32]) -> Result<(), KnfError> {
+        let frames = spectra.len() / self.params.n_fft as usize;
+        let outputs = (frames * self.num_bins()) as u32;
+        let groups = outputs.div_ceil(WORKGROUP_SIZE);
+        let groups_x = groups.min(MAX_WORKGROUPS_X);
+        let groups_y = groups.div_ceil(groups_x);
+        let params = Params {
+            num_frames: frames as u32,
+            row_stride: groups_x * WORKGROUP_SIZE,
+            ..self.params
+        };
+
+        let device = &self.device;
+        let spectra = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("knf spectra"),
+            contents: bytemuck::cast_slice(spectra),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("knf params"),
+            contents: bytemuck::bytes_of(&params),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let size = std::mem::size_of_val(out) as u64;
+        let mels = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("knf mels"),
+            size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let staging = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("knf staging"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[
+                (0, &spectra),
+                (1, &self.ranges),
+                (2, &self.weights),
+                (3, &mels),
+                (4, &params),
+            ]
+            .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                binding,
+                resource: buffer.as_entire_binding(),
+            }),
+        });
+
+        let mut encoder = device.create_command_encoder(&Default::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&Default::default());
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(groups_x, groups_y, 1);
+        }
+        encoder.copy_buffer_to_buffer(&mels, 0, &staging, 0, size);
+        self.queue.submit([encoder.finish()]);
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        staging
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |r| drop(tx.send(r)));
+        device.poll(wgpu::Maintain::Wait);
+        rx.recv()
+            .map_err(|e| KnfError::Gpu(e.to_string()))?
+            .map_err(|e| KnfError::Gpu(e.to_string()))?;
+        out.copy_from_slice(bytemuck::cast_slice(&staging.slice(..).get_mapped_range()));
+        staging.unmap();
+        Ok(())
+    }
+}
+
+/// FBANK features like [`compute_features`], with the mel stage on the GPU.
+/// Falls back to the CPU when no GPU is available or
+/// `subtract_mean_log_energy` needs the sequential per-frame state.
+pub fn compute_features_gpu(wave: &[f32], opts: &FbankOptions) -> Result<FeatureMatrix, KnfError> {
+    if opts.subtract_mean_log_energy {
+        return compute_features(wave, opts);
+    }
+    match GpuMelStage::new(opts) {
+        Ok(stage) => compute_features_with_stage(&stage, wave, opts),
+        Err(KnfError::Gpu(_)) => compute_features(wave, opts),
+        Err(e) => Err(e),
+    }
+}
+
+/// [`compute_features_gpu`] with an existing stage built for `opts`, e.g. to
+/// reuse the device across utterances.
+pub fn compute_features_with_stage(
+    stage: &GpuMelStage,
+    wave: &[f32],
+    opts: &FbankOptions,
+) -> Result<FeatureMatrix, KnfError> {
+    opts.validate()?;
+    let frame_opts = &opts.frame_opts;
+    let n_fft = frame_opts.padded_window_size();
+    let num_bins = stage.num_bins();
+    if stage.params.n_fft as usize != n_fft || num_bins != opts.mel_opts.num_bins {
+        return Err(KnfError::invalid(
+            "GpuMelStage was built for different options",
+        ));
+    }
+
+    let wave = &highpass_filtered(wave, frame_opts)[..];
+    let frames = num_frames(wave.len(), frame_opts, true);
+    let window = Window::new(frame_opts);
+    let mut rfft = Rfft::new(n_fft, false);
+    let mut spectra = vec![0.0; frames * n_fft];
+    let mut energies = vec![0.0; frames];
+    for (frame, (spectrum, energy)) in spectra
+        .chunks_exact_mut(n_fft)
+        .zip(energies.iter_mut())
+        .enumerate()
+    {
+        *energy = extract_window(0, wave, frame, frame_opts, window.as_ref(), spectrum)?;
+        if opts.use_energy && !opts.raw_energy {
+            *energy = log_energy(inner_product(spectrum, spectrum));
+        }
+        rfft.compute(spectrum);
+    }
+
+    let mut mels = vec![0.0; frames * num_bins];
+    stage.compute(&spectra, &mut mels)?;
+
+    // Same layout as FbankComputer: energy first unless htk_compat
+    let mel_offset = (opts.use_energy && !opts.htk_compat) as usize;
+    let dim = num_bins + mel_offset;
+    let energy_column = (mel_offset == 1).then_some(0);
+    let log_energy_floor = if opts.energy_floor > 0.0 {
+        opts.energy_floor.ln()
+    } else {
+        -1e10
+    };
+
+    let mut data = vec![0.0; frames * dim];
+    for ((row, mel), energy) in data
+        .chunks_exact_mut(dim)
+        .zip(mels.chunks_exact(num_bins))
+        .zip(energies)
+    {
+        row[mel_offset..mel_offset + num_bins].copy_from_slice(mel);
+        if let Some(column) = energy_column {
+            row[column] = energy.max(log_energy_floor);
+        }
+    }
+
+    Ok(FeatureMatrix {
+        data,
+        num_frames: frames,
+        dim,
+    })
+}
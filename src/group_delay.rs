@@ -0,0 +1,203 @@
+//! Group delay and the modified group delay function (MODGDF) of Hegde &
+//! Murthy (2007), phase-based features used e.g. in anti-spoofing.
+
+use crate::cepstrum::{lifter_low, MAGNITUDE_FLOOR};
+use crate::cfft::{Cfft, Complex};
+use crate::error::KnfError;
+use crate::mfcc::dct_matrix;
+use crate::online::Computer;
+use crate::utils::inner_product;
+use crate::window::FrameOptions;
+
+/// Spectra of `x[n]` and `n x[n]`, whose cross term gives the group delay
+/// without phase unwrapping.
+fn spectra(frame: &[f32], fft: &mut Cfft, x: &mut [Complex<f32>], y: &mut [Complex<f32>]) {
+    for (n, ((&v, x), y)) in frame.iter().zip(x.iter_mut()).zip(y.iter_mut()).enumerate() {
+        *x = Complex::new(v, 0.0);
+        *y = Complex::new(n as f32 * v, 0.0);
+    }
+    fft.compute(x);
+    fft.compute(y);
+}
+
+/// Group delay `-d arg X / d omega` in samples of `frame` at bins
+/// `0..=len / 2`, computed as `(X_r Y_r + X_i Y_i) / |X|^2` with `Y` the
+/// spectrum of `n x[n]`.
+pub fn group_delay(frame: &[f32]) -> Result<Vec<f32>, KnfError> {
+    if frame.is_empty() {
+        return Err(KnfError::invalid("Group delay input must be non-empty"));
+    }
+    let n = frame.len();
+    let mut x = vec![Complex::new(0.0, 0.0); n];
+    let mut y = x.clone();
+    spectra(frame, &mut Cfft::new(n, false), &mut x, &mut y);
+    Ok(x[..=n / 2]
+        .iter()
+        .zip(&y)
+        .map(|(x, y)| (x.re * y.re + x.im * y.im) / x.norm_sqr().max(MAGNITUDE_FLOOR))
+        .collect())
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct ModGdfOptions {
+    pub frame_opts: FrameOptions,
+    /// Compression exponent of the group delay magnitude.
+    pub alpha: f32,
+    /// Exponent of the smoothed spectrum in the denominator.
+    pub gamma: f32,
+    /// Quefrencies kept when cepstrally smoothing the magnitude spectrum.
+    pub lifter_cutoff: usize,
+    /// Orthonormal DCT-II coefficients per frame; 0 outputs the modified
+    /// group delay spectrum at bins `0..=padded_window_size / 2` instead.
+    pub num_ceps: usize,
+}
+
+impl Default for ModGdfOptions {
+    fn default() -> Self {
+        Self {
+            frame_opts: FrameOptions::default(),
+            alpha: 0.4,
+            gamma: 0.9,
+            lifter_cutoff: 8,
+            num_ceps: 13,
+        }
+    }
+}
+
+impl ModGdfOptions {
+    pub fn validate(&self) -> Result<(), KnfError> {
+        self.frame_opts.validate()?;
+        if self.alpha.is_nan() || self.alpha <= 0.0 || self.gamma.is_nan() || self.gamma <= 0.0 {
+            return Err(KnfError::invalid("alpha and gamma must be positive"));
+        }
+        let n_fft = self.frame_opts.padded_window_size();
+        if self.lifter_cutoff == 0 || self.lifter_cutoff > n_fft / 2 {
+            return Err(KnfError::invalid(format!(
+                "lifter_cutoff ({}) must be in [1, {}]",
+                self.lifter_cutoff,
+                n_fft / 2
+            )));
+        }
+        if self.num_ceps > n_fft / 2 + 1 {
+            return Err(KnfError::invalid(format!(
+                "num_ceps ({}) must not exceed the number of bins ({})",
+                self.num_ceps,
+                n_fft / 2 + 1
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Per-frame MODGDF: `sign(tau) |tau|^alpha` with
+/// `tau = (X_r Y_r + X_i Y_i) / S^(2 gamma)`, where `S` is the cepstrally
+/// smoothed magnitude spectrum, optionally followed by a DCT.
+pub struct ModGdfComputer {
+    pub opts: ModGdfOptions,
+    fft: Cfft,
+    ifft: Cfft,
+    x: Vec<Complex<f32>>,
+    y: Vec<Complex<f32>>,
+    spectrum: Vec<Complex<f32>>,
+    cepstrum: Vec<f32>,
+    modgd: Vec<f32>,
+    dct_matrix: Vec<f32>, // flattened [num_ceps * num_bins]
+}
+
+impl ModGdfComputer {
+    pub fn new(opts: ModGdfOptions) -> Result<Self, KnfError> {
+        opts.validate()?;
+        let n_fft = opts.frame_opts.padded_window_size();
+        let num_bins = n_fft / 2 + 1;
+        Ok(Self {
+            fft: Cfft::new(n_fft, false),
+            ifft: Cfft::new(n_fft, true),
+            x: vec![Complex::new(0.0, 0.0); n_fft],
+            y: vec![Complex::new(0.0, 0.0); n_fft],
+            spectrum: vec![Complex::new(0.0, 0.0); n_fft],
+            cepstrum: vec![0.0; n_fft],
+            modgd: vec![0.0; num_bins],
+            dct_matrix: dct_matrix(opts.num_ceps, num_bins),
+            opts,
+        })
+    }
+
+    pub fn dim(&self) -> usize {
+        if self.opts.num_ceps > 0 {
+            self.opts.num_ceps
+        } else {
+            self.modgd.len()
+        }
+    }
+
+    pub fn compute(&mut self, signal_frame: &mut [f32], feature: &mut [f32]) {
+        let n_fft = self.x.len();
+        spectra(
+            &signal_frame[..n_fft],
+            &mut self.fft,
+            &mut self.x,
+            &mut self.y,
+        );
+
+        // Cepstral smoothing: keep the low quefrencies of ln |X|
+        for (s, x) in self.spectrum.iter_mut().zip(&self.x) {
+            *s = Complex::new(x.norm().max(MAGNITUDE_FLOOR).ln() / n_fft as f32, 0.0);
+        }
+        self.ifft.compute(&mut self.spectrum);
+        for (c, s) in self.cepstrum.iter_mut().zip(&self.spectrum) {
+            *c = s.re;
+        }
+        lifter_low(&mut self.cepstrum, self.opts.lifter_cutoff);
+        for (s, &c) in self.spectrum.iter_mut().zip(&self.cepstrum) {
+            *s = Complex::new(c, 0.0);
+        }
+        self.fft.compute(&mut self.spectrum);
+
+        let (alpha, gamma) = (self.opts.alpha, self.opts.gamma);
+        for (k, out) in self.modgd.iter_mut().enumerate() {
+            let (x, y) = (self.x[k], self.y[k]);
+            let tau = (x.re * y.re + x.im * y.im) / (2.0 * gamma * self.spectrum[k].re).exp();
+            *out = tau.signum() * tau.abs().powf(alpha);
+        }
+
+        if self.opts.num_ceps == 0 {
+            let dim = self.modgd.len();
+            feature[..dim].copy_from_slice(&self.modgd);
+            return;
+        }
+        let num_bins = self.modgd.len();
+        for (i, val) in feature.iter_mut().enumerate().take(self.opts.num_ceps) {
+            let row = &self.dct_matrix[i * num_bins..(i + 1) * num_bins];
+            *val = inner_product(row, &self.modgd);
+        }
+    }
+}
+
+impl Computer for ModGdfComputer {
+    fn dim(&self) -> usize {
+        ModGdfComputer::dim(self)
+    }
+
+    fn frame_opts(&self) -> &FrameOptions {
+        &self.opts.frame_opts
+    }
+
+    fn need_raw_energy(&self) -> bool {
+        false
+    }
+
+    fn compute(
+        &mut self,
+        _raw_log_energy: f32,
+        _vtln_warp: f32,
+        window: &mut [f32],
+        feature: &mut [f32],
+    ) {
+        ModGdfComputer::compute(self, window, feature)
+    }
+}
@@ -0,0 +1,256 @@
+use crate::error::KnfError;
+use crate::istft::{istft_compute, IstftOptions};
+use crate::mel::{MelBanks, MelOptions};
+use crate::stft::{stft_compute, StftOptions, StftResult};
+use crate::window::FrameOptions;
+use rand::Rng;
+
+/// Maps mel energies back to a linear power spectrum with the regularized
+/// pseudo-inverse of the mel filterbank, `M^T (M M^T + lambda I)^-1`.
+///
+/// The output has `num_fft_bins + 1` bins (the Nyquist bin, which mel banks
+/// never integrate, is left at zero) and negative values are clipped to zero.
+pub struct InverseMelScale {
+    pub num_bins: usize,
+    pub num_fft_bins: usize,
+    // Row-major [fft_bin * num_bins + mel_bin]
+    pinv: Vec<f32>,
+}
+
+impl InverseMelScale {
+    /// `regularization` is relative to the mean diagonal of `M M^T`, so the same
+    /// value works for normalized and unnormalized filters; use 0 for the exact
+    /// pseudo-inverse when every mel bin covers at least one FFT bin.
+    pub fn new(banks: &MelBanks, regularization: f32) -> Result<Self, KnfError> {
+        if regularization < 0.0 {
+            return Err(KnfError::invalid("regularization must be non-negative"));
+        }
+        let n = banks.num_bins;
+        let cols = banks.num_fft_bins;
+        let dense: Vec<f64> = banks.to_dense().iter().map(|&w| w as f64).collect();
+
+        // Gram matrix M M^T
+        let mut gram = vec![0.0f64; n * n];
+        for i in 0..n {
+            for j in 0..=i {
+                let row_i = &dense[i * cols..(i + 1) * cols];
+                let row_j = &dense[j * cols..(j + 1) * cols];
+                let dot: f64 = row_i.iter().zip(row_j).map(|(a, b)| a * b).sum();
+                gram[i * n + j] = dot;
+                gram[j * n + i] = dot;
+            }
+        }
+        let mean_diag = (0..n).map(|i| gram[i * n + i]).sum::<f64>() / n.max(1) as f64;
+        let lambda = regularization as f64 * mean_diag;
+        for i in 0..n {
+            gram[i * n + i] += lambda;
+        }
+
+        let chol = cholesky(&gram, n).ok_or(KnfError::invalid(
+            "Mel filterbank is rank deficient; use a positive regularization",
+        ))?;
+
+        // Solve (M M^T + lambda I) X = M, one FFT column at a time
+        let mut pinv = vec![0.0; cols * n];
+        let mut column = vec![0.0f64; n];
+        for k in 0..cols {
+            for (i, c) in column.iter_mut().enumerate() {
+                *c = dense[i * cols + k];
+            }
+            cholesky_solve(&chol, n, &mut column);
+            for (i, &c) in column.iter().enumerate() {
+                pinv[k * n + i] = c as f32;
+            }
+        }
+
+        Ok(Self {
+            num_bins: n,
+            num_fft_bins: cols,
+            pinv,
+        })
+    }
+
+    /// Linear power spectrum (`num_fft_bins + 1` values) of one frame of mel energies.
+    pub fn compute(&self, mel_energies: &[f32], power_out: &mut [f32]) {
+        assert_eq!(mel_energies.len(), self.num_bins);
+        assert_eq!(power_out.len(), self.num_fft_bins + 1);
+
+        for (k, out) in power_out[..self.num_fft_bins].iter_mut().enumerate() {
+            let row = &self.pinv[k * self.num_bins..(k + 1) * self.num_bins];
+            let x: f32 = row.iter().zip(mel_energies).map(|(w, m)| w * m).sum();
+            *out = x.max(0.0);
+        }
+        power_out[self.num_fft_bins] = 0.0;
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct GriffinLimOptions {
+    pub n_iter: usize,
+    /// Fast Griffin-Lim momentum (librosa's default 0.99; 0 for plain Griffin-Lim).
+    pub momentum: f32,
+    /// Start from random phases instead of zero phase.
+    pub random_init: bool,
+}
+
+impl Default for GriffinLimOptions {
+    fn default() -> Self {
+        Self {
+            n_iter: 32,
+            momentum: 0.99,
+            random_init: true,
+        }
+    }
+}
+
+/// Estimates a waveform from STFT magnitudes (`[frame][bin]`, `n_fft / 2 + 1`
+/// bins per frame) with the fast Griffin-Lim algorithm, as
+/// `librosa.griffinlim`.
+pub fn griffin_lim(
+    magnitudes: &[Vec<f32>],
+    stft_opts: &StftOptions,
+    opts: &GriffinLimOptions,
+) -> Result<Vec<f32>, KnfError> {
+    let bins = stft_opts.n_fft / 2 + 1;
+    if let Some(m) = magnitudes.iter().find(|m| m.len() != bins) {
+        return Err(KnfError::SizeMismatch {
+            what: "magnitude bins per frame",
+            expected: bins,
+            actual: m.len(),
+        });
+    }
+    if magnitudes.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let num_frames = magnitudes.len();
+    let istft_opts = IstftOptions::from(stft_opts);
+    let mag: Vec<f32> = magnitudes.iter().flatten().copied().collect();
+
+    // Unit phasors
+    let (mut cos, mut sin) = (vec![1.0f32; mag.len()], vec![0.0f32; mag.len()]);
+    if opts.random_init {
+        let mut rng = rand::thread_rng();
+        for (c, s) in cos.iter_mut().zip(sin.iter_mut()) {
+            let phase = rng.gen::<f32>() * 2.0 * std::f32::consts::PI;
+            (*s, *c) = phase.sin_cos();
+        }
+    }
+
+    let with_phase = |cos: &[f32], sin: &[f32]| StftResult {
+        real: mag.iter().zip(cos).map(|(m, c)| m * c).collect(),
+        imag: mag.iter().zip(sin).map(|(m, s)| m * s).collect(),
+        num_frames,
+        n_fft: stft_opts.n_fft,
+    };
+
+    let accel = opts.momentum / (1.0 + opts.momentum);
+    let mut prev: Option<StftResult> = None;
+    for _ in 0..opts.n_iter {
+        let audio = istft_compute(&istft_opts, &with_phase(&cos, &sin))?;
+        let rebuilt = stft_compute(stft_opts, &audio)?;
+        if rebuilt.num_frames != num_frames {
+            return Err(KnfError::invalid(
+                "STFT round trip changed the number of frames",
+            ));
+        }
+
+        for k in 0..mag.len() {
+            let (mut re, mut im) = (rebuilt.real[k], rebuilt.imag[k]);
+            if let Some(p) = &prev {
+                re -= accel * p.real[k];
+                im -= accel * p.imag[k];
+            }
+            let norm = (re * re + im * im).sqrt() + 1e-16;
+            cos[k] = re / norm;
+            sin[k] = im / norm;
+        }
+        prev = Some(rebuilt);
+    }
+
+    istft_compute(&istft_opts, &with_phase(&cos, &sin))
+}
+
+/// Reconstructs audio from a power mel spectrogram (`[frame][mel_bin]`), like
+/// `librosa.feature.inverse.mel_to_audio`: mel energies are projected to a
+/// linear power spectrum with [`InverseMelScale`] and phases are recovered with
+/// [`griffin_lim`] using its default options.
+///
+/// The mel banks are rebuilt from `mel_opts` for `stft_opts.n_fft` at the
+/// sampling rate `stft_opts.samp_freq`.
+pub fn mel_to_audio(
+    mel: &[Vec<f32>],
+    mel_opts: &MelOptions,
+    stft_opts: &StftOptions,
+) -> Result<Vec<f32>, KnfError> {
+    let frame_opts = FrameOptions {
+        samp_freq: stft_opts.samp_freq,
+        n_fft: stft_opts.n_fft,
+        ..Default::default()
+    };
+    let banks = MelBanks::new(mel_opts, &frame_opts, 1.0)?;
+    let inverse = InverseMelScale::new(&banks, 1e-4)?;
+
+    let magnitudes = mel
+        .iter()
+        .map(|frame| {
+            if frame.len() != banks.num_bins {
+                return Err(KnfError::SizeMismatch {
+                    what: "mel bins per frame",
+                    expected: banks.num_bins,
+                    actual: frame.len(),
+                });
+            }
+            let mut power = vec![0.0; banks.num_fft_bins + 1];
+            inverse.compute(frame, &mut power);
+            Ok(power.iter().map(|p| p.sqrt()).collect())
+        })
+        .collect::<Result<Vec<Vec<f32>>, KnfError>>()?;
+
+    griffin_lim(&magnitudes, stft_opts, &GriffinLimOptions::default())
+}
+
+/// Lower-triangular Cholesky factor of a symmetric positive definite matrix.
+fn cholesky(a: &[f64], n: usize) -> Option<Vec<f64>> {
+    let mut l = vec![0.0; n * n];
+    for i in 0..n {
+        for j in 0..=i {
+            let mut sum = a[i * n + j];
+            for k in 0..j {
+                sum -= l[i * n + k] * l[j * n + k];
+            }
+            if i == j {
+                if sum <= 0.0 {
+                    return None;
+                }
+                l[i * n + i] = sum.sqrt();
+            } else {
+                l[i * n + j] = sum / l[j * n + j];
+            }
+        }
+    }
+    Some(l)
+}
+
+/// Solves `L L^T x = b` in place.
+fn cholesky_solve(l: &[f64], n: usize, b: &mut [f64]) {
+    for i in 0..n {
+        let mut sum = b[i];
+        for k in 0..i {
+            sum -= l[i * n + k] * b[k];
+        }
+        b[i] = sum / l[i * n + i];
+    }
+    for i in (0..n).rev() {
+        let mut sum = b[i];
+        for k in i + 1..n {
+            sum -= l[k * n + i] * b[k];
+        }
+        b[i] = sum / l[i * n + i];
+    }
+}
@@ -1,12 +1,22 @@
+use crate::error::KnfError;
 use crate::rfft::Rfft;
-use crate::stft::{StftOptions, StftResult};
-use crate::window::{FrameOptions, Window};
-
+use crate::stft::{padded_window, StftOptions, StftResult};
+use crate::window::{check_custom_window, WindowType};
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct IstftOptions {
     pub n_fft: usize,
     pub hop_length: usize,
     pub win_length: usize,
-    pub window_type: String,
+    pub window_type: WindowType,
+    /// Synthesis window coefficients used instead of `window_type`; must have
+    /// `win_length` values.
+    pub custom_window: Option<Vec<f32>>,
     pub center: bool,
     pub normalized: bool,
 }
@@ -17,7 +27,8 @@ impl Default for IstftOptions {
             n_fft: 400,
             hop_length: 160,
             win_length: 400,
-            window_type: "povey".to_string(),
+            window_type: WindowType::Povey,
+            custom_window: None,
             center: true,
             normalized: false,
         }
@@ -30,98 +41,205 @@ impl From<&StftOptions> for IstftOptions {
             n_fft: s.n_fft,
             hop_length: s.hop_length,
             win_length: s.win_length,
-            window_type: s.frame_opts.window_type.clone(),
+            window_type: s.window_type,
+            custom_window: s.custom_window.clone(),
             center: s.center,
             normalized: s.normalized,
         }
     }
 }
 
-pub fn istft_compute(opts: &IstftOptions, stft: &StftResult) -> Result<Vec<f32>, String> {
-    if stft.num_frames == 0 || opts.n_fft == 0 {
-        return Ok(Vec::new());
+impl IstftOptions {
+    pub fn validate(&self) -> Result<(), KnfError> {
+        if self.n_fft == 0 || self.hop_length == 0 {
+            return Err(KnfError::invalid("n_fft and hop_length must be positive"));
+        }
+        if self.win_length == 0 || self.win_length > self.n_fft {
+            return Err(KnfError::invalid(format!(
+                "win_length ({}) must be in [1, n_fft ({})]",
+                self.win_length, self.n_fft
+            )));
+        }
+        check_custom_window(self.custom_window.as_deref(), self.win_length)
     }
+}
 
-    let n_fft = opts.n_fft;
-    let hop = opts.hop_length;
-    let total_len = n_fft + (stft.num_frames - 1) * hop;
-
-    let mut samples = vec![0.0; total_len];
-    let mut denom = vec![0.0; total_len];
-
-    // Prepare window
-    let mut win_opts = FrameOptions::default();
-    win_opts.window_type = opts.window_type.clone();
-    win_opts.samp_freq = 1000.0;
-    win_opts.frame_length_ms = opts.win_length as f32;
-    // For ISTFT we usually use a Hanning or Povey window same as analysis
-    let window = Window::new(&win_opts).ok_or("Failed to create window")?;
-
-    let mut ifft = Rfft::new(n_fft, true);
-    let mut frame_buf = vec![0.0; n_fft];
-
-    let bins = n_fft / 2 + 1;
-    let inv_n = 1.0 / n_fft as f32;
-    let pre_scale = if opts.normalized {
-        (n_fft as f32).sqrt()
-    } else {
-        1.0
-    };
-
-    for i in 0..stft.num_frames {
-        // Pack into RFFT format [Re0, ReN/2, Re1, Im1...]
-        let r_ptr = &stft.real[i * bins..(i + 1) * bins];
-        let i_ptr = &stft.imag[i * bins..(i + 1) * bins];
+pub fn istft_compute(opts: &IstftOptions, stft: &StftResult) -> Result<Vec<f32>, KnfError> {
+    let mut istft = OnlineIstft::new(opts.clone())?;
+    let mut samples = istft.accept(stft)?;
+    samples.extend(istft.finish());
+    Ok(samples)
+}
 
-        frame_buf[0] = r_ptr[0] * pre_scale;
-        if n_fft % 2 == 0 {
-            frame_buf[1] = r_ptr[bins - 1] * pre_scale;
-        }
+/// Streaming ISTFT: spectrogram frames go in, audio comes out as soon as no
+/// later frame can overlap it. The output of all chunks followed by
+/// [`finish`](Self::finish) equals [`istft_compute`] over all frames.
+pub struct OnlineIstft {
+    opts: IstftOptions,
+    window: Vec<f32>,
+    ifft: Rfft,
+    frame_buf: Vec<f32>,
+    // Overlap-add sums and window-square normalization from `ola_start` on
+    samples: Vec<f32>,
+    denom: Vec<f32>,
+    ola_start: usize,
+    num_frames: usize,
+    // Output position (before centering is trimmed) up to which audio was emitted
+    emitted: usize,
+}
 
-        for k in 1..(bins - 1) {
-            // Up to bins-1 because bins-1 is N/2
-            frame_buf[2 * k] = r_ptr[k] * pre_scale;
-            frame_buf[2 * k + 1] = i_ptr[k] * pre_scale;
-        }
+impl OnlineIstft {
+    pub fn new(opts: IstftOptions) -> Result<Self, KnfError> {
+        opts.validate()?;
+        // Synthesis window, centered in n_fft like the analysis window
+        let window = padded_window(
+            opts.window_type,
+            opts.custom_window.as_deref(),
+            opts.win_length,
+            opts.n_fft,
+        );
+        Ok(Self {
+            ifft: Rfft::new(opts.n_fft, true),
+            frame_buf: vec![0.0; opts.n_fft],
+            window,
+            samples: Vec::new(),
+            denom: Vec::new(),
+            ola_start: 0,
+            num_frames: 0,
+            emitted: Self::trim(&opts),
+            opts,
+        })
+    }
 
-        ifft.compute(&mut frame_buf);
+    /// Samples of each frame that are held back until later frames arrive.
+    pub fn latency(&self) -> usize {
+        self.opts.n_fft.saturating_sub(self.opts.hop_length)
+    }
 
-        // realfft inverse is unnormalized, multiply by 1/N
-        for x in frame_buf.iter_mut() {
-            *x *= inv_n;
-        }
+    pub fn num_frames(&self) -> usize {
+        self.num_frames
+    }
 
-        // Apply window
-        window.apply(&mut frame_buf[..opts.win_length.min(n_fft)]);
+    /// Adds one frame of `n_fft / 2 + 1` bins; returns the audio completed by it.
+    pub fn accept_frame(&mut self, real: &[f32], imag: &[f32]) -> Result<Vec<f32>, KnfError> {
+        self.add_frame(real, imag)?;
+        Ok(self.emit(self.ready_end()))
+    }
 
-        // Overlap Add
-        let start = i * hop;
-        for k in 0..n_fft {
-            samples[start + k] += frame_buf[k];
+    /// Adds all frames of `stft`; returns the audio completed by them.
+    pub fn accept(&mut self, stft: &StftResult) -> Result<Vec<f32>, KnfError> {
+        for i in 0..stft.num_frames {
+            let (real, imag) = stft.frame(i);
+            self.add_frame(real, imag)?;
         }
+        Ok(self.emit(self.ready_end()))
+    }
 
-        // Accumulate Window Squares for normalization
-        for k in 0..opts.win_length.min(n_fft) {
-            denom[start + k] += window.data[k] * window.data[k];
+    /// Returns the remaining audio and resets for a new signal.
+    pub fn finish(&mut self) -> Vec<f32> {
+        let end = match self.num_frames {
+            0 => 0,
+            n => (n - 1) * self.opts.hop_length + self.opts.n_fft - Self::trim(&self.opts),
+        };
+        let samples = self.emit(end);
+        self.samples.clear();
+        self.denom.clear();
+        self.ola_start = 0;
+        self.num_frames = 0;
+        self.emitted = Self::trim(&self.opts);
+        samples
+    }
+
+    /// Samples cut from each end of the output with `center`.
+    fn trim(opts: &IstftOptions) -> usize {
+        if opts.center {
+            opts.n_fft / 2
+        } else {
+            0
         }
     }
 
-    // Normalize by window overlap
-    for i in 0..total_len {
-        if denom[i] > 1e-10 {
-            samples[i] /= denom[i];
+    /// End of the output that later frames cannot change, short of the
+    /// samples that would be trimmed if the signal ended now.
+    fn ready_end(&self) -> usize {
+        match self.num_frames {
+            0 => 0,
+            n => (n * self.opts.hop_length)
+                .min((n - 1) * self.opts.hop_length + self.opts.n_fft - Self::trim(&self.opts)),
         }
     }
 
-    // Trim centering padding
-    if opts.center {
-        let cut = n_fft / 2;
-        if total_len > 2 * cut {
-            let trimmed = samples[cut..total_len - cut].to_vec();
-            return Ok(trimmed);
+    fn add_frame(&mut self, real: &[f32], imag: &[f32]) -> Result<(), KnfError> {
+        let n_fft = self.opts.n_fft;
+        let bins = n_fft / 2 + 1;
+        KnfError::check_len("STFT bins per frame", bins, real.len())?;
+        KnfError::check_len("STFT bins per frame", bins, imag.len())?;
+
+        let frame_buf = &mut self.frame_buf;
+        let inv_n = 1.0 / n_fft as f32;
+        let pre_scale = if self.opts.normalized {
+            (n_fft as f32).sqrt()
+        } else {
+            1.0
+        };
+
+        // Pack into RFFT format [Re0, ReN/2, Re1, Im1...]
+        frame_buf[0] = real[0] * pre_scale;
+        if n_fft.is_multiple_of(2) {
+            frame_buf[1] = real[bins - 1] * pre_scale;
+        } else if n_fft > 1 {
+            frame_buf[1] = real[bins - 1] * pre_scale;
+            frame_buf[n_fft - 1] = imag[bins - 1] * pre_scale;
+        }
+
+        for k in 1..(bins - 1) {
+            // Up to bins-1 because bins-1 is N/2
+            frame_buf[2 * k] = real[k] * pre_scale;
+            frame_buf[2 * k + 1] = imag[k] * pre_scale;
+        }
+
+        self.ifft.compute(frame_buf);
+
+        // realfft inverse is unnormalized, multiply by 1/N; then apply the window
+        for (x, w) in frame_buf.iter_mut().zip(&self.window) {
+            *x = *x * inv_n * w;
+        }
+
+        // Overlap-add, accumulating window squares for normalization
+        let start = self.num_frames * self.opts.hop_length - self.ola_start;
+        if self.samples.len() < start + n_fft {
+            self.samples.resize(start + n_fft, 0.0);
+            self.denom.resize(start + n_fft, 0.0);
+        }
+        for (k, (&x, &w)) in frame_buf.iter().zip(&self.window).enumerate() {
+            self.samples[start + k] += x;
+            self.denom[start + k] += w * w;
         }
-        return Ok(vec![]);
+        self.num_frames += 1;
+        Ok(())
     }
 
-    Ok(samples)
+    /// Normalized samples from `emitted` up to `end`; releases the overlap-add
+    /// buffer before them.
+    fn emit(&mut self, end: usize) -> Vec<f32> {
+        let mut out = Vec::new();
+        if end > self.emitted {
+            let range = self.emitted - self.ola_start..end - self.ola_start;
+            out.extend(
+                self.samples[range.clone()]
+                    .iter()
+                    .zip(&self.denom[range])
+                    .map(|(&x, &d)| if d > 1e-10 { x / d } else { x }),
+            );
+            self.emitted = end;
+        }
+
+        let finalized = self.num_frames * self.opts.hop_length;
+        let release = self.emitted.min(finalized).saturating_sub(self.ola_start);
+        let release = release.min(self.samples.len());
+        self.samples.drain(..release);
+        self.denom.drain(..release);
+        self.ola_start += release;
+        out
+    }
 }
@@ -1,20 +1,136 @@
+pub mod align;
+pub mod augment;
+#[cfg(feature = "capture")]
+pub mod capture;
+pub mod cepstrum;
+pub mod cfft;
+pub mod channel;
+pub mod composed;
+pub mod convolve;
+pub mod decibel;
+#[cfg(feature = "decode")]
+pub mod decode;
+pub mod denoise;
+pub mod dynamics;
+pub mod endpoint;
+pub mod error;
+#[cfg(feature = "safetensors")]
+pub mod export;
 pub mod fbank;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod filter;
+pub mod fingerprint;
+pub mod gammatone;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+pub mod group_delay;
+pub mod inverse;
 pub mod istft;
+pub mod loudness;
+pub mod lpc;
 pub mod mel;
 pub mod mfcc;
+pub mod offline;
 pub mod online;
+pub mod onset;
+pub mod pcm;
+pub mod pipeline;
+pub mod pool;
 pub mod raw;
+pub mod resample;
 pub mod rfft;
+#[cfg(feature = "simd")]
+mod simd;
+pub mod source;
+pub mod stats;
 pub mod stft;
+#[cfg(feature = "async")]
+pub mod stream;
+pub mod transform;
+pub mod tuning;
 pub mod utils;
+pub mod vocoder;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "wav")]
+pub mod wav;
 pub mod whisper;
+pub mod whisper_window;
 pub mod window;
+pub mod wsola;
+pub mod xcorr;
+pub mod yin;
 
-pub use fbank::{FbankComputer, FbankOptions};
-pub use istft::{istft_compute, IstftOptions};
-pub use mfcc::{MfccComputer, MfccOptions};
-pub use online::OnlineFeature;
+pub use augment::{apply_gain_curve, apply_gain_db, ClipMode, GainOptions, GainPerturbation};
+pub use cepstrum::{
+    complex_cepstrum, inverse_complex_cepstrum, lifter_high, lifter_low, real_cepstrum,
+    spectral_envelope, unwrap_phase, ComplexCepstrum,
+};
+pub use composed::ComposedComputer;
+pub use convolve::{convolve, reverberate, Convolver};
+pub use decibel::{
+    amplitude_to_db, db_to_amplitude, db_to_power, power_to_db, DbOptions, DbReference,
+};
+#[cfg(feature = "decode")]
+pub use decode::extract_features_from_file;
+pub use denoise::{
+    spectral_gate, wiener_denoise, SpectralGate, SpectralGateOptions, WienerFilter, WienerOptions,
+};
+pub use dynamics::{Agc, AgcOptions, Compressor, CompressorOptions};
+pub use endpoint::{Endpoint, EndpointOptions, EndpointRule, Endpointer, EnergyVad};
+pub use error::KnfError;
+pub use fbank::{FbankComputer, FbankOptions, MelFloorMode};
+pub use filter::{
+    a_weight, fir_filter, Biquad, BiquadCascade, BiquadType, DeEmphasis, FirFilter,
+    FirstOrderHighPass, PreEmphasis,
+};
+pub use gammatone::{
+    erb_bandwidth, erb_rate_to_hz, hz_to_erb_rate, Compression, GammatoneComputer,
+    GammatoneFbankOptions, GammatoneOptions, GfccComputer, GfccOptions,
+};
+#[cfg(feature = "gpu")]
+pub use gpu::{compute_features_gpu, GpuMelStage};
+pub use group_delay::{group_delay, ModGdfComputer, ModGdfOptions};
+pub use inverse::{griffin_lim, mel_to_audio, GriffinLimOptions, InverseMelScale};
+pub use istft::{istft_compute, IstftOptions, OnlineIstft};
+pub use loudness::{integrated_loudness, normalize_loudness, LoudnessMeter, LoudnessOptions};
+pub use lpc::{
+    levinson_durbin, lpc, lpc_to_cepstrum, lpc_to_lsf, lsf_to_lpc, Lpc, LpcComputer, LpcOptions,
+    LpccComputer, LpccOptions,
+};
+pub use mfcc::{LfccComputer, LfccOptions, MfccComputer, MfccOptions};
+pub use offline::{
+    compute_features, compute_features_batch, compute_features_batch_with, compute_features_i16,
+    compute_features_with, FeatureMatrix,
+};
+#[cfg(feature = "rayon")]
+pub use offline::{compute_features_par, compute_features_par_with};
+pub use online::{Computer, ComputerState, MultiChannelFeature, OnlineCheckpoint, OnlineFeature};
+pub use onset::{
+    onset_detect, onset_strength, OnsetAggregate, OnsetDetectOptions, OnsetStrengthOptions,
+};
+pub use pcm::{apply_channel_policy, deinterleave, i16_to_f32, ChannelPolicy, PcmScale};
+pub use pipeline::{OnlineFeaturePipeline, OnlinePipelineOptions};
+pub use pool::{ReadyFrames, StreamPool};
 pub use raw::{RawAudioComputer, RawAudioOptions};
-pub use stft::{stft_compute, StftOptions, StftResult};
-pub use whisper::{WhisperComputer, WhisperOptions};
-pub use window::FrameOptions;
+pub use resample::{
+    resample_waveform, ArbitraryResample, LinearResample, StreamingArbitraryResample,
+};
+pub use source::{ReadSource, SampleSource};
+pub use stats::SignalStats;
+pub use stft::{stft_compute, stft_multi_resolution, StftOptions, StftResult};
+#[cfg(feature = "async")]
+pub use stream::FeatureStream;
+pub use tuning::{estimate_tuning, pitch_tuning, TuningOptions};
+pub use vocoder::{phase_vocoder, pitch_shift, time_stretch, TimeStretchOptions};
+#[cfg(feature = "wav")]
+pub use wav::read_wave;
+pub use whisper::{
+    WhisperComputer, WhisperFeatureExtractor, WhisperOptions, WhisperStreamingExtractor,
+};
+pub use whisper_window::{WhisperWindow, WhisperWindowOptions};
+pub use window::{DitherMode, FrameIterator, FrameOptions, WindowType};
+pub use wsola::{wsola, OnlineWsola, WsolaOptions};
+pub use xcorr::{autocorr, xcorr, Correlation, XcorrMode, XcorrNormalization, XcorrOptions};
+pub use yin::{pyin, yin, PitchTrack, PyinOptions, YinComputer, YinOptions};
@@ -0,0 +1,201 @@
+//! Loudness measurement after ITU-R BS.1770-4 (LUFS), for normalizing
+//! corpora to a target loudness.
+
+use crate::error::KnfError;
+use crate::filter::BiquadCascade;
+use std::collections::VecDeque;
+
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct LoudnessOptions {
+    pub samp_freq: f32,
+    /// Channels of the interleaved input.
+    pub num_channels: usize,
+    /// Per-channel weights, e.g. 1.41 for surround channels; empty weighs
+    /// every channel 1.0.
+    pub channel_weights: Vec<f32>,
+    /// Sample value of 0 dBFS; the default suits [`PcmScale::Kaldi`](crate::PcmScale::Kaldi).
+    pub full_scale: f32,
+}
+
+impl Default for LoudnessOptions {
+    fn default() -> Self {
+        Self {
+            samp_freq: 16000.0,
+            num_channels: 1,
+            channel_weights: Vec::new(),
+            full_scale: 32768.0,
+        }
+    }
+}
+
+impl LoudnessOptions {
+    pub fn validate(&self) -> Result<(), KnfError> {
+        if self.samp_freq <= 0.0 || self.full_scale <= 0.0 {
+            return Err(KnfError::invalid(
+                "samp_freq and full_scale must be positive",
+            ));
+        }
+        if self.num_channels == 0 {
+            return Err(KnfError::invalid("num_channels must be positive"));
+        }
+        if !self.channel_weights.is_empty() && self.channel_weights.len() != self.num_channels {
+            return Err(KnfError::SizeMismatch {
+                what: "channel_weights",
+                expected: self.num_channels,
+                actual: self.channel_weights.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Streaming loudness meter: K-weighted mean square over 400 ms blocks with
+/// 75% overlap, gated at -70 LUFS and then 10 LU below the ungated level.
+#[derive(Clone, Debug)]
+pub struct LoudnessMeter {
+    opts: LoudnessOptions,
+    filters: Vec<BiquadCascade>,
+    weights: Vec<f64>,
+    // Samples per channel in a 100 ms step
+    step: usize,
+    // Weighted sum of squares of the current step, and samples in it
+    step_energy: f64,
+    step_len: usize,
+    // Energies of the latest four steps, which make up one block
+    recent: VecDeque<f64>,
+    // Mean square of every complete block
+    blocks: Vec<f64>,
+    channel: usize,
+}
+
+impl LoudnessMeter {
+    pub fn new(opts: LoudnessOptions) -> Result<Self, KnfError> {
+        opts.validate()?;
+        let filter = BiquadCascade::k_weighting(opts.samp_freq)?;
+        let weights = if opts.channel_weights.is_empty() {
+            vec![1.0; opts.num_channels]
+        } else {
+            opts.channel_weights.iter().map(|&w| w as f64).collect()
+        };
+        Ok(Self {
+            filters: vec![filter; opts.num_channels],
+            weights,
+            step: ((opts.samp_freq * 0.1).round() as usize).max(1),
+            step_energy: 0.0,
+            step_len: 0,
+            recent: VecDeque::with_capacity(4),
+            blocks: Vec::new(),
+            channel: 0,
+            opts,
+        })
+    }
+
+    /// Adds interleaved samples; chunks may split a frame across calls.
+    pub fn accept_waveform(&mut self, samples: &[f32]) {
+        let scale = 1.0 / self.opts.full_scale;
+        for &x in samples {
+            let c = self.channel;
+            let y = self.filters[c].process_sample(x * scale) as f64;
+            self.step_energy += self.weights[c] * y * y;
+            self.channel += 1;
+            if self.channel == self.opts.num_channels {
+                self.channel = 0;
+                self.step_len += 1;
+                if self.step_len == self.step {
+                    self.finish_step();
+                }
+            }
+        }
+    }
+
+    fn finish_step(&mut self) {
+        if self.recent.len() == 4 {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(self.step_energy);
+        self.step_energy = 0.0;
+        self.step_len = 0;
+        if self.recent.len() == 4 {
+            let energy: f64 = self.recent.iter().sum();
+            self.blocks.push(energy / (4 * self.step) as f64);
+        }
+    }
+
+    /// Number of complete 400 ms blocks.
+    pub fn num_blocks(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Loudness of the latest 400 ms block, or `None` before the first one.
+    pub fn momentary(&self) -> Option<f32> {
+        self.blocks.last().map(|&z| lufs(z) as f32)
+    }
+
+    /// Gated loudness of everything so far in LUFS; negative infinity when
+    /// no block passes the gates (silence, or under 400 ms of input).
+    pub fn integrated(&self) -> f32 {
+        let gated_mean = |threshold: f64| {
+            let (sum, count) = self
+                .blocks
+                .iter()
+                .filter(|&&z| lufs(z) > threshold)
+                .fold((0.0, 0usize), |(s, n), &z| (s + z, n + 1));
+            (count > 0).then(|| sum / count as f64)
+        };
+        let Some(ungated) = gated_mean(ABSOLUTE_GATE_LUFS) else {
+            return f32::NEG_INFINITY;
+        };
+        let relative = lufs(ungated) + RELATIVE_GATE_LU;
+        match gated_mean(relative.max(ABSOLUTE_GATE_LUFS)) {
+            Some(z) => lufs(z) as f32,
+            None => f32::NEG_INFINITY,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.filters.iter_mut().for_each(BiquadCascade::reset);
+        self.step_energy = 0.0;
+        self.step_len = 0;
+        self.recent.clear();
+        self.blocks.clear();
+        self.channel = 0;
+    }
+}
+
+fn lufs(mean_square: f64) -> f64 {
+    -0.691 + 10.0 * mean_square.log10()
+}
+
+/// Integrated loudness of interleaved `samples` in LUFS.
+pub fn integrated_loudness(samples: &[f32], opts: &LoudnessOptions) -> Result<f32, KnfError> {
+    let mut meter = LoudnessMeter::new(opts.clone())?;
+    meter.accept_waveform(samples);
+    Ok(meter.integrated())
+}
+
+/// Scales `samples` in place to `target_lufs` and returns the gain applied
+/// in dB. Fails on input too short or quiet to measure.
+pub fn normalize_loudness(
+    samples: &mut [f32],
+    opts: &LoudnessOptions,
+    target_lufs: f32,
+) -> Result<f32, KnfError> {
+    let loudness = integrated_loudness(samples, opts)?;
+    if !loudness.is_finite() {
+        return Err(KnfError::invalid(
+            "Loudness cannot be measured: input is silent or shorter than 400 ms",
+        ));
+    }
+    let gain_db = target_lufs - loudness;
+    let gain = 10f32.powf(gain_db / 20.0);
+    samples.iter_mut().for_each(|x| *x *= gain);
+    Ok(gain_db)
+}
@@ -0,0 +1,425 @@
+//! Linear prediction analysis: autocorrelation of a windowed frame followed
+//! by the Levinson-Durbin recursion.
+
+use crate::error::KnfError;
+use crate::mfcc::lifter_coeffs;
+use crate::online::Computer;
+use crate::utils::log_energy;
+use crate::window::FrameOptions;
+
+/// LPC analysis of one frame.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Lpc {
+    /// Coefficients `[1, a_1, ..., a_p]` of the inverse filter
+    /// `A(z) = 1 + a_1 z^-1 + ... + a_p z^-p`, so `x[n]` is predicted as
+    /// `-(a_1 x[n-1] + ... + a_p x[n-p])`.
+    pub coeffs: Vec<f32>,
+    /// Reflection (PARCOR) coefficients `k_1, ..., k_p`.
+    pub reflection: Vec<f32>,
+    /// Energy of the prediction residual over the frame.
+    pub error: f32,
+}
+
+impl Lpc {
+    pub fn order(&self) -> usize {
+        self.reflection.len()
+    }
+}
+
+/// Autocorrelation `r[0..=max_lag]` of `signal`.
+pub(crate) fn autocorrelation(signal: &[f32], max_lag: usize) -> Vec<f64> {
+    (0..=max_lag)
+        .map(|lag| {
+            signal
+                .iter()
+                .zip(signal.iter().skip(lag))
+                .map(|(&a, &b)| a as f64 * b as f64)
+                .sum()
+        })
+        .collect()
+}
+
+/// Levinson-Durbin recursion over the autocorrelation `r[0..=order]`.
+///
+/// A zero `r[0]` (a silent frame) gives `A(z) = 1` with zero error. If the
+/// error vanishes before `order` is reached, the remaining coefficients are 0.
+pub fn levinson_durbin(autocorr: &[f32], order: usize) -> Result<Lpc, KnfError> {
+    let r: Vec<f64> = autocorr.iter().map(|&x| x as f64).collect();
+    levinson(&r, order)
+}
+
+fn levinson(r: &[f64], order: usize) -> Result<Lpc, KnfError> {
+    if r.len() <= order {
+        return Err(KnfError::SizeMismatch {
+            what: "autocorrelation",
+            expected: order + 1,
+            actual: r.len(),
+        });
+    }
+    if r[0] < 0.0 {
+        return Err(KnfError::invalid(
+            "r[0] of an autocorrelation must be non-negative",
+        ));
+    }
+    let mut a = vec![0.0f64; order + 1];
+    let mut prev = vec![0.0f64; order + 1];
+    let mut reflection = vec![0.0f32; order];
+    a[0] = 1.0;
+    let mut error = r[0];
+    for i in 1..=order {
+        if error <= r[0] * 1e-12 {
+            break;
+        }
+        let acc: f64 = (0..i).map(|j| a[j] * r[i - j]).sum();
+        let k = -acc / error;
+        prev[..i].copy_from_slice(&a[..i]);
+        for j in 1..i {
+            a[j] += k * prev[i - j];
+        }
+        a[i] = k;
+        reflection[i - 1] = k as f32;
+        error *= 1.0 - k * k;
+    }
+    Ok(Lpc {
+        coeffs: a.iter().map(|&x| x as f32).collect(),
+        reflection,
+        error: error.max(0.0) as f32,
+    })
+}
+
+/// LPC analysis of order `order` of an (already windowed) frame.
+pub fn lpc(frame: &[f32], order: usize) -> Result<Lpc, KnfError> {
+    levinson(&autocorrelation(frame, order), order)
+}
+
+/// First `num_ceps` cepstral coefficients of the all-pole model `G / A(z)`
+/// with `G^2 = lpc.error`, by the standard recursion
+/// `c_n = -a_n - sum_{k=1}^{n-1} (k / n) c_k a_{n-k}` (with `a_n = 0` for
+/// `n > p`); `c_0 = ln G`.
+pub fn lpc_to_cepstrum(lpc: &Lpc, num_ceps: usize) -> Vec<f32> {
+    let a = &lpc.coeffs;
+    let coeff = |n: usize| a.get(n).copied().unwrap_or(0.0);
+    let mut c = vec![0.0f32; num_ceps];
+    if let Some(c0) = c.first_mut() {
+        *c0 = 0.5 * log_energy(lpc.error);
+    }
+    for n in 1..num_ceps {
+        let acc: f32 = (1..n).map(|k| k as f32 * c[k] * coeff(n - k)).sum::<f32>();
+        c[n] = -coeff(n) - acc / n as f32;
+    }
+    c
+}
+
+/// Grid points on `(0, pi)` searched for sign changes before bisection.
+const LSF_GRID_SIZE: usize = 4096;
+
+/// Symmetric `P(z) = A(z) + z^-(p+1) A(1/z)` and antisymmetric
+/// `Q(z) = A(z) - z^-(p+1) A(1/z)` of `coeffs = [1, a_1, ..., a_p]`.
+fn lsp_polynomials(coeffs: &[f64]) -> (Vec<f64>, Vec<f64>) {
+    let n = coeffs.len() + 1;
+    let at = |i: usize| if i < coeffs.len() { coeffs[i] } else { 0.0 };
+    let p = (0..n).map(|i| at(i) + at(n - 1 - i)).collect();
+    let q = (0..n).map(|i| at(i) - at(n - 1 - i)).collect();
+    (p, q)
+}
+
+/// Roots in `(0, pi)` of the real function `e^{jwm} P(e^{jw})` (cosine
+/// series, `symmetric`) or `-j e^{jwm} Q(e^{jw})` (sine series), with `m`
+/// half the polynomial degree.
+fn unit_circle_roots(poly: &[f64], symmetric: bool) -> Vec<f64> {
+    let m = (poly.len() - 1) as f64 / 2.0;
+    let eval = |w: f64| -> f64 {
+        poly.iter()
+            .enumerate()
+            .map(|(k, &c)| {
+                let x = (m - k as f64) * w;
+                c * if symmetric { x.cos() } else { x.sin() }
+            })
+            .sum()
+    };
+    let step = std::f64::consts::PI / LSF_GRID_SIZE as f64;
+    let mut roots = Vec::new();
+    let mut lo = step;
+    let mut f_lo = eval(lo);
+    for i in 2..LSF_GRID_SIZE {
+        let hi = i as f64 * step;
+        let f_hi = eval(hi);
+        if f_lo == 0.0 {
+            roots.push(lo);
+        } else if f_lo * f_hi < 0.0 {
+            let (mut a, mut b, mut f_a) = (lo, hi, f_lo);
+            for _ in 0..60 {
+                let mid = 0.5 * (a + b);
+                let f_mid = eval(mid);
+                if f_a * f_mid <= 0.0 {
+                    b = mid;
+                } else {
+                    a = mid;
+                    f_a = f_mid;
+                }
+            }
+            roots.push(0.5 * (a + b));
+        }
+        lo = hi;
+        f_lo = f_hi;
+    }
+    roots
+}
+
+/// Line spectral frequencies in radians, increasing in `(0, pi)`, of the
+/// inverse filter `coeffs = [1, a_1, ..., a_p]`: the angles of the
+/// unit-circle roots of `P(z)` and `Q(z)`, which interlace starting with
+/// `P`. The line spectral pairs are their cosines.
+///
+/// Fails unless `A(z)` is minimum phase (all roots inside the unit circle),
+/// as for the output of [`lpc`].
+pub fn lpc_to_lsf(coeffs: &[f32]) -> Result<Vec<f32>, KnfError> {
+    if coeffs.first() != Some(&1.0) {
+        return Err(KnfError::invalid(
+            "LPC coefficients must start with a leading 1",
+        ));
+    }
+    let order = coeffs.len() - 1;
+    let a: Vec<f64> = coeffs.iter().map(|&x| x as f64).collect();
+    let (p, q) = lsp_polynomials(&a);
+    let p_roots = unit_circle_roots(&p, true);
+    let q_roots = unit_circle_roots(&q, false);
+    if p_roots.len() != order.div_ceil(2) || q_roots.len() != order / 2 {
+        return Err(KnfError::invalid("LPC coefficients are not minimum phase"));
+    }
+    let mut lsf = Vec::with_capacity(order);
+    for i in 0..order {
+        lsf.push(if i % 2 == 0 {
+            p_roots[i / 2]
+        } else {
+            q_roots[i / 2]
+        });
+    }
+    if lsf.windows(2).any(|w| w[0] >= w[1]) {
+        return Err(KnfError::invalid("LPC coefficients are not minimum phase"));
+    }
+    Ok(lsf.iter().map(|&w| w as f32).collect())
+}
+
+/// Inverse filter `[1, a_1, ..., a_p]` of increasing line spectral
+/// frequencies in `(0, pi)`, e.g. interpolated between frames.
+pub fn lsf_to_lpc(lsf: &[f32]) -> Result<Vec<f32>, KnfError> {
+    let pi = std::f32::consts::PI;
+    if lsf.iter().any(|&w| w.is_nan() || w <= 0.0 || w >= pi)
+        || lsf.windows(2).any(|w| w[0] >= w[1])
+    {
+        return Err(KnfError::invalid(
+            "Line spectral frequencies must be increasing in (0, pi)",
+        ));
+    }
+    let order = lsf.len();
+    // Trivial roots: z = -1 for P and z = 1 for Q (even order), or both for Q
+    let (mut p, mut q) = if order.is_multiple_of(2) {
+        (vec![1.0, 1.0], vec![1.0, -1.0])
+    } else {
+        (vec![1.0], vec![1.0, 0.0, -1.0])
+    };
+    for (i, &w) in lsf.iter().enumerate() {
+        let poly = if i % 2 == 0 { &mut p } else { &mut q };
+        *poly = multiply(poly, &[1.0, -2.0 * (w as f64).cos(), 1.0]);
+    }
+    Ok((0..=order).map(|i| (0.5 * (p[i] + q[i])) as f32).collect())
+}
+
+fn multiply(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut out = vec![0.0; a.len() + b.len() - 1];
+    for (i, &x) in a.iter().enumerate() {
+        for (j, &y) in b.iter().enumerate() {
+            out[i + j] += x * y;
+        }
+    }
+    out
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct LpcOptions {
+    pub frame_opts: FrameOptions,
+    pub order: usize,
+}
+
+impl Default for LpcOptions {
+    fn default() -> Self {
+        Self {
+            frame_opts: FrameOptions::default(),
+            order: 12,
+        }
+    }
+}
+
+impl LpcOptions {
+    pub fn validate(&self) -> Result<(), KnfError> {
+        self.frame_opts.validate()?;
+        if self.order == 0 || self.order >= self.frame_opts.window_size() {
+            return Err(KnfError::invalid(format!(
+                "LPC order ({}) must be in [1, window_size ({}))",
+                self.order,
+                self.frame_opts.window_size()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Per-frame LPC. Each feature row is `[a_1, ..., a_p, error]`.
+pub struct LpcComputer {
+    pub opts: LpcOptions,
+}
+
+impl LpcComputer {
+    pub fn new(opts: LpcOptions) -> Result<Self, KnfError> {
+        opts.validate()?;
+        Ok(Self { opts })
+    }
+
+    pub fn dim(&self) -> usize {
+        self.opts.order + 1
+    }
+
+    /// Full analysis of one windowed frame.
+    pub fn analyze(&self, signal_frame: &[f32]) -> Lpc {
+        let frame = &signal_frame[..self.opts.frame_opts.window_size().min(signal_frame.len())];
+        // The order is below the frame length and r[0] is a sum of squares
+        lpc(frame, self.opts.order).unwrap_or_default()
+    }
+
+    pub fn compute(&mut self, signal_frame: &mut [f32], feature: &mut [f32]) {
+        let lpc = self.analyze(signal_frame);
+        let order = self.opts.order;
+        if lpc.coeffs.len() == order + 1 && feature.len() > order {
+            feature[..order].copy_from_slice(&lpc.coeffs[1..]);
+            feature[order] = lpc.error;
+        }
+    }
+}
+
+impl Computer for LpcComputer {
+    fn dim(&self) -> usize {
+        LpcComputer::dim(self)
+    }
+
+    fn frame_opts(&self) -> &FrameOptions {
+        &self.opts.frame_opts
+    }
+
+    fn need_raw_energy(&self) -> bool {
+        false
+    }
+
+    fn compute(
+        &mut self,
+        _raw_log_energy: f32,
+        _vtln_warp: f32,
+        window: &mut [f32],
+        feature: &mut [f32],
+    ) {
+        LpcComputer::compute(self, window, feature)
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct LpccOptions {
+    pub frame_opts: FrameOptions,
+    pub order: usize,
+    /// Cepstra per frame, including `c_0`; may exceed `order`.
+    pub num_ceps: usize,
+    pub cepstral_lifter: f32,
+}
+
+impl Default for LpccOptions {
+    fn default() -> Self {
+        Self {
+            frame_opts: FrameOptions::default(),
+            order: 12,
+            num_ceps: 13,
+            cepstral_lifter: 0.0,
+        }
+    }
+}
+
+impl LpccOptions {
+    pub fn validate(&self) -> Result<(), KnfError> {
+        self.lpc_options().validate()?;
+        if self.num_ceps == 0 {
+            return Err(KnfError::invalid("num_ceps must be positive"));
+        }
+        if self.cepstral_lifter < 0.0 {
+            return Err(KnfError::invalid("cepstral_lifter must be non-negative"));
+        }
+        Ok(())
+    }
+
+    fn lpc_options(&self) -> LpcOptions {
+        LpcOptions {
+            frame_opts: self.frame_opts.clone(),
+            order: self.order,
+        }
+    }
+}
+
+/// LPC-derived cepstral coefficients (LPCC), with the gain term in `c_0`.
+pub struct LpccComputer {
+    pub opts: LpccOptions,
+    lpc: LpcComputer,
+    lifter_coeffs: Vec<f32>,
+}
+
+impl LpccComputer {
+    pub fn new(opts: LpccOptions) -> Result<Self, KnfError> {
+        opts.validate()?;
+        Ok(Self {
+            lpc: LpcComputer::new(opts.lpc_options())?,
+            lifter_coeffs: lifter_coeffs(opts.num_ceps, opts.cepstral_lifter),
+            opts,
+        })
+    }
+
+    pub fn dim(&self) -> usize {
+        self.opts.num_ceps
+    }
+
+    pub fn compute(&mut self, signal_frame: &mut [f32], feature: &mut [f32]) {
+        let ceps = lpc_to_cepstrum(&self.lpc.analyze(signal_frame), self.opts.num_ceps);
+        for ((out, c), l) in feature.iter_mut().zip(ceps).zip(&self.lifter_coeffs) {
+            *out = c * l;
+        }
+    }
+}
+
+impl Computer for LpccComputer {
+    fn dim(&self) -> usize {
+        LpccComputer::dim(self)
+    }
+
+    fn frame_opts(&self) -> &FrameOptions {
+        &self.opts.frame_opts
+    }
+
+    fn need_raw_energy(&self) -> bool {
+        false
+    }
+
+    fn compute(
+        &mut self,
+        _raw_log_energy: f32,
+        _vtln_warp: f32,
+        window: &mut [f32],
+        feature: &mut [f32],
+    ) {
+        LpccComputer::compute(self, window, feature)
+    }
+}
@@ -1,16 +1,70 @@
+use crate::error::KnfError;
+use crate::gammatone::{erb_rate_to_hz, hz_to_erb_rate};
+use crate::utils::inner_product;
 use crate::window::FrameOptions;
+use std::collections::hash_map::{Entry, HashMap};
+
+/// Frequency scale on which the triangle edges of [`MelBanks`] are evenly
+/// spaced.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum FrequencyScale {
+    /// The mel scale selected by `use_htk_mel_formula` (Kaldi banks) or
+    /// `use_slaney_mel_scale` (librosa banks).
+    #[default]
+    Mel,
+    /// ERB rate of Glasberg & Moore, `21.4 * log10(1 + 0.00437 f)`.
+    Erb,
+    /// Hz, as LFCC front-ends use.
+    Linear,
+}
+
+/// Normalization of the librosa-style banks, as `librosa.filters.mel`'s
+/// `norm` argument.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum MelNorm {
+    /// Each triangle scaled to unit area (librosa's default).
+    #[default]
+    Slaney,
+    /// Peak weight 1.
+    None,
+}
 
 #[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct MelOptions {
     pub num_bins: usize,
     pub low_freq: f32,
     pub high_freq: f32,
     pub vtln_low: f32,
     pub vtln_high: f32,
+    /// HTK compatibility: drop the lowest weight of the first bin when
+    /// `low_freq` is nonzero and floor mel energies at 1.0 (so log-mels are >= 0).
     pub htk_mode: bool,
+    /// Build filters like `librosa.filters.mel` instead of Kaldi's `MelBanks`.
     pub is_librosa: bool,
+    /// With `is_librosa`, use the Slaney mel scale (librosa's default) instead of HTK.
     pub use_slaney_mel_scale: bool,
-    pub norm: String,
+    /// Use HTK's `2595 * log10(1 + f / 700)` instead of Kaldi's `1127 * ln(1 + f / 700)`.
+    pub use_htk_mel_formula: bool,
+    /// Scale the triangles are spaced on; the mel scale options only apply
+    /// to [`FrequencyScale::Mel`].
+    pub frequency_scale: FrequencyScale,
+    /// Only used with `is_librosa`.
+    pub norm: MelNorm,
     pub floor_to_int_bin: bool,
     pub debug_mel: bool,
 }
@@ -26,18 +80,57 @@ impl Default for MelOptions {
             htk_mode: false,
             is_librosa: false,
             use_slaney_mel_scale: true,
-            norm: "slaney".to_string(),
+            use_htk_mel_formula: false,
+            frequency_scale: FrequencyScale::Mel,
+            norm: MelNorm::Slaney,
             floor_to_int_bin: false,
             debug_mel: false,
         }
     }
 }
 
+#[derive(Clone, Debug)]
 pub struct MelBanks {
     pub num_bins: usize,
     pub num_fft_bins: usize,
-    // Flattened weights: [bin_idx * num_fft_bins + fft_bin_idx]
-    pub weights: Vec<f32>,
+    pub htk_mode: bool,
+    /// Per-bin `(first_fft_bin, weights)`, trimmed to the nonzero span of each
+    /// triangle as in Kaldi's `MelBanks::GetBins`.
+    pub bins: Vec<(usize, Vec<f32>)>,
+    /// Per-bin `[left, center, right]` triangle edges in Hz (after VTLN warping).
+    pub edges_hz: Vec<[f32; 3]>,
+    /// The same edges on the scale the banks were built with (mel, ERB rate,
+    /// or Hz for linear banks).
+    pub edges_mel: Vec<[f32; 3]>,
+}
+
+impl MelOptions {
+    /// Checks the bin count and that `low_freq < high_freq <= nyquist` for the
+    /// sampling rate in `frame_opts` (a non-positive `high_freq` is relative to
+    /// the Nyquist frequency).
+    pub fn validate(&self, frame_opts: &FrameOptions) -> Result<(), KnfError> {
+        let min_bins = if self.is_librosa { 1 } else { 3 };
+        if self.num_bins < min_bins {
+            return Err(KnfError::invalid(format!(
+                "num_bins ({}) must be at least {}",
+                self.num_bins, min_bins
+            )));
+        }
+
+        let nyquist = 0.5 * frame_opts.samp_freq;
+        let high_freq = if self.high_freq > 0.0 {
+            self.high_freq
+        } else {
+            nyquist + self.high_freq
+        };
+        if self.low_freq < 0.0 || high_freq > nyquist || high_freq <= self.low_freq {
+            return Err(KnfError::invalid(format!(
+                "Invalid frequency range for Mel banks: low_freq {} and high_freq {} with nyquist {}",
+                self.low_freq, high_freq, nyquist
+            )));
+        }
+        Ok(())
+    }
 }
 
 impl MelBanks {
@@ -45,7 +138,7 @@ impl MelBanks {
         opts: &MelOptions,
         frame_opts: &FrameOptions,
         vtln_warp: f32,
-    ) -> Result<Self, String> {
+    ) -> Result<Self, KnfError> {
         let window_length_padded = frame_opts.padded_window_size();
         let num_fft_bins = window_length_padded / 2;
         let sample_freq = frame_opts.samp_freq;
@@ -57,29 +150,48 @@ impl MelBanks {
             nyquist + opts.high_freq
         };
 
-        if opts.low_freq < 0.0
-            || opts.low_freq >= nyquist
-            || high_freq <= 0.0
-            || high_freq > nyquist
-            || high_freq <= opts.low_freq
-        {
-            return Err("Invalid frequency range for Mel banks".to_string());
+        opts.validate(frame_opts)?;
+
+        if opts.is_librosa {
+            return Self::new_librosa(opts, frame_opts, high_freq);
         }
 
         let fft_bin_width = sample_freq / window_length_padded as f32;
-        let mel_low = Self::mel_scale(opts.low_freq);
-        let mel_high = Self::mel_scale(high_freq);
+        let scale = match opts.frequency_scale {
+            FrequencyScale::Erb => KaldiScale::Erb,
+            FrequencyScale::Linear => KaldiScale::Linear,
+            FrequencyScale::Mel if opts.use_htk_mel_formula => KaldiScale::Htk,
+            FrequencyScale::Mel => KaldiScale::Kaldi,
+        };
+        let mel_low = Self::mel_scale(opts.low_freq, scale);
+        let mel_high = Self::mel_scale(high_freq, scale);
         let mel_delta = (mel_high - mel_low) / (opts.num_bins as f32 + 1.0);
 
-        // VTLN setup (simplified for brevity, matching C logic)
         let vtln_low = opts.vtln_low;
         let vtln_high = if opts.vtln_high < 0.0 {
             opts.vtln_high + nyquist
         } else {
             opts.vtln_high
         };
+        if (vtln_warp - 1.0).abs() > 1e-5 || !vtln_warp.is_finite() {
+            if !(vtln_warp > 0.0 && vtln_warp.is_finite()) {
+                return Err(KnfError::invalid(format!(
+                    "vtln_warp must be positive, got {}",
+                    vtln_warp
+                )));
+            }
+            if !(vtln_low > opts.low_freq && vtln_high > vtln_low && vtln_high < high_freq) {
+                return Err(KnfError::invalid(format!(
+                    "Bad VTLN cutoffs vtln_low {} and vtln_high {} for frequency range [{}, {}]",
+                    vtln_low, vtln_high, opts.low_freq, high_freq
+                )));
+            }
+        }
 
-        let mut weights = vec![0.0; opts.num_bins * num_fft_bins];
+        let mut bins = Vec::with_capacity(opts.num_bins);
+        let mut edges_hz = Vec::with_capacity(opts.num_bins);
+        let mut edges_mel = Vec::with_capacity(opts.num_bins);
+        let mut row = vec![0.0; num_fft_bins];
 
         for bin in 0..opts.num_bins {
             let mut left_mel = mel_low + bin as f32 * mel_delta;
@@ -94,6 +206,7 @@ impl MelBanks {
                     high_freq,
                     vtln_warp,
                     left_mel,
+                    scale,
                 );
                 center_mel = Self::vtln_warp_mel(
                     vtln_low,
@@ -102,6 +215,7 @@ impl MelBanks {
                     high_freq,
                     vtln_warp,
                     center_mel,
+                    scale,
                 );
                 right_mel = Self::vtln_warp_mel(
                     vtln_low,
@@ -110,12 +224,13 @@ impl MelBanks {
                     high_freq,
                     vtln_warp,
                     right_mel,
+                    scale,
                 );
             }
 
-            for i in 0..num_fft_bins {
+            for (i, w) in row.iter_mut().enumerate() {
                 let freq = fft_bin_width * i as f32;
-                let mel = Self::mel_scale(freq);
+                let mel = Self::mel_scale(freq, scale);
                 let weight = if mel > left_mel && mel < right_mel {
                     if mel <= center_mel {
                         (mel - left_mel) / (center_mel - left_mel)
@@ -126,44 +241,248 @@ impl MelBanks {
                     0.0
                 };
 
-                if weight > 0.0 {
-                    weights[bin * num_fft_bins + i] = weight;
-                }
+                *w = weight;
+            }
+
+            let (first, mut weights) = trim_bin(&row);
+            // Kaldi's htk_mode zeroes the first nonzero weight of the first bin
+            if opts.htk_mode && bin == 0 && mel_low != 0.0 && !weights.is_empty() {
+                weights[0] = 0.0;
+            }
+            bins.push((first, weights));
+
+            let mels = [left_mel, center_mel, right_mel];
+            edges_hz.push(mels.map(|m| Self::inverse_mel_scale(m, scale)));
+            edges_mel.push(mels);
+        }
+
+        Ok(Self {
+            num_bins: opts.num_bins,
+            num_fft_bins,
+            htk_mode: opts.htk_mode,
+            bins,
+            edges_hz,
+            edges_mel,
+        })
+    }
+
+    /// Filterbank construction matching `librosa.filters.mel`: triangles are
+    /// built in Hz between mel-spaced edges, and VTLN warping is not applied.
+    fn new_librosa(
+        opts: &MelOptions,
+        frame_opts: &FrameOptions,
+        high_freq: f32,
+    ) -> Result<Self, KnfError> {
+        let slaney_norm = opts.norm == MelNorm::Slaney;
+
+        let n_fft = frame_opts.padded_window_size();
+        let num_fft_bins = n_fft / 2;
+        let sample_freq = frame_opts.samp_freq as f64;
+        let scale = match opts.frequency_scale {
+            FrequencyScale::Erb => MelScale::Erb,
+            FrequencyScale::Linear => MelScale::Linear,
+            FrequencyScale::Mel if opts.use_slaney_mel_scale => MelScale::Slaney,
+            FrequencyScale::Mel => MelScale::Htk,
+        };
+
+        let mel_low = scale.hz_to_mel(opts.low_freq as f64);
+        let mel_high = scale.hz_to_mel(high_freq as f64);
+        let mel_step = (mel_high - mel_low) / (opts.num_bins + 1) as f64;
+        let edges_hz: Vec<f64> = (0..opts.num_bins + 2)
+            .map(|i| scale.mel_to_hz(mel_low + i as f64 * mel_step))
+            .collect();
+
+        let mut bins = Vec::with_capacity(opts.num_bins);
+        let mut edges = Vec::with_capacity(opts.num_bins);
+        let mut row = vec![0.0; num_fft_bins];
+        for bin in 0..opts.num_bins {
+            let (left, center, right) = (edges_hz[bin], edges_hz[bin + 1], edges_hz[bin + 2]);
+            // Slaney normalization scales each triangle to unit area
+            let enorm = if slaney_norm {
+                2.0 / (right - left)
+            } else {
+                1.0
+            };
+            for (i, w) in row.iter_mut().enumerate() {
+                let freq = i as f64 * sample_freq / n_fft as f64;
+                let lower = (freq - left) / (center - left);
+                let upper = (right - freq) / (right - center);
+                let weight = lower.min(upper).max(0.0);
+                *w = (weight * enorm) as f32;
             }
+            bins.push(trim_bin(&row));
+            edges.push([left, center, right]);
         }
 
         Ok(Self {
             num_bins: opts.num_bins,
             num_fft_bins,
-            weights,
+            htk_mode: false,
+            bins,
+            edges_hz: edges.iter().map(|e| e.map(|f| f as f32)).collect(),
+            edges_mel: edges
+                .iter()
+                .map(|e| e.map(|f| scale.hz_to_mel(f) as f32))
+                .collect(),
         })
     }
 
+    /// Builds banks from a precomputed row-major `num_bins x cols` weight matrix,
+    /// e.g. the filters shipped with OpenAI Whisper. `cols` is either N/2 or
+    /// N/2 + 1 for the padded window size N of `frame_opts`; a Nyquist column,
+    /// if present, must be zero since the Nyquist bin is not integrated.
+    ///
+    /// Edge frequencies are unknown for such banks, so `edges_hz` and
+    /// `edges_mel` are left empty.
+    pub fn from_weights(
+        weights: &[f32],
+        num_bins: usize,
+        frame_opts: &FrameOptions,
+    ) -> Result<Self, KnfError> {
+        let num_fft_bins = frame_opts.padded_window_size() / 2;
+        if num_bins == 0 || !weights.len().is_multiple_of(num_bins) {
+            return Err(KnfError::input(format!(
+                "Cannot split {} mel weights into {} bins",
+                weights.len(),
+                num_bins
+            )));
+        }
+        let cols = weights.len() / num_bins;
+        if cols != num_fft_bins && cols != num_fft_bins + 1 {
+            return Err(KnfError::input(format!(
+                "Mel weights have {} columns, expected {} or {} for n_fft {}",
+                cols,
+                num_fft_bins,
+                num_fft_bins + 1,
+                frame_opts.padded_window_size()
+            )));
+        }
+
+        let mut bins = Vec::with_capacity(num_bins);
+        for row in weights.chunks(cols) {
+            if cols > num_fft_bins && row[num_fft_bins] != 0.0 {
+                return Err(KnfError::input(
+                    "Mel weights with a nonzero Nyquist column are not supported",
+                ));
+            }
+            bins.push(trim_bin(&row[..num_fft_bins]));
+        }
+
+        Ok(Self {
+            num_bins,
+            num_fft_bins,
+            htk_mode: false,
+            bins,
+            edges_hz: Vec::new(),
+            edges_mel: Vec::new(),
+        })
+    }
+
+    /// Loads the `num_bins x cols` float32 array `name` from an `.npz` archive,
+    /// e.g. `mel_80` or `mel_128` from Whisper's `mel_filters.npz`.
+    #[cfg(feature = "npz")]
+    pub fn from_npz<P: AsRef<std::path::Path>>(
+        path: P,
+        name: &str,
+        frame_opts: &FrameOptions,
+    ) -> Result<Self, KnfError> {
+        let mut archive = npyz::npz::NpzArchive::open(path).map_err(KnfError::io)?;
+        let array = archive
+            .by_name(name)
+            .map_err(KnfError::io)?
+            .ok_or_else(|| KnfError::input(format!("Array {} not found in npz archive", name)))?;
+        let num_bins = match array.shape() {
+            &[rows, _] => rows as usize,
+            shape => {
+                return Err(KnfError::input(format!(
+                    "Expected a 2-D mel filter array, got shape {:?}",
+                    shape
+                )))
+            }
+        };
+        if array.order() != npyz::Order::C {
+            return Err(KnfError::input(
+                "Fortran-order mel filter arrays are not supported",
+            ));
+        }
+        let weights: Vec<f32> = array.into_vec().map_err(KnfError::io)?;
+        Self::from_weights(&weights, num_bins, frame_opts)
+    }
+
     pub fn compute(&self, fft_energies: &[f32], mel_energies_out: &mut [f32]) {
         assert_eq!(fft_energies.len(), self.num_fft_bins + 1); // +1 because FFT result usually has DC and Nyquist packed
         assert_eq!(mel_energies_out.len(), self.num_bins);
 
-        // The FFT energies passed here are expected to be the power spectrum (N/2 + 1) elements.
-        // But `weights` was built for `num_fft_bins` (N/2).
-        // Typically, we ignore the Nyquist bin or handle it if `weights` supports it.
-        // The C code loop `for (int32_t c = 0; c < cols; ++c)` iterates `num_fft_bins`.
+        // Weights cover the first N/2 power bins; the Nyquist bin is never used,
+        // matching Kaldi.
+        for ((first, weights), out) in self.bins.iter().zip(mel_energies_out.iter_mut()) {
+            *out = inner_product(weights, &fft_energies[*first..]);
+            if self.htk_mode && *out < 1.0 {
+                *out = 1.0;
+            }
+        }
+    }
+
+    /// [`compute`](Self::compute) in double precision, for parity checks
+    /// where f32 accumulation error over wide triangles matters.
+    pub fn compute_f64(&self, fft_energies: &[f64], mel_energies_out: &mut [f64]) {
+        assert_eq!(fft_energies.len(), self.num_fft_bins + 1);
+        assert_eq!(mel_energies_out.len(), self.num_bins);
 
-        for r in 0..self.num_bins {
-            let mut sum = 0.0;
-            let row_offset = r * self.num_fft_bins;
-            for c in 0..self.num_fft_bins {
-                sum += self.weights[row_offset + c] * fft_energies[c];
+        for ((first, weights), out) in self.bins.iter().zip(mel_energies_out.iter_mut()) {
+            *out = weights
+                .iter()
+                .zip(&fft_energies[*first..])
+                .map(|(&w, e)| w as f64 * e)
+                .sum();
+            if self.htk_mode && *out < 1.0 {
+                *out = 1.0;
             }
-            mel_energies_out[r] = sum;
         }
     }
 
-    fn mel_scale(freq: f32) -> f32 {
-        1127.0 * (1.0 + freq / 700.0).ln()
+    /// Weight of `fft_bin` in mel bin `bin`.
+    pub fn weight(&self, bin: usize, fft_bin: usize) -> f32 {
+        let (first, weights) = &self.bins[bin];
+        fft_bin
+            .checked_sub(*first)
+            .and_then(|i| weights.get(i))
+            .copied()
+            .unwrap_or(0.0)
     }
 
-    fn inverse_mel_scale(mel: f32) -> f32 {
-        700.0 * ((mel / 1127.0).exp() - 1.0)
+    /// Center frequency of each bin in Hz, like Kaldi's `GetCenterFreqs`.
+    pub fn center_freqs(&self) -> Vec<f32> {
+        self.edges_hz.iter().map(|e| e[1]).collect()
+    }
+
+    /// Dense `num_bins x num_fft_bins` weight matrix, laid out
+    /// `[bin * num_fft_bins + fft_bin]`.
+    pub fn to_dense(&self) -> Vec<f32> {
+        let mut dense = vec![0.0; self.num_bins * self.num_fft_bins];
+        for (bin, (first, weights)) in self.bins.iter().enumerate() {
+            let start = bin * self.num_fft_bins + first;
+            dense[start..start + weights.len()].copy_from_slice(weights);
+        }
+        dense
+    }
+
+    fn mel_scale(freq: f32, scale: KaldiScale) -> f32 {
+        match scale {
+            KaldiScale::Kaldi => 1127.0 * (1.0 + freq / 700.0).ln(),
+            KaldiScale::Htk => 2595.0 * (1.0 + freq / 700.0).log10(),
+            KaldiScale::Erb => hz_to_erb_rate(freq),
+            KaldiScale::Linear => freq,
+        }
+    }
+
+    fn inverse_mel_scale(mel: f32, scale: KaldiScale) -> f32 {
+        match scale {
+            KaldiScale::Kaldi => 700.0 * ((mel / 1127.0).exp() - 1.0),
+            KaldiScale::Htk => 700.0 * (10.0f32.powf(mel / 2595.0) - 1.0),
+            KaldiScale::Erb => erb_rate_to_hz(mel),
+            KaldiScale::Linear => mel,
+        }
     }
 
     fn vtln_warp_mel(
@@ -173,11 +492,12 @@ impl MelBanks {
         high_freq: f32,
         vtln_warp: f32,
         mel: f32,
+        scale: KaldiScale,
     ) -> f32 {
-        let freq = Self::inverse_mel_scale(mel);
+        let freq = Self::inverse_mel_scale(mel, scale);
         let warped =
             Self::vtln_warp_freq(vtln_low, vtln_high, low_freq, high_freq, vtln_warp, freq);
-        Self::mel_scale(warped)
+        Self::mel_scale(warped, scale)
     }
 
     fn vtln_warp_freq(
@@ -208,3 +528,130 @@ impl MelBanks {
         }
     }
 }
+
+/// Scale of the Kaldi-style banks.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum KaldiScale {
+    /// Kaldi: `1127 * ln(1 + f / 700)`
+    Kaldi,
+    /// HTK: `2595 * log10(1 + f / 700)`
+    Htk,
+    /// ERB rate (Glasberg & Moore)
+    Erb,
+    /// Hz
+    Linear,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MelScale {
+    /// HTK: `2595 * log10(1 + f / 700)`
+    Htk,
+    /// Slaney (librosa default): linear below 1 kHz, logarithmic above
+    Slaney,
+    /// ERB rate (Glasberg & Moore)
+    Erb,
+    /// Hz
+    Linear,
+}
+
+const SLANEY_F_SP: f64 = 200.0 / 3.0;
+const SLANEY_MIN_LOG_HZ: f64 = 1000.0;
+const SLANEY_MIN_LOG_MEL: f64 = SLANEY_MIN_LOG_HZ / SLANEY_F_SP;
+
+/// [`MelBanks`] for each VTLN warp factor in use, like Kaldi's
+/// `FbankComputer::GetMelBanks`. Warped banks are built and validated by
+/// [`prepare`](Self::prepare), so lookups during extraction cannot fail.
+#[derive(Clone, Debug)]
+pub struct MelBanksCache {
+    mel_opts: MelOptions,
+    frame_opts: FrameOptions,
+    unwarped: MelBanks,
+    warped: HashMap<u32, MelBanks>,
+}
+
+impl MelBanksCache {
+    pub fn new(mel_opts: &MelOptions, frame_opts: &FrameOptions) -> Result<Self, KnfError> {
+        Ok(Self {
+            unwarped: MelBanks::new(mel_opts, frame_opts, 1.0)?,
+            mel_opts: mel_opts.clone(),
+            frame_opts: frame_opts.clone(),
+            warped: HashMap::new(),
+        })
+    }
+
+    /// The banks without warping.
+    pub fn unwarped(&self) -> &MelBanks {
+        &self.unwarped
+    }
+
+    /// Builds the banks for `vtln_warp` unless they are cached; fails if the
+    /// warp is not positive or the VTLN cutoffs do not fit the frequency range.
+    pub fn prepare(&mut self, vtln_warp: f32) -> Result<&MelBanks, KnfError> {
+        if vtln_warp == 1.0 {
+            return Ok(&self.unwarped);
+        }
+        let banks = match self.warped.entry(vtln_warp.to_bits()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                entry.insert(MelBanks::new(&self.mel_opts, &self.frame_opts, vtln_warp)?)
+            }
+        };
+        Ok(banks)
+    }
+
+    /// The banks for `vtln_warp`, or the unwarped banks if that warp has not
+    /// been [`prepare`](Self::prepare)d.
+    pub fn get(&self, vtln_warp: f32) -> &MelBanks {
+        self.warped
+            .get(&vtln_warp.to_bits())
+            .unwrap_or(&self.unwarped)
+    }
+}
+
+fn slaney_logstep() -> f64 {
+    6.4f64.ln() / 27.0
+}
+
+impl MelScale {
+    fn hz_to_mel(self, freq: f64) -> f64 {
+        match self {
+            Self::Htk => 2595.0 * (1.0 + freq / 700.0).log10(),
+            Self::Slaney => {
+                if freq >= SLANEY_MIN_LOG_HZ {
+                    SLANEY_MIN_LOG_MEL + (freq / SLANEY_MIN_LOG_HZ).ln() / slaney_logstep()
+                } else {
+                    freq / SLANEY_F_SP
+                }
+            }
+            Self::Erb => hz_to_erb_rate(freq as f32) as f64,
+            Self::Linear => freq,
+        }
+    }
+
+    fn mel_to_hz(self, mel: f64) -> f64 {
+        match self {
+            Self::Htk => 700.0 * (10f64.powf(mel / 2595.0) - 1.0),
+            Self::Slaney => {
+                if mel >= SLANEY_MIN_LOG_MEL {
+                    SLANEY_MIN_LOG_HZ * (slaney_logstep() * (mel - SLANEY_MIN_LOG_MEL)).exp()
+                } else {
+                    SLANEY_F_SP * mel
+                }
+            }
+            Self::Erb => erb_rate_to_hz(mel as f32) as f64,
+            Self::Linear => mel,
+        }
+    }
+}
+
+/// Splits a dense filter row into its first nonzero index and the weights up
+/// to the last nonzero one.
+pub(crate) fn trim_bin(row: &[f32]) -> (usize, Vec<f32>) {
+    match row.iter().position(|&w| w != 0.0) {
+        Some(first) => {
+            let last = row.iter().rposition(|&w| w != 0.0).unwrap();
+            (first, row[first..=last].to_vec())
+        }
+        None => (0, Vec::new()),
+    }
+}
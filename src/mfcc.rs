@@ -1,10 +1,19 @@
 // Reuse fbank options structure or components if preferred
-use crate::mel::{MelBanks, MelOptions};
+use crate::error::KnfError;
+use crate::mel::{FrequencyScale, MelBanksCache, MelOptions};
+use crate::online::Computer;
 use crate::rfft::Rfft;
 use crate::utils::{compute_power_spectrum_inplace, inner_product, log_energy, PI, SQRT2};
-use crate::window::FrameOptions;
+use crate::window::{DitherMode, FrameOptions};
+use rustdct::{DctPlanner, TransformType2And3};
+use std::sync::Arc;
 
 #[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct MfccOptions {
     pub frame_opts: FrameOptions,
     pub mel_opts: MelOptions,
@@ -14,6 +23,9 @@ pub struct MfccOptions {
     pub raw_energy: bool,
     pub htk_compat: bool,
     pub energy_floor: f32,
+    /// Compute the cepstra with an O(N log N) DCT-II instead of the dense DCT
+    /// matrix. Equal up to rounding; the matrix path matches Kaldi exactly.
+    pub fast_dct: bool,
 }
 
 impl Default for MfccOptions {
@@ -27,53 +39,117 @@ impl Default for MfccOptions {
             raw_energy: true,
             htk_compat: false,
             energy_floor: 0.0,
+            fast_dct: false,
         }
     }
 }
 
+impl MfccOptions {
+    pub fn validate(&self) -> Result<(), KnfError> {
+        self.frame_opts.validate()?;
+        self.mel_opts.validate(&self.frame_opts)?;
+        if self.num_ceps == 0 || self.num_ceps > self.mel_opts.num_bins {
+            return Err(KnfError::invalid(format!(
+                "num_ceps ({}) must be in [1, num_bins ({})]",
+                self.num_ceps, self.mel_opts.num_bins
+            )));
+        }
+        if self.cepstral_lifter < 0.0 {
+            return Err(KnfError::invalid("cepstral_lifter must be non-negative"));
+        }
+        Ok(())
+    }
+
+    /// Defaults of Kaldi's `compute-mfcc-feats`: 23 bins, 13 cepstra with the
+    /// energy in C0, lifter 22 and Gaussian dither 1.0.
+    pub fn kaldi_default() -> Self {
+        let mut opts = Self::default();
+        opts.frame_opts.dither = 1.0;
+        opts.frame_opts.dither_mode = DitherMode::Gaussian;
+        opts.mel_opts.num_bins = 23;
+        opts.num_ceps = 13;
+        opts.cepstral_lifter = 22.0;
+        opts.use_energy = true;
+        opts.raw_energy = true;
+        opts
+    }
+}
+
+/// Unnormalized DCT-II plan with the orthonormal scales of the DCT matrix.
+struct FastDct {
+    dct: Arc<dyn TransformType2And3<f32>>,
+    buffer: Vec<f32>,
+    scratch: Vec<f32>,
+    k0_factor: f32,
+    k_factor: f32,
+}
+
+/// Orthonormal DCT-II matrix, flattened `[num_ceps * num_bins]`.
+pub(crate) fn dct_matrix(num_ceps: usize, num_bins: usize) -> Vec<f32> {
+    let mut matrix = vec![0.0; num_ceps * num_bins];
+    let k_factor = (2.0 / num_bins as f32).sqrt();
+    let k0_factor = (1.0 / num_bins as f32).sqrt();
+
+    for i in 0..num_ceps {
+        for j in 0..num_bins {
+            let val = if i == 0 {
+                k0_factor
+            } else {
+                k_factor * (PI / num_bins as f32 * (j as f32 + 0.5) * i as f32).cos()
+            };
+            matrix[i * num_bins + j] = val;
+        }
+    }
+    matrix
+}
+
+/// Sinusoidal cepstral liftering weights; all 1.0 when `lifter` is 0.
+pub(crate) fn lifter_coeffs(num_ceps: usize, lifter: f32) -> Vec<f32> {
+    let mut coeffs = vec![1.0; num_ceps];
+    if lifter != 0.0 {
+        for (i, coeff) in coeffs.iter_mut().enumerate() {
+            *coeff = 1.0 + 0.5 * lifter * (PI * i as f32 / lifter).sin();
+        }
+    }
+    coeffs
+}
+
 pub struct MfccComputer {
     pub opts: MfccOptions,
     rfft: Rfft,
-    mel_banks: MelBanks,
+    mel_banks: MelBanksCache,
     mel_energies: Vec<f32>,
     dct_matrix: Vec<f32>, // flattened [num_ceps * num_bins]
+    fast_dct: Option<FastDct>,
     lifter_coeffs: Vec<f32>,
     log_energy_floor: f32,
 }
 
 impl MfccComputer {
-    pub fn new(opts: MfccOptions) -> Result<Self, String> {
+    pub fn new(opts: MfccOptions) -> Result<Self, KnfError> {
+        opts.validate()?;
         let n_fft = opts.frame_opts.padded_window_size();
         let rfft = Rfft::new(n_fft, false);
-        let mel_banks = MelBanks::new(&opts.mel_opts, &opts.frame_opts, 1.0)?;
+        let mel_banks = MelBanksCache::new(&opts.mel_opts, &opts.frame_opts)?;
 
         let mel_energies = vec![0.0; opts.mel_opts.num_bins];
 
-        // Compute DCT Matrix
-        let mut dct_matrix = vec![0.0; opts.num_ceps * opts.mel_opts.num_bins];
+        let dct_matrix = dct_matrix(opts.num_ceps, opts.mel_opts.num_bins);
         let k_factor = (2.0 / opts.mel_opts.num_bins as f32).sqrt();
         let k0_factor = (1.0 / opts.mel_opts.num_bins as f32).sqrt();
 
-        for i in 0..opts.num_ceps {
-            for j in 0..opts.mel_opts.num_bins {
-                let val = if i == 0 {
-                    k0_factor
-                } else {
-                    k_factor
-                        * (PI / opts.mel_opts.num_bins as f32 * (j as f32 + 0.5) * i as f32).cos()
-                };
-                dct_matrix[i * opts.mel_opts.num_bins + j] = val;
+        let fast_dct = opts.fast_dct.then(|| {
+            let dct = DctPlanner::new().plan_dct2(opts.mel_opts.num_bins);
+            FastDct {
+                buffer: vec![0.0; opts.mel_opts.num_bins],
+                scratch: vec![0.0; dct.get_scratch_len()],
+                dct,
+                k0_factor,
+                k_factor,
             }
-        }
+        });
 
-        // Compute Lifter coeffs
-        let mut lifter_coeffs = vec![1.0; opts.num_ceps];
-        if opts.cepstral_lifter != 0.0 {
-            for i in 0..opts.num_ceps {
-                lifter_coeffs[i] =
-                    1.0 + 0.5 * opts.cepstral_lifter * (PI * i as f32 / opts.cepstral_lifter).sin();
-            }
-        }
+        let lifter_coeffs = lifter_coeffs(opts.num_ceps, opts.cepstral_lifter);
 
         let log_energy_floor = if opts.energy_floor > 0.0 {
             opts.energy_floor.ln()
@@ -87,6 +163,7 @@ impl MfccComputer {
             mel_banks,
             mel_energies,
             dct_matrix,
+            fast_dct,
             lifter_coeffs,
             log_energy_floor,
         })
@@ -96,10 +173,18 @@ impl MfccComputer {
         self.opts.num_ceps
     }
 
+    /// Builds the mel banks for `vtln_warp`; see
+    /// [`MelBanksCache::prepare`](crate::mel::MelBanksCache::prepare).
+    pub fn prepare_vtln_warp(&mut self, vtln_warp: f32) -> Result<(), KnfError> {
+        self.mel_banks.prepare(vtln_warp).map(|_| ())
+    }
+
+    /// Computes one frame; see [`FbankComputer::compute`](crate::FbankComputer::compute)
+    /// for `vtln_warp`.
     pub fn compute(
         &mut self,
         mut signal_raw_log_energy: f32,
-        _vtln_warp: f32,
+        vtln_warp: f32,
         signal_frame: &mut [f32],
         feature: &mut [f32],
     ) {
@@ -111,9 +196,9 @@ impl MfccComputer {
         self.rfft.compute(signal_frame);
         compute_power_spectrum_inplace(signal_frame);
 
-        let fft_bins = self.mel_banks.num_fft_bins + 1; // use only power spectrum bins
-        self.mel_banks
-            .compute(&signal_frame[..fft_bins], &mut self.mel_energies);
+        let mel_banks = self.mel_banks.get(vtln_warp);
+        let fft_bins = mel_banks.num_fft_bins + 1; // use only power spectrum bins
+        mel_banks.compute(&signal_frame[..fft_bins], &mut self.mel_energies);
 
         // Log Mel
         for x in self.mel_energies.iter_mut() {
@@ -121,12 +206,26 @@ impl MfccComputer {
         }
 
         // DCT
-        for i in 0..self.opts.num_ceps {
-            let row_offset = i * self.opts.mel_opts.num_bins;
-            feature[i] = inner_product(
-                &self.dct_matrix[row_offset..row_offset + self.opts.mel_opts.num_bins],
-                &self.mel_energies,
-            );
+        if let Some(fast) = &mut self.fast_dct {
+            fast.buffer.copy_from_slice(&self.mel_energies);
+            fast.dct
+                .process_dct2_with_scratch(&mut fast.buffer, &mut fast.scratch);
+            for (i, val) in feature.iter_mut().enumerate().take(self.opts.num_ceps) {
+                let factor = if i == 0 {
+                    fast.k0_factor
+                } else {
+                    fast.k_factor
+                };
+                *val = factor * fast.buffer[i];
+            }
+        } else {
+            for (i, val) in feature.iter_mut().enumerate().take(self.opts.num_ceps) {
+                let row_offset = i * self.opts.mel_opts.num_bins;
+                *val = inner_product(
+                    &self.dct_matrix[row_offset..row_offset + self.opts.mel_opts.num_bins],
+                    &self.mel_energies,
+                );
+            }
         }
 
         // Lifter
@@ -138,7 +237,7 @@ impl MfccComputer {
 
         // Energy replace
         if self.opts.use_energy {
-            if self.opts.energy_floor > 0.0 && signal_raw_log_energy < self.log_energy_floor {
+            if signal_raw_log_energy < self.log_energy_floor {
                 signal_raw_log_energy = self.log_energy_floor;
             }
             feature[0] = signal_raw_log_energy;
@@ -158,3 +257,148 @@ impl MfccComputer {
         }
     }
 }
+
+impl Computer for MfccComputer {
+    fn dim(&self) -> usize {
+        MfccComputer::dim(self)
+    }
+
+    fn frame_opts(&self) -> &FrameOptions {
+        &self.opts.frame_opts
+    }
+
+    fn need_raw_energy(&self) -> bool {
+        self.opts.use_energy && self.opts.raw_energy
+    }
+
+    fn compute(
+        &mut self,
+        raw_log_energy: f32,
+        vtln_warp: f32,
+        window: &mut [f32],
+        feature: &mut [f32],
+    ) {
+        MfccComputer::compute(self, raw_log_energy, vtln_warp, window, feature)
+    }
+
+    fn prepare_vtln_warp(&mut self, vtln_warp: f32) -> Result<(), KnfError> {
+        MfccComputer::prepare_vtln_warp(self, vtln_warp)
+    }
+}
+
+/// Defaults follow the ASVspoof LFCC baseline: 20 filters and 20 cepstra
+/// over the full band.
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct LfccOptions {
+    pub frame_opts: FrameOptions,
+    pub num_bins: usize,
+    pub low_freq: f32,
+    /// Non-positive values are relative to the Nyquist frequency.
+    pub high_freq: f32,
+    pub num_ceps: usize,
+    pub cepstral_lifter: f32,
+    /// Replace C0 with the frame log-energy, as for MFCC.
+    pub use_energy: bool,
+}
+
+impl Default for LfccOptions {
+    fn default() -> Self {
+        Self {
+            frame_opts: FrameOptions::default(),
+            num_bins: 20,
+            low_freq: 0.0,
+            high_freq: 0.0,
+            num_ceps: 20,
+            cepstral_lifter: 0.0,
+            use_energy: false,
+        }
+    }
+}
+
+impl LfccOptions {
+    /// The equivalent MFCC configuration on linearly spaced banks.
+    pub fn to_mfcc_options(&self) -> MfccOptions {
+        MfccOptions {
+            frame_opts: self.frame_opts.clone(),
+            mel_opts: MelOptions {
+                num_bins: self.num_bins,
+                low_freq: self.low_freq,
+                high_freq: self.high_freq,
+                frequency_scale: FrequencyScale::Linear,
+                ..Default::default()
+            },
+            num_ceps: self.num_ceps,
+            cepstral_lifter: self.cepstral_lifter,
+            use_energy: self.use_energy,
+            ..Default::default()
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), KnfError> {
+        self.to_mfcc_options().validate()
+    }
+}
+
+/// Linear-frequency cepstral coefficients: MFCC computed on triangular banks
+/// spaced linearly in Hz.
+pub struct LfccComputer {
+    pub opts: LfccOptions,
+    mfcc: MfccComputer,
+}
+
+impl LfccComputer {
+    pub fn new(opts: LfccOptions) -> Result<Self, KnfError> {
+        Ok(Self {
+            mfcc: MfccComputer::new(opts.to_mfcc_options())?,
+            opts,
+        })
+    }
+
+    pub fn dim(&self) -> usize {
+        self.opts.num_ceps
+    }
+
+    pub fn compute(
+        &mut self,
+        signal_raw_log_energy: f32,
+        vtln_warp: f32,
+        signal_frame: &mut [f32],
+        feature: &mut [f32],
+    ) {
+        self.mfcc
+            .compute(signal_raw_log_energy, vtln_warp, signal_frame, feature)
+    }
+}
+
+impl Computer for LfccComputer {
+    fn dim(&self) -> usize {
+        LfccComputer::dim(self)
+    }
+
+    fn frame_opts(&self) -> &FrameOptions {
+        &self.opts.frame_opts
+    }
+
+    fn need_raw_energy(&self) -> bool {
+        self.opts.use_energy
+    }
+
+    fn compute(
+        &mut self,
+        raw_log_energy: f32,
+        vtln_warp: f32,
+        window: &mut [f32],
+        feature: &mut [f32],
+    ) {
+        LfccComputer::compute(self, raw_log_energy, vtln_warp, window, feature)
+    }
+
+    fn prepare_vtln_warp(&mut self, vtln_warp: f32) -> Result<(), KnfError> {
+        self.mfcc.prepare_vtln_warp(vtln_warp)
+    }
+}
@@ -0,0 +1,192 @@
+//! One-shot extraction over a complete waveform.
+
+use crate::error::KnfError;
+use crate::fbank::{FbankComputer, FbankOptions};
+use crate::online::Computer;
+use crate::pcm::{i16_to_f32, PcmScale};
+use crate::window::FrameIterator;
+#[cfg(feature = "rayon")]
+use crate::window::{extract_window, highpass_filtered, num_frames, Window};
+
+/// Row-major `num_frames x dim` feature matrix.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FeatureMatrix {
+    pub data: Vec<f32>,
+    pub num_frames: usize,
+    pub dim: usize,
+}
+
+impl FeatureMatrix {
+    pub fn is_empty(&self) -> bool {
+        self.num_frames == 0
+    }
+
+    pub fn row(&self, frame: usize) -> &[f32] {
+        &self.data[frame * self.dim..(frame + 1) * self.dim]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[f32]> {
+        (0..self.num_frames).map(move |f| self.row(f))
+    }
+
+    pub fn to_rows(&self) -> Vec<Vec<f32>> {
+        self.rows().map(|r| r.to_vec()).collect()
+    }
+}
+
+/// Kaldi FBANK features of `wave` (at `opts.frame_opts.samp_freq`), with all
+/// frames flushed as after `OnlineFeature::input_finished`.
+pub fn compute_features(wave: &[f32], opts: &FbankOptions) -> Result<FeatureMatrix, KnfError> {
+    let mut computer = FbankComputer::new(opts.clone())?;
+    compute_features_with(&mut computer, wave)
+}
+
+/// [`compute_features`] of 16-bit PCM samples mapped to floats with `scale`.
+pub fn compute_features_i16(
+    wave: &[i16],
+    scale: PcmScale,
+    opts: &FbankOptions,
+) -> Result<FeatureMatrix, KnfError> {
+    compute_features(&i16_to_f32(wave, scale), opts)
+}
+
+/// Like [`compute_features`] for any [`Computer`]. Stateful computers (e.g.
+/// FBANK mean log-energy subtraction) keep the state of earlier calls.
+pub fn compute_features_with<C: Computer + ?Sized>(
+    computer: &mut C,
+    wave: &[f32],
+) -> Result<FeatureMatrix, KnfError> {
+    let opts = computer.frame_opts().clone();
+    let dim = computer.dim();
+    let mut frame_iter = FrameIterator::new(wave, &opts);
+    let frames = frame_iter.num_frames();
+
+    let mut data = vec![0.0; frames * dim];
+    while let Some((frame, window_buf, raw_log_energy)) = frame_iter.next_frame() {
+        let feature = &mut data[frame * dim..(frame + 1) * dim];
+        computer.compute(raw_log_energy, 1.0, window_buf, feature);
+    }
+
+    Ok(FeatureMatrix {
+        data,
+        num_frames: frames,
+        dim,
+    })
+}
+
+/// FBANK features of each utterance in `waves`, see [`compute_features_batch_with`].
+pub fn compute_features_batch<W: AsRef<[f32]> + Sync>(
+    waves: &[W],
+    opts: &FbankOptions,
+) -> Result<Vec<FeatureMatrix>, KnfError> {
+    compute_features_batch_with(|| FbankComputer::new(opts.clone()), waves)
+}
+
+/// Feature matrices of many utterances, in order. Computers from
+/// `make_computer` are reused (and [`reset`](Computer::reset)) across
+/// utterances; with the `rayon` feature utterances are processed in parallel,
+/// with a computer made for each rayon split (possibly several per thread).
+pub fn compute_features_batch_with<C, F, W>(
+    make_computer: F,
+    waves: &[W],
+) -> Result<Vec<FeatureMatrix>, KnfError>
+where
+    C: Computer + Send,
+    F: Fn() -> Result<C, KnfError> + Sync,
+    W: AsRef<[f32]> + Sync,
+{
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+
+        waves
+            .par_iter()
+            .map_init(&make_computer, |computer, wave| {
+                let computer = computer.as_mut().map_err(|e| e.clone())?;
+                computer.reset();
+                compute_features_with(computer, wave.as_ref())
+            })
+            .collect()
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    {
+        let mut computer = make_computer()?;
+        waves
+            .iter()
+            .map(|wave| {
+                computer.reset();
+                compute_features_with(&mut computer, wave.as_ref())
+            })
+            .collect()
+    }
+}
+
+/// Frames per rayon work item.
+#[cfg(feature = "rayon")]
+const PAR_CHUNK_FRAMES: usize = 256;
+
+/// [`compute_features`] with frames computed in parallel on the rayon thread
+/// pool. Falls back to the sequential path when
+/// `subtract_mean_log_energy` makes frames depend on their predecessors.
+#[cfg(feature = "rayon")]
+pub fn compute_features_par(wave: &[f32], opts: &FbankOptions) -> Result<FeatureMatrix, KnfError> {
+    if opts.subtract_mean_log_energy {
+        return compute_features(wave, opts);
+    }
+    compute_features_par_with(|| FbankComputer::new(opts.clone()), wave)
+}
+
+/// Parallel [`compute_features_with`]. `make_computer` is called once per
+/// rayon split, which can be many times per thread (not once per thread), so
+/// keep it cheap; the computers must not carry state across frames.
+#[cfg(feature = "rayon")]
+pub fn compute_features_par_with<C, F>(
+    make_computer: F,
+    wave: &[f32],
+) -> Result<FeatureMatrix, KnfError>
+where
+    C: Computer + Send,
+    F: Fn() -> Result<C, KnfError> + Sync,
+{
+    use rayon::prelude::*;
+
+    let first = make_computer()?;
+    let opts = first.frame_opts().clone();
+    let dim = first.dim();
+    drop(first);
+
+    let wave = &highpass_filtered(wave, &opts)[..];
+    let window = Window::new(&opts);
+    let frames = num_frames(wave.len(), &opts, true);
+    let mut data = vec![0.0; frames * dim];
+    if dim == 0 {
+        return Ok(FeatureMatrix {
+            data,
+            num_frames: frames,
+            dim,
+        });
+    }
+
+    data.par_chunks_mut(PAR_CHUNK_FRAMES * dim)
+        .enumerate()
+        .try_for_each_init(
+            || (make_computer(), vec![0.0; opts.padded_window_size()]),
+            |(computer, window_buf), (chunk, out)| {
+                let computer = computer.as_mut().map_err(|e| e.clone())?;
+                for (i, feature) in out.chunks_exact_mut(dim).enumerate() {
+                    let frame = chunk * PAR_CHUNK_FRAMES + i;
+                    let raw_log_energy =
+                        extract_window(0, wave, frame, &opts, window.as_ref(), window_buf)?;
+                    computer.compute(raw_log_energy, 1.0, window_buf, feature);
+                }
+                Ok::<_, KnfError>(())
+            },
+        )?;
+
+    Ok(FeatureMatrix {
+        data,
+        num_frames: frames,
+        dim,
+    })
+}
@@ -1,28 +1,130 @@
+use crate::error::KnfError;
 use crate::fbank::FbankComputer;
+use crate::filter::FirstOrderHighPass;
 use crate::mfcc::MfccComputer;
+use crate::pcm::{apply_channel_policy, i16_to_f32, ChannelPolicy, PcmScale};
+use crate::raw::RawAudioComputer;
+use crate::source::SampleSource;
+use crate::whisper::WhisperComputer;
 use crate::window::{extract_window, first_sample_of_frame, num_frames, FrameOptions, Window};
+use std::ops::Range;
+use std::sync::Arc;
 
 pub enum FeatureComputer {
     Fbank(FbankComputer),
     Mfcc(MfccComputer),
+    Whisper(WhisperComputer),
+    Raw(RawAudioComputer),
 }
 
-impl FeatureComputer {
-    pub fn frame_opts(&self) -> &FrameOptions {
+/// A per-frame feature computer that can be driven by [`OnlineFeature`].
+pub trait Computer {
+    fn dim(&self) -> usize;
+
+    fn frame_opts(&self) -> &FrameOptions;
+
+    /// Whether `compute` expects the log-energy of the frame before windowing.
+    fn need_raw_energy(&self) -> bool;
+
+    fn compute(
+        &mut self,
+        raw_log_energy: f32,
+        vtln_warp: f32,
+        window: &mut [f32],
+        feature: &mut [f32],
+    );
+
+    /// Sets up whatever `compute` needs for `vtln_warp` (e.g. warped mel
+    /// banks), failing if the warp cannot be used. Computers that ignore the
+    /// warp keep the default.
+    fn prepare_vtln_warp(&mut self, _vtln_warp: f32) -> Result<(), KnfError> {
+        Ok(())
+    }
+
+    /// Clears state carried across frames (e.g. FBANK mean log-energy) before
+    /// a new utterance.
+    fn reset(&mut self) {}
+
+    /// Snapshot of the state cleared by [`reset`](Self::reset), used by
+    /// [`OnlineFeature::checkpoint`]. Stateless computers keep the default.
+    fn save_state(&self) -> ComputerState {
+        ComputerState::default()
+    }
+
+    /// Restores a state from [`save_state`](Self::save_state) of this computer.
+    fn restore_state(&mut self, _state: &ComputerState) {}
+}
+
+/// Cross-frame state of a [`Computer`], in a layout chosen by the computer
+/// (e.g. [`ComposedComputer`](crate::ComposedComputer) keeps one child per
+/// computer). Empty for stateless computers.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ComputerState {
+    pub values: Vec<f64>,
+    pub children: Vec<ComputerState>,
+}
+
+impl<C: Computer + ?Sized> Computer for Box<C> {
+    fn dim(&self) -> usize {
+        (**self).dim()
+    }
+
+    fn frame_opts(&self) -> &FrameOptions {
+        (**self).frame_opts()
+    }
+
+    fn need_raw_energy(&self) -> bool {
+        (**self).need_raw_energy()
+    }
+
+    fn compute(
+        &mut self,
+        raw_log_energy: f32,
+        vtln_warp: f32,
+        window: &mut [f32],
+        feature: &mut [f32],
+    ) {
+        (**self).compute(raw_log_energy, vtln_warp, window, feature)
+    }
+
+    fn prepare_vtln_warp(&mut self, vtln_warp: f32) -> Result<(), KnfError> {
+        (**self).prepare_vtln_warp(vtln_warp)
+    }
+
+    fn reset(&mut self) {
+        (**self).reset()
+    }
+
+    fn save_state(&self) -> ComputerState {
+        (**self).save_state()
+    }
+
+    fn restore_state(&mut self, state: &ComputerState) {
+        (**self).restore_state(state)
+    }
+}
+
+impl Computer for FeatureComputer {
+    fn frame_opts(&self) -> &FrameOptions {
         match self {
-            Self::Fbank(c) => &c.opts.frame_opts,
-            Self::Mfcc(c) => &c.opts.frame_opts,
+            Self::Fbank(c) => c.frame_opts(),
+            Self::Mfcc(c) => c.frame_opts(),
+            Self::Whisper(c) => c.frame_opts(),
+            Self::Raw(c) => c.frame_opts(),
         }
     }
 
-    pub fn dim(&self) -> usize {
+    fn dim(&self) -> usize {
         match self {
             Self::Fbank(c) => c.dim(),
             Self::Mfcc(c) => c.dim(),
+            Self::Whisper(c) => c.dim(),
+            Self::Raw(c) => c.dim(),
         }
     }
 
-    pub fn compute(
+    fn compute(
         &mut self,
         raw_log_energy: f32,
         vtln_warp: f32,
@@ -32,70 +134,360 @@ impl FeatureComputer {
         match self {
             Self::Fbank(c) => c.compute(raw_log_energy, vtln_warp, window, feature),
             Self::Mfcc(c) => c.compute(raw_log_energy, vtln_warp, window, feature),
+            Self::Whisper(c) => c.compute(raw_log_energy, vtln_warp, window, feature),
+            Self::Raw(c) => c.compute(raw_log_energy, vtln_warp, window, feature),
+        }
+    }
+
+    fn prepare_vtln_warp(&mut self, vtln_warp: f32) -> Result<(), KnfError> {
+        match self {
+            Self::Fbank(c) => c.prepare_vtln_warp(vtln_warp),
+            Self::Mfcc(c) => c.prepare_vtln_warp(vtln_warp),
+            Self::Whisper(c) => c.prepare_vtln_warp(vtln_warp),
+            Self::Raw(c) => c.prepare_vtln_warp(vtln_warp),
+        }
+    }
+
+    fn need_raw_energy(&self) -> bool {
+        match self {
+            Self::Fbank(c) => c.need_raw_energy(),
+            Self::Mfcc(c) => c.need_raw_energy(),
+            Self::Whisper(c) => c.need_raw_energy(),
+            Self::Raw(c) => c.need_raw_energy(),
+        }
+    }
+
+    fn reset(&mut self) {
+        match self {
+            Self::Fbank(c) => c.reset(),
+            Self::Mfcc(c) => c.reset(),
+            Self::Whisper(c) => c.reset(),
+            Self::Raw(c) => c.reset(),
+        }
+    }
+
+    fn save_state(&self) -> ComputerState {
+        match self {
+            Self::Fbank(c) => c.save_state(),
+            Self::Mfcc(c) => c.save_state(),
+            Self::Whisper(c) => c.save_state(),
+            Self::Raw(c) => c.save_state(),
         }
     }
 
-    pub fn need_raw_energy(&self) -> bool {
+    fn restore_state(&mut self, state: &ComputerState) {
         match self {
-            Self::Fbank(c) => c.opts.use_energy && c.opts.raw_energy,
-            Self::Mfcc(c) => c.opts.use_energy && c.opts.raw_energy,
+            Self::Fbank(c) => c.restore_state(state),
+            Self::Mfcc(c) => c.restore_state(state),
+            Self::Whisper(c) => c.restore_state(state),
+            Self::Raw(c) => c.restore_state(state),
         }
     }
 }
 
-pub struct OnlineFeature {
-    computer: FeatureComputer,
-    window_function: Option<Window>,
+/// Streaming feature extraction over incrementally supplied audio.
+///
+/// With `dither == 0.0` the frames produced are identical to offline extraction
+/// (`extract_window` over the whole signal for `num_frames(n, opts, true)` frames),
+/// regardless of how the audio is chunked. For `snip_edges == false` this covers
+/// the edges too: the leading frames reflect around the first sample, and frames
+/// that extend past the end of the signal are only emitted after
+/// [`input_finished`](Self::input_finished), reflected around the last sample.
+pub struct OnlineFeature<C: Computer = FeatureComputer> {
+    computer: C,
+    window_function: Option<Arc<Window>>,
+    highpass: Option<FirstOrderHighPass>,
     waveform: Vec<f32>,
     waveform_offset: usize,
     input_finished: bool,
+    frame_limit: Option<usize>,
+    vtln_warp: f32,
+    /// Computed frames not yet released with [`pop_frames`](Self::pop_frames)
+    /// or [`discard_frames_before`](Self::discard_frames_before);
+    /// `features[0]` is frame [`first_frame`](Self::first_frame).
     pub features: Vec<Vec<f32>>,
+    features_offset: usize,
 }
 
-impl OnlineFeature {
-    pub fn new(computer: FeatureComputer) -> Self {
-        let opts = computer.frame_opts();
-        let window_function = Window::new(opts);
+impl<C: Computer> OnlineFeature<C> {
+    pub fn new(computer: C) -> Self {
+        let window_function = Window::new(computer.frame_opts()).map(Arc::new);
+        Self::with_window(computer, window_function)
+    }
+
+    /// Like [`new`](Self::new) with a window built for the computer's frame
+    /// options, e.g. shared by the streams of a [`StreamPool`](crate::StreamPool).
+    pub(crate) fn with_window(computer: C, window_function: Option<Arc<Window>>) -> Self {
+        let highpass = computer.frame_opts().highpass();
         Self {
             computer,
             window_function,
+            highpass,
             waveform: Vec::new(),
             waveform_offset: 0,
             input_finished: false,
+            frame_limit: None,
+            vtln_warp: 1.0,
             features: Vec::new(),
+            features_offset: 0,
+        }
+    }
+
+    pub fn computer(&self) -> &C {
+        &self.computer
+    }
+
+    pub(crate) fn window_function(&self) -> Option<&Arc<Window>> {
+        self.window_function.as_ref()
+    }
+
+    /// Caps the number of frames computed. Audio for frames beyond the limit is
+    /// buffered and processed once the limit is raised (or removed with `None`).
+    pub fn set_frame_limit(&mut self, limit: Option<usize>) {
+        self.frame_limit = limit;
+        self.compute_new();
+    }
+
+    pub fn frame_limit(&self) -> Option<usize> {
+        self.frame_limit
+    }
+
+    /// VTLN warp factor passed to the computer for frames computed from now
+    /// on, e.g. once a speaker's warp has been estimated. Defaults to 1.0.
+    ///
+    /// Fails, keeping the previous warp, if `vtln_warp` is not positive and
+    /// finite or the computer cannot use it (e.g. bad VTLN cutoffs).
+    pub fn set_vtln_warp(&mut self, vtln_warp: f32) -> Result<(), KnfError> {
+        if !(vtln_warp > 0.0 && vtln_warp.is_finite()) {
+            return Err(KnfError::invalid(format!(
+                "vtln_warp must be positive, got {}",
+                vtln_warp
+            )));
         }
+        self.computer.prepare_vtln_warp(vtln_warp)?;
+        self.vtln_warp = vtln_warp;
+        Ok(())
+    }
+
+    pub fn vtln_warp(&self) -> f32 {
+        self.vtln_warp
     }
 
-    pub fn accept_waveform(&mut self, sampling_rate: f32, waveform: &[f32]) {
+    /// Number of frames the buffered audio allows that are held back by the frame limit.
+    pub fn num_frames_pending(&self) -> usize {
         let opts = self.computer.frame_opts();
-        if (sampling_rate - opts.samp_freq).abs() > 1.0 {
-            panic!(
-                "Sampling rate mismatch: expected {}, got {}",
-                opts.samp_freq, sampling_rate
-            );
+        let total_samples = self.waveform_offset + self.waveform.len();
+        num_frames(total_samples, opts, self.input_finished).saturating_sub(self.num_frames_ready())
+    }
+
+    /// Buffers `waveform` and computes the new frames. Fails without
+    /// buffering anything if `sampling_rate` differs from the frame options'.
+    pub fn accept_waveform(
+        &mut self,
+        sampling_rate: f32,
+        waveform: &[f32],
+    ) -> Result<(), KnfError> {
+        let expected = self.computer.frame_opts().samp_freq;
+        if (sampling_rate - expected).abs() > 1.0 {
+            return Err(KnfError::SamplingRateMismatch {
+                expected,
+                actual: sampling_rate,
+            });
         }
+        let len = self.waveform.len();
         self.waveform.extend_from_slice(waveform);
+        self.filter_from(len);
         self.compute_new();
+        Ok(())
+    }
+
+    /// Pulls up to `max_samples` samples from `source` straight into the
+    /// waveform buffer and computes the new frames. Calls
+    /// [`input_finished`](Self::input_finished) once `source` is exhausted.
+    /// Returns the number of samples read.
+    pub fn pull_from<S: SampleSource + ?Sized>(
+        &mut self,
+        source: &mut S,
+        max_samples: usize,
+    ) -> Result<usize, KnfError> {
+        if self.input_finished {
+            return Ok(0);
+        }
+        let len = self.waveform.len();
+        self.waveform.resize(len + max_samples, 0.0);
+        let read = source.read_samples(&mut self.waveform[len..]);
+        let n = match read {
+            Ok(Some(n)) => n.min(max_samples),
+            _ => 0,
+        };
+        self.waveform.truncate(len + n);
+        self.filter_from(len);
+        if read?.is_some() {
+            self.compute_new();
+        } else {
+            self.input_finished();
+        }
+        Ok(n)
+    }
+
+    /// Like [`accept_waveform`](Self::accept_waveform) for 16-bit PCM input.
+    pub fn accept_waveform_i16(
+        &mut self,
+        sampling_rate: f32,
+        waveform: &[i16],
+        scale: PcmScale,
+    ) -> Result<(), KnfError> {
+        self.accept_waveform(sampling_rate, &i16_to_f32(waveform, scale))
     }
 
+    /// Flushes the remaining frames. With `snip_edges == false` this emits the
+    /// tail frames that overlap the end of the signal.
     pub fn input_finished(&mut self) {
         self.input_finished = true;
         self.compute_new();
     }
 
+    pub fn is_input_finished(&self) -> bool {
+        self.input_finished
+    }
+
+    /// Starts a new utterance: drops buffered audio and frames and resets the
+    /// computer, keeping its window, FFT plans and buffers. The frame limit is kept.
+    pub fn reset(&mut self) {
+        self.computer.reset();
+        if let Some(highpass) = self.highpass.as_mut() {
+            highpass.reset();
+        }
+        self.waveform.clear();
+        self.waveform_offset = 0;
+        self.input_finished = false;
+        self.features.clear();
+        self.features_offset = 0;
+    }
+
+    /// Total number of frames computed so far, including released ones.
     pub fn num_frames_ready(&self) -> usize {
-        self.features.len()
+        self.features_offset + self.features.len()
     }
 
+    /// `None` if the frame is not ready or has already been released.
     pub fn get_frame(&self, frame: usize) -> Option<&[f32]> {
-        self.features.get(frame).map(|v| v.as_slice())
+        let index = frame.checked_sub(self.features_offset)?;
+        self.features.get(index).map(|v| v.as_slice())
+    }
+
+    /// Continues a checkpointed stream with a fresh `computer`, configured
+    /// like the checkpointed one. Frame numbering carries on from
+    /// [`OnlineCheckpoint::num_frames`].
+    pub fn resume(computer: C, checkpoint: &OnlineCheckpoint) -> Result<Self, KnfError> {
+        if *computer.frame_opts() != checkpoint.frame_opts {
+            return Err(KnfError::invalid(
+                "Checkpoint was taken with different frame options",
+            ));
+        }
+        if computer.dim() != checkpoint.dim {
+            return Err(KnfError::SizeMismatch {
+                what: "feature dim",
+                expected: checkpoint.dim,
+                actual: computer.dim(),
+            });
+        }
+        let mut feature = Self::new(computer);
+        feature.rollback(checkpoint);
+        Ok(feature)
+    }
+
+    /// Saves the streaming state so that [`rollback`](Self::rollback) can
+    /// return to this point, e.g. to re-feed audio after endpoint rescoring.
+    pub fn checkpoint(&self) -> OnlineCheckpoint {
+        OnlineCheckpoint {
+            frame_opts: self.computer.frame_opts().clone(),
+            dim: self.computer.dim(),
+            waveform: self.waveform.clone(),
+            waveform_offset: self.waveform_offset,
+            num_frames: self.num_frames_ready(),
+            input_finished: self.input_finished,
+            highpass: self.highpass.clone(),
+            computer: self.computer.save_state(),
+        }
+    }
+
+    /// Returns to `checkpoint`: audio fed since is dropped, and frames from
+    /// [`OnlineCheckpoint::num_frames`] on are recomputed from the audio fed next.
+    pub fn rollback(&mut self, checkpoint: &OnlineCheckpoint) {
+        self.waveform.clear();
+        self.waveform.extend_from_slice(&checkpoint.waveform);
+        self.waveform_offset = checkpoint.waveform_offset;
+        self.input_finished = checkpoint.input_finished;
+        if checkpoint.highpass.is_some() {
+            self.highpass.clone_from(&checkpoint.highpass);
+        }
+        self.computer.restore_state(&checkpoint.computer);
+
+        let keep = checkpoint
+            .num_frames
+            .checked_sub(self.features_offset)
+            .filter(|&keep| keep <= self.features.len());
+        match keep {
+            Some(keep) => self.features.truncate(keep),
+            None => {
+                self.features.clear();
+                self.features_offset = checkpoint.num_frames;
+            }
+        }
+    }
+
+    /// Copies frames `range` into `out` as a row-major `[range.len(), dim]`
+    /// block, e.g. to fill a model's input tensor.
+    pub fn get_frames(&self, range: Range<usize>, out: &mut [f32]) -> Result<(), KnfError> {
+        copy_frames(
+            &self.features,
+            self.features_offset,
+            self.computer.dim(),
+            range,
+            out,
+        )
+    }
+
+    /// Index of the oldest frame still held.
+    pub fn first_frame(&self) -> usize {
+        self.features_offset
+    }
+
+    /// Removes and returns up to `n` of the oldest held frames. Frame indices
+    /// stay absolute, so later frames keep their numbers.
+    pub fn pop_frames(&mut self, n: usize) -> Vec<Vec<f32>> {
+        let n = n.min(self.features.len());
+        self.features_offset += n;
+        self.features.drain(..n).collect()
+    }
+
+    /// Releases all held frames before frame `t`.
+    pub fn discard_frames_before(&mut self, t: usize) {
+        let n = t
+            .saturating_sub(self.features_offset)
+            .min(self.features.len());
+        self.features.drain(..n);
+        self.features_offset += n;
+    }
+
+    /// Applies the high-pass to the waveform from `start` on.
+    fn filter_from(&mut self, start: usize) {
+        if let Some(highpass) = self.highpass.as_mut() {
+            for x in self.waveform[start..].iter_mut() {
+                *x = highpass.process_sample(*x);
+            }
+        }
     }
 
     fn compute_new(&mut self) {
         let opts = self.computer.frame_opts().clone(); // clone to avoid borrow conflict
         let total_samples = self.waveform_offset + self.waveform.len();
-        let prev_frames = self.features.len();
-        let new_frames = num_frames(total_samples, &opts, self.input_finished);
+        let prev_frames = self.num_frames_ready();
+        let mut new_frames = num_frames(total_samples, &opts, self.input_finished);
+        if let Some(limit) = self.frame_limit {
+            new_frames = new_frames.min(limit);
+        }
 
         if new_frames <= prev_frames {
             return;
@@ -111,7 +503,7 @@ impl OnlineFeature {
                 &self.waveform,
                 frame,
                 &opts,
-                self.window_function.as_ref(),
+                self.window_function.as_deref(),
                 &mut window_buf,
             )
             .expect("Failed to extract window");
@@ -121,12 +513,18 @@ impl OnlineFeature {
             // This is exactly what "raw_log_energy" implies.
 
             let mut feature_vec = vec![0.0; dim];
-            self.computer
-                .compute(raw_log_energy, 1.0, &mut window_buf, &mut feature_vec);
+            self.computer.compute(
+                raw_log_energy,
+                self.vtln_warp,
+                &mut window_buf,
+                &mut feature_vec,
+            );
             self.features.push(feature_vec);
         }
 
-        // Garbage collect waveform
+        // Garbage collect waveform. Samples before the next frame's start are never
+        // read again: with snip_edges=false a tail frame reflects at most
+        // frame_length / 2 samples, all of which lie inside the frame itself.
         let first_sample_next = first_sample_of_frame(new_frames, &opts);
         let discard = first_sample_next - self.waveform_offset as isize;
 
@@ -139,3 +537,142 @@ impl OnlineFeature {
         }
     }
 }
+
+/// Streaming state of an [`OnlineFeature`], see [`OnlineFeature::checkpoint`].
+/// With the `serde` feature it can be stored and resumed in another process
+/// with [`OnlineFeature::resume`].
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OnlineCheckpoint {
+    frame_opts: FrameOptions,
+    dim: usize,
+    waveform: Vec<f32>,
+    waveform_offset: usize,
+    num_frames: usize,
+    input_finished: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    highpass: Option<FirstOrderHighPass>,
+    computer: ComputerState,
+}
+
+impl OnlineCheckpoint {
+    /// Frames ready when the checkpoint was taken.
+    pub fn num_frames(&self) -> usize {
+        self.num_frames
+    }
+}
+
+/// Copies `range` out of `frames`, whose first entry is frame `offset`.
+pub(crate) fn copy_frames(
+    frames: &[Vec<f32>],
+    offset: usize,
+    dim: usize,
+    range: Range<usize>,
+    out: &mut [f32],
+) -> Result<(), KnfError> {
+    let expected = range.len() * dim;
+    if out.len() != expected {
+        return Err(KnfError::SizeMismatch {
+            what: "frame buffer",
+            expected,
+            actual: out.len(),
+        });
+    }
+    if range.is_empty() {
+        return Ok(());
+    }
+    let missing = if range.start < offset {
+        Some(range.start)
+    } else if range.end > offset + frames.len() {
+        Some((offset + frames.len()).max(range.start))
+    } else {
+        None
+    };
+    if let Some(frame) = missing {
+        return Err(KnfError::FrameOutOfRange { frame });
+    }
+    let held = &frames[range.start - offset..range.end - offset];
+    for (row, frame) in out.chunks_exact_mut(dim.max(1)).zip(held) {
+        row.copy_from_slice(frame);
+    }
+    Ok(())
+}
+
+/// Online features for interleaved multi-channel audio: one [`OnlineFeature`]
+/// per output stream of the [`ChannelPolicy`].
+pub struct MultiChannelFeature<C: Computer = FeatureComputer> {
+    num_channels: usize,
+    policy: ChannelPolicy,
+    streams: Vec<OnlineFeature<C>>,
+}
+
+impl<C: Computer> MultiChannelFeature<C> {
+    /// `make_computer` is called once per output stream.
+    pub fn new(
+        num_channels: usize,
+        policy: ChannelPolicy,
+        mut make_computer: impl FnMut() -> Result<C, KnfError>,
+    ) -> Result<Self, KnfError> {
+        if num_channels == 0 {
+            return Err(KnfError::invalid("num_channels must be at least 1"));
+        }
+        if let ChannelPolicy::Select(c) = policy {
+            if c >= num_channels {
+                return Err(KnfError::invalid(format!(
+                    "Channel {} out of range for {} channels",
+                    c, num_channels
+                )));
+            }
+        }
+        let streams = (0..policy.num_outputs(num_channels))
+            .map(|_| make_computer().map(OnlineFeature::new))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            num_channels,
+            policy,
+            streams,
+        })
+    }
+
+    /// Accepts interleaved samples; chunks must contain whole multi-channel frames.
+    pub fn accept_waveform(&mut self, sampling_rate: f32, samples: &[f32]) -> Result<(), KnfError> {
+        let waves = apply_channel_policy(samples, self.num_channels, self.policy)?;
+        for (stream, wave) in self.streams.iter_mut().zip(&waves) {
+            stream.accept_waveform(sampling_rate, wave)?;
+        }
+        Ok(())
+    }
+
+    pub fn input_finished(&mut self) {
+        for stream in self.streams.iter_mut() {
+            stream.input_finished();
+        }
+    }
+
+    pub fn reset(&mut self) {
+        for stream in self.streams.iter_mut() {
+            stream.reset();
+        }
+    }
+
+    pub fn num_streams(&self) -> usize {
+        self.streams.len()
+    }
+
+    pub fn stream(&self, index: usize) -> &OnlineFeature<C> {
+        &self.streams[index]
+    }
+
+    /// Frames ready in every stream.
+    pub fn num_frames_ready(&self) -> usize {
+        self.streams
+            .iter()
+            .map(|s| s.num_frames_ready())
+            .min()
+            .unwrap_or(0)
+    }
+
+    pub fn get_frame(&self, stream: usize, frame: usize) -> Option<&[f32]> {
+        self.streams.get(stream)?.get_frame(frame)
+    }
+}
@@ -0,0 +1,175 @@
+//! Onset strength (spectral flux) over log-mel features and peak-picking
+//! onset detection, following `librosa.onset`.
+
+use crate::error::KnfError;
+use crate::offline::FeatureMatrix;
+
+/// How [`onset_strength`] combines the per-bin flux of a frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum OnsetAggregate {
+    #[default]
+    Mean,
+    Median,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct OnsetStrengthOptions {
+    /// Frames between the compared spectra.
+    pub lag: usize,
+    /// Bins of the maximum filter applied across frequency to the reference
+    /// spectrum, which suppresses vibrato (SuperFlux); 1 disables it.
+    pub max_size: usize,
+    pub aggregate: OnsetAggregate,
+}
+
+impl Default for OnsetStrengthOptions {
+    fn default() -> Self {
+        Self {
+            lag: 1,
+            max_size: 1,
+            aggregate: OnsetAggregate::Mean,
+        }
+    }
+}
+
+impl OnsetStrengthOptions {
+    pub fn validate(&self) -> Result<(), KnfError> {
+        if self.lag == 0 {
+            return Err(KnfError::invalid("lag must be positive"));
+        }
+        if self.max_size == 0 {
+            return Err(KnfError::invalid("max_size must be positive"));
+        }
+        Ok(())
+    }
+}
+
+/// Onset strength of each frame of log-magnitude features (e.g. log-mel
+/// FBANK or a dB mel spectrogram): the aggregated positive difference between
+/// a frame and the (maximum-filtered) frame `lag` earlier. The first `lag`
+/// frames have strength 0.
+pub fn onset_strength(
+    features: &FeatureMatrix,
+    opts: &OnsetStrengthOptions,
+) -> Result<Vec<f32>, KnfError> {
+    opts.validate()?;
+    let dim = features.dim;
+    let mut strength = vec![0.0; features.num_frames];
+    let mut reference = vec![0.0; dim];
+    let mut flux = vec![0.0; dim];
+    let half = opts.max_size / 2;
+    for (t, out) in strength.iter_mut().enumerate().skip(opts.lag) {
+        let prev = features.row(t - opts.lag);
+        for (f, r) in reference.iter_mut().enumerate() {
+            let lo = f.saturating_sub(half);
+            let hi = (f + opts.max_size - half).min(dim);
+            *r = prev[lo..hi]
+                .iter()
+                .copied()
+                .fold(f32::NEG_INFINITY, f32::max);
+        }
+        for ((d, &x), &r) in flux.iter_mut().zip(features.row(t)).zip(&reference) {
+            *d = (x - r).max(0.0);
+        }
+        *out = match opts.aggregate {
+            OnsetAggregate::Median => median(&mut flux),
+            OnsetAggregate::Mean => flux.iter().sum::<f32>() / dim.max(1) as f32,
+        };
+    }
+    Ok(strength)
+}
+
+fn median(values: &mut [f32]) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(f32::total_cmp);
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        0.5 * (values[mid - 1] + values[mid])
+    } else {
+        values[mid]
+    }
+}
+
+/// Peak picking of `librosa.util.peak_pick`, in frames. The defaults are
+/// librosa's `onset_detect` settings converted to 10 ms frames.
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct OnsetDetectOptions {
+    /// A peak is the maximum of frames `[n - pre_max, n + post_max)`.
+    pub pre_max: usize,
+    pub post_max: usize,
+    /// ... and exceeds the mean of frames `[n - pre_avg, n + post_avg)` by `delta`.
+    pub pre_avg: usize,
+    pub post_avg: usize,
+    pub delta: f32,
+    /// Minimum frames between consecutive onsets.
+    pub wait: usize,
+    /// Scale the envelope to [0, 1] before picking, so `delta` is relative.
+    pub normalize: bool,
+}
+
+impl Default for OnsetDetectOptions {
+    fn default() -> Self {
+        Self {
+            pre_max: 3,
+            post_max: 1,
+            pre_avg: 10,
+            post_avg: 1,
+            delta: 0.07,
+            wait: 3,
+            normalize: true,
+        }
+    }
+}
+
+/// Frames of the onsets in an onset strength envelope.
+pub fn onset_detect(strength: &[f32], opts: &OnsetDetectOptions) -> Result<Vec<usize>, KnfError> {
+    if opts.post_max == 0 || opts.post_avg == 0 {
+        return Err(KnfError::invalid("post_max and post_avg must be positive"));
+    }
+    if strength.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut env = strength.to_vec();
+    if opts.normalize {
+        let min = env.iter().copied().fold(f32::INFINITY, f32::min);
+        env.iter_mut().for_each(|x| *x -= min);
+        let max = env.iter().copied().fold(0.0f32, f32::max);
+        if max > 0.0 {
+            env.iter_mut().for_each(|x| *x /= max);
+        }
+    }
+
+    let n = env.len();
+    let mut onsets: Vec<usize> = Vec::new();
+    for (i, &x) in env.iter().enumerate() {
+        let window = |pre: usize, post: usize| &env[i.saturating_sub(pre)..(i + post).min(n)];
+        let max = window(opts.pre_max, opts.post_max)
+            .iter()
+            .copied()
+            .fold(f32::NEG_INFINITY, f32::max);
+        let avg_window = window(opts.pre_avg, opts.post_avg);
+        let avg = avg_window.iter().sum::<f32>() / avg_window.len() as f32;
+        let waited = onsets.last().is_none_or(|&last| i > last + opts.wait);
+        if x == max && x >= avg + opts.delta && waited {
+            onsets.push(i);
+        }
+    }
+    Ok(onsets)
+}
@@ -0,0 +1,111 @@
+use crate::error::KnfError;
+
+/// How integer PCM samples are mapped to floats.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum PcmScale {
+    /// Keep the integer range (`i16::MAX` -> 32767.0), as Kaldi reads wav files.
+    /// Use this for parity with Kaldi features, whose energies and dither
+    /// assume this scale.
+    #[default]
+    Kaldi,
+    /// Scale to [-1, 1) by dividing by 32768, as torchaudio, librosa and
+    /// Whisper do.
+    Normalized,
+}
+
+impl PcmScale {
+    pub fn factor(self) -> f32 {
+        match self {
+            PcmScale::Kaldi => 1.0,
+            PcmScale::Normalized => 1.0 / 32768.0,
+        }
+    }
+}
+
+/// Converts 16-bit PCM samples to floats.
+pub fn i16_to_f32(samples: &[i16], scale: PcmScale) -> Vec<f32> {
+    let factor = scale.factor();
+    samples.iter().map(|&s| s as f32 * factor).collect()
+}
+
+/// How interleaved multi-channel audio is turned into feature input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChannelPolicy {
+    /// Use a single channel (0-based).
+    Select(usize),
+    /// Average all channels to mono.
+    Average,
+    /// Compute features for every channel separately.
+    PerChannel,
+}
+
+impl ChannelPolicy {
+    /// Number of feature streams the policy produces for `num_channels` channels.
+    pub fn num_outputs(self, num_channels: usize) -> usize {
+        match self {
+            ChannelPolicy::PerChannel => num_channels,
+            _ => 1,
+        }
+    }
+}
+
+/// Splits interleaved samples (`[frame * num_channels + channel]`) into one
+/// vector per channel. A trailing partial frame is an error.
+pub fn deinterleave(samples: &[f32], num_channels: usize) -> Result<Vec<Vec<f32>>, KnfError> {
+    check_interleaved(samples.len(), num_channels)?;
+    let num_frames = samples.len() / num_channels;
+    Ok((0..num_channels)
+        .map(|c| {
+            (0..num_frames)
+                .map(|t| samples[t * num_channels + c])
+                .collect()
+        })
+        .collect())
+}
+
+/// Applies `policy` to interleaved samples, returning one waveform per output
+/// stream (a single one unless the policy is `PerChannel`).
+pub fn apply_channel_policy(
+    samples: &[f32],
+    num_channels: usize,
+    policy: ChannelPolicy,
+) -> Result<Vec<Vec<f32>>, KnfError> {
+    check_interleaved(samples.len(), num_channels)?;
+    match policy {
+        ChannelPolicy::Select(c) => {
+            if c >= num_channels {
+                return Err(KnfError::invalid(format!(
+                    "Channel {} out of range for {} channels",
+                    c, num_channels
+                )));
+            }
+            Ok(vec![samples
+                .iter()
+                .skip(c)
+                .step_by(num_channels)
+                .copied()
+                .collect()])
+        }
+        ChannelPolicy::Average => {
+            let scale = 1.0 / num_channels as f32;
+            Ok(vec![samples
+                .chunks(num_channels)
+                .map(|frame| frame.iter().sum::<f32>() * scale)
+                .collect()])
+        }
+        ChannelPolicy::PerChannel => deinterleave(samples, num_channels),
+    }
+}
+
+fn check_interleaved(len: usize, num_channels: usize) -> Result<(), KnfError> {
+    if num_channels == 0 {
+        return Err(KnfError::invalid("num_channels must be at least 1"));
+    }
+    if !len.is_multiple_of(num_channels) {
+        return Err(KnfError::input(format!(
+            "{} samples is not a whole number of {}-channel frames",
+            len, num_channels
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,238 @@
+use crate::error::KnfError;
+use crate::fbank::{FbankComputer, FbankOptions};
+use crate::mfcc::{MfccComputer, MfccOptions};
+use crate::online::{copy_frames, Computer, FeatureComputer, OnlineFeature};
+use crate::pcm::PcmScale;
+use crate::transform::{
+    CmvnOptions, DeltaOptions, OnlineCmvn, OnlineDelta, OnlineSplice, SpliceOptions,
+};
+use crate::yin::{YinComputer, YinOptions};
+use std::ops::Range;
+
+/// Configuration for [`OnlineFeaturePipeline`], similar to Kaldi's online2 feature config.
+///
+/// Stages are applied in the order base features -> CMVN -> pitch append ->
+/// deltas -> splicing -> subsampling; as in Kaldi, CMVN only normalizes the
+/// base features.
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct OnlinePipelineOptions {
+    pub feature_type: String, // "fbank", "mfcc"
+    pub fbank_opts: FbankOptions,
+    pub mfcc_opts: MfccOptions,
+    pub cmvn: Option<CmvnOptions>,
+    /// Appends `[voiced_prob, ln f0]` from [`YinComputer`] to each frame. Its
+    /// frames must have the base frame shift and sampling rate; set
+    /// `snip_edges = false` on both for centered, equally many frames, else
+    /// the last pitch frame is repeated at the end.
+    pub pitch: Option<YinOptions>,
+    pub delta: Option<DeltaOptions>,
+    pub splice: Option<SpliceOptions>,
+    pub subsampling_factor: usize,
+}
+
+impl Default for OnlinePipelineOptions {
+    fn default() -> Self {
+        Self {
+            feature_type: "fbank".to_string(),
+            fbank_opts: FbankOptions::default(),
+            mfcc_opts: MfccOptions::default(),
+            cmvn: None,
+            pitch: None,
+            delta: None,
+            splice: None,
+            subsampling_factor: 1,
+        }
+    }
+}
+
+pub struct OnlineFeaturePipeline {
+    pub opts: OnlinePipelineOptions,
+    base: OnlineFeature,
+    cmvn: Option<OnlineCmvn>,
+    pitch: Option<PitchAppend>,
+    delta: Option<OnlineDelta>,
+    splice: Option<OnlineSplice>,
+    dim: usize,
+    pub features: Vec<Vec<f32>>,
+}
+
+impl OnlineFeaturePipeline {
+    pub fn new(opts: OnlinePipelineOptions) -> Result<Self, KnfError> {
+        if opts.subsampling_factor == 0 {
+            return Err(KnfError::invalid("subsampling_factor must be at least 1"));
+        }
+
+        let computer = match opts.feature_type.as_str() {
+            "fbank" => FeatureComputer::Fbank(FbankComputer::new(opts.fbank_opts.clone())?),
+            "mfcc" => FeatureComputer::Mfcc(MfccComputer::new(opts.mfcc_opts.clone())?),
+            other => {
+                return Err(KnfError::invalid(format!(
+                    "Unsupported feature type: {}",
+                    other
+                )))
+            }
+        };
+
+        let mut dim = computer.dim();
+        let cmvn = opts.cmvn.clone().map(|c| OnlineCmvn::new(c, dim));
+        let pitch = match &opts.pitch {
+            Some(pitch_opts) => {
+                let base = computer.frame_opts();
+                let pitch = &pitch_opts.frame_opts;
+                if pitch.samp_freq != base.samp_freq || pitch.window_shift() != base.window_shift()
+                {
+                    return Err(KnfError::invalid(
+                        "Pitch frames must share the base sampling rate and frame shift",
+                    ));
+                }
+                let computer = YinComputer::new(pitch_opts.clone())?;
+                dim += computer.dim();
+                Some(PitchAppend {
+                    feature: OnlineFeature::new(computer),
+                    frames: Vec::new(),
+                })
+            }
+            None => None,
+        };
+        let delta = opts.delta.clone().map(OnlineDelta::new);
+        if let Some(d) = &delta {
+            dim = d.dim(dim);
+        }
+        let splice = opts.splice.clone().map(OnlineSplice::new);
+        if let Some(s) = &splice {
+            dim = s.dim(dim);
+        }
+
+        Ok(Self {
+            opts,
+            base: OnlineFeature::new(computer),
+            cmvn,
+            pitch,
+            delta,
+            splice,
+            dim,
+            features: Vec::new(),
+        })
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// Number of base frames that must be computed beyond a frame before it
+    /// can be output (before `input_finished` is called).
+    pub fn latency(&self) -> usize {
+        self.delta.as_ref().map_or(0, |d| d.context())
+            + self.splice.as_ref().map_or(0, |s| s.context())
+    }
+
+    pub fn accept_waveform(
+        &mut self,
+        sampling_rate: f32,
+        waveform: &[f32],
+    ) -> Result<(), KnfError> {
+        self.base.accept_waveform(sampling_rate, waveform)?;
+        if let Some(pitch) = &mut self.pitch {
+            pitch.feature.accept_waveform(sampling_rate, waveform)?;
+        }
+        self.compute_new();
+        Ok(())
+    }
+
+    pub fn accept_waveform_i16(
+        &mut self,
+        sampling_rate: f32,
+        waveform: &[i16],
+        scale: PcmScale,
+    ) -> Result<(), KnfError> {
+        self.base
+            .accept_waveform_i16(sampling_rate, waveform, scale)?;
+        if let Some(pitch) = &mut self.pitch {
+            pitch
+                .feature
+                .accept_waveform_i16(sampling_rate, waveform, scale)?;
+        }
+        self.compute_new();
+        Ok(())
+    }
+
+    pub fn input_finished(&mut self) {
+        self.base.input_finished();
+        if let Some(pitch) = &mut self.pitch {
+            pitch.feature.input_finished();
+        }
+        self.compute_new();
+    }
+
+    pub fn is_input_finished(&self) -> bool {
+        self.base.is_input_finished()
+    }
+
+    pub fn num_frames_ready(&self) -> usize {
+        self.features.len()
+    }
+
+    pub fn get_frame(&self, frame: usize) -> Option<&[f32]> {
+        self.features.get(frame).map(|v| v.as_slice())
+    }
+
+    /// Copies frames `range` into `out` as a row-major `[range.len(), dim]` block.
+    pub fn get_frames(&self, range: Range<usize>, out: &mut [f32]) -> Result<(), KnfError> {
+        copy_frames(&self.features, 0, self.dim, range, out)
+    }
+
+    fn compute_new(&mut self) {
+        let finished = self.base.is_input_finished();
+        let mut frames: &[Vec<f32>] = &self.base.features;
+
+        if let Some(cmvn) = &mut self.cmvn {
+            cmvn.advance(frames, finished);
+            frames = &cmvn.frames;
+        }
+        if let Some(pitch) = &mut self.pitch {
+            pitch.advance(frames, finished);
+            frames = &pitch.frames;
+        }
+        if let Some(delta) = &mut self.delta {
+            delta.advance(frames, finished);
+            frames = &delta.frames;
+        }
+        if let Some(splice) = &mut self.splice {
+            splice.advance(frames, finished);
+            frames = &splice.frames;
+        }
+
+        let factor = self.opts.subsampling_factor;
+        let ready = frames.len().div_ceil(factor);
+        for i in self.features.len()..ready {
+            self.features.push(frames[i * factor].clone());
+        }
+    }
+}
+
+/// Appends pitch frames to the base frames once both are ready.
+struct PitchAppend {
+    feature: OnlineFeature<YinComputer>,
+    frames: Vec<Vec<f32>>,
+}
+
+impl PitchAppend {
+    fn advance(&mut self, input: &[Vec<f32>], finished: bool) {
+        let pitch = &self.feature.features;
+        let ready = if finished {
+            input.len()
+        } else {
+            input.len().min(pitch.len())
+        };
+        for (t, frame) in input.iter().enumerate().take(ready).skip(self.frames.len()) {
+            // Inputs shorter than one pitch window get no pitch at all.
+            let p = pitch.get(t).or(pitch.last()).map_or(&[0.0; 2][..], |p| p);
+            self.frames.push([frame.as_slice(), p].concat());
+        }
+    }
+}
@@ -0,0 +1,154 @@
+//! Many concurrent [`OnlineFeature`] streams, as kept by a streaming ASR server.
+
+use crate::error::KnfError;
+use crate::offline::FeatureMatrix;
+use crate::online::{Computer, FeatureComputer, OnlineFeature};
+use crate::window::{FrameOptions, Window};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+
+/// Frames released from one stream by [`StreamPool::collect_ready`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReadyFrames<K> {
+    pub id: K,
+    /// Absolute index of the first row of `frames`.
+    pub first_frame: usize,
+    pub frames: FeatureMatrix,
+    /// Input is finished and these are the stream's last frames.
+    pub is_final: bool,
+}
+
+/// Online feature streams keyed by stream id.
+///
+/// Closed streams are [`reset`](OnlineFeature::reset) and recycled for the
+/// next [`open`](Self::open), so buffers are allocated once per concurrent
+/// stream rather than once per utterance. Streams with the same frame options
+/// share one window, and FFT plans are shared by all streams of a size; only
+/// scratch and sample buffers are per stream.
+pub struct StreamPool<K, C: Computer = FeatureComputer> {
+    make_computer: Box<dyn FnMut() -> Result<C, KnfError> + Send>,
+    streams: HashMap<K, OnlineFeature<C>>,
+    idle: Vec<OnlineFeature<C>>,
+    /// Window of the first stream built, reused by later ones.
+    window: Option<(FrameOptions, Option<Arc<Window>>)>,
+}
+
+impl<K: Eq + Hash + Clone, C: Computer> StreamPool<K, C> {
+    /// `make_computer` is called whenever a stream is opened and no closed
+    /// stream can be recycled.
+    pub fn new(make_computer: impl FnMut() -> Result<C, KnfError> + Send + 'static) -> Self {
+        Self {
+            make_computer: Box::new(make_computer),
+            streams: HashMap::new(),
+            idle: Vec::new(),
+            window: None,
+        }
+    }
+
+    /// Starts a new stream; fails if `id` is already open.
+    pub fn open(&mut self, id: K) -> Result<&mut OnlineFeature<C>, KnfError> {
+        if self.streams.contains_key(&id) {
+            return Err(KnfError::input("Stream is already open"));
+        }
+        let stream = match self.idle.pop() {
+            Some(stream) => stream,
+            None => self.new_stream()?,
+        };
+        Ok(self.streams.entry(id).or_insert(stream))
+    }
+
+    /// Ends a stream, dropping its pending frames. Returns `false` for an
+    /// unknown id.
+    pub fn close(&mut self, id: &K) -> bool {
+        match self.streams.remove(id) {
+            Some(mut stream) => {
+                stream.reset();
+                self.idle.push(stream);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn new_stream(&mut self) -> Result<OnlineFeature<C>, KnfError> {
+        let computer = (self.make_computer)()?;
+        if let Some((opts, window)) = &self.window {
+            if opts == computer.frame_opts() {
+                return Ok(OnlineFeature::with_window(computer, window.clone()));
+            }
+        }
+        let stream = OnlineFeature::new(computer);
+        self.window = Some((
+            stream.computer().frame_opts().clone(),
+            stream.window_function().cloned(),
+        ));
+        Ok(stream)
+    }
+
+    pub fn get(&self, id: &K) -> Option<&OnlineFeature<C>> {
+        self.streams.get(id)
+    }
+
+    pub fn get_mut(&mut self, id: &K) -> Option<&mut OnlineFeature<C>> {
+        self.streams.get_mut(id)
+    }
+
+    pub fn accept_waveform(
+        &mut self,
+        id: &K,
+        sampling_rate: f32,
+        samples: &[f32],
+    ) -> Result<(), KnfError> {
+        self.stream_mut(id)?.accept_waveform(sampling_rate, samples)
+    }
+
+    pub fn input_finished(&mut self, id: &K) -> Result<(), KnfError> {
+        self.stream_mut(id)?.input_finished();
+        Ok(())
+    }
+
+    /// Number of open streams.
+    pub fn len(&self) -> usize {
+        self.streams.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.streams.is_empty()
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = &K> {
+        self.streams.keys()
+    }
+
+    /// Releases the held frames of every stream that has any, in no
+    /// particular order.
+    pub fn collect_ready(&mut self) -> Vec<ReadyFrames<K>> {
+        let mut ready = Vec::new();
+        for (id, stream) in self.streams.iter_mut() {
+            if stream.features.is_empty() {
+                continue;
+            }
+            let dim = stream.computer().dim();
+            let first_frame = stream.first_frame();
+            let frames = stream.pop_frames(stream.features.len());
+            ready.push(ReadyFrames {
+                id: id.clone(),
+                first_frame,
+                frames: FeatureMatrix {
+                    num_frames: frames.len(),
+                    data: frames.concat(),
+                    dim,
+                },
+                is_final: stream.is_input_finished(),
+            });
+        }
+        ready
+    }
+
+    fn stream_mut(&mut self, id: &K) -> Result<&mut OnlineFeature<C>, KnfError> {
+        self.streams
+            .get_mut(id)
+            .ok_or_else(|| KnfError::input("Unknown stream id"))
+    }
+}
@@ -1,18 +1,16 @@
+use crate::online::Computer;
 use crate::window::FrameOptions;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct RawAudioOptions {
     pub frame_opts: FrameOptions,
 }
 
-impl Default for RawAudioOptions {
-    fn default() -> Self {
-        Self {
-            frame_opts: FrameOptions::default(),
-        }
-    }
-}
-
 pub struct RawAudioComputer {
     pub opts: RawAudioOptions,
 }
@@ -31,3 +29,27 @@ impl RawAudioComputer {
         }
     }
 }
+
+impl Computer for RawAudioComputer {
+    fn dim(&self) -> usize {
+        RawAudioComputer::dim(self)
+    }
+
+    fn frame_opts(&self) -> &FrameOptions {
+        &self.opts.frame_opts
+    }
+
+    fn need_raw_energy(&self) -> bool {
+        false
+    }
+
+    fn compute(
+        &mut self,
+        raw_log_energy: f32,
+        vtln_warp: f32,
+        window: &mut [f32],
+        feature: &mut [f32],
+    ) {
+        RawAudioComputer::compute(self, raw_log_energy, vtln_warp, window, feature)
+    }
+}
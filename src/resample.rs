@@ -0,0 +1,476 @@
+use crate::error::KnfError;
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+
+/// Windowed-sinc resampler between integer sampling rates, ported from Kaldi's
+/// `LinearResample`.
+///
+/// Input may be fed in chunks with [`resample`](Self::resample); the output of
+/// all chunks followed by [`flush`](Self::flush) equals resampling the whole
+/// signal at once.
+pub struct LinearResample {
+    samp_rate_in: u32,
+    samp_rate_out: u32,
+    filter_cutoff: f32,
+    num_zeros: usize,
+    input_samples_in_unit: usize,
+    output_samples_in_unit: usize,
+    // For each output sample within a unit, the first input sample and the
+    // filter weights applied from there.
+    first_index: Vec<i64>,
+    weights: Vec<Vec<f32>>,
+    input_sample_offset: i64,
+    output_sample_offset: i64,
+    input_remainder: Vec<f32>,
+}
+
+impl LinearResample {
+    /// `filter_cutoff` (Hz) must be below half of both rates; `num_zeros`
+    /// controls the filter length (Kaldi uses 6 with a cutoff of `0.99 * min_rate / 2`).
+    pub fn new(
+        samp_rate_in: u32,
+        samp_rate_out: u32,
+        filter_cutoff: f32,
+        num_zeros: usize,
+    ) -> Result<Self, KnfError> {
+        if samp_rate_in == 0 || samp_rate_out == 0 {
+            return Err(KnfError::invalid("Sampling rates must be positive"));
+        }
+        let nyquist = 0.5 * samp_rate_in.min(samp_rate_out) as f32;
+        if filter_cutoff <= 0.0 || filter_cutoff > nyquist {
+            return Err(KnfError::invalid(format!(
+                "Filter cutoff {} must be in (0, {}]",
+                filter_cutoff, nyquist
+            )));
+        }
+        if num_zeros == 0 {
+            return Err(KnfError::invalid("num_zeros must be positive"));
+        }
+
+        let base_freq = gcd(samp_rate_in, samp_rate_out);
+        let mut resampler = Self {
+            samp_rate_in,
+            samp_rate_out,
+            filter_cutoff,
+            num_zeros,
+            input_samples_in_unit: (samp_rate_in / base_freq) as usize,
+            output_samples_in_unit: (samp_rate_out / base_freq) as usize,
+            first_index: Vec::new(),
+            weights: Vec::new(),
+            input_sample_offset: 0,
+            output_sample_offset: 0,
+            input_remainder: Vec::new(),
+        };
+        resampler.set_indexes_and_weights();
+        Ok(resampler)
+    }
+
+    pub fn samp_rate_in(&self) -> u32 {
+        self.samp_rate_in
+    }
+
+    pub fn samp_rate_out(&self) -> u32 {
+        self.samp_rate_out
+    }
+
+    /// Resamples the next chunk of input. Output samples whose filter support
+    /// extends past the received input are held back until more input arrives
+    /// or [`flush`](Self::flush) is called.
+    pub fn resample(&mut self, input: &[f32]) -> Vec<f32> {
+        self.resample_impl(input, false)
+    }
+
+    /// Emits the remaining output, treating the signal as zero after the last
+    /// input sample, and resets the resampler for a new signal.
+    pub fn flush(&mut self) -> Vec<f32> {
+        self.resample_impl(&[], true)
+    }
+
+    pub fn reset(&mut self) {
+        self.input_sample_offset = 0;
+        self.output_sample_offset = 0;
+        self.input_remainder.clear();
+    }
+
+    /// Number of output samples available after `input_num_samp` input samples.
+    fn num_output_samples(&self, input_num_samp: i64, flush: bool) -> i64 {
+        // Time is measured in "ticks" of the least common multiple of both
+        // rates, so input and output periods are integers.
+        let tick_freq = lcm(self.samp_rate_in, self.samp_rate_out) as i64;
+        let ticks_per_input_period = tick_freq / self.samp_rate_in as i64;
+
+        let mut interval_length_in_ticks = input_num_samp * ticks_per_input_period;
+        if !flush {
+            let window_width = self.num_zeros as f64 / (2.0 * self.filter_cutoff as f64);
+            let window_width_ticks = (window_width * tick_freq as f64).floor() as i64;
+            interval_length_in_ticks -= window_width_ticks;
+        }
+        if interval_length_in_ticks <= 0 {
+            return 0;
+        }
+
+        let ticks_per_output_period = tick_freq / self.samp_rate_out as i64;
+        let mut last_output_samp = interval_length_in_ticks / ticks_per_output_period;
+        if last_output_samp * ticks_per_output_period == interval_length_in_ticks {
+            last_output_samp -= 1;
+        }
+        last_output_samp + 1
+    }
+
+    fn resample_impl(&mut self, input: &[f32], flush: bool) -> Vec<f32> {
+        let input_dim = input.len() as i64;
+        let tot_input_samp = self.input_sample_offset + input_dim;
+        let tot_output_samp = self.num_output_samples(tot_input_samp, flush);
+        assert!(tot_output_samp >= self.output_sample_offset);
+
+        let mut output = Vec::with_capacity((tot_output_samp - self.output_sample_offset) as usize);
+        for samp_out in self.output_sample_offset..tot_output_samp {
+            let (first_samp_in, samp_out_wrapped) = self.indexes(samp_out);
+            let weights = &self.weights[samp_out_wrapped];
+            let first_input_index = first_samp_in - self.input_sample_offset;
+
+            let remainder_dim = self.input_remainder.len() as i64;
+            let mut this_output = 0.0;
+            for (i, &weight) in weights.iter().enumerate() {
+                let input_index = first_input_index + i as i64;
+                if input_index < 0 && remainder_dim + input_index >= 0 {
+                    this_output +=
+                        weight * self.input_remainder[(remainder_dim + input_index) as usize];
+                } else if input_index >= 0 && input_index < input_dim {
+                    this_output += weight * input[input_index as usize];
+                } else if input_index >= input_dim {
+                    // Only reachable when flushing: samples past the end are zero.
+                    debug_assert!(flush);
+                }
+            }
+            output.push(this_output);
+        }
+
+        if flush {
+            self.reset();
+        } else {
+            self.set_remainder(input);
+            self.input_sample_offset = tot_input_samp;
+            self.output_sample_offset = tot_output_samp;
+        }
+        output
+    }
+
+    fn indexes(&self, samp_out: i64) -> (i64, usize) {
+        let unit_index = samp_out / self.output_samples_in_unit as i64;
+        let samp_out_wrapped = (samp_out % self.output_samples_in_unit as i64) as usize;
+        let first_samp_in =
+            self.first_index[samp_out_wrapped] + unit_index * self.input_samples_in_unit as i64;
+        (first_samp_in, samp_out_wrapped)
+    }
+
+    /// Keeps the tail of the input that later output samples may still need.
+    fn set_remainder(&mut self, input: &[f32]) {
+        let old_remainder = std::mem::take(&mut self.input_remainder);
+        let max_remainder_needed = (self.samp_rate_in as f64 * self.num_zeros as f64
+            / self.filter_cutoff as f64)
+            .ceil() as i64;
+
+        let input_dim = input.len() as i64;
+        let old_dim = old_remainder.len() as i64;
+        self.input_remainder = (-max_remainder_needed..0)
+            .map(|index| {
+                let input_index = index + input_dim;
+                if input_index >= 0 {
+                    input[input_index as usize]
+                } else if input_index + old_dim >= 0 {
+                    old_remainder[(input_index + old_dim) as usize]
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+    }
+
+    fn set_indexes_and_weights(&mut self) {
+        for i in 0..self.output_samples_in_unit {
+            let output_t = i as f64 / self.samp_rate_out as f64;
+            let (first, weights) = sinc_weights(
+                output_t,
+                self.samp_rate_in as f32,
+                self.filter_cutoff,
+                self.num_zeros,
+            );
+            self.first_index.push(first);
+            self.weights.push(weights);
+        }
+    }
+}
+
+/// Resamples a whole waveform with Kaldi's default filter, as in Kaldi's
+/// `ResampleWaveform`: a cutoff of 99% of the lower Nyquist frequency and 6 zeros.
+pub fn resample_waveform(
+    waveform: &[f32],
+    samp_rate_in: u32,
+    samp_rate_out: u32,
+) -> Result<Vec<f32>, KnfError> {
+    if samp_rate_in == samp_rate_out {
+        return Ok(waveform.to_vec());
+    }
+    let cutoff = 0.99 * 0.5 * samp_rate_in.min(samp_rate_out) as f32;
+    let mut resampler = LinearResample::new(samp_rate_in, samp_rate_out, cutoff, 6)?;
+    let mut output = resampler.resample(waveform);
+    output.extend(resampler.flush());
+    Ok(output)
+}
+
+/// Evaluates a fixed-length signal at arbitrary times, ported from Kaldi's
+/// `ArbitraryResample`. Samples outside the signal are treated as zero.
+pub struct ArbitraryResample {
+    num_samples_in: usize,
+    first_index: Vec<usize>,
+    weights: Vec<Vec<f32>>,
+}
+
+impl ArbitraryResample {
+    /// `sample_points` are the output times in seconds; `filter_cutoff` (Hz)
+    /// must not exceed half of `samp_rate_in`.
+    pub fn new(
+        num_samples_in: usize,
+        samp_rate_in: f32,
+        filter_cutoff: f32,
+        sample_points: &[f64],
+        num_zeros: usize,
+    ) -> Result<Self, KnfError> {
+        check_filter(samp_rate_in, filter_cutoff, num_zeros)?;
+        let (mut first_index, mut weights) = (Vec::new(), Vec::new());
+        for &t in sample_points {
+            let (first, w) = sinc_weights(t, samp_rate_in, filter_cutoff, num_zeros);
+            // Clip the support to the signal
+            let start = (-first).max(0) as usize;
+            let end = (num_samples_in as i64 - first).clamp(0, w.len() as i64) as usize;
+            first_index.push((first + start as i64) as usize);
+            weights.push(w.get(start..end).unwrap_or_default().to_vec());
+        }
+        Ok(Self {
+            num_samples_in,
+            first_index,
+            weights,
+        })
+    }
+
+    pub fn num_samples_in(&self) -> usize {
+        self.num_samples_in
+    }
+
+    pub fn num_samples_out(&self) -> usize {
+        self.weights.len()
+    }
+
+    pub fn resample(&self, input: &[f32]) -> Vec<f32> {
+        assert_eq!(input.len(), self.num_samples_in);
+        self.first_index
+            .iter()
+            .zip(&self.weights)
+            .map(|(&first, w)| w.iter().zip(&input[first..]).map(|(w, x)| w * x).sum())
+            .collect()
+    }
+}
+
+enum SampleTimes {
+    Explicit(VecDeque<f64>),
+    /// Output sample `n` is read at input time `n * step`.
+    Uniform {
+        step: f64,
+        next: u64,
+    },
+}
+
+/// Streaming counterpart of [`ArbitraryResample`]: audio arrives in chunks and
+/// each output time is evaluated as soon as the filter support around it has
+/// been received. Output times must be non-decreasing, which lets consumed
+/// audio be discarded.
+pub struct StreamingArbitraryResample {
+    samp_rate_in: f32,
+    filter_cutoff: f32,
+    num_zeros: usize,
+    times: SampleTimes,
+    last_time: f64,
+    waveform: Vec<f32>,
+    waveform_offset: usize,
+    input_finished: bool,
+}
+
+impl StreamingArbitraryResample {
+    /// Evaluates the times given with [`push_times`](Self::push_times).
+    pub fn new(samp_rate_in: f32, filter_cutoff: f32, num_zeros: usize) -> Result<Self, KnfError> {
+        check_filter(samp_rate_in, filter_cutoff, num_zeros)?;
+        Ok(Self {
+            samp_rate_in,
+            filter_cutoff,
+            num_zeros,
+            times: SampleTimes::Explicit(VecDeque::new()),
+            last_time: f64::NEG_INFINITY,
+            waveform: Vec::new(),
+            waveform_offset: 0,
+            input_finished: false,
+        })
+    }
+
+    /// Reads the input every `ratio` input samples, e.g. `ratio = 1.1` plays
+    /// the signal 10% faster (speed perturbation) at the same sampling rate.
+    /// The cutoff is lowered for `ratio > 1` to avoid aliasing.
+    pub fn with_ratio(samp_rate_in: f32, ratio: f64, num_zeros: usize) -> Result<Self, KnfError> {
+        if ratio <= 0.0 || !ratio.is_finite() {
+            return Err(KnfError::invalid("Resampling ratio must be positive"));
+        }
+        let cutoff = 0.99 * 0.5 * samp_rate_in * (1.0 / ratio).min(1.0) as f32;
+        let mut resampler = Self::new(samp_rate_in, cutoff, num_zeros)?;
+        resampler.times = SampleTimes::Uniform {
+            step: ratio / samp_rate_in as f64,
+            next: 0,
+        };
+        Ok(resampler)
+    }
+
+    /// Queues output times in seconds. Panics if the times decrease or if the
+    /// resampler was created with [`with_ratio`](Self::with_ratio).
+    pub fn push_times(&mut self, times: &[f64]) {
+        let SampleTimes::Explicit(queue) = &mut self.times else {
+            panic!("push_times on a fixed-ratio resampler");
+        };
+        for &t in times {
+            assert!(t >= self.last_time, "Output times must be non-decreasing");
+            self.last_time = t;
+            queue.push_back(t);
+        }
+    }
+
+    /// Buffers a chunk of input and returns the output samples that became available.
+    pub fn accept_waveform(&mut self, waveform: &[f32]) -> Vec<f32> {
+        assert!(
+            !self.input_finished,
+            "accept_waveform called after input_finished"
+        );
+        self.waveform.extend_from_slice(waveform);
+        self.compute_available()
+    }
+
+    /// Marks the end of input. Returns the remaining outputs, treating samples
+    /// after the end as zero; for a fixed ratio these are the times up to the
+    /// end of the signal.
+    pub fn input_finished(&mut self) -> Vec<f32> {
+        self.input_finished = true;
+        self.compute_available()
+    }
+
+    fn next_time(&self) -> Option<f64> {
+        match &self.times {
+            SampleTimes::Explicit(queue) => queue.front().copied(),
+            SampleTimes::Uniform { step, next } => Some(*next as f64 * step),
+        }
+    }
+
+    fn pop_time(&mut self) {
+        match &mut self.times {
+            SampleTimes::Explicit(queue) => {
+                queue.pop_front();
+            }
+            SampleTimes::Uniform { next, .. } => *next += 1,
+        }
+    }
+
+    fn compute_available(&mut self) -> Vec<f32> {
+        let total = self.waveform_offset + self.waveform.len();
+        let mut output = Vec::new();
+        while let Some(t) = self.next_time() {
+            let uniform = matches!(self.times, SampleTimes::Uniform { .. });
+            if uniform && self.input_finished && t * self.samp_rate_in as f64 >= total as f64 {
+                break;
+            }
+            let (first, weights) =
+                sinc_weights(t, self.samp_rate_in, self.filter_cutoff, self.num_zeros);
+            if !self.input_finished && first + weights.len() as i64 > total as i64 {
+                break;
+            }
+
+            let mut y = 0.0;
+            for (i, w) in weights.iter().enumerate() {
+                let index = first + i as i64 - self.waveform_offset as i64;
+                if index >= 0 && (index as usize) < self.waveform.len() {
+                    y += w * self.waveform[index as usize];
+                }
+            }
+            output.push(y);
+            self.pop_time();
+        }
+
+        // Discard audio before the support of the next output time
+        if let Some(t) = self.next_time() {
+            let (first, _) = sinc_weights(t, self.samp_rate_in, self.filter_cutoff, self.num_zeros);
+            let discard =
+                (first - self.waveform_offset as i64).clamp(0, self.waveform.len() as i64);
+            self.waveform.drain(..discard as usize);
+            self.waveform_offset += discard as usize;
+        }
+        output
+    }
+}
+
+fn check_filter(samp_rate_in: f32, filter_cutoff: f32, num_zeros: usize) -> Result<(), KnfError> {
+    if samp_rate_in <= 0.0 {
+        return Err(KnfError::invalid("Sampling rate must be positive"));
+    }
+    if filter_cutoff <= 0.0 || filter_cutoff > 0.5 * samp_rate_in {
+        return Err(KnfError::invalid(format!(
+            "Filter cutoff {} must be in (0, {}]",
+            filter_cutoff,
+            0.5 * samp_rate_in
+        )));
+    }
+    if num_zeros == 0 {
+        return Err(KnfError::invalid("num_zeros must be positive"));
+    }
+    Ok(())
+}
+
+/// First input index and filter weights for evaluating the signal at time `t`.
+fn sinc_weights(
+    t: f64,
+    samp_rate_in: f32,
+    filter_cutoff: f32,
+    num_zeros: usize,
+) -> (i64, Vec<f32>) {
+    let samp_rate_in = samp_rate_in as f64;
+    let window_width = num_zeros as f64 / (2.0 * filter_cutoff as f64);
+    let min_input_index = ((t - window_width) * samp_rate_in).ceil() as i64;
+    let max_input_index = ((t + window_width) * samp_rate_in).floor() as i64;
+    let weights = (min_input_index..=max_input_index)
+        .map(|i| {
+            let delta_t = i as f64 / samp_rate_in - t;
+            (filter_func(delta_t, filter_cutoff as f64, num_zeros as f64) / samp_rate_in) as f32
+        })
+        .collect();
+    (min_input_index, weights)
+}
+
+/// Hann-windowed sinc low-pass filter.
+fn filter_func(t: f64, cutoff: f64, num_zeros: f64) -> f64 {
+    let window = if t.abs() < num_zeros / (2.0 * cutoff) {
+        0.5 * (1.0 + (2.0 * PI * cutoff / num_zeros * t).cos())
+    } else {
+        0.0
+    };
+    let filter = if t != 0.0 {
+        (2.0 * PI * cutoff * t).sin() / (PI * t)
+    } else {
+        2.0 * cutoff
+    };
+    filter * window
+}
+
+fn gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+fn lcm(a: u32, b: u32) -> u64 {
+    a as u64 / gcd(a, b) as u64 * b as u64
+}
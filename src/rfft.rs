@@ -1,25 +1,48 @@
 use realfft::num_traits::Zero;
-use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
+use realfft::{ComplexToReal, FftNum, RealFftPlanner, RealToComplex};
 use rustfft::num_complex::Complex;
-use std::sync::Arc;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Runs `f` with the process-wide planner for `T`. `RealFftPlanner` caches
+/// its plans, so all `Rfft`s of one size and direction share the same plan
+/// (and its twiddle tables) instead of each building their own.
+fn with_planner<T: FftNum, R>(f: impl FnOnce(&mut RealFftPlanner<T>) -> R) -> R {
+    static PLANNERS: OnceLock<Mutex<HashMap<TypeId, Box<dyn Any + Send>>>> = OnceLock::new();
+    let mut planners = PLANNERS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    let planner = planners
+        .entry(TypeId::of::<T>())
+        .or_insert_with(|| Box::new(RealFftPlanner::<T>::new()));
+    f(planner
+        .downcast_mut()
+        .expect("planner of another sample type"))
+}
 
 /// A wrapper around `realfft` to mimic the behavior of the C `knf_rfft`.
-pub struct Rfft {
+///
+/// Generic over the sample type; `Rfft<f64>` gives double-precision spectra
+/// for parity checks against Kaldi or librosa.
+pub struct Rfft<T: FftNum = f32> {
     n: usize,
     inverse: bool,
-    r2c: Option<Arc<dyn RealToComplex<f32>>>,
-    c2r: Option<Arc<dyn ComplexToReal<f32>>>,
-    scratch: Vec<Complex<f32>>, // Scratch buffer for FFT computation
+    r2c: Option<Arc<dyn RealToComplex<T>>>,
+    c2r: Option<Arc<dyn ComplexToReal<T>>>,
+    scratch: Vec<Complex<T>>, // Scratch buffer for FFT computation
 }
 
-impl Rfft {
+impl<T: FftNum> Rfft<T> {
     pub fn new(n: usize, inverse: bool) -> Self {
-        let mut planner = RealFftPlanner::<f32>::new();
-        let (r2c, c2r) = if !inverse {
-            (Some(planner.plan_fft_forward(n)), None)
-        } else {
-            (None, Some(planner.plan_fft_inverse(n)))
-        };
+        let (r2c, c2r) = with_planner::<T, _>(|planner| {
+            if !inverse {
+                (Some(planner.plan_fft_forward(n)), None)
+            } else {
+                (None, Some(planner.plan_fft_inverse(n)))
+            }
+        });
 
         // Pre-allocate scratch space based on the planner requirements
         let scratch_len = if let Some(ref p) = r2c {
@@ -43,11 +66,13 @@ impl Rfft {
     /// Input `data` is the real signal (length `n`).
     /// Output is written back into `data` mimicking the C-style packed format:
     /// [Re(0), Re(N/2), Re(1), Im(1), Re(2), Im(2), ...]
+    /// For odd `n` the highest bin `(n - 1) / 2` is complex: its real part is
+    /// stored in `data[1]` and its imaginary part in `data[n - 1]`.
     ///
     /// For Inverse (Complex->Real):
     /// Input `data` is packed complex.
     /// Output is real signal.
-    pub fn compute(&mut self, data: &mut [f32]) {
+    pub fn compute(&mut self, data: &mut [T]) {
         if !self.inverse {
             self.compute_forward(data);
         } else {
@@ -55,7 +80,7 @@ impl Rfft {
         }
     }
 
-    fn compute_forward(&mut self, data: &mut [f32]) {
+    fn compute_forward(&mut self, data: &mut [T]) {
         let n = self.n;
         if data.len() < n {
             panic!("Data length {} too small for FFT size {}", data.len(), n);
@@ -79,11 +104,11 @@ impl Rfft {
         // Format: [Re(0), Re(N/2), Re(1), Im(1), ... ]
 
         data[0] = output_complex[0].re;
-        if n % 2 == 0 {
+        if n.is_multiple_of(2) {
             data[1] = output_complex[n / 2].re;
-        } else {
-            // Should usually be power of 2 in this library, but handle odd case
-            data[1] = 0.0;
+        } else if n > 1 {
+            data[1] = output_complex[n / 2].re;
+            data[n - 1] = output_complex[n / 2].im;
         }
 
         for i in 1..n / 2 {
@@ -92,21 +117,23 @@ impl Rfft {
         }
     }
 
-    fn compute_inverse(&mut self, data: &mut [f32]) {
+    fn compute_inverse(&mut self, data: &mut [T]) {
         let n = self.n;
         // Unpack from [Re(0), Re(N/2), Re(1), Im(1)...] to [Complex]
         let mut input_complex = vec![Complex::zero(); n / 2 + 1];
 
-        input_complex[0] = Complex::new(data[0], 0.0);
-        if n % 2 == 0 {
-            input_complex[n / 2] = Complex::new(data[1], 0.0);
+        input_complex[0] = Complex::new(data[0], T::zero());
+        if n.is_multiple_of(2) {
+            input_complex[n / 2] = Complex::new(data[1], T::zero());
+        } else if n > 1 {
+            input_complex[n / 2] = Complex::new(data[1], data[n - 1]);
         }
 
         for i in 1..n / 2 {
             input_complex[i] = Complex::new(data[2 * i], data[2 * i + 1]);
         }
 
-        let mut output_real = vec![0.0; n];
+        let mut output_real = vec![T::zero(); n];
 
         self.c2r
             .as_ref()
@@ -0,0 +1,85 @@
+//! Explicit SIMD kernels for the f32 dot products behind frame energies, mel
+//! integration and the MFCC DCT. Selected at runtime on x86_64 (AVX + FMA,
+//! falling back to SSE) and always NEON on aarch64; other targets use the
+//! scalar loop.
+
+/// Dot product over the common prefix of `a` and `b`.
+pub(crate) fn dot(a: &[f32], b: &[f32]) -> f32 {
+    let n = a.len().min(b.len());
+    let (a, b) = (&a[..n], &b[..n]);
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        if std::arch::is_x86_feature_detected!("avx") && std::arch::is_x86_feature_detected!("fma")
+        {
+            // SAFETY: the required CPU features were detected above.
+            return unsafe { x86::dot_avx_fma(a, b) };
+        }
+        // SAFETY: SSE is part of the x86_64 baseline.
+        return unsafe { x86::dot_sse(a, b) };
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        // SAFETY: NEON is part of the aarch64 baseline.
+        return unsafe { neon::dot(a, b) };
+    }
+
+    #[allow(unreachable_code)]
+    scalar(a, b)
+}
+
+#[cfg_attr(any(target_arch = "x86_64", target_arch = "aarch64"), allow(dead_code))]
+fn scalar(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    #[target_feature(enable = "avx,fma")]
+    pub(super) unsafe fn dot_avx_fma(a: &[f32], b: &[f32]) -> f32 {
+        let chunks = a.len() / 8;
+        let mut acc = _mm256_setzero_ps();
+        for i in 0..chunks {
+            let x = _mm256_loadu_ps(a.as_ptr().add(i * 8));
+            let y = _mm256_loadu_ps(b.as_ptr().add(i * 8));
+            acc = _mm256_fmadd_ps(x, y, acc);
+        }
+        let mut lanes = [0.0f32; 8];
+        _mm256_storeu_ps(lanes.as_mut_ptr(), acc);
+        lanes.iter().sum::<f32>() + super::scalar(&a[chunks * 8..], &b[chunks * 8..])
+    }
+
+    #[target_feature(enable = "sse")]
+    pub(super) unsafe fn dot_sse(a: &[f32], b: &[f32]) -> f32 {
+        let chunks = a.len() / 4;
+        let mut acc = _mm_setzero_ps();
+        for i in 0..chunks {
+            let x = _mm_loadu_ps(a.as_ptr().add(i * 4));
+            let y = _mm_loadu_ps(b.as_ptr().add(i * 4));
+            acc = _mm_add_ps(acc, _mm_mul_ps(x, y));
+        }
+        let mut lanes = [0.0f32; 4];
+        _mm_storeu_ps(lanes.as_mut_ptr(), acc);
+        lanes.iter().sum::<f32>() + super::scalar(&a[chunks * 4..], &b[chunks * 4..])
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod neon {
+    use std::arch::aarch64::*;
+
+    #[target_feature(enable = "neon")]
+    pub(super) unsafe fn dot(a: &[f32], b: &[f32]) -> f32 {
+        let chunks = a.len() / 4;
+        let mut acc = vdupq_n_f32(0.0);
+        for i in 0..chunks {
+            let x = vld1q_f32(a.as_ptr().add(i * 4));
+            let y = vld1q_f32(b.as_ptr().add(i * 4));
+            acc = vfmaq_f32(acc, x, y);
+        }
+        vaddvq_f32(acc) + super::scalar(&a[chunks * 4..], &b[chunks * 4..])
+    }
+}
@@ -0,0 +1,123 @@
+//! Pull-based audio input for [`OnlineFeature::pull_from`](crate::OnlineFeature::pull_from).
+
+use crate::error::KnfError;
+use crate::pcm::PcmScale;
+use std::collections::VecDeque;
+use std::io::{ErrorKind, Read};
+
+/// Audio that is pulled on demand, e.g. from an audio driver's ring buffer.
+pub trait SampleSource {
+    /// Writes up to `buf.len()` samples into `buf` and returns how many were
+    /// written; `Some(0)` if none are available right now and `None` once the
+    /// source is exhausted.
+    fn read_samples(&mut self, buf: &mut [f32]) -> Result<Option<usize>, KnfError>;
+}
+
+impl<S: SampleSource + ?Sized> SampleSource for &mut S {
+    fn read_samples(&mut self, buf: &mut [f32]) -> Result<Option<usize>, KnfError> {
+        (**self).read_samples(buf)
+    }
+}
+
+/// Consumes the slice, like `io::Read` for `&[u8]`.
+impl SampleSource for &[f32] {
+    fn read_samples(&mut self, buf: &mut [f32]) -> Result<Option<usize>, KnfError> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+        let n = buf.len().min(self.len());
+        buf[..n].copy_from_slice(&self[..n]);
+        *self = &self[n..];
+        Ok(Some(n))
+    }
+}
+
+/// A ring buffer filled by a producer; never exhausted.
+impl SampleSource for VecDeque<f32> {
+    fn read_samples(&mut self, buf: &mut [f32]) -> Result<Option<usize>, KnfError> {
+        let n = buf.len().min(self.len());
+        for (out, sample) in buf.iter_mut().zip(self.drain(..n)) {
+            *out = sample;
+        }
+        Ok(Some(n))
+    }
+}
+
+/// 16-bit little-endian PCM from a reader (e.g. stdin or a socket). A reader
+/// returning `WouldBlock` counts as no samples available.
+pub struct ReadSource<R> {
+    reader: R,
+    scale: PcmScale,
+    bytes: Vec<u8>,
+    // Odd byte left over from the previous read
+    pending: Option<u8>,
+}
+
+impl<R: Read> ReadSource<R> {
+    pub fn new(reader: R, scale: PcmScale) -> Self {
+        Self {
+            reader,
+            scale,
+            bytes: Vec::new(),
+            pending: None,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read> SampleSource for ReadSource<R> {
+    fn read_samples(&mut self, buf: &mut [f32]) -> Result<Option<usize>, KnfError> {
+        if buf.is_empty() {
+            return Ok(Some(0));
+        }
+        let start = usize::from(self.pending.is_some());
+        self.bytes.resize(2 * buf.len(), 0);
+        if let Some(byte) = self.pending.take() {
+            self.bytes[0] = byte;
+        }
+        let read = loop {
+            match self.reader.read(&mut self.bytes[start..]) {
+                // End of input; a trailing odd byte is dropped
+                Ok(0) => return Ok(None),
+                Ok(read) => break read,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break 0,
+                Err(e) => return Err(KnfError::io(e)),
+            }
+        };
+
+        let available = start + read;
+        let n = available / 2;
+        let factor = self.scale.factor();
+        for (out, pair) in buf.iter_mut().zip(self.bytes[..2 * n].chunks_exact(2)) {
+            *out = i16::from_le_bytes([pair[0], pair[1]]) as f32 * factor;
+        }
+        if available % 2 == 1 {
+            self.pending = Some(self.bytes[available - 1]);
+        }
+        Ok(Some(n))
+    }
+}
+
+/// Source calling `f` for each pull, see [`from_fn`].
+pub struct FromFn<F>(F);
+
+/// Wraps a callback with the [`SampleSource::read_samples`] contract.
+pub fn from_fn<F>(f: F) -> FromFn<F>
+where
+    F: FnMut(&mut [f32]) -> Result<Option<usize>, KnfError>,
+{
+    FromFn(f)
+}
+
+impl<F> SampleSource for FromFn<F>
+where
+    F: FnMut(&mut [f32]) -> Result<Option<usize>, KnfError>,
+{
+    fn read_samples(&mut self, buf: &mut [f32]) -> Result<Option<usize>, KnfError> {
+        (self.0)(buf)
+    }
+}
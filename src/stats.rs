@@ -0,0 +1,111 @@
+//! Summary statistics of a waveform, for flagging unusable audio (silent,
+//! clipped, DC-shifted) before feature extraction.
+
+use crate::error::KnfError;
+use crate::pcm::PcmScale;
+
+/// Peak, RMS, DC offset and clipping of a signal, accumulated over chunks.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SignalStats {
+    pub samp_freq: f32,
+    /// Samples with at least this magnitude count as clipped.
+    pub clip_level: f32,
+    pub num_samples: u64,
+    pub num_clipped: u64,
+    /// Largest sample magnitude.
+    pub peak: f32,
+    sum: f64,
+    sum_sq: f64,
+}
+
+impl SignalStats {
+    pub fn new(samp_freq: f32, clip_level: f32) -> Result<Self, KnfError> {
+        if samp_freq <= 0.0 {
+            return Err(KnfError::invalid("samp_freq must be positive"));
+        }
+        if clip_level <= 0.0 {
+            return Err(KnfError::invalid("clip_level must be positive"));
+        }
+        Ok(Self {
+            samp_freq,
+            clip_level,
+            num_samples: 0,
+            num_clipped: 0,
+            peak: 0.0,
+            sum: 0.0,
+            sum_sq: 0.0,
+        })
+    }
+
+    /// Counts full-scale 16-bit samples in `scale` as clipped.
+    pub fn for_scale(samp_freq: f32, scale: PcmScale) -> Result<Self, KnfError> {
+        Self::new(samp_freq, i16::MAX as f32 * scale.factor())
+    }
+
+    /// Statistics of a whole waveform.
+    pub fn compute(wave: &[f32], samp_freq: f32, scale: PcmScale) -> Result<Self, KnfError> {
+        let mut stats = Self::for_scale(samp_freq, scale)?;
+        stats.accept_waveform(wave);
+        Ok(stats)
+    }
+
+    pub fn accept_waveform(&mut self, wave: &[f32]) {
+        for &x in wave {
+            let magnitude = x.abs();
+            self.peak = self.peak.max(magnitude);
+            if magnitude >= self.clip_level {
+                self.num_clipped += 1;
+            }
+            self.sum += x as f64;
+            self.sum_sq += x as f64 * x as f64;
+        }
+        self.num_samples += wave.len() as u64;
+    }
+
+    /// Adds the statistics of another part of the same signal.
+    pub fn merge(&mut self, other: &SignalStats) {
+        self.num_samples += other.num_samples;
+        self.num_clipped += other.num_clipped;
+        self.peak = self.peak.max(other.peak);
+        self.sum += other.sum;
+        self.sum_sq += other.sum_sq;
+    }
+
+    /// Duration in seconds.
+    pub fn duration(&self) -> f32 {
+        self.num_samples as f32 / self.samp_freq
+    }
+
+    /// Mean sample value.
+    pub fn dc_offset(&self) -> f32 {
+        if self.num_samples == 0 {
+            return 0.0;
+        }
+        (self.sum / self.num_samples as f64) as f32
+    }
+
+    /// Root mean square, including any DC offset.
+    pub fn rms(&self) -> f32 {
+        if self.num_samples == 0 {
+            return 0.0;
+        }
+        (self.sum_sq / self.num_samples as f64).sqrt() as f32
+    }
+
+    /// Fraction of samples that are clipped.
+    pub fn clipped_fraction(&self) -> f32 {
+        if self.num_samples == 0 {
+            return 0.0;
+        }
+        self.num_clipped as f32 / self.num_samples as f32
+    }
+
+    pub fn reset(&mut self) {
+        self.num_samples = 0;
+        self.num_clipped = 0;
+        self.peak = 0.0;
+        self.sum = 0.0;
+        self.sum_sq = 0.0;
+    }
+}
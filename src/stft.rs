@@ -1,12 +1,28 @@
+use crate::decibel::{to_db, DbOptions};
+use crate::error::KnfError;
+use crate::offline::FeatureMatrix;
 use crate::rfft::Rfft;
-use crate::window::{FrameOptions, Window};
+use crate::window::{check_custom_window, Window, WindowType};
 
 #[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct StftOptions {
-    pub frame_opts: FrameOptions,
+    /// Sampling rate of the signal; only used to relate bins to frequencies
+    /// (e.g. by [`mel_to_audio`](crate::mel_to_audio)).
+    pub samp_freq: f32,
     pub n_fft: usize,
     pub hop_length: usize,
+    /// Window length; the window is zero-padded on both sides to `n_fft`
+    /// like in `torch.stft`.
     pub win_length: usize,
+    pub window_type: WindowType,
+    /// Window coefficients used instead of `window_type`; must have
+    /// `win_length` values.
+    pub custom_window: Option<Vec<f32>>,
     pub center: bool,
     pub pad_mode: String, // "reflect", "replicate", "constant"
     pub normalized: bool,
@@ -14,13 +30,13 @@ pub struct StftOptions {
 
 impl Default for StftOptions {
     fn default() -> Self {
-        let mut f = FrameOptions::default();
-        f.window_type = "povey".to_string();
         Self {
-            frame_opts: f, // Used mainly for window generation helper
+            samp_freq: 16000.0,
             n_fft: 400,
             hop_length: 160,
             win_length: 400,
+            window_type: WindowType::Povey,
+            custom_window: None,
             center: true,
             pad_mode: "reflect".to_string(),
             normalized: false,
@@ -28,6 +44,44 @@ impl Default for StftOptions {
     }
 }
 
+impl StftOptions {
+    pub fn validate(&self) -> Result<(), KnfError> {
+        if self.n_fft == 0 || self.hop_length == 0 {
+            return Err(KnfError::invalid("n_fft and hop_length must be positive"));
+        }
+        if self.win_length == 0 || self.win_length > self.n_fft {
+            return Err(KnfError::invalid(format!(
+                "win_length ({}) must be in [1, n_fft ({})]",
+                self.win_length, self.n_fft
+            )));
+        }
+        if !matches!(self.pad_mode.as_str(), "reflect" | "replicate" | "constant") {
+            return Err(KnfError::invalid(format!(
+                "Unsupported pad mode: {}",
+                self.pad_mode
+            )));
+        }
+        check_custom_window(self.custom_window.as_deref(), self.win_length)
+    }
+}
+
+/// The `win_length` window centered in `n_fft` zeros.
+pub(crate) fn padded_window(
+    window_type: WindowType,
+    custom_window: Option<&[f32]>,
+    win_length: usize,
+    n_fft: usize,
+) -> Vec<f32> {
+    let window = match custom_window {
+        Some(data) => Window::from_data(data.to_vec()),
+        None => Window::from_type(window_type, win_length, 0.42),
+    };
+    let left = (n_fft - win_length) / 2;
+    let mut padded = vec![0.0; n_fft];
+    padded[left..left + win_length].copy_from_slice(&window.data);
+    padded
+}
+
 pub struct StftResult {
     pub real: Vec<f32>, // [frame_idx * bins + bin_idx]
     pub imag: Vec<f32>,
@@ -35,26 +89,91 @@ pub struct StftResult {
     pub n_fft: usize,
 }
 
-pub fn stft_compute(opts: &StftOptions, waveform: &[f32]) -> Result<StftResult, String> {
-    if opts.n_fft == 0 || opts.hop_length == 0 || opts.win_length == 0 {
-        return Err("Invalid STFT parameters".to_string());
+impl StftResult {
+    /// Bins per frame, `n_fft / 2 + 1`.
+    pub fn num_bins(&self) -> usize {
+        self.n_fft / 2 + 1
+    }
+
+    /// Real and imaginary parts of frame `i`.
+    pub fn frame(&self, i: usize) -> (&[f32], &[f32]) {
+        let bins = self.num_bins();
+        let range = i * bins..(i + 1) * bins;
+        (&self.real[range.clone()], &self.imag[range])
+    }
+
+    /// `|X|` as a `num_frames x num_bins` matrix.
+    pub fn magnitude(&self) -> FeatureMatrix {
+        self.map_bins(|re, im| re.hypot(im))
+    }
+
+    /// `|X|^2` as a `num_frames x num_bins` matrix.
+    pub fn power(&self) -> FeatureMatrix {
+        self.map_bins(|re, im| re * re + im * im)
     }
 
-    // Prepare window
-    let mut win_opts = opts.frame_opts.clone();
-    win_opts.frame_length_ms = 0.0; // Not used directly if we override size
-                                    // We manually construct window of win_length
-                                    // The Window struct uses frame_opts.window_size(). Let's force it.
-                                    // Hack: adjust samp_freq/length to match win_length samples?
-                                    // Easier: Reuse Window::new but we need to ensure size is win_length.
-                                    // FrameOptions calculates size from ms. Let's make a temporary options struct that yields win_length.
-    win_opts.samp_freq = 1000.0;
-    win_opts.frame_length_ms = opts.win_length as f32; // 1000 * 0.001 * X = X
-    let window = Window::new(&win_opts).ok_or("Failed to create window")?;
+    /// Power in decibels like `librosa.power_to_db` with `ref=1.0` and
+    /// `amin=1e-10`; with `top_db`, values are floored at `max - top_db`.
+    pub fn to_db(&self, top_db: Option<f32>) -> FeatureMatrix {
+        let mut db = self.power();
+        let opts = DbOptions {
+            top_db,
+            ..Default::default()
+        };
+        to_db(&mut db.data, 1e-10, &opts, 10.0);
+        db
+    }
+
+    fn map_bins(&self, f: impl Fn(f32, f32) -> f32) -> FeatureMatrix {
+        FeatureMatrix {
+            data: self
+                .real
+                .iter()
+                .zip(&self.imag)
+                .map(|(&re, &im)| f(re, im))
+                .collect(),
+            num_frames: self.num_frames,
+            dim: self.num_bins(),
+        }
+    }
+}
+
+pub fn stft_compute(opts: &StftOptions, waveform: &[f32]) -> Result<StftResult, KnfError> {
+    stft_with_buffers(opts, waveform, &mut Vec::new(), &mut Vec::new())
+}
+
+/// STFTs of one waveform at several resolutions (e.g. for multi-resolution
+/// STFT losses), in the order of `opts`; padding and frame buffers are shared.
+pub fn stft_multi_resolution(
+    opts: &[StftOptions],
+    waveform: &[f32],
+) -> Result<Vec<StftResult>, KnfError> {
+    let mut padded_wave = Vec::new();
+    let mut frame_buf = Vec::new();
+    opts.iter()
+        .map(|o| stft_with_buffers(o, waveform, &mut padded_wave, &mut frame_buf))
+        .collect()
+}
+
+fn stft_with_buffers(
+    opts: &StftOptions,
+    waveform: &[f32],
+    padded_wave: &mut Vec<f32>,
+    frame_buf: &mut Vec<f32>,
+) -> Result<StftResult, KnfError> {
+    opts.validate()?;
+
+    let window = padded_window(
+        opts.window_type,
+        opts.custom_window.as_deref(),
+        opts.win_length,
+        opts.n_fft,
+    );
 
     // Padding
     let pad = if opts.center { opts.n_fft / 2 } else { 0 };
-    let mut padded_wave = Vec::with_capacity(waveform.len() + 2 * pad);
+    padded_wave.clear();
+    padded_wave.reserve(waveform.len() + 2 * pad);
 
     if opts.center {
         match opts.pad_mode.as_str() {
@@ -115,7 +234,8 @@ pub fn stft_compute(opts: &StftOptions, waveform: &[f32]) -> Result<StftResult,
     let mut imag = vec![0.0; num_frames * bins];
 
     let mut rfft = Rfft::new(opts.n_fft, false);
-    let mut frame_buf = vec![0.0; opts.n_fft];
+    frame_buf.clear();
+    frame_buf.resize(opts.n_fft, 0.0);
 
     for i in 0..num_frames {
         let start = i * opts.hop_length;
@@ -123,10 +243,12 @@ pub fn stft_compute(opts: &StftOptions, waveform: &[f32]) -> Result<StftResult,
         frame_buf.copy_from_slice(&padded_wave[start..end]);
 
         // Apply window
-        window.apply(&mut frame_buf[..opts.win_length.min(opts.n_fft)]);
+        for (x, w) in frame_buf.iter_mut().zip(&window) {
+            *x *= w;
+        }
 
         // FFT
-        rfft.compute(&mut frame_buf);
+        rfft.compute(frame_buf);
 
         // Unpack RFFT result [Re0, ReN/2, Re1, Im1, ...]
         // Bin 0
@@ -135,10 +257,13 @@ pub fn stft_compute(opts: &StftOptions, waveform: &[f32]) -> Result<StftResult,
 
         // Bin N/2
         if bins > 1 {
-            // If n_fft is even, frame_buf[1] is Re(N/2).
-            // If odd, packing is different, but assume even for standard audio STFT.
+            // frame_buf[1] is Re(N/2); for odd n_fft its imaginary part is last.
             real[i * bins + (bins - 1)] = frame_buf[1];
-            imag[i * bins + (bins - 1)] = 0.0;
+            imag[i * bins + (bins - 1)] = if opts.n_fft.is_multiple_of(2) {
+                0.0
+            } else {
+                frame_buf[opts.n_fft - 1]
+            };
         }
 
         for k in 1..(bins - 1) {
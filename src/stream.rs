@@ -0,0 +1,75 @@
+//! Async adapter turning a stream of audio chunks into a stream of frames.
+
+use crate::online::{Computer, FeatureComputer, OnlineFeature};
+use futures_core::Stream;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Yields the feature frames of the audio chunks from `chunks`, flushing the
+/// tail frames once `chunks` ends.
+///
+/// Works with any executor (e.g. tokio). Chunks must be at the computer's
+/// sampling rate. A new chunk is only polled once all frames computed so far
+/// have been taken, so a slow consumer applies backpressure upstream.
+pub struct FeatureStream<S, C: Computer = FeatureComputer> {
+    chunks: S,
+    feature: OnlineFeature<C>,
+}
+
+impl<S, C: Computer> FeatureStream<S, C> {
+    pub fn new(chunks: S, computer: C) -> Self {
+        Self::with_feature(chunks, OnlineFeature::new(computer))
+    }
+
+    /// Continues an existing (e.g. [`resume`](OnlineFeature::resume)d) stream.
+    pub fn with_feature(chunks: S, feature: OnlineFeature<C>) -> Self {
+        Self { chunks, feature }
+    }
+
+    pub fn feature(&self) -> &OnlineFeature<C> {
+        &self.feature
+    }
+
+    pub fn into_inner(self) -> (S, OnlineFeature<C>) {
+        (self.chunks, self.feature)
+    }
+}
+
+impl<S, A, C> Stream for FeatureStream<S, C>
+where
+    S: Stream<Item = A> + Unpin,
+    A: AsRef<[f32]>,
+    C: Computer + Unpin,
+{
+    type Item = Vec<f32>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(frame) = this.feature.pop_frames(1).pop() {
+                return Poll::Ready(Some(frame));
+            }
+            if this.feature.is_input_finished() {
+                return Poll::Ready(None);
+            }
+            match Pin::new(&mut this.chunks).poll_next(cx) {
+                Poll::Ready(Some(chunk)) => {
+                    let samp_freq = this.feature.computer().frame_opts().samp_freq;
+                    // Fed at the computer's own rate, so this cannot fail.
+                    let _ = this.feature.accept_waveform(samp_freq, chunk.as_ref());
+                }
+                Poll::Ready(None) => this.feature.input_finished(),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let held = self.feature.features.len();
+        if self.feature.is_input_finished() {
+            (held, Some(held))
+        } else {
+            (held, None)
+        }
+    }
+}
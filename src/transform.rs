@@ -0,0 +1,343 @@
+use crate::error::KnfError;
+use crate::utils::PI;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct CmvnOptions {
+    /// Number of past frames (including the current one) used for the statistics.
+    pub cmn_window: usize,
+    pub normalize_variance: bool,
+}
+
+impl Default for CmvnOptions {
+    fn default() -> Self {
+        Self {
+            cmn_window: 600,
+            normalize_variance: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct DeltaOptions {
+    pub order: usize,
+    pub window: usize,
+}
+
+impl Default for DeltaOptions {
+    fn default() -> Self {
+        Self {
+            order: 2,
+            window: 2,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct SpliceOptions {
+    pub left_context: usize,
+    pub right_context: usize,
+}
+
+impl Default for SpliceOptions {
+    fn default() -> Self {
+        Self {
+            left_context: 4,
+            right_context: 4,
+        }
+    }
+}
+
+/// Ramp of a [`FrameTaperOptions`] fade.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum TaperShape {
+    #[default]
+    Linear,
+    /// Raised cosine.
+    Hann,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct FrameTaperOptions {
+    pub fade_in_frames: usize,
+    pub fade_out_frames: usize,
+    pub shape: TaperShape,
+    /// Only weight this coefficient (e.g. the energy term); all coefficients if `None`.
+    pub coefficient: Option<usize>,
+}
+
+impl Default for FrameTaperOptions {
+    fn default() -> Self {
+        Self {
+            fade_in_frames: 0,
+            fade_out_frames: 0,
+            shape: TaperShape::Linear,
+            coefficient: None,
+        }
+    }
+}
+
+/// Per-frame weights that ramp up over the first `fade_in_frames` frames and
+/// down over the last `fade_out_frames`; all other frames get weight 1.
+pub fn taper_weights(num_frames: usize, opts: &FrameTaperOptions) -> Vec<f32> {
+    let ramp = |i: usize, len: usize| {
+        let x = (i + 1) as f32 / (len + 1) as f32;
+        match opts.shape {
+            TaperShape::Linear => x,
+            TaperShape::Hann => 0.5 - 0.5 * (PI * x).cos(),
+        }
+    };
+
+    let mut weights = vec![1.0; num_frames];
+    for (i, w) in weights.iter_mut().enumerate() {
+        if i < opts.fade_in_frames {
+            *w = ramp(i, opts.fade_in_frames);
+        }
+        let from_end = num_frames - 1 - i;
+        if from_end < opts.fade_out_frames {
+            *w = w.min(ramp(from_end, opts.fade_out_frames));
+        }
+    }
+    weights
+}
+
+/// Multiplies each frame (or only `coefficient` of each frame) by its weight.
+/// Fails without touching the frames if there is not one weight per frame or
+/// a frame has no `coefficient`.
+pub fn apply_frame_weights<F: AsMut<[f32]>>(
+    frames: &mut [F],
+    weights: &[f32],
+    coefficient: Option<usize>,
+) -> Result<(), KnfError> {
+    if weights.len() != frames.len() {
+        return Err(KnfError::SizeMismatch {
+            what: "frame weights",
+            expected: frames.len(),
+            actual: weights.len(),
+        });
+    }
+    if let Some(c) = coefficient {
+        for frame in frames.iter_mut() {
+            let len = frame.as_mut().len();
+            if len <= c {
+                return Err(KnfError::SizeMismatch {
+                    what: "frame dim",
+                    expected: c + 1,
+                    actual: len,
+                });
+            }
+        }
+    }
+    for (frame, &w) in frames.iter_mut().zip(weights.iter()) {
+        let frame = frame.as_mut();
+        match coefficient {
+            Some(c) => frame[c] *= w,
+            None => frame.iter_mut().for_each(|x| *x *= w),
+        }
+    }
+    Ok(())
+}
+
+pub fn apply_frame_taper<F: AsMut<[f32]>>(
+    frames: &mut [F],
+    opts: &FrameTaperOptions,
+) -> Result<(), KnfError> {
+    let weights = taper_weights(frames.len(), opts);
+    apply_frame_weights(frames, &weights, opts.coefficient)
+}
+
+/// Sliding-window cepstral mean (and optionally variance) normalization.
+///
+/// Only past frames are used, so the stage adds no latency.
+pub struct OnlineCmvn {
+    pub opts: CmvnOptions,
+    pub frames: Vec<Vec<f32>>,
+    sum: Vec<f64>,
+    sum_sq: Vec<f64>,
+}
+
+impl OnlineCmvn {
+    pub fn new(opts: CmvnOptions, dim: usize) -> Self {
+        Self {
+            opts,
+            frames: Vec::new(),
+            sum: vec![0.0; dim],
+            sum_sq: vec![0.0; dim],
+        }
+    }
+
+    pub fn advance(&mut self, input: &[Vec<f32>], _input_finished: bool) {
+        let window = self.opts.cmn_window.max(1);
+        for t in self.frames.len()..input.len() {
+            for (d, &x) in input[t].iter().enumerate() {
+                self.sum[d] += x as f64;
+                self.sum_sq[d] += (x as f64) * (x as f64);
+            }
+            if t >= window {
+                for (d, &x) in input[t - window].iter().enumerate() {
+                    self.sum[d] -= x as f64;
+                    self.sum_sq[d] -= (x as f64) * (x as f64);
+                }
+            }
+
+            let count = (t + 1).min(window) as f64;
+            let out = input[t]
+                .iter()
+                .enumerate()
+                .map(|(d, &x)| {
+                    let mean = self.sum[d] / count;
+                    let mut y = x as f64 - mean;
+                    if self.opts.normalize_variance {
+                        let var = (self.sum_sq[d] / count - mean * mean).max(1e-10);
+                        y /= var.sqrt();
+                    }
+                    y as f32
+                })
+                .collect();
+            self.frames.push(out);
+        }
+    }
+}
+
+/// Kaldi-style delta (and delta-delta, ...) features.
+pub struct OnlineDelta {
+    pub opts: DeltaOptions,
+    pub frames: Vec<Vec<f32>>,
+    scales: Vec<Vec<f32>>,
+}
+
+impl OnlineDelta {
+    pub fn new(opts: DeltaOptions) -> Self {
+        let mut scales = vec![vec![1.0f32]];
+        for i in 1..=opts.order {
+            let prev = &scales[i - 1];
+            let window = opts.window as isize;
+            let prev_offset = (prev.len() as isize - 1) / 2;
+            let cur_offset = prev_offset + window;
+            let mut cur = vec![0.0f32; prev.len() + 2 * opts.window];
+            let mut normalizer = 0.0f32;
+            for j in -window..=window {
+                normalizer += (j * j) as f32;
+                for k in -prev_offset..=prev_offset {
+                    cur[(j + k + cur_offset) as usize] +=
+                        j as f32 * prev[(k + prev_offset) as usize];
+                }
+            }
+            for x in cur.iter_mut() {
+                *x /= normalizer;
+            }
+            scales.push(cur);
+        }
+
+        Self {
+            opts,
+            frames: Vec::new(),
+            scales,
+        }
+    }
+
+    /// Number of future frames needed before a frame can be output.
+    pub fn context(&self) -> usize {
+        self.opts.order * self.opts.window
+    }
+
+    pub fn dim(&self, input_dim: usize) -> usize {
+        input_dim * (self.opts.order + 1)
+    }
+
+    pub fn advance(&mut self, input: &[Vec<f32>], input_finished: bool) {
+        let num_input = input.len();
+        let ready = if input_finished {
+            num_input
+        } else {
+            num_input.saturating_sub(self.context())
+        };
+
+        for t in self.frames.len()..ready {
+            let dim = input[t].len();
+            let mut out = vec![0.0; self.dim(dim)];
+            for (i, scales) in self.scales.iter().enumerate() {
+                let max_offset = (scales.len() as isize - 1) / 2;
+                let output = &mut out[i * dim..(i + 1) * dim];
+                for j in -max_offset..=max_offset {
+                    let scale = scales[(j + max_offset) as usize];
+                    if scale == 0.0 {
+                        continue;
+                    }
+                    let frame = (t as isize + j).clamp(0, num_input as isize - 1) as usize;
+                    for (o, &x) in output.iter_mut().zip(input[frame].iter()) {
+                        *o += scale * x;
+                    }
+                }
+            }
+            self.frames.push(out);
+        }
+    }
+}
+
+/// Concatenates each frame with its left and right neighbours.
+pub struct OnlineSplice {
+    pub opts: SpliceOptions,
+    pub frames: Vec<Vec<f32>>,
+}
+
+impl OnlineSplice {
+    pub fn new(opts: SpliceOptions) -> Self {
+        Self {
+            opts,
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn context(&self) -> usize {
+        self.opts.right_context
+    }
+
+    pub fn dim(&self, input_dim: usize) -> usize {
+        input_dim * (self.opts.left_context + self.opts.right_context + 1)
+    }
+
+    pub fn advance(&mut self, input: &[Vec<f32>], input_finished: bool) {
+        let num_input = input.len();
+        let ready = if input_finished {
+            num_input
+        } else {
+            num_input.saturating_sub(self.context())
+        };
+
+        let left = self.opts.left_context as isize;
+        let right = self.opts.right_context as isize;
+        for t in self.frames.len()..ready {
+            let mut out = Vec::with_capacity(self.dim(input[t].len()));
+            for j in -left..=right {
+                let frame = (t as isize + j).clamp(0, num_input as isize - 1) as usize;
+                out.extend_from_slice(&input[frame]);
+            }
+            self.frames.push(out);
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! Estimation of the global tuning offset from A440, after
+//! `librosa.estimate_tuning`.
+
+use crate::error::KnfError;
+use crate::stft::{stft_compute, StftOptions};
+use crate::window::WindowType;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct TuningOptions {
+    /// STFT of the analysis; `samp_freq` relates bins to frequencies. The
+    /// default is librosa's: 2048-point Hann, hop 512, zero-padded centering.
+    pub stft: StftOptions,
+    /// Frequency range in Hz of the spectral peaks considered.
+    pub fmin: f32,
+    pub fmax: f32,
+    /// Peaks below this fraction of their frame's maximum are ignored.
+    pub threshold: f32,
+    /// Histogram resolution in fractions of a bin.
+    pub resolution: f32,
+    pub bins_per_octave: usize,
+}
+
+impl Default for TuningOptions {
+    fn default() -> Self {
+        Self {
+            stft: StftOptions {
+                n_fft: 2048,
+                hop_length: 512,
+                win_length: 2048,
+                window_type: WindowType::Hann,
+                pad_mode: "constant".to_string(),
+                ..Default::default()
+            },
+            fmin: 150.0,
+            fmax: 4000.0,
+            threshold: 0.1,
+            resolution: 0.01,
+            bins_per_octave: 12,
+        }
+    }
+}
+
+impl TuningOptions {
+    pub fn validate(&self) -> Result<(), KnfError> {
+        self.stft.validate()?;
+        if self.stft.n_fft < 3 {
+            return Err(KnfError::invalid("n_fft must be at least 3"));
+        }
+        if self.fmin < 0.0 || self.fmax <= self.fmin {
+            return Err(KnfError::invalid(format!(
+                "Invalid frequency range [{}, {}]",
+                self.fmin, self.fmax
+            )));
+        }
+        check_tuning_args(self.resolution, self.bins_per_octave)
+    }
+}
+
+fn check_tuning_args(resolution: f32, bins_per_octave: usize) -> Result<(), KnfError> {
+    if !(resolution > 0.0 && resolution < 1.0) {
+        return Err(KnfError::invalid("resolution must be in (0, 1)"));
+    }
+    if bins_per_octave == 0 {
+        return Err(KnfError::invalid("bins_per_octave must be positive"));
+    }
+    Ok(())
+}
+
+/// Tuning offset in fractions of a bin, in `[-0.5, 0.5)`, of `wave`: the mode
+/// of the deviations from the equal-tempered grid of the spectral peaks
+/// (parabolically interpolated) that are at least as strong as their median.
+pub fn estimate_tuning(wave: &[f32], opts: &TuningOptions) -> Result<f32, KnfError> {
+    opts.validate()?;
+    let peaks = spectral_peaks(wave, opts)?;
+    if peaks.is_empty() {
+        return Ok(0.0);
+    }
+    let mut mags: Vec<f32> = peaks.iter().map(|&(_, m)| m).collect();
+    mags.sort_by(f32::total_cmp);
+    let mid = mags.len() / 2;
+    let median = if mags.len().is_multiple_of(2) {
+        0.5 * (mags[mid - 1] + mags[mid])
+    } else {
+        mags[mid]
+    };
+    let freqs: Vec<f32> = peaks
+        .iter()
+        .filter(|&&(_, m)| m >= median)
+        .map(|&(f, _)| f)
+        .collect();
+    pitch_tuning(&freqs, opts.resolution, opts.bins_per_octave)
+}
+
+/// Tuning offset of a set of frequencies in Hz, like `librosa.pitch_tuning`:
+/// the left edge of the fullest histogram bin of their deviations from the
+/// A440 grid. Non-positive frequencies are ignored; with none left the
+/// offset is 0.
+pub fn pitch_tuning(
+    freqs: &[f32],
+    resolution: f32,
+    bins_per_octave: usize,
+) -> Result<f32, KnfError> {
+    check_tuning_args(resolution, bins_per_octave)?;
+    let num_bins = (1.0 / resolution).ceil() as usize;
+    let mut counts = vec![0usize; num_bins];
+    let mut any = false;
+    for &f in freqs.iter().filter(|&&f| f > 0.0) {
+        // Octaves above A440 / 16 (C0 minus a quarter tone)
+        let octs = (f as f64 / (440.0 / 16.0)).log2();
+        let mut residual = (bins_per_octave as f64 * octs).rem_euclid(1.0);
+        if residual >= 0.5 {
+            residual -= 1.0;
+        }
+        let index = ((residual + 0.5) * num_bins as f64).floor() as usize;
+        counts[index.min(num_bins - 1)] += 1;
+        any = true;
+    }
+    if !any {
+        return Ok(0.0);
+    }
+    let best = counts
+        .iter()
+        .enumerate()
+        .fold(0, |best, (i, &c)| if c > counts[best] { i } else { best });
+    Ok(-0.5 + best as f32 / num_bins as f32)
+}
+
+/// `(frequency, magnitude)` of the interpolated local maxima of each STFT
+/// frame within `[fmin, fmax)`, like `librosa.piptrack`.
+fn spectral_peaks(wave: &[f32], opts: &TuningOptions) -> Result<Vec<(f32, f32)>, KnfError> {
+    let stft = stft_compute(&opts.stft, wave)?;
+    let mag = stft.magnitude();
+    let n_fft = opts.stft.n_fft as f32;
+    let sr = opts.stft.samp_freq;
+    let fmax = opts.fmax.min(sr / 2.0);
+    let bins = mag.dim;
+
+    let mut peaks = Vec::new();
+    for s in mag.rows() {
+        let max = s.iter().copied().fold(0.0f32, f32::max);
+        let floor = opts.threshold * max;
+        // Magnitudes at or below the floor count as 0 for the peak search
+        let gated = |i: usize| if s[i] > floor { s[i] } else { 0.0 };
+        for i in 1..bins - 1 {
+            let freq = i as f32 * sr / n_fft;
+            if freq < opts.fmin || freq >= fmax {
+                continue;
+            }
+            let x = gated(i);
+            if !(x > gated(i - 1) && x >= gated(i + 1)) {
+                continue;
+            }
+            let avg = 0.5 * (s[i + 1] - s[i - 1]);
+            let curvature = 2.0 * s[i] - s[i + 1] - s[i - 1];
+            let shift = if curvature.abs() < f32::MIN_POSITIVE {
+                avg / (curvature + 1.0)
+            } else {
+                avg / curvature
+            };
+            peaks.push(((i as f32 + shift) * sr / n_fft, s[i] + 0.5 * avg * shift));
+        }
+    }
+    Ok(peaks)
+}
@@ -1,3 +1,4 @@
+use realfft::num_traits::Float;
 use std::f32;
 
 pub const PI: f32 = f32::consts::PI;
@@ -5,8 +6,33 @@ pub const TWO_PI: f32 = 2.0 * PI;
 pub const SQRT2: f32 = f32::consts::SQRT_2;
 
 /// Computes the inner product (dot product) of two vectors.
-pub fn inner_product(a: &[f32], b: &[f32]) -> f32 {
-    a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+pub fn inner_product<T: InnerProduct>(a: &[T], b: &[T]) -> T {
+    T::inner_product(a, b)
+}
+
+/// Sample types supported by [`inner_product`]; f32 uses the SIMD kernels
+/// when the `simd` feature is enabled.
+pub trait InnerProduct: Sized {
+    fn inner_product(a: &[Self], b: &[Self]) -> Self;
+}
+
+impl InnerProduct for f32 {
+    fn inner_product(a: &[f32], b: &[f32]) -> f32 {
+        #[cfg(feature = "simd")]
+        {
+            crate::simd::dot(a, b)
+        }
+        #[cfg(not(feature = "simd"))]
+        {
+            a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+        }
+    }
+}
+
+impl InnerProduct for f64 {
+    fn inner_product(a: &[f64], b: &[f64]) -> f64 {
+        a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+    }
 }
 
 /// Computes the power spectrum from a complex FFT output.
@@ -18,7 +44,7 @@ pub fn inner_product(a: &[f32], b: &[f32]) -> f32 {
 /// `realfft` returns a `Complex<f32>` slice. We will adapt this function in `src/fbank.rs`
 /// or `src/rfft.rs` specifically for the `rustfft` output format, but we keep a generic
 /// logic here for reference if needed.
-pub fn compute_power_spectrum_inplace(spectrum: &mut [f32]) {
+pub fn compute_power_spectrum_inplace<T: Float>(spectrum: &mut [T]) {
     let dim = spectrum.len();
     let half_dim = dim / 2;
 
@@ -26,11 +52,19 @@ pub fn compute_power_spectrum_inplace(spectrum: &mut [f32]) {
     // complex_fft[0] -> Energy at DC
     // complex_fft[1] -> Energy at Nyquist
     // complex_fft[2*i], complex_fft[2*i+1] -> Real, Imag at i
+    // For odd sizes the last bin also has an imaginary part at the end.
+    if dim < 2 {
+        spectrum[0] = spectrum[0] * spectrum[0];
+        return;
+    }
 
     let first = spectrum[0];
     let last = spectrum[1];
     let first_energy = first * first;
-    let last_energy = last * last;
+    let mut last_energy = last * last;
+    if dim % 2 == 1 {
+        last_energy = last_energy + spectrum[dim - 1] * spectrum[dim - 1];
+    }
 
     for i in 1..half_dim {
         let real = spectrum[i * 2];
@@ -0,0 +1,201 @@
+use crate::error::KnfError;
+use crate::istft::{istft_compute, IstftOptions};
+use crate::resample::StreamingArbitraryResample;
+use crate::stft::{stft_compute, StftOptions, StftResult};
+use crate::window::WindowType;
+use std::f32::consts::PI;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct TimeStretchOptions {
+    /// Analysis/synthesis STFT; defaults to librosa's 2048-point Hann window
+    /// with a hop of 512.
+    pub stft: StftOptions,
+    /// Identity phase locking (Laroche & Dolson): bins around each spectral
+    /// peak keep their phase offset to the peak, which reduces phasiness.
+    pub phase_locking: bool,
+}
+
+impl Default for TimeStretchOptions {
+    fn default() -> Self {
+        Self {
+            stft: StftOptions {
+                n_fft: 2048,
+                hop_length: 512,
+                win_length: 2048,
+                window_type: WindowType::Hann,
+                ..Default::default()
+            },
+            phase_locking: false,
+        }
+    }
+}
+
+/// Changes the duration of `wave` by `1 / rate` without changing its pitch,
+/// like `librosa.effects.time_stretch`; `rate > 1` speeds the audio up.
+///
+/// The output has `round(wave.len() / rate)` samples.
+pub fn time_stretch(
+    wave: &[f32],
+    rate: f32,
+    opts: &TimeStretchOptions,
+) -> Result<Vec<f32>, KnfError> {
+    check_rate(rate)?;
+    if wave.is_empty() {
+        return Ok(Vec::new());
+    }
+    let stft = stft_compute(&opts.stft, wave)?;
+    let stretched = phase_vocoder(&stft, rate, opts.stft.hop_length, opts.phase_locking)?;
+    let mut out = istft_compute(&IstftOptions::from(&opts.stft), &stretched)?;
+    out.resize((wave.len() as f64 / rate as f64).round() as usize, 0.0);
+    Ok(out)
+}
+
+/// Shifts the pitch of `wave` by `semitones` (negative to lower it) without
+/// changing its duration, like `librosa.effects.pitch_shift`: the audio is
+/// stretched by the pitch factor and resampled back to its original length.
+pub fn pitch_shift(
+    wave: &[f32],
+    semitones: f32,
+    opts: &TimeStretchOptions,
+) -> Result<Vec<f32>, KnfError> {
+    if !semitones.is_finite() {
+        return Err(KnfError::invalid("semitones must be finite"));
+    }
+    let factor = 2f64.powf(semitones as f64 / 12.0);
+    let stretched = time_stretch(wave, (1.0 / factor) as f32, opts)?;
+    let mut resampler = StreamingArbitraryResample::with_ratio(opts.stft.samp_freq, factor, 6)?;
+    let mut out = resampler.accept_waveform(&stretched);
+    out.extend(resampler.input_finished());
+    out.resize(wave.len(), 0.0);
+    Ok(out)
+}
+
+/// Resamples the frames of `stft` in time by `rate`, interpolating magnitudes
+/// and propagating phases by each bin's instantaneous frequency, as
+/// `librosa.phase_vocoder`. `hop_length` is the hop `stft` was computed with.
+pub fn phase_vocoder(
+    stft: &StftResult,
+    rate: f32,
+    hop_length: usize,
+    phase_locking: bool,
+) -> Result<StftResult, KnfError> {
+    check_rate(rate)?;
+    let bins = stft.num_bins();
+    let num_frames = stft.num_frames;
+    let out_frames = (num_frames as f64 / rate as f64).ceil() as usize;
+    let mut real = Vec::with_capacity(out_frames * bins);
+    let mut imag = Vec::with_capacity(out_frames * bins);
+    if num_frames == 0 {
+        return Ok(StftResult {
+            real,
+            imag,
+            num_frames: 0,
+            n_fft: stft.n_fft,
+        });
+    }
+
+    // Expected phase advance of each bin over one hop
+    let advance: Vec<f32> = (0..bins)
+        .map(|k| 2.0 * PI * k as f32 * hop_length as f32 / stft.n_fft as f32)
+        .collect();
+    // Frames past the end are silent, as librosa pads with zeros
+    let zeros = vec![0.0; bins];
+    let frame = |i: usize| {
+        if i < num_frames {
+            stft.frame(i)
+        } else {
+            (&zeros[..], &zeros[..])
+        }
+    };
+
+    let mut phase_acc: Vec<f32> = {
+        let (re, im) = frame(0);
+        re.iter().zip(im).map(|(&r, &i)| i.atan2(r)).collect()
+    };
+    let mut mag = vec![0.0; bins];
+    let mut phase = vec![0.0; bins];
+    let mut peaks = Vec::new();
+
+    for t in 0..out_frames {
+        let step = t as f64 * rate as f64;
+        let i = step as usize;
+        let alpha = (step - i as f64) as f32;
+        let (re0, im0) = frame(i);
+        let (re1, im1) = frame(i + 1);
+
+        for k in 0..bins {
+            mag[k] = (1.0 - alpha) * re0[k].hypot(im0[k]) + alpha * re1[k].hypot(im1[k]);
+            phase[k] = phase_acc[k];
+        }
+
+        if phase_locking {
+            find_peaks(&mag, &mut peaks);
+            lock_phases(&peaks, re0, im0, &mut phase);
+        }
+
+        for k in 0..bins {
+            let (sin, cos) = phase[k].sin_cos();
+            real.push(mag[k] * cos);
+            imag.push(mag[k] * sin);
+
+            // Deviation from the expected advance, wrapped to [-pi, pi]
+            let mut delta = im1[k].atan2(re1[k]) - im0[k].atan2(re0[k]) - advance[k];
+            delta -= 2.0 * PI * (delta / (2.0 * PI)).round();
+            phase_acc[k] = phase[k] + advance[k] + delta;
+        }
+    }
+
+    Ok(StftResult {
+        real,
+        imag,
+        num_frames: out_frames,
+        n_fft: stft.n_fft,
+    })
+}
+
+fn check_rate(rate: f32) -> Result<(), KnfError> {
+    if !(rate.is_finite() && rate > 0.0) {
+        return Err(KnfError::invalid(format!(
+            "Stretch rate must be positive, got {}",
+            rate
+        )));
+    }
+    Ok(())
+}
+
+/// Bins whose magnitude is a local maximum.
+fn find_peaks(mag: &[f32], peaks: &mut Vec<usize>) {
+    peaks.clear();
+    for k in 0..mag.len() {
+        let left = k == 0 || mag[k] > mag[k - 1];
+        let right = k + 1 == mag.len() || mag[k] >= mag[k + 1];
+        if left && right && mag[k] > 0.0 {
+            peaks.push(k);
+        }
+    }
+}
+
+/// Sets the phase of every bin relative to the nearest peak, keeping the
+/// analysis phase difference between the two.
+fn lock_phases(peaks: &[usize], re: &[f32], im: &[f32], phase: &mut [f32]) {
+    if peaks.is_empty() {
+        return;
+    }
+    let analysis = |k: usize| im[k].atan2(re[k]);
+    let mut p = 0;
+    for k in 0..phase.len() {
+        // Region boundaries lie halfway between neighbouring peaks
+        while p + 1 < peaks.len() && k.abs_diff(peaks[p]) > k.abs_diff(peaks[p + 1]) {
+            p += 1;
+        }
+        let peak = peaks[p];
+        if k != peak {
+            phase[k] = phase[peak] + analysis(k) - analysis(peak);
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! wasm-bindgen wrapper around [`OnlineFeature`] for extracting features in
+//! the browser; exported to JavaScript as `OnlineFeature`.
+
+use crate::error::KnfError;
+use crate::fbank::{FbankComputer, FbankOptions};
+use crate::mfcc::{MfccComputer, MfccOptions};
+use crate::online::{Computer, FeatureComputer, OnlineFeature};
+use crate::whisper::{WhisperComputer, WhisperOptions};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(js_name = OnlineFeature)]
+pub struct WasmOnlineFeature {
+    feature: OnlineFeature,
+    dim: usize,
+}
+
+impl WasmOnlineFeature {
+    fn from_computer(computer: Result<FeatureComputer, KnfError>) -> Result<Self, JsError> {
+        let computer = computer?;
+        Ok(Self {
+            dim: computer.dim(),
+            feature: OnlineFeature::new(computer),
+        })
+    }
+}
+
+#[wasm_bindgen(js_class = OnlineFeature)]
+impl WasmOnlineFeature {
+    /// Kaldi FBANK features; other options keep their defaults.
+    pub fn fbank(
+        samp_freq: f32,
+        num_mel_bins: usize,
+        dither: f32,
+        snip_edges: bool,
+    ) -> Result<WasmOnlineFeature, JsError> {
+        let mut opts = FbankOptions::default();
+        opts.frame_opts.samp_freq = samp_freq;
+        opts.frame_opts.dither = dither;
+        opts.frame_opts.snip_edges = snip_edges;
+        opts.mel_opts.num_bins = num_mel_bins;
+        Self::from_computer(FbankComputer::new(opts).map(FeatureComputer::Fbank))
+    }
+
+    /// Kaldi MFCC features; other options keep their defaults.
+    pub fn mfcc(
+        samp_freq: f32,
+        num_mel_bins: usize,
+        num_ceps: usize,
+        dither: f32,
+        snip_edges: bool,
+    ) -> Result<WasmOnlineFeature, JsError> {
+        let mut opts = MfccOptions::default();
+        opts.frame_opts.samp_freq = samp_freq;
+        opts.frame_opts.dither = dither;
+        opts.frame_opts.snip_edges = snip_edges;
+        opts.mel_opts.num_bins = num_mel_bins;
+        opts.num_ceps = num_ceps;
+        Self::from_computer(MfccComputer::new(opts).map(FeatureComputer::Mfcc))
+    }
+
+    /// Whisper linear mel power energies (not log-mels) with `dim` mel bins
+    /// (80 or 128).
+    pub fn whisper(dim: usize) -> Result<WasmOnlineFeature, JsError> {
+        let opts = WhisperOptions {
+            dim,
+            ..Default::default()
+        };
+        Self::from_computer(WhisperComputer::new(opts).map(FeatureComputer::Whisper))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// Appends samples in the Kaldi integer range (i.e. scaled by 32768 for
+    /// Web Audio's [-1, 1) floats).
+    #[wasm_bindgen(js_name = acceptWaveform)]
+    pub fn accept_waveform(&mut self, sampling_rate: f32, samples: &[f32]) -> Result<(), JsError> {
+        Ok(self.feature.accept_waveform(sampling_rate, samples)?)
+    }
+
+    #[wasm_bindgen(js_name = inputFinished)]
+    pub fn input_finished(&mut self) {
+        self.feature.input_finished();
+    }
+
+    /// Clears all audio and frames to start a new utterance.
+    pub fn reset(&mut self) {
+        self.feature.reset();
+    }
+
+    #[wasm_bindgen(js_name = numFramesReady)]
+    pub fn num_frames_ready(&self) -> usize {
+        self.feature.num_frames_ready()
+    }
+
+    #[wasm_bindgen(js_name = isLastFrame)]
+    pub fn is_last_frame(&self, frame: usize) -> bool {
+        self.feature.is_input_finished() && frame + 1 == self.feature.num_frames_ready()
+    }
+
+    /// One frame as a `Float32Array`, or `undefined` if it is not ready.
+    #[wasm_bindgen(js_name = getFrame)]
+    pub fn get_frame(&self, frame: usize) -> Option<Vec<f32>> {
+        self.feature.get_frame(frame).map(|f| f.to_vec())
+    }
+
+    /// Frames `[start, start + count)` (clamped to the ready frames) as one
+    /// row-major `Float32Array`.
+    #[wasm_bindgen(js_name = getFrames)]
+    pub fn get_frames(&self, start: usize, count: usize) -> Vec<f32> {
+        let ready = self.feature.num_frames_ready();
+        let end = start.saturating_add(count).min(ready);
+        (start.min(end)..end)
+            .filter_map(|frame| self.feature.get_frame(frame))
+            .flatten()
+            .copied()
+            .collect()
+    }
+}
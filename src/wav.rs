@@ -0,0 +1,47 @@
+use crate::error::KnfError;
+use crate::pcm::{apply_channel_policy, ChannelPolicy, PcmScale};
+use std::path::Path;
+
+/// Reads a wav file as mono samples (multi-channel files are averaged) in the
+/// Kaldi integer range, returning the samples and the sampling rate.
+pub fn read_wave<P: AsRef<Path>>(path: P) -> Result<(Vec<f32>, u32), KnfError> {
+    let (mut channels, sample_rate) =
+        read_wave_channels(path, ChannelPolicy::Average, PcmScale::Kaldi)?;
+    Ok((channels.pop().unwrap_or_default(), sample_rate))
+}
+
+/// Reads a wav file, applying `policy` to its channels and `scale` to its samples.
+///
+/// Integer samples of any bit depth and float samples are first mapped to
+/// [-1, 1); `PcmScale::Kaldi` then multiplies by 32768, i.e. every format is
+/// brought to the 16-bit integer range Kaldi features expect.
+pub fn read_wave_channels<P: AsRef<Path>>(
+    path: P,
+    policy: ChannelPolicy,
+    scale: PcmScale,
+) -> Result<(Vec<Vec<f32>>, u32), KnfError> {
+    let mut reader = hound::WavReader::open(path).map_err(KnfError::io)?;
+    let spec = reader.spec();
+
+    let gain = match scale {
+        PcmScale::Kaldi => 32768.0,
+        PcmScale::Normalized => 1.0,
+    };
+    let samples: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader
+            .samples::<f32>()
+            .map(|s| s.map(|x| x * gain))
+            .collect::<Result<_, _>>(),
+        hound::SampleFormat::Int => {
+            let factor = gain / (1u64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|s| s.map(|x| x as f32 * factor))
+                .collect::<Result<_, _>>()
+        }
+    }
+    .map_err(KnfError::io)?;
+
+    let channels = apply_channel_policy(&samples, spec.channels as usize, policy)?;
+    Ok((channels, spec.sample_rate))
+}
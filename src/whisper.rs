@@ -1,9 +1,21 @@
-use crate::mel::{MelBanks, MelOptions};
+use crate::error::KnfError;
+use crate::mel::{MelBanks, MelNorm, MelOptions};
+use crate::online::Computer;
 use crate::rfft::Rfft;
 use crate::utils::compute_power_spectrum_inplace;
-use crate::window::FrameOptions;
+use crate::window::{FrameOptions, Window, WindowType};
+
+/// Samples in one 30 s Whisper chunk at 16 kHz.
+pub const WHISPER_N_SAMPLES: usize = 480000;
+/// Frames in one 30 s Whisper chunk (10 ms hop).
+pub const WHISPER_N_FRAMES: usize = 3000;
 
 #[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct WhisperOptions {
     pub frame_opts: FrameOptions,
     pub dim: usize,
@@ -11,16 +23,18 @@ pub struct WhisperOptions {
 
 impl Default for WhisperOptions {
     fn default() -> Self {
-        let mut f = FrameOptions::default();
-        f.samp_freq = 16000.0;
-        f.frame_shift_ms = 10.0;
-        f.frame_length_ms = 25.0;
-        f.dither = 0.0;
-        f.preemph_coeff = 0.0;
-        f.remove_dc_offset = false;
-        f.window_type = "hann".to_string();
-        f.round_to_power_of_two = false;
-        f.snip_edges = false;
+        let f = FrameOptions {
+            samp_freq: 16000.0,
+            frame_shift_ms: 10.0,
+            frame_length_ms: 25.0,
+            dither: 0.0,
+            preemph_coeff: 0.0,
+            remove_dc_offset: false,
+            window_type: WindowType::Hann,
+            round_to_power_of_two: false,
+            snip_edges: false,
+            ..Default::default()
+        };
 
         Self {
             frame_opts: f,
@@ -29,6 +43,30 @@ impl Default for WhisperOptions {
     }
 }
 
+impl WhisperOptions {
+    pub fn validate(&self) -> Result<(), KnfError> {
+        self.frame_opts.validate()?;
+        if self.dim == 0 {
+            return Err(KnfError::invalid("dim must be positive"));
+        }
+        Ok(())
+    }
+
+    /// Whisper tiny.en (and every other pre-v3 model): 80 mel bins.
+    pub fn tiny_en() -> Self {
+        Self::default()
+    }
+
+    /// Whisper large-v3 (and turbo): 128 mel bins, otherwise the same 25 ms /
+    /// 10 ms framing with a 400-point FFT at 16 kHz as the 80-bin models.
+    pub fn large_v3() -> Self {
+        Self {
+            dim: 128,
+            ..Default::default()
+        }
+    }
+}
+
 pub struct WhisperComputer {
     pub opts: WhisperOptions,
     rfft: Rfft,
@@ -36,14 +74,17 @@ pub struct WhisperComputer {
 }
 
 impl WhisperComputer {
-    pub fn new(opts: WhisperOptions) -> Result<Self, String> {
-        let mut mel_opts = MelOptions::default();
-        mel_opts.num_bins = opts.dim;
-        mel_opts.low_freq = 0.0;
-        mel_opts.is_librosa = true;
-        mel_opts.use_slaney_mel_scale = true;
-        mel_opts.norm = "slaney".to_string();
+    pub fn new(opts: WhisperOptions) -> Result<Self, KnfError> {
+        let mel_opts = MelOptions {
+            num_bins: opts.dim,
+            low_freq: 0.0,
+            is_librosa: true,
+            use_slaney_mel_scale: true,
+            norm: MelNorm::Slaney,
+            ..Default::default()
+        };
 
+        opts.validate()?;
         let n_fft = opts.frame_opts.padded_window_size(); // likely 400 since round=false
         let rfft = Rfft::new(n_fft, false);
 
@@ -56,6 +97,33 @@ impl WhisperComputer {
         })
     }
 
+    /// Uses precomputed mel filters instead of building them, e.g. banks loaded
+    /// with `MelBanks::from_npz` from Whisper's `mel_filters.npz`.
+    pub fn with_mel_banks(opts: WhisperOptions, mel_banks: MelBanks) -> Result<Self, KnfError> {
+        opts.validate()?;
+        if mel_banks.num_bins != opts.dim {
+            return Err(KnfError::SizeMismatch {
+                what: "mel bins",
+                expected: opts.dim,
+                actual: mel_banks.num_bins,
+            });
+        }
+        let n_fft = opts.frame_opts.padded_window_size();
+        if mel_banks.num_fft_bins != n_fft / 2 {
+            return Err(KnfError::SizeMismatch {
+                what: "mel FFT bins",
+                expected: n_fft / 2,
+                actual: mel_banks.num_fft_bins,
+            });
+        }
+
+        Ok(Self {
+            opts,
+            rfft: Rfft::new(n_fft, false),
+            mel_banks,
+        })
+    }
+
     pub fn dim(&self) -> usize {
         self.opts.dim
     }
@@ -81,3 +149,280 @@ impl WhisperComputer {
         // So we leave it linear here to match C.
     }
 }
+
+impl Computer for WhisperComputer {
+    fn dim(&self) -> usize {
+        WhisperComputer::dim(self)
+    }
+
+    fn frame_opts(&self) -> &FrameOptions {
+        &self.opts.frame_opts
+    }
+
+    fn need_raw_energy(&self) -> bool {
+        false
+    }
+
+    fn compute(
+        &mut self,
+        raw_log_energy: f32,
+        vtln_warp: f32,
+        window: &mut [f32],
+        feature: &mut [f32],
+    ) {
+        WhisperComputer::compute(self, raw_log_energy, vtln_warp, window, feature)
+    }
+}
+
+/// End-to-end Whisper log-mel extraction, matching `whisper.audio.log_mel_spectrogram`:
+/// centered STFT with reflect padding and a Hann window, power mel energies,
+/// then [`whisper_log_mel_normalize`].
+pub struct WhisperFeatureExtractor {
+    computer: WhisperComputer,
+    window: Window,
+    frame: Vec<f32>,
+}
+
+impl WhisperFeatureExtractor {
+    pub fn new(opts: WhisperOptions) -> Result<Self, KnfError> {
+        let window =
+            Window::new(&opts.frame_opts).ok_or(KnfError::invalid("Failed to create window"))?;
+        Ok(Self {
+            computer: WhisperComputer::new(opts)?,
+            window,
+            frame: Vec::new(),
+        })
+    }
+
+    /// Uses an existing computer, e.g. one built with [`WhisperComputer::with_mel_banks`].
+    pub fn with_computer(computer: WhisperComputer) -> Result<Self, KnfError> {
+        let window = Window::new(&computer.opts.frame_opts)
+            .ok_or(KnfError::invalid("Failed to create window"))?;
+        Ok(Self {
+            computer,
+            window,
+            frame: Vec::new(),
+        })
+    }
+
+    pub fn dim(&self) -> usize {
+        self.computer.dim()
+    }
+
+    pub fn opts(&self) -> &WhisperOptions {
+        &self.computer.opts
+    }
+
+    /// Number of frames produced for `num_samples` samples. Whisper drops the
+    /// last STFT frame, so this is `num_samples / hop`.
+    pub fn num_frames(&self, num_samples: usize) -> usize {
+        num_samples / self.computer.opts.frame_opts.window_shift()
+    }
+
+    /// Linear mel energies laid out `[mel * num_frames + frame]`.
+    pub fn mel_energies(&mut self, waveform: &[f32]) -> Vec<f32> {
+        let num_mels = self.computer.dim();
+        let num_frames = self.num_frames(waveform.len());
+
+        let mut mel = vec![0.0; num_mels * num_frames];
+        let mut energies = vec![0.0; num_mels];
+        for t in 0..num_frames {
+            self.compute_frame(t, |i| waveform[reflect(i, waveform.len())], &mut energies);
+            for (m, &e) in energies.iter().enumerate() {
+                mel[m * num_frames + t] = e;
+            }
+        }
+        mel
+    }
+
+    /// Mel energies of frame `t`, reading the (already reflected) sample at
+    /// absolute index `i` through `sample(i)`.
+    fn compute_frame(&mut self, t: usize, sample: impl Fn(isize) -> f32, energies: &mut [f32]) {
+        let frame_opts = &self.computer.opts.frame_opts;
+        let n_fft = frame_opts.padded_window_size();
+        // center=True framing with reflect padding, as in torch.stft
+        let start = (t * frame_opts.window_shift()) as isize - (n_fft / 2) as isize;
+
+        self.frame.resize(n_fft, 0.0);
+        for (k, x) in self.frame.iter_mut().enumerate() {
+            *x = sample(start + k as isize);
+        }
+        self.window.apply(&mut self.frame[..self.window.data.len()]);
+        self.computer.compute(0.0, 1.0, &mut self.frame, energies);
+    }
+
+    /// Normalized log-mel features laid out `[mel * num_frames + frame]`, ready
+    /// to be fed to a Whisper encoder.
+    pub fn compute(&mut self, waveform: &[f32]) -> Vec<f32> {
+        let mut mel = self.mel_energies(waveform);
+        whisper_log_mel_normalize(&mut mel);
+        mel
+    }
+
+    /// Features of one 30 s chunk: the waveform is zero-padded or trimmed to
+    /// [`WHISPER_N_SAMPLES`] first, so the result always has [`WHISPER_N_FRAMES`]
+    /// frames, as for a single-segment `whisper.transcribe` call.
+    pub fn compute_chunk(&mut self, waveform: &[f32]) -> Vec<f32> {
+        self.compute(&whisper_pad_or_trim(waveform, WHISPER_N_SAMPLES))
+    }
+}
+
+/// Whisper's log-mel post-processing, in place: `log10(max(x, 1e-10))`, clamp
+/// to `max - 8`, then `(x + 4) / 4`. The maximum is taken over the whole slice,
+/// so pass all frames of an utterance (or window) at once.
+pub fn whisper_log_mel_normalize(mel: &mut [f32]) {
+    for x in mel.iter_mut() {
+        *x = x.max(1e-10).log10();
+    }
+    let max = mel.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    for x in mel.iter_mut() {
+        *x = (x.max(max - 8.0) + 4.0) / 4.0;
+    }
+}
+
+/// Streaming counterpart of [`WhisperFeatureExtractor`]: accepts audio in
+/// chunks and emits linear mel energy frames as soon as their window is
+/// available, keeping only the audio still needed by future frames.
+///
+/// The concatenated frames are identical to `mel_energies` on the whole
+/// utterance, including the reflect padding at both ends (the final frames are
+/// emitted after [`input_finished`](Self::input_finished)). Whisper's log-mel
+/// normalization depends on the maximum over a window, so apply
+/// [`whisper_log_mel_normalize`] to the frames of each window.
+pub struct WhisperStreamingExtractor {
+    extractor: WhisperFeatureExtractor,
+    waveform: Vec<f32>,
+    waveform_offset: usize,
+    input_finished: bool,
+    pub features: Vec<Vec<f32>>,
+}
+
+impl WhisperStreamingExtractor {
+    pub fn new(opts: WhisperOptions) -> Result<Self, KnfError> {
+        Ok(Self {
+            extractor: WhisperFeatureExtractor::new(opts)?,
+            waveform: Vec::new(),
+            waveform_offset: 0,
+            input_finished: false,
+            features: Vec::new(),
+        })
+    }
+
+    pub fn dim(&self) -> usize {
+        self.extractor.dim()
+    }
+
+    /// Fails once [`input_finished`](Self::input_finished) has been called.
+    pub fn accept_waveform(&mut self, waveform: &[f32]) -> Result<(), KnfError> {
+        if self.input_finished {
+            return Err(KnfError::input(
+                "accept_waveform called after input_finished",
+            ));
+        }
+        self.waveform.extend_from_slice(waveform);
+        self.compute_new();
+        Ok(())
+    }
+
+    pub fn input_finished(&mut self) {
+        self.input_finished = true;
+        self.compute_new();
+    }
+
+    pub fn is_input_finished(&self) -> bool {
+        self.input_finished
+    }
+
+    pub fn num_frames_ready(&self) -> usize {
+        self.features.len()
+    }
+
+    pub fn get_frame(&self, frame: usize) -> Option<&[f32]> {
+        self.features.get(frame).map(|v| v.as_slice())
+    }
+
+    fn compute_new(&mut self) {
+        let frame_opts = &self.extractor.opts().frame_opts;
+        let hop = frame_opts.window_shift();
+        let n_fft = frame_opts.padded_window_size();
+        let pad = n_fft / 2;
+        let total = self.waveform_offset + self.waveform.len();
+
+        loop {
+            let t = self.features.len();
+            if t >= self.extractor.num_frames(total) {
+                break;
+            }
+            // Until the input is finished, the window (and the reflected
+            // samples at the start) must lie inside the received audio.
+            if !self.input_finished && (t * hop + n_fft - pad).max(pad + 1) > total {
+                break;
+            }
+            let (waveform, offset) = (&self.waveform, self.waveform_offset);
+            let mut energies = vec![0.0; self.extractor.dim()];
+            self.extractor.compute_frame(
+                t,
+                |i| waveform[reflect(i, total) - offset],
+                &mut energies,
+            );
+            self.features.push(energies);
+        }
+
+        // Keep what the next frame's window can reach; reflections at the end
+        // stay inside that window and the start is only reflected for frames
+        // before `pad`.
+        let next_start = self.features.len() * hop;
+        if next_start > pad {
+            let discard = (next_start - pad).saturating_sub(self.waveform_offset);
+            let discard = discard.min(self.waveform.len());
+            self.waveform.drain(..discard);
+            self.waveform_offset += discard;
+        }
+    }
+}
+
+/// Zero-pads the end of `waveform` or trims it to exactly `length` samples,
+/// like `whisper.audio.pad_or_trim`.
+pub fn whisper_pad_or_trim(waveform: &[f32], length: usize) -> Vec<f32> {
+    let mut out = waveform[..waveform.len().min(length)].to_vec();
+    out.resize(length, 0.0);
+    out
+}
+
+/// Pads (with `pad_value`) or trims a `[mel * num_frames + frame]` matrix along
+/// the time axis to exactly `length` frames.
+pub fn whisper_pad_or_trim_frames(
+    mel: &[f32],
+    num_mels: usize,
+    length: usize,
+    pad_value: f32,
+) -> Vec<f32> {
+    assert!(
+        num_mels > 0 && mel.len().is_multiple_of(num_mels),
+        "mel matrix size is not a multiple of num_mels"
+    );
+    let num_frames = mel.len() / num_mels;
+    let mut out = Vec::with_capacity(num_mels * length);
+    for row in mel.chunks(num_frames.max(1)).take(num_mels) {
+        let mut row = row[..num_frames.min(length)].to_vec();
+        row.resize(length, pad_value);
+        out.extend(row);
+    }
+    out.resize(num_mels * length, pad_value);
+    out
+}
+
+fn reflect(mut idx: isize, len: usize) -> usize {
+    if len == 1 {
+        return 0;
+    }
+    let len = len as isize;
+    while idx < 0 || idx >= len {
+        if idx < 0 {
+            idx = -idx;
+        } else {
+            idx = 2 * (len - 1) - idx;
+        }
+    }
+    idx as usize
+}
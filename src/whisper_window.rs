@@ -0,0 +1,100 @@
+use crate::error::KnfError;
+use crate::whisper::{whisper_pad_or_trim, WhisperFeatureExtractor, WhisperOptions};
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct WhisperWindowOptions {
+    pub whisper_opts: WhisperOptions,
+    /// Length of the context window in seconds (Whisper uses 30 s / 3000 frames).
+    pub chunk_length_s: f32,
+    /// Audio kept from the end of the current window when sliding to the next one.
+    pub overlap_s: f32,
+}
+
+impl Default for WhisperWindowOptions {
+    fn default() -> Self {
+        Self {
+            whisper_opts: WhisperOptions::default(),
+            chunk_length_s: 30.0,
+            overlap_s: 0.0,
+        }
+    }
+}
+
+/// Rolling context window for Whisper-style models in streaming mode.
+///
+/// Audio is buffered as it arrives; [`log_mel`](Self::log_mel) zero-pads or
+/// truncates the front of the buffer to exactly one window and returns the
+/// normalized log-mel tensor, and [`advance`](Self::advance) slides the window.
+pub struct WhisperWindow {
+    pub opts: WhisperWindowOptions,
+    extractor: WhisperFeatureExtractor,
+    buffer: Vec<f32>,
+    offset: usize,
+}
+
+impl WhisperWindow {
+    pub fn new(opts: WhisperWindowOptions) -> Result<Self, KnfError> {
+        if opts.chunk_length_s <= 0.0
+            || opts.overlap_s < 0.0
+            || opts.overlap_s >= opts.chunk_length_s
+        {
+            return Err(KnfError::invalid("Invalid Whisper window/overlap lengths"));
+        }
+        let extractor = WhisperFeatureExtractor::new(opts.whisper_opts.clone())?;
+        Ok(Self {
+            opts,
+            extractor,
+            buffer: Vec::new(),
+            offset: 0,
+        })
+    }
+
+    pub fn accept_waveform(&mut self, waveform: &[f32]) {
+        self.buffer.extend_from_slice(waveform);
+    }
+
+    /// Number of samples in one window (480000 for 30 s at 16 kHz).
+    pub fn window_samples(&self) -> usize {
+        (self.opts.chunk_length_s * self.opts.whisper_opts.frame_opts.samp_freq) as usize
+    }
+
+    /// Number of frames in the tensor returned by `log_mel` (3000 for 30 s).
+    pub fn num_frames(&self) -> usize {
+        self.window_samples() / self.opts.whisper_opts.frame_opts.window_shift()
+    }
+
+    pub fn num_samples_buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Whether a full window of audio is buffered.
+    pub fn is_full(&self) -> bool {
+        self.buffer.len() >= self.window_samples()
+    }
+
+    /// Absolute index of the first sample of the current window.
+    pub fn window_offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Log-mel tensor of the current window, laid out `[mel * num_frames + frame]`
+    /// like Whisper's `(n_mels, n_frames)` input.
+    pub fn log_mel(&mut self) -> Vec<f32> {
+        let audio = whisper_pad_or_trim(&self.buffer, self.window_samples());
+        self.extractor.compute(&audio)
+    }
+
+    /// Slides the window forward by `chunk_length_s - overlap_s`, discarding the
+    /// audio before the new window start.
+    pub fn advance(&mut self) {
+        let overlap = (self.opts.overlap_s * self.opts.whisper_opts.frame_opts.samp_freq) as usize;
+        let step = (self.window_samples() - overlap).min(self.buffer.len());
+        self.buffer.drain(..step);
+        self.offset += step;
+    }
+}
@@ -1,18 +1,43 @@
+use crate::error::KnfError;
+use crate::filter::FirstOrderHighPass;
 use crate::utils::TWO_PI;
 use rand::Rng;
+use rand_distr::StandardNormal;
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct FrameOptions {
     pub samp_freq: f32,
     pub frame_shift_ms: f32,
     pub frame_length_ms: f32,
+    /// Frame shift in samples; overrides `frame_shift_ms` when non-zero.
+    pub frame_shift_samples: usize,
+    /// Frame length in samples; overrides `frame_length_ms` when non-zero.
+    pub frame_length_samples: usize,
     pub dither: f32,
+    pub dither_mode: DitherMode,
     pub preemph_coeff: f32,
+    /// Cutoff in Hz of a first-order high-pass applied to the audio before
+    /// framing, e.g. to remove rumble; 0 disables it (Kaldi has no such option).
+    pub highpass_cutoff: f32,
     pub remove_dc_offset: bool,
-    pub window_type: String,
+    pub window_type: WindowType,
     pub round_to_power_of_two: bool,
     pub blackman_coeff: f32,
     pub snip_edges: bool,
+    /// FFT size; frames are zero-padded from the window size up to it. 0 derives
+    /// it from the window size and `round_to_power_of_two`.
+    pub n_fft: usize,
+    /// Window coefficients used instead of `window_type`, e.g. copied from an
+    /// existing model. Must have exactly `window_size()` values.
+    pub custom_window: Option<Vec<f32>>,
 }
 
 impl Default for FrameOptions {
@@ -21,34 +46,197 @@ impl Default for FrameOptions {
             samp_freq: 16000.0,
             frame_shift_ms: 10.0,
             frame_length_ms: 25.0,
+            frame_shift_samples: 0,
+            frame_length_samples: 0,
             dither: 0.00003,
+            dither_mode: DitherMode::Uniform,
             preemph_coeff: 0.97,
+            highpass_cutoff: 0.0,
             remove_dc_offset: true,
-            window_type: "povey".to_string(),
+            window_type: WindowType::Povey,
             round_to_power_of_two: true,
             blackman_coeff: 0.42,
             snip_edges: true,
+            n_fft: 0,
+            custom_window: None,
         }
     }
 }
 
 impl FrameOptions {
     pub fn window_shift(&self) -> usize {
+        if self.frame_shift_samples > 0 {
+            return self.frame_shift_samples;
+        }
         (self.samp_freq * 0.001 * self.frame_shift_ms) as usize
     }
 
     pub fn window_size(&self) -> usize {
+        if self.frame_length_samples > 0 {
+            return self.frame_length_samples;
+        }
         (self.samp_freq * 0.001 * self.frame_length_ms) as usize
     }
 
     pub fn padded_window_size(&self) -> usize {
         let size = self.window_size();
-        if self.round_to_power_of_two {
+        if self.n_fft > 0 {
+            self.n_fft
+        } else if self.round_to_power_of_two {
             size.next_power_of_two()
         } else {
             size
         }
     }
+
+    /// Rejects options that would produce empty frames, NaNs or a failing
+    /// window lookup, before anything is computed.
+    pub fn validate(&self) -> Result<(), KnfError> {
+        if self.samp_freq <= 0.0 {
+            return Err(KnfError::invalid("samp_freq must be positive"));
+        }
+        if self.window_shift() == 0 {
+            return Err(KnfError::invalid("frame shift must be at least one sample"));
+        }
+        if self.window_size() < 2 {
+            return Err(KnfError::invalid(
+                "frame length must be at least two samples",
+            ));
+        }
+        if self.dither < 0.0 {
+            return Err(KnfError::invalid("dither must be non-negative"));
+        }
+        if !(0.0..=1.0).contains(&self.preemph_coeff) {
+            return Err(KnfError::invalid(format!(
+                "preemph_coeff ({}) must be in [0, 1]",
+                self.preemph_coeff
+            )));
+        }
+        if self.highpass_cutoff != 0.0 {
+            FirstOrderHighPass::new(self.samp_freq, self.highpass_cutoff)?;
+        }
+        self.check_n_fft()?;
+        check_custom_window(self.custom_window.as_deref(), self.window_size())
+    }
+
+    /// The high-pass selected by `highpass_cutoff`, if any.
+    pub(crate) fn highpass(&self) -> Option<FirstOrderHighPass> {
+        if self.highpass_cutoff == 0.0 {
+            return None;
+        }
+        FirstOrderHighPass::new(self.samp_freq, self.highpass_cutoff).ok()
+    }
+
+    pub(crate) fn check_n_fft(&self) -> Result<(), KnfError> {
+        if self.n_fft > 0 && self.n_fft < self.window_size() {
+            return Err(KnfError::invalid(format!(
+                "n_fft ({}) must not be smaller than the window size ({})",
+                self.n_fft,
+                self.window_size()
+            )));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn check_custom_window(window: Option<&[f32]>, size: usize) -> Result<(), KnfError> {
+    match window {
+        Some(w) if w.len() != size => Err(KnfError::SizeMismatch {
+            what: "custom window",
+            expected: size,
+            actual: w.len(),
+        }),
+        Some(w) if w.iter().any(|x| !x.is_finite()) => {
+            Err(KnfError::invalid("custom window values must be finite"))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Analysis window shape. Parses from and displays as Kaldi's names
+/// (`"povey"`, `"hamming"`, ...), which are also used by serde.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum WindowType {
+    /// Symmetric Hann window, Kaldi's `"hanning"`.
+    Hanning,
+    Sine,
+    Hamming,
+    /// Periodic Hann window (`torch.hann_window`), as used by Whisper.
+    Hann,
+    /// Hann window raised to the power 0.85 (Kaldi's default).
+    #[default]
+    Povey,
+    Rectangular,
+    /// Generalized Blackman window with `FrameOptions::blackman_coeff`.
+    Blackman,
+}
+
+impl WindowType {
+    pub const ALL: [WindowType; 7] = [
+        Self::Hanning,
+        Self::Sine,
+        Self::Hamming,
+        Self::Hann,
+        Self::Povey,
+        Self::Rectangular,
+        Self::Blackman,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Hanning => "hanning",
+            Self::Sine => "sine",
+            Self::Hamming => "hamming",
+            Self::Hann => "hann",
+            Self::Povey => "povey",
+            Self::Rectangular => "rectangular",
+            Self::Blackman => "blackman",
+        }
+    }
+}
+
+impl fmt::Display for WindowType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for WindowType {
+    type Err = KnfError;
+
+    fn from_str(s: &str) -> Result<Self, KnfError> {
+        Self::ALL
+            .into_iter()
+            .find(|w| w.as_str() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|w| w.as_str()).collect();
+                KnfError::invalid(format!(
+                    "Unknown window type {:?}; expected one of {}",
+                    s,
+                    names.join(", ")
+                ))
+            })
+    }
+}
+
+/// Noise distribution added to each frame, scaled by `FrameOptions::dither`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum DitherMode {
+    /// Uniform noise in `[-0.5, 0.5)`.
+    #[default]
+    Uniform,
+    /// Standard normal noise, as Kaldi's `RandGauss`.
+    Gaussian,
 }
 
 pub struct Window {
@@ -56,32 +244,45 @@ pub struct Window {
 }
 
 impl Window {
+    /// Wraps precomputed window coefficients.
+    pub fn from_data(data: Vec<f32>) -> Self {
+        Self { data }
+    }
+
     pub fn new(opts: &FrameOptions) -> Option<Self> {
         let size = opts.window_size();
         if size == 0 {
             return None;
         }
+        if let Some(data) = &opts.custom_window {
+            return Some(Self::from_data(data.clone()));
+        }
+        Some(Self::from_type(opts.window_type, size, opts.blackman_coeff))
+    }
+
+    /// `size` coefficients of `window_type`; `blackman_coeff` is only used
+    /// by [`WindowType::Blackman`].
+    pub fn from_type(window_type: WindowType, size: usize, blackman_coeff: f32) -> Self {
         let mut data = vec![0.0; size];
         let a = TWO_PI / (size as f32 - 1.0);
         let a_hann = TWO_PI / (size as f32); // used for hann specifically
 
-        for i in 0..size {
+        for (i, d) in data.iter_mut().enumerate() {
             let x = i as f32;
-            data[i] = match opts.window_type.as_str() {
-                "hanning" => 0.5 - 0.5 * (a * x).cos(),
-                "sine" => (0.5 * a * x).sin(),
-                "hamming" => 0.54 - 0.46 * (a * x).cos(),
-                "hann" => 0.50 - 0.50 * (a_hann * x).cos(),
-                "povey" => (0.5 - 0.5 * (a * x).cos()).powf(0.85),
-                "rectangular" => 1.0,
-                "blackman" => {
-                    opts.blackman_coeff - 0.5 * (a * x).cos()
-                        + (0.5 - opts.blackman_coeff) * (2.0 * a * x).cos()
+            *d = match window_type {
+                WindowType::Hanning => 0.5 - 0.5 * (a * x).cos(),
+                WindowType::Sine => (0.5 * a * x).sin(),
+                WindowType::Hamming => 0.54 - 0.46 * (a * x).cos(),
+                WindowType::Hann => 0.50 - 0.50 * (a_hann * x).cos(),
+                WindowType::Povey => (0.5 - 0.5 * (a * x).cos()).powf(0.85),
+                WindowType::Rectangular => 1.0,
+                WindowType::Blackman => {
+                    blackman_coeff - 0.5 * (a * x).cos()
+                        + (0.5 - blackman_coeff) * (2.0 * a * x).cos()
                 }
-                _ => return None,
             };
         }
-        Some(Self { data })
+        Self { data }
     }
 
     pub fn apply(&self, wave: &mut [f32]) {
@@ -143,18 +344,19 @@ pub fn extract_window(
     opts: &FrameOptions,
     window_function: Option<&Window>,
     window_out: &mut [f32],
-) -> Result<f32, ()> {
+) -> Result<f32, KnfError> {
     let frame_length = opts.window_size();
     let num_samples = sample_offset + wave.len();
     let start_sample = first_sample_of_frame(frame_index, opts);
     let end_sample = start_sample + frame_length as isize;
 
-    if opts.snip_edges {
-        if start_sample < sample_offset as isize || end_sample > num_samples as isize {
-            return Err(());
-        }
-    } else if !(sample_offset == 0 || start_sample >= sample_offset as isize) {
-        return Err(());
+    let out_of_range = if opts.snip_edges {
+        start_sample < sample_offset as isize || end_sample > num_samples as isize
+    } else {
+        !(sample_offset == 0 || start_sample >= sample_offset as isize)
+    };
+    if out_of_range {
+        return Err(KnfError::FrameOutOfRange { frame: frame_index });
     }
 
     // Zero out buffer
@@ -164,7 +366,7 @@ pub fn extract_window(
     // let wave_end = wave_start + frame_length as isize;
 
     // Copy wave data
-    for s in 0..frame_length {
+    for (s, out) in window_out.iter_mut().enumerate().take(frame_length) {
         let s_in_wave = s as isize + wave_start;
         let mut idx = s_in_wave;
 
@@ -178,7 +380,7 @@ pub fn extract_window(
         }
 
         if idx >= 0 && (idx as usize) < wave.len() {
-            window_out[s] = wave[idx as usize];
+            *out = wave[idx as usize];
         }
     }
 
@@ -186,7 +388,11 @@ pub fn extract_window(
     if opts.dither != 0.0 {
         let mut rng = rand::thread_rng();
         for x in window_out.iter_mut().take(frame_length) {
-            *x += opts.dither * (rng.gen::<f32>() - 0.5);
+            let noise = match opts.dither_mode {
+                DitherMode::Uniform => rng.gen::<f32>() - 0.5,
+                DitherMode::Gaussian => rng.sample::<f32, _>(StandardNormal),
+            };
+            *x += opts.dither * noise;
         }
     }
 
@@ -225,3 +431,80 @@ pub fn extract_window(
 
     Ok(log_energy)
 }
+
+/// `wave` after the high-pass of `opts.highpass_cutoff`, if enabled.
+pub(crate) fn highpass_filtered<'a>(wave: &'a [f32], opts: &FrameOptions) -> Cow<'a, [f32]> {
+    match opts.highpass() {
+        Some(mut highpass) => Cow::Owned(highpass.process(wave)),
+        None => Cow::Borrowed(wave),
+    }
+}
+
+/// Frames `0..num_frames(wave.len(), opts, true)` of a complete waveform, as
+/// [`extract_window`] produces them, without manual index bookkeeping.
+///
+/// [`next_frame`](Self::next_frame) reuses one buffer; the [`Iterator`]
+/// implementation copies each frame.
+pub struct FrameIterator<'a> {
+    wave: Cow<'a, [f32]>,
+    opts: &'a FrameOptions,
+    window: Option<Window>,
+    buf: Vec<f32>,
+    frame: usize,
+    num_frames: usize,
+}
+
+impl<'a> FrameIterator<'a> {
+    pub fn new(wave: &'a [f32], opts: &'a FrameOptions) -> Self {
+        Self {
+            wave: highpass_filtered(wave, opts),
+            opts,
+            window: Window::new(opts),
+            buf: vec![0.0; opts.padded_window_size()],
+            frame: 0,
+            num_frames: num_frames(wave.len(), opts, true),
+        }
+    }
+
+    /// Total number of frames, including those already returned.
+    pub fn num_frames(&self) -> usize {
+        self.num_frames
+    }
+
+    /// `(frame_index, windowed frame, raw_log_energy)` of the next frame. The
+    /// frame is zero-padded to `padded_window_size()` and may be modified in
+    /// place, e.g. by [`Computer::compute`](crate::Computer::compute).
+    pub fn next_frame(&mut self) -> Option<(usize, &mut [f32], f32)> {
+        if self.frame >= self.num_frames {
+            return None;
+        }
+        let frame = self.frame;
+        self.frame += 1;
+        let raw_log_energy = extract_window(
+            0,
+            &self.wave,
+            frame,
+            self.opts,
+            self.window.as_ref(),
+            &mut self.buf,
+        )
+        .ok()?;
+        Some((frame, &mut self.buf, raw_log_energy))
+    }
+}
+
+impl Iterator for FrameIterator<'_> {
+    type Item = (usize, Vec<f32>, f32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_frame()
+            .map(|(frame, data, raw_log_energy)| (frame, data.to_vec(), raw_log_energy))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.num_frames - self.frame;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for FrameIterator<'_> {}
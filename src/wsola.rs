@@ -0,0 +1,256 @@
+use crate::error::KnfError;
+use crate::window::{Window, WindowType};
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct WsolaOptions {
+    pub samp_freq: f32,
+    /// Length of the overlap-added segments; they overlap by half.
+    pub frame_length_ms: f32,
+    /// How far a segment may be moved from its nominal position to line up
+    /// with the previous one.
+    pub tolerance_ms: f32,
+}
+
+impl Default for WsolaOptions {
+    fn default() -> Self {
+        Self {
+            samp_freq: 16000.0,
+            frame_length_ms: 25.0,
+            tolerance_ms: 10.0,
+        }
+    }
+}
+
+impl WsolaOptions {
+    pub fn validate(&self) -> Result<(), KnfError> {
+        if self.frame_length() < 2 {
+            return Err(KnfError::invalid(
+                "WSOLA frame length must be at least 2 samples",
+            ));
+        }
+        if self.tolerance_ms.is_nan() || self.tolerance_ms < 0.0 {
+            return Err(KnfError::invalid("WSOLA tolerance must be non-negative"));
+        }
+        Ok(())
+    }
+
+    pub fn frame_length(&self) -> usize {
+        (self.samp_freq * 0.001 * self.frame_length_ms) as usize
+    }
+
+    pub fn tolerance(&self) -> usize {
+        (self.samp_freq * 0.001 * self.tolerance_ms) as usize
+    }
+}
+
+/// Changes the duration of `wave` by `1 / rate` without changing its pitch
+/// using waveform-similarity overlap-add (WSOLA), which keeps speech more
+/// natural than [`time_stretch`](crate::time_stretch); `rate > 1` speeds it up.
+///
+/// The output has `round(wave.len() / rate)` samples.
+pub fn wsola(wave: &[f32], rate: f32, opts: &WsolaOptions) -> Result<Vec<f32>, KnfError> {
+    let mut processor = OnlineWsola::new(opts.clone(), rate)?;
+    let mut out = processor.accept_waveform(wave);
+    out.extend(processor.finish());
+    Ok(out)
+}
+
+/// Streaming WSOLA. The output of all chunks followed by
+/// [`finish`](Self::finish) equals [`wsola`] over the whole signal.
+pub struct OnlineWsola {
+    rate: f64,
+    frame_length: usize,
+    hop: usize,
+    tolerance: usize,
+    window: Vec<f32>,
+    // Input from absolute sample `input_offset` on
+    input: Vec<f32>,
+    input_offset: usize,
+    num_frames: usize,
+    // Absolute start of the last segment that was overlap-added
+    prev_pos: Option<usize>,
+    // Overlap-add sums and window sums from the current output position on
+    ola: Vec<f32>,
+    wsum: Vec<f32>,
+    // Completed output that is not returned yet, and the count returned so far
+    ready: Vec<f32>,
+    num_output: usize,
+    continuation: Vec<f32>,
+}
+
+impl OnlineWsola {
+    pub fn new(opts: WsolaOptions, rate: f32) -> Result<Self, KnfError> {
+        opts.validate()?;
+        if !(rate.is_finite() && rate > 0.0) {
+            return Err(KnfError::invalid(format!(
+                "Stretch rate must be positive, got {}",
+                rate
+            )));
+        }
+        let frame_length = opts.frame_length();
+        Ok(Self {
+            rate: rate as f64,
+            frame_length,
+            hop: frame_length / 2,
+            tolerance: opts.tolerance(),
+            window: Window::from_type(WindowType::Hann, frame_length, 0.42).data,
+            input: Vec::new(),
+            input_offset: 0,
+            num_frames: 0,
+            prev_pos: None,
+            ola: vec![0.0; frame_length],
+            wsum: vec![0.0; frame_length],
+            ready: Vec::new(),
+            num_output: 0,
+            continuation: vec![0.0; frame_length],
+        })
+    }
+
+    /// Adds the next chunk of input; returns the output completed by it.
+    pub fn accept_waveform(&mut self, wave: &[f32]) -> Vec<f32> {
+        self.input.extend_from_slice(wave);
+        self.process(false);
+        let limit = (self.num_input() as f64 / self.rate).floor() as usize;
+        self.take_output(limit)
+    }
+
+    /// Returns the remaining output and resets for a new signal.
+    pub fn finish(&mut self) -> Vec<f32> {
+        self.process(true);
+        // Samples after the last segment only have part of their overlap
+        for i in 0..self.frame_length - self.hop {
+            self.ready.push(self.normalized(i));
+        }
+        let total = (self.num_input() as f64 / self.rate).round() as usize;
+        self.ready.resize(total - self.num_output, 0.0);
+        let out = std::mem::take(&mut self.ready);
+
+        self.input.clear();
+        self.input_offset = 0;
+        self.num_frames = 0;
+        self.prev_pos = None;
+        self.ola.iter_mut().for_each(|x| *x = 0.0);
+        self.wsum.iter_mut().for_each(|x| *x = 0.0);
+        self.num_output = 0;
+        out
+    }
+
+    fn num_input(&self) -> usize {
+        self.input_offset + self.input.len()
+    }
+
+    fn nominal(&self, frame: usize) -> usize {
+        (frame as f64 * self.hop as f64 * self.rate).round() as usize
+    }
+
+    /// Overlap-adds every segment the input allows; after the end of input,
+    /// segments are zero-padded.
+    fn process(&mut self, finished: bool) {
+        let n = self.frame_length;
+        loop {
+            let nominal = self.nominal(self.num_frames);
+            let total = self.num_input();
+            if finished {
+                if nominal >= total {
+                    break;
+                }
+            } else {
+                let need = (nominal + self.tolerance + n)
+                    .max(self.prev_pos.map_or(0, |p| p + self.hop + n));
+                if need > total {
+                    break;
+                }
+            }
+
+            let pos = match self.prev_pos {
+                None => nominal,
+                Some(prev) => self.best_position(nominal, prev + self.hop),
+            };
+            for i in 0..n {
+                self.ola[i] += self.sample(pos + i) * self.window[i];
+                self.wsum[i] += self.window[i];
+            }
+            for i in 0..self.hop {
+                let x = self.normalized(i);
+                self.ready.push(x);
+            }
+            self.ola.drain(..self.hop);
+            self.ola.resize(n, 0.0);
+            self.wsum.drain(..self.hop);
+            self.wsum.resize(n, 0.0);
+
+            self.prev_pos = Some(pos);
+            self.num_frames += 1;
+
+            // Drop input that neither the next candidates nor the natural
+            // continuation of this segment can reach
+            let keep_from = self
+                .nominal(self.num_frames)
+                .saturating_sub(self.tolerance)
+                .min(pos + self.hop);
+            let drop = keep_from
+                .saturating_sub(self.input_offset)
+                .min(self.input.len());
+            self.input.drain(..drop);
+            self.input_offset += drop;
+        }
+    }
+
+    /// Start within `nominal ± tolerance` whose segment correlates best with
+    /// the segment at `continuation`.
+    fn best_position(&mut self, nominal: usize, continuation: usize) -> usize {
+        for i in 0..self.frame_length {
+            self.continuation[i] = self.sample(continuation + i);
+        }
+        let correlation = |start: usize| -> f32 {
+            let begin = start - self.input_offset;
+            let end = (begin + self.frame_length).min(self.input.len());
+            self.input[begin.min(end)..end]
+                .iter()
+                .zip(&self.continuation)
+                .map(|(x, y)| x * y)
+                .sum()
+        };
+
+        let mut best = nominal;
+        let mut best_corr = correlation(nominal);
+        let lo = nominal
+            .saturating_sub(self.tolerance)
+            .max(self.input_offset);
+        for start in lo..=nominal + self.tolerance {
+            let corr = correlation(start);
+            if corr > best_corr {
+                best = start;
+                best_corr = corr;
+            }
+        }
+        best
+    }
+
+    /// Input sample at absolute index `i`, zero past the received input.
+    fn sample(&self, i: usize) -> f32 {
+        self.input
+            .get(i - self.input_offset)
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    fn normalized(&self, i: usize) -> f32 {
+        if self.wsum[i] > 1e-6 {
+            self.ola[i] / self.wsum[i]
+        } else {
+            self.ola[i]
+        }
+    }
+
+    fn take_output(&mut self, limit: usize) -> Vec<f32> {
+        let n = limit.saturating_sub(self.num_output).min(self.ready.len());
+        self.num_output += n;
+        self.ready.drain(..n).collect()
+    }
+}
@@ -0,0 +1,149 @@
+//! Auto- and cross-correlation by FFT, e.g. for pitch, alignment or time
+//! difference of arrival estimates.
+
+use crate::error::KnfError;
+use crate::rfft::Rfft;
+
+/// Lags returned by [`xcorr`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum XcorrMode {
+    /// Every lag with any overlap.
+    #[default]
+    Full,
+    /// Only lags where the shorter input lies entirely within the longer one.
+    Valid,
+}
+
+/// Scaling of each correlation value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum XcorrNormalization {
+    #[default]
+    None,
+    /// Divided by the longer length.
+    Biased,
+    /// Divided by the overlap at each lag.
+    Unbiased,
+    /// Divided by the geometric mean of the energies, so an autocorrelation
+    /// is 1 at lag 0.
+    Coeff,
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct XcorrOptions {
+    pub mode: XcorrMode,
+    pub normalization: XcorrNormalization,
+    /// Restricts the lags to `[-max_lag, max_lag]`.
+    pub max_lag: Option<usize>,
+}
+
+/// Correlation values for the consecutive lags starting at `first_lag`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Correlation {
+    pub values: Vec<f32>,
+    pub first_lag: isize,
+}
+
+impl Correlation {
+    /// Lag of `values[index]`.
+    pub fn lag(&self, index: usize) -> isize {
+        self.first_lag + index as isize
+    }
+
+    /// Lag of the largest value, or `None` if there are no values.
+    pub fn peak_lag(&self) -> Option<isize> {
+        let values = &self.values;
+        if values.is_empty() {
+            return None;
+        }
+        let best = values
+            .iter()
+            .enumerate()
+            .fold(0, |best, (i, &v)| if v > values[best] { i } else { best });
+        Some(self.lag(best))
+    }
+}
+
+/// Cross-correlation `c[m] = sum_n a[n + m] * b[n]`, like `numpy.correlate`
+/// and MATLAB's `xcorr`: a positive peak lag means `a` lags behind `b`.
+pub fn xcorr(a: &[f32], b: &[f32], opts: &XcorrOptions) -> Result<Correlation, KnfError> {
+    if a.is_empty() || b.is_empty() {
+        return Err(KnfError::invalid("Correlation inputs must be non-empty"));
+    }
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let (mut lo, mut hi) = match opts.mode {
+        XcorrMode::Valid => ((n - m).min(0), (n - m).max(0)),
+        XcorrMode::Full => (1 - m, n - 1),
+    };
+    if let Some(max_lag) = opts.max_lag {
+        lo = lo.max(-(max_lag as isize));
+        hi = hi.min(max_lag as isize);
+    }
+    if lo > hi {
+        return Ok(Correlation {
+            values: Vec::new(),
+            first_lag: 0,
+        });
+    }
+
+    let n_fft = (a.len() + b.len() - 1).next_power_of_two().max(2);
+    let mut fft = Rfft::new(n_fft, false);
+    let mut spec_a = vec![0.0; n_fft];
+    spec_a[..a.len()].copy_from_slice(a);
+    fft.compute(&mut spec_a);
+    let mut spec_b = vec![0.0; n_fft];
+    spec_b[..b.len()].copy_from_slice(b);
+    fft.compute(&mut spec_b);
+    multiply_conjugate(&mut spec_a, &spec_b);
+    Rfft::new(n_fft, true).compute(&mut spec_a);
+
+    let scale = 1.0 / n_fft as f32;
+    let energy = |x: &[f32]| x.iter().map(|&v| v as f64 * v as f64).sum::<f64>();
+    let coeff = (energy(a) * energy(b)).sqrt() as f32;
+    let values = (lo..=hi)
+        .map(|lag| {
+            let c = spec_a[lag.rem_euclid(n_fft as isize) as usize] * scale;
+            match opts.normalization {
+                XcorrNormalization::Biased => c / n.max(m) as f32,
+                XcorrNormalization::Unbiased => c / ((n - lag).min(m) - (-lag).max(0)) as f32,
+                XcorrNormalization::Coeff if coeff > 0.0 => c / coeff,
+                _ => c,
+            }
+        })
+        .collect();
+    Ok(Correlation {
+        values,
+        first_lag: lo,
+    })
+}
+
+/// Autocorrelation of `x`; in [`XcorrMode::Full`] the lags run from `1 - len` to
+/// `len - 1`.
+pub fn autocorr(x: &[f32], opts: &XcorrOptions) -> Result<Correlation, KnfError> {
+    xcorr(x, x, opts)
+}
+
+/// `x *= conj(y)` for spectra in the packed layout of [`Rfft`] (even size).
+fn multiply_conjugate(x: &mut [f32], y: &[f32]) {
+    x[0] *= y[0];
+    x[1] *= y[1];
+    for (x, y) in x[2..].chunks_exact_mut(2).zip(y[2..].chunks_exact(2)) {
+        let (re, im) = (x[0], x[1]);
+        x[0] = re * y[0] + im * y[1];
+        x[1] = im * y[0] - re * y[1];
+    }
+}
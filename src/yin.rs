@@ -0,0 +1,549 @@
+//! Fundamental frequency estimation with YIN (de Cheveigné & Kawahara, 2002)
+//! and probabilistic YIN (Mauch & Dixon, 2014), following `librosa.yin` and
+//! `librosa.pyin`.
+
+use crate::error::KnfError;
+use crate::online::Computer;
+use crate::window::{FrameIterator, FrameOptions, WindowType};
+
+/// Per-frame output of [`yin`] and [`pyin`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PitchTrack {
+    /// Fundamental frequency in Hz. Unvoiced frames keep the best guess, so
+    /// mask them with `voiced` where needed.
+    pub f0: Vec<f32>,
+    pub voiced: Vec<bool>,
+    pub voiced_prob: Vec<f32>,
+}
+
+impl PitchTrack {
+    pub fn num_frames(&self) -> usize {
+        self.f0.len()
+    }
+}
+
+/// Frames suited to YIN: 64 ms long, unwindowed, without dither or
+/// pre-emphasis. The difference function spans half a frame, so
+/// `samp_freq / fmin` must stay below half the frame length.
+fn default_frame_opts() -> FrameOptions {
+    FrameOptions {
+        frame_length_ms: 64.0,
+        dither: 0.0,
+        preemph_coeff: 0.0,
+        window_type: WindowType::Rectangular,
+        ..Default::default()
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct YinOptions {
+    pub frame_opts: FrameOptions,
+    /// Search range of the fundamental frequency in Hz.
+    pub fmin: f32,
+    pub fmax: f32,
+    /// Absolute threshold on the cumulative mean normalized difference: the
+    /// first trough below it gives the period, else the global minimum does.
+    pub trough_threshold: f32,
+}
+
+impl Default for YinOptions {
+    fn default() -> Self {
+        Self {
+            frame_opts: default_frame_opts(),
+            fmin: 65.0,
+            fmax: 2093.0,
+            trough_threshold: 0.1,
+        }
+    }
+}
+
+impl YinOptions {
+    pub fn validate(&self) -> Result<(), KnfError> {
+        check_range(&self.frame_opts, self.fmin, self.fmax)?;
+        if self.trough_threshold.is_nan() || self.trough_threshold < 0.0 {
+            return Err(KnfError::invalid("trough_threshold must be non-negative"));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct PyinOptions {
+    pub frame_opts: FrameOptions,
+    pub fmin: f32,
+    pub fmax: f32,
+    /// Thresholds evenly spaced in (0, 1] with a Beta prior.
+    pub n_thresholds: usize,
+    pub beta_parameters: [f32; 2],
+    /// Prior of the troughs below a threshold, favouring shorter periods.
+    pub boltzmann_parameter: f32,
+    /// Pitch resolution of the HMM states in semitones.
+    pub resolution: f32,
+    /// Maximum pitch change in octaves per second.
+    pub max_transition_rate: f32,
+    /// Probability of switching between voiced and unvoiced per frame.
+    pub switch_prob: f32,
+    /// Probability given to the global minimum when no trough is below a
+    /// threshold.
+    pub no_trough_prob: f32,
+}
+
+impl Default for PyinOptions {
+    fn default() -> Self {
+        Self {
+            frame_opts: default_frame_opts(),
+            fmin: 65.0,
+            fmax: 2093.0,
+            n_thresholds: 100,
+            beta_parameters: [2.0, 18.0],
+            boltzmann_parameter: 2.0,
+            resolution: 0.1,
+            max_transition_rate: 35.92,
+            switch_prob: 0.01,
+            no_trough_prob: 0.01,
+        }
+    }
+}
+
+impl PyinOptions {
+    pub fn validate(&self) -> Result<(), KnfError> {
+        check_range(&self.frame_opts, self.fmin, self.fmax)?;
+        if self.n_thresholds == 0 {
+            return Err(KnfError::invalid("n_thresholds must be positive"));
+        }
+        if self.beta_parameters.iter().any(|&p| p.is_nan() || p < 1.0) {
+            return Err(KnfError::invalid("beta_parameters must be at least 1"));
+        }
+        if self.boltzmann_parameter.is_nan() || self.boltzmann_parameter <= 0.0 {
+            return Err(KnfError::invalid("boltzmann_parameter must be positive"));
+        }
+        if !(self.resolution > 0.0 && self.resolution <= 1.0) {
+            return Err(KnfError::invalid("resolution must be in (0, 1]"));
+        }
+        if self.max_transition_rate.is_nan() || self.max_transition_rate < 0.0 {
+            return Err(KnfError::invalid(
+                "max_transition_rate must be non-negative",
+            ));
+        }
+        for (name, p) in [
+            ("switch_prob", self.switch_prob),
+            ("no_trough_prob", self.no_trough_prob),
+        ] {
+            if !(0.0..=1.0).contains(&p) {
+                return Err(KnfError::invalid(format!("{} must be in [0, 1]", name)));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn check_range(frame_opts: &FrameOptions, fmin: f32, fmax: f32) -> Result<(), KnfError> {
+    frame_opts.validate()?;
+    let sr = frame_opts.samp_freq;
+    if fmin.is_nan() || fmin <= 0.0 || fmax <= fmin || fmax > 0.5 * sr {
+        return Err(KnfError::invalid(format!(
+            "Invalid f0 range [{}, {}] with nyquist {}",
+            fmin,
+            fmax,
+            0.5 * sr
+        )));
+    }
+    let (min_period, max_period) = periods(frame_opts, fmin, fmax);
+    let frame_length = frame_opts.window_size();
+    if max_period + frame_length / 2 + 1 > frame_length {
+        return Err(KnfError::invalid(format!(
+            "Frames of {} samples are too short for fmin {}",
+            frame_length, fmin
+        )));
+    }
+    if max_period < min_period + 2 {
+        return Err(KnfError::invalid(format!(
+            "f0 range [{}, {}] spans too few periods",
+            fmin, fmax
+        )));
+    }
+    Ok(())
+}
+
+fn periods(frame_opts: &FrameOptions, fmin: f32, fmax: f32) -> (usize, usize) {
+    let sr = frame_opts.samp_freq;
+    ((sr / fmax).floor() as usize, (sr / fmin).ceil() as usize)
+}
+
+/// Cumulative mean normalized difference `d'(tau)` of one frame into
+/// `cmnd` for `tau` in `[min_period, diff.len() - 1]`, with `d(tau)` summed
+/// over the first half of the frame. Where the difference function vanishes
+/// (silence) `d'` is 1.
+fn difference(x: &[f32], min_period: usize, diff: &mut [f64], cmnd: &mut Vec<f32>) {
+    let win_length = x.len() / 2;
+    for (tau, d) in diff.iter_mut().enumerate().skip(1) {
+        *d = x[..win_length]
+            .iter()
+            .zip(&x[tau..tau + win_length])
+            .map(|(&a, &b)| {
+                let e = (a - b) as f64;
+                e * e
+            })
+            .sum();
+    }
+    let mut cumulative = 0.0;
+    cmnd.clear();
+    for (tau, &d) in diff.iter().enumerate().skip(1) {
+        cumulative += d;
+        if tau >= min_period {
+            let mean = cumulative / tau as f64;
+            cmnd.push(if mean > 0.0 { (d / mean) as f32 } else { 1.0 });
+        }
+    }
+}
+
+/// [`difference`] of each frame of `wave`.
+fn difference_frames(
+    wave: &[f32],
+    frame_opts: &FrameOptions,
+    min_period: usize,
+    max_period: usize,
+) -> Vec<Vec<f32>> {
+    let frame_length = frame_opts.window_size();
+    let mut frames = FrameIterator::new(wave, frame_opts);
+    let mut out = Vec::with_capacity(frames.num_frames());
+    let mut diff = vec![0.0f64; max_period + 1];
+    while let Some((_, frame, _)) = frames.next_frame() {
+        let mut cmnd = Vec::with_capacity(max_period - min_period + 1);
+        difference(&frame[..frame_length], min_period, &mut diff, &mut cmnd);
+        out.push(cmnd);
+    }
+    out
+}
+
+/// Offset of the vertex of the parabola through `x[i - 1..=i + 1]`, or 0 at
+/// the edges and where the vertex lies outside the neighbours.
+fn parabolic_shift(x: &[f32], i: usize) -> f32 {
+    if i == 0 || i + 1 >= x.len() {
+        return 0.0;
+    }
+    let a = x[i + 1] + x[i - 1] - 2.0 * x[i];
+    let b = 0.5 * (x[i + 1] - x[i - 1]);
+    if b.abs() >= a.abs() {
+        0.0
+    } else {
+        -b / a
+    }
+}
+
+/// Indices of the troughs of `x`: strict decreases followed by a non-increase,
+/// where the first element counts if it is below the second.
+fn troughs(x: &[f32]) -> Vec<usize> {
+    (0..x.len())
+        .filter(|&i| {
+            if i == 0 {
+                x.len() > 1 && x[0] < x[1]
+            } else {
+                x[i] < x[i - 1] && x.get(i + 1).is_none_or(|&next| x[i] <= next)
+            }
+        })
+        .collect()
+}
+
+fn argmin(x: &[f32]) -> usize {
+    x.iter()
+        .enumerate()
+        .fold(0, |best, (i, &v)| if v < x[best] { i } else { best })
+}
+
+/// YIN estimate of one frame from its [`difference`]: f0, whether it is
+/// voiced, and the voicing probability.
+fn yin_estimate(cmnd: &[f32], min_period: usize, opts: &YinOptions) -> (f32, bool, f32) {
+    let below = troughs(cmnd)
+        .into_iter()
+        .find(|&i| cmnd[i] < opts.trough_threshold);
+    let index = below.unwrap_or_else(|| argmin(cmnd));
+    let period = (min_period + index) as f32 + parabolic_shift(cmnd, index);
+    (
+        opts.frame_opts.samp_freq / period,
+        below.is_some(),
+        (1.0 - cmnd[index]).clamp(0.0, 1.0),
+    )
+}
+
+/// YIN estimate of each frame. A frame is voiced when a trough falls below
+/// `trough_threshold`; `voiced_prob` is `1 - d'` at the chosen period,
+/// clamped to [0, 1].
+pub fn yin(wave: &[f32], opts: &YinOptions) -> Result<PitchTrack, KnfError> {
+    opts.validate()?;
+    let (min_period, max_period) = periods(&opts.frame_opts, opts.fmin, opts.fmax);
+    let mut track = PitchTrack::default();
+    for cmnd in difference_frames(wave, &opts.frame_opts, min_period, max_period) {
+        let (f0, voiced, prob) = yin_estimate(&cmnd, min_period, opts);
+        track.f0.push(f0);
+        track.voiced.push(voiced);
+        track.voiced_prob.push(prob);
+    }
+    Ok(track)
+}
+
+/// Frame-by-frame [`yin`] for [`OnlineFeature`](crate::OnlineFeature) and
+/// pipelines: each frame gives `[voiced_prob, ln f0]`.
+pub struct YinComputer {
+    pub opts: YinOptions,
+    min_period: usize,
+    diff: Vec<f64>,
+    cmnd: Vec<f32>,
+}
+
+impl YinComputer {
+    pub fn new(opts: YinOptions) -> Result<Self, KnfError> {
+        opts.validate()?;
+        let (min_period, max_period) = periods(&opts.frame_opts, opts.fmin, opts.fmax);
+        Ok(Self {
+            opts,
+            min_period,
+            diff: vec![0.0; max_period + 1],
+            cmnd: Vec::with_capacity(max_period - min_period + 1),
+        })
+    }
+
+    pub fn dim(&self) -> usize {
+        2
+    }
+
+    pub fn compute(&mut self, signal_frame: &mut [f32], feature: &mut [f32]) {
+        let frame_length = self.opts.frame_opts.window_size();
+        difference(
+            &signal_frame[..frame_length],
+            self.min_period,
+            &mut self.diff,
+            &mut self.cmnd,
+        );
+        let (f0, _, prob) = yin_estimate(&self.cmnd, self.min_period, &self.opts);
+        feature[0] = prob;
+        feature[1] = f0.ln();
+    }
+}
+
+impl Computer for YinComputer {
+    fn dim(&self) -> usize {
+        YinComputer::dim(self)
+    }
+
+    fn frame_opts(&self) -> &FrameOptions {
+        &self.opts.frame_opts
+    }
+
+    fn need_raw_energy(&self) -> bool {
+        false
+    }
+
+    fn compute(
+        &mut self,
+        _raw_log_energy: f32,
+        _vtln_warp: f32,
+        window: &mut [f32],
+        feature: &mut [f32],
+    ) {
+        YinComputer::compute(self, window, feature)
+    }
+}
+
+/// Probability mass of a Beta(a, b) distribution in each of `n` equal bins
+/// of [0, 1], by Simpson's rule.
+fn beta_bin_probs(n: usize, [a, b]: [f32; 2]) -> Vec<f64> {
+    const STEPS: usize = 16;
+    let (a, b) = (a as f64, b as f64);
+    let pdf = |x: f64| x.powf(a - 1.0) * (1.0 - x).powf(b - 1.0);
+    let width = 1.0 / n as f64;
+    let h = width / STEPS as f64;
+    let mut probs: Vec<f64> = (0..n)
+        .map(|k| {
+            let x0 = k as f64 * width;
+            let inner: f64 = (1..STEPS)
+                .map(|s| {
+                    let weight = if s % 2 == 1 { 4.0 } else { 2.0 };
+                    weight * pdf(x0 + s as f64 * h)
+                })
+                .sum();
+            h / 3.0 * (pdf(x0) + inner + pdf(x0 + width))
+        })
+        .collect();
+    let total: f64 = probs.iter().sum();
+    probs.iter_mut().for_each(|p| *p /= total);
+    probs
+}
+
+/// pYIN: every trough below each of a Beta-distributed set of thresholds is
+/// a pitch candidate; Viterbi decoding of an HMM over pitch bins and voicing
+/// picks the track. `voiced_prob` is the total candidate probability of each
+/// frame.
+pub fn pyin(wave: &[f32], opts: &PyinOptions) -> Result<PitchTrack, KnfError> {
+    opts.validate()?;
+    let sr = opts.frame_opts.samp_freq;
+    let (min_period, max_period) = periods(&opts.frame_opts, opts.fmin, opts.fmax);
+    let frames = difference_frames(wave, &opts.frame_opts, min_period, max_period);
+
+    let n_thresholds = opts.n_thresholds;
+    let beta_probs = beta_bin_probs(n_thresholds, opts.beta_parameters);
+    let bins_per_semitone = (1.0 / opts.resolution).ceil() as usize;
+    let bins_per_octave = (12 * bins_per_semitone) as f64;
+    let n_bins =
+        (bins_per_octave * (opts.fmax as f64 / opts.fmin as f64).log2()).floor() as usize + 1;
+    let lambda = opts.boltzmann_parameter as f64;
+    // Boltzmann pmf of position k among n troughs
+    let boltzmann = |k: usize, n: usize| {
+        (1.0 - (-lambda).exp()) * (-lambda * k as f64).exp() / (1.0 - (-lambda * n as f64).exp())
+    };
+
+    // Observation probabilities: voiced pitch bins, then unvoiced copies
+    let mut observations = Vec::with_capacity(frames.len());
+    let mut voiced_prob = Vec::with_capacity(frames.len());
+    for cmnd in &frames {
+        let mut obs = vec![0.0f64; 2 * n_bins];
+        let candidates = troughs(cmnd);
+        if !candidates.is_empty() {
+            let mut probs = vec![0.0f64; candidates.len()];
+            for (k, &beta) in beta_probs.iter().enumerate() {
+                let threshold = (k + 1) as f32 / n_thresholds as f32;
+                let n_below = candidates.iter().filter(|&&i| cmnd[i] < threshold).count();
+                let mut position = 0;
+                for (p, &i) in probs.iter_mut().zip(&candidates) {
+                    if cmnd[i] < threshold {
+                        *p += boltzmann(position, n_below) * beta;
+                        position += 1;
+                    }
+                }
+            }
+            let heights: Vec<f32> = candidates.iter().map(|&i| cmnd[i]).collect();
+            let global_min = argmin(&heights);
+            let n_not_below = (0..n_thresholds)
+                .filter(|&k| heights[global_min] >= (k + 1) as f32 / n_thresholds as f32)
+                .count();
+            probs[global_min] +=
+                opts.no_trough_prob as f64 * beta_probs[..n_not_below].iter().sum::<f64>();
+
+            for (&i, &p) in candidates.iter().zip(&probs) {
+                if p <= 0.0 {
+                    continue;
+                }
+                let period = (min_period + i) as f32 + parabolic_shift(cmnd, i);
+                let f0 = sr / period;
+                let bin = (bins_per_octave * (f0 as f64 / opts.fmin as f64).log2()).round();
+                obs[bin.clamp(0.0, (n_bins - 1) as f64) as usize] += p;
+            }
+        }
+        let voiced: f64 = obs[..n_bins].iter().sum::<f64>().clamp(0.0, 1.0);
+        obs[n_bins..].fill((1.0 - voiced) / n_bins as f64);
+        observations.push(obs);
+        voiced_prob.push(voiced as f32);
+    }
+
+    let hop_seconds = opts.frame_opts.window_shift() as f32 / sr;
+    let max_semitones = (opts.max_transition_rate * 12.0 * hop_seconds).round() as usize;
+    let transition_width = max_semitones * bins_per_semitone + 1;
+    let states = viterbi(
+        &observations,
+        n_bins,
+        transition_width,
+        opts.switch_prob as f64,
+    );
+
+    let freq = |bin: usize| opts.fmin * 2f32.powf(bin as f32 / bins_per_octave as f32);
+    Ok(PitchTrack {
+        f0: states.iter().map(|&s| freq(s % n_bins)).collect(),
+        voiced: states.iter().map(|&s| s < n_bins).collect(),
+        voiced_prob,
+    })
+}
+
+/// Most likely state sequence of the pYIN HMM. Within a voicing half the
+/// pitch moves by at most `width / 2` bins with triangular weights; the
+/// voicing switches with probability `switch_prob`. Decoding starts in a
+/// uniformly distributed unvoiced state.
+fn viterbi(observations: &[Vec<f64>], n_bins: usize, width: usize, switch_prob: f64) -> Vec<usize> {
+    let log = |p: f64| (p + f64::MIN_POSITIVE).ln();
+    let half = width / 2;
+    let scale = (width as f64 + 1.0) / 2.0;
+    // Log transition weights of each source bin, by offset to the target
+    let local: Vec<Vec<f64>> = (0..n_bins)
+        .map(|i| {
+            let lo = i.saturating_sub(half);
+            let hi = (i + half).min(n_bins - 1);
+            let weights: Vec<f64> = (lo..=hi)
+                .map(|j| 1.0 - (j as f64 - i as f64).abs() / scale)
+                .collect();
+            let total: f64 = weights.iter().sum();
+            weights.iter().map(|w| log(w / total)).collect()
+        })
+        .collect();
+    let log_stay = log(1.0 - switch_prob);
+    let log_switch = log(switch_prob);
+
+    let num_states = 2 * n_bins;
+    let mut value: Vec<f64> = (0..num_states)
+        .map(|s| {
+            if s < n_bins {
+                log(0.0)
+            } else {
+                log(1.0 / n_bins as f64)
+            }
+        })
+        .collect();
+    if let Some(first) = observations.first() {
+        for (v, &o) in value.iter_mut().zip(first) {
+            *v += log(o);
+        }
+    }
+    let mut backpointers: Vec<Vec<u32>> = Vec::with_capacity(observations.len());
+    let mut next = vec![0.0f64; num_states];
+    for obs in observations.iter().skip(1) {
+        let mut pointers = vec![0u32; num_states];
+        for (target, (v, ptr)) in next.iter_mut().zip(pointers.iter_mut()).enumerate() {
+            let (voicing, j) = (target / n_bins, target % n_bins);
+            let mut best = f64::NEG_INFINITY;
+            let mut best_state = 0;
+            for i in j.saturating_sub(half)..=(j + half).min(n_bins - 1) {
+                let trans = local[i][j - i.saturating_sub(half)];
+                for from_voicing in 0..2 {
+                    let source = from_voicing * n_bins + i;
+                    let switch = if from_voicing == voicing {
+                        log_stay
+                    } else {
+                        log_switch
+                    };
+                    let score = value[source] + trans + switch;
+                    if score > best {
+                        best = score;
+                        best_state = source;
+                    }
+                }
+            }
+            *v = best + log(obs[target]);
+            *ptr = best_state as u32;
+        }
+        std::mem::swap(&mut value, &mut next);
+        backpointers.push(pointers);
+    }
+
+    if observations.is_empty() {
+        return Vec::new();
+    }
+    let mut states = vec![0usize; observations.len()];
+    let mut state = value
+        .iter()
+        .enumerate()
+        .fold(0, |best, (i, &v)| if v > value[best] { i } else { best });
+    for (t, pointers) in backpointers.iter().enumerate().rev() {
+        states[t + 1] = state;
+        state = pointers[state] as usize;
+    }
+    states[0] = state;
+    states
+}
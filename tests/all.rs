@@ -1,14 +1,43 @@
-use kaldi_native_fbank::fbank::{FbankComputer, FbankOptions};
+#![allow(clippy::field_reassign_with_default, clippy::needless_range_loop)]
+
+use kaldi_native_fbank::align::FrameAligner;
+use kaldi_native_fbank::cfft::{Cfft, Complex};
+use kaldi_native_fbank::channel::feature_channel;
+use kaldi_native_fbank::ComposedComputer;
+use kaldi_native_fbank::{DitherMode, KnfError};
+use kaldi_native_fbank::fbank::{FbankComputer, FbankOptions, MelFloorMode};
+use kaldi_native_fbank::fingerprint;
+use kaldi_native_fbank::{griffin_lim, mel_to_audio, GriffinLimOptions, InverseMelScale};
 use kaldi_native_fbank::istft_compute;
-use kaldi_native_fbank::mel::{MelBanks, MelOptions};
-use kaldi_native_fbank::online::{FeatureComputer, OnlineFeature};
+use kaldi_native_fbank::mel::{FrequencyScale, MelBanks, MelNorm, MelOptions};
+use kaldi_native_fbank::online::{Computer, FeatureComputer, OnlineFeature};
+use kaldi_native_fbank::transform::{
+    apply_frame_taper, apply_frame_weights, taper_weights, CmvnOptions, DeltaOptions,
+    FrameTaperOptions, SpliceOptions, TaperShape,
+};
 use kaldi_native_fbank::rfft::Rfft;
-use kaldi_native_fbank::whisper::{WhisperComputer, WhisperOptions};
-use kaldi_native_fbank::window::{extract_window, FrameOptions, Window};
+use kaldi_native_fbank::utils::{compute_power_spectrum_inplace, inner_product};
+use kaldi_native_fbank::whisper::{
+    whisper_log_mel_normalize, whisper_pad_or_trim, whisper_pad_or_trim_frames, WhisperComputer,
+    WhisperFeatureExtractor, WhisperOptions, WhisperStreamingExtractor, WHISPER_N_FRAMES,
+    WHISPER_N_SAMPLES,
+};
+use kaldi_native_fbank::whisper_window::{WhisperWindow, WhisperWindowOptions};
+use kaldi_native_fbank::window::{
+    extract_window, num_frames, FrameIterator, FrameOptions, Window, WindowType,
+};
 use kaldi_native_fbank::MfccComputer;
 use kaldi_native_fbank::MfccOptions;
 use kaldi_native_fbank::IstftOptions;
 use kaldi_native_fbank::StftOptions;
+use kaldi_native_fbank::{
+    apply_channel_policy, deinterleave, i16_to_f32, ChannelPolicy, MultiChannelFeature, PcmScale,
+};
+use kaldi_native_fbank::{OnlineFeaturePipeline, OnlinePipelineOptions};
+use kaldi_native_fbank::{RawAudioComputer, RawAudioOptions};
+use kaldi_native_fbank::{
+    resample_waveform, ArbitraryResample, LinearResample, StreamingArbitraryResample,
+};
 use rand::Rng;
 use std::f32::consts::PI;
 use kaldi_native_fbank::stft_compute;
@@ -28,7 +57,7 @@ fn test_feature_demo() {
 
     let mut opts = FbankOptions::default();
     opts.frame_opts.dither = 0.0;
-    opts.frame_opts.window_type = "hann".to_string();
+    opts.frame_opts.window_type = WindowType::Hann;
     opts.mel_opts.num_bins = 23;
     opts.use_energy = false;
     opts.raw_energy = false;
@@ -57,7 +86,7 @@ fn test_feature_demo() {
     // Online Compute
     let comp_online = FbankComputer::new(opts.clone()).unwrap();
     let mut online = OnlineFeature::new(FeatureComputer::Fbank(comp_online));
-    online.accept_waveform(sample_rate, &wave);
+    online.accept_waveform(sample_rate, &wave).unwrap();
     online.input_finished();
 
     assert!(online.num_frames_ready() >= frames_to_check);
@@ -130,7 +159,7 @@ fn test_online() {
         wave[i] = (2.0 * PI * freq * (i as f32 / samp_freq)).sin();
     }
 
-    feat.accept_waveform(samp_freq, &wave);
+    feat.accept_waveform(samp_freq, &wave).unwrap();
     feat.input_finished();
 
     let ready = feat.num_frames_ready();
@@ -299,3 +328,4179 @@ fn test_rfft() {
         );
     }
 }
+
+#[test]
+fn test_rfft_odd() {
+    let n = 7;
+    let signal: Vec<f32> = (0..n).map(|i| ((i * i) % 5) as f32 - 1.5).collect();
+    let mut packed = signal.clone();
+    Rfft::new(n, false).compute(&mut packed);
+
+    // [Re0, Re3, Re1, Im1, Re2, Im2, Im3] against a naive DFT
+    let dft = |k: usize| {
+        signal.iter().enumerate().fold((0.0f32, 0.0f32), |(re, im), (t, &x)| {
+            let phi = -2.0 * PI * (k * t) as f32 / n as f32;
+            (re + x * phi.cos(), im + x * phi.sin())
+        })
+    };
+    let expected = [
+        dft(0).0,
+        dft(3).0,
+        dft(1).0,
+        dft(1).1,
+        dft(2).0,
+        dft(2).1,
+        dft(3).1,
+    ];
+    for (a, b) in packed.iter().zip(expected) {
+        assert!((a - b).abs() < 1e-4, "{:?} vs {:?}", packed, expected);
+    }
+
+    Rfft::new(n, true).compute(&mut packed);
+    for (a, b) in packed.iter().zip(&signal) {
+        assert!((a - b * n as f32).abs() < 1e-4);
+    }
+
+    // Odd-sized STFT round trip
+    let wave: Vec<f32> = (0..4000).map(|i| (i as f32 * 0.03).sin()).collect();
+    let mut stft = StftOptions::default();
+    stft.n_fft = 401;
+    stft.win_length = 401;
+    stft.window_type = WindowType::Hann;
+    let res = stft_compute(&stft, &wave).unwrap();
+    let recon = istft_compute(&IstftOptions::from(&stft), &res).unwrap();
+    for (a, b) in recon.iter().zip(&wave).skip(200).take(3500) {
+        assert!((a - b).abs() < 1e-3, "{} vs {}", a, b);
+    }
+}
+
+#[test]
+fn test_cfft() {
+    let n = 12;
+    let signal: Vec<Complex<f32>> = (0..n)
+        .map(|i| Complex::new((i as f32 * 0.7).cos(), (i as f32 * 0.3).sin()))
+        .collect();
+    let mut spectrum = signal.clone();
+    let mut fft = Cfft::new(n, false);
+    fft.compute(&mut spectrum);
+
+    for (k, bin) in spectrum.iter().enumerate() {
+        let expected: Complex<f32> = signal
+            .iter()
+            .enumerate()
+            .map(|(t, x)| x * Complex::from_polar(1.0, -2.0 * PI * (k * t) as f32 / n as f32))
+            .sum();
+        assert!((bin - expected).norm() < 1e-4, "{} vs {}", bin, expected);
+    }
+
+    // Real input agrees with Rfft's packed bins
+    let real: Vec<f32> = signal.iter().map(|x| x.re).collect();
+    let mut packed = real.clone();
+    Rfft::new(n, false).compute(&mut packed);
+    let mut full: Vec<Complex<f32>> = real.iter().map(|&x| Complex::new(x, 0.0)).collect();
+    fft.compute(&mut full);
+    assert!((full[3].re - packed[6]).abs() < 1e-4 && (full[3].im - packed[7]).abs() < 1e-4);
+
+    Cfft::new(n, true).compute(&mut spectrum);
+    for (a, b) in spectrum.iter().zip(&signal) {
+        assert!((a - b * n as f32).norm() < 1e-4);
+    }
+}
+
+#[test]
+fn test_f64_building_blocks() {
+    let opts = FbankOptions::default();
+    let n = opts.frame_opts.padded_window_size();
+    let banks = MelBanks::new(&opts.mel_opts, &opts.frame_opts, 1.0).unwrap();
+
+    let signal: Vec<f64> = (0..n).map(|i| (i as f64 * 0.37).sin() * 1000.0).collect();
+    let mut spectrum64 = signal.clone();
+    Rfft::<f64>::new(n, false).compute(&mut spectrum64);
+    compute_power_spectrum_inplace(&mut spectrum64);
+    let mut mel64 = vec![0.0f64; banks.num_bins];
+    banks.compute_f64(&spectrum64[..n / 2 + 1], &mut mel64);
+
+    let mut spectrum32: Vec<f32> = signal.iter().map(|&x| x as f32).collect();
+    Rfft::new(n, false).compute(&mut spectrum32);
+    compute_power_spectrum_inplace(&mut spectrum32);
+    let mut mel32 = vec![0.0f32; banks.num_bins];
+    banks.compute(&spectrum32[..n / 2 + 1], &mut mel32);
+
+    for (a, b) in mel32.iter().zip(&mel64) {
+        assert!(((*a as f64) - b).abs() <= 1e-3 * b.abs() + 1e-3, "{} vs {}", a, b);
+    }
+    assert_eq!(inner_product(&[1.0f64, 2.0], &[3.0, 4.0]), 11.0);
+
+    let mut complex: Vec<Complex<f64>> = signal.iter().map(|&x| Complex::new(x, 0.0)).collect();
+    Cfft::<f64>::new(n, false).compute(&mut complex);
+    assert!((complex[5].norm_sqr() - spectrum64[5]).abs() < 1e-6 * spectrum64[5]);
+}
+
+#[test]
+fn test_online_pipeline_pitch() {
+    let mut opts = OnlinePipelineOptions::default();
+    opts.fbank_opts.frame_opts.dither = 0.0;
+    opts.fbank_opts.frame_opts.snip_edges = false;
+    opts.fbank_opts.mel_opts.num_bins = 23;
+    opts.fbank_opts.use_energy = false;
+    let mut pitch = kaldi_native_fbank::YinOptions::default();
+    pitch.frame_opts.snip_edges = false;
+    opts.pitch = Some(pitch);
+
+    let wave: Vec<f32> = (0..8000)
+        .map(|i| (2.0 * PI * 440.0 * (i as f32 / 16000.0)).sin())
+        .collect();
+    let mut whole = OnlineFeaturePipeline::new(opts.clone()).unwrap();
+    assert_eq!(whole.dim(), 25);
+    whole.accept_waveform(16000.0, &wave).unwrap();
+    whole.input_finished();
+    assert_eq!(whole.num_frames_ready(), 50);
+    for t in 5..45 {
+        let frame = whole.get_frame(t).unwrap();
+        assert!(frame[23] > 0.9, "frame {}: voiced_prob {}", t, frame[23]);
+        assert!((frame[24] - 440.0f32.ln()).abs() < 0.01, "frame {}", t);
+    }
+
+    let mut chunked = OnlineFeaturePipeline::new(opts.clone()).unwrap();
+    for chunk in wave.chunks(731) {
+        chunked.accept_waveform(16000.0, chunk).unwrap();
+    }
+    chunked.input_finished();
+    assert_eq!(chunked.features, whole.features);
+
+    opts.pitch.as_mut().unwrap().frame_opts.frame_shift_ms = 5.0;
+    assert!(OnlineFeaturePipeline::new(opts).is_err());
+}
+
+#[test]
+fn test_online_pipeline() {
+    let mut opts = OnlinePipelineOptions::default();
+    opts.fbank_opts.frame_opts.dither = 0.0;
+    opts.fbank_opts.mel_opts.num_bins = 23;
+    opts.fbank_opts.use_energy = false;
+    opts.cmvn = Some(CmvnOptions::default());
+    opts.delta = Some(DeltaOptions::default());
+    opts.splice = Some(SpliceOptions {
+        left_context: 1,
+        right_context: 1,
+    });
+    opts.subsampling_factor = 3;
+
+    let n = 8000;
+    let wave: Vec<f32> = (0..n)
+        .map(|i| (2.0 * PI * 440.0 * (i as f32 / 16000.0)).sin())
+        .collect();
+
+    let mut whole = OnlineFeaturePipeline::new(opts.clone()).unwrap();
+    assert_eq!(whole.dim(), 23 * 3 * 3);
+    assert_eq!(whole.latency(), 5);
+    whole.accept_waveform(16000.0, &wave).unwrap();
+    let before_flush = whole.num_frames_ready();
+    whole.input_finished();
+    // 48 base frames, subsampled by 3
+    assert_eq!(whole.num_frames_ready(), 16);
+    assert!(before_flush < whole.num_frames_ready());
+
+    let mut chunked = OnlineFeaturePipeline::new(opts).unwrap();
+    for chunk in wave.chunks(731) {
+        chunked.accept_waveform(16000.0, chunk).unwrap();
+    }
+    chunked.input_finished();
+
+    assert_eq!(chunked.num_frames_ready(), whole.num_frames_ready());
+    for t in 0..whole.num_frames_ready() {
+        let a = whole.get_frame(t).unwrap();
+        let b = chunked.get_frame(t).unwrap();
+        assert_eq!(a.len(), whole.dim());
+        for (x, y) in a.iter().zip(b) {
+            assert!((x - y).abs() < 1e-4, "frame {}: {} vs {}", t, x, y);
+        }
+    }
+}
+
+#[test]
+fn test_fbank_mel_floor() {
+    let mut opts = FbankOptions::default();
+    opts.frame_opts.dither = 0.0;
+    opts.use_energy = false;
+    let padded = opts.frame_opts.padded_window_size();
+
+    let mut comp = FbankComputer::new(opts.clone()).unwrap();
+    let mut feat = vec![0.0; comp.dim()];
+    comp.compute(0.0, 1.0, &mut vec![0.0; padded], &mut feat);
+    for x in &feat {
+        assert!((x - 1e-20f32.ln()).abs() < 1e-3);
+    }
+
+    opts.mel_floor = 1e-6;
+    opts.mel_floor_mode = MelFloorMode::Add;
+    let mut comp = FbankComputer::new(opts.clone()).unwrap();
+    comp.compute(0.0, 1.0, &mut vec![0.0; padded], &mut feat);
+    for x in &feat {
+        assert!((x - 1e-6f32.ln()).abs() < 1e-3);
+    }
+}
+
+struct FrameEnergy {
+    frame_opts: FrameOptions,
+}
+
+impl Computer for FrameEnergy {
+    fn dim(&self) -> usize {
+        1
+    }
+
+    fn frame_opts(&self) -> &FrameOptions {
+        &self.frame_opts
+    }
+
+    fn need_raw_energy(&self) -> bool {
+        true
+    }
+
+    fn compute(&mut self, raw_log_energy: f32, _vtln_warp: f32, _w: &mut [f32], f: &mut [f32]) {
+        f[0] = raw_log_energy;
+    }
+}
+
+#[test]
+fn test_custom_computer() {
+    let mut frame_opts = FrameOptions::default();
+    frame_opts.dither = 0.0;
+    let mut online = OnlineFeature::new(FrameEnergy { frame_opts });
+    online.accept_waveform(16000.0, &vec![0.5; 1600]).unwrap();
+    online.input_finished();
+
+    assert_eq!(online.num_frames_ready(), 8);
+    for t in 0..online.num_frames_ready() {
+        let frame = online.get_frame(t).unwrap();
+        assert_eq!(frame.len(), 1);
+        assert!(frame[0].is_finite());
+    }
+}
+
+#[test]
+fn test_online_matches_offline_no_snip_edges() {
+    let mut opts = FbankOptions::default();
+    opts.frame_opts.dither = 0.0;
+    opts.frame_opts.snip_edges = false;
+
+    // Whisper-style framing: no power-of-two padding, no DC removal or preemphasis
+    let mut whisper_like = opts.clone();
+    whisper_like.frame_opts.window_type = WindowType::Hann;
+    whisper_like.frame_opts.round_to_power_of_two = false;
+    whisper_like.frame_opts.remove_dc_offset = false;
+    whisper_like.frame_opts.preemph_coeff = 0.0;
+
+    let mut rng = rand::thread_rng();
+    for (opts, &n) in [&opts, &whisper_like]
+        .into_iter()
+        .flat_map(|o| [100usize, 1234, 5000, 16000].iter().map(move |n| (o, n)))
+    {
+        let wave: Vec<f32> = (0..n).map(|_| rng.gen::<f32>() * 2.0 - 1.0).collect();
+
+        let mut comp = FbankComputer::new(opts.clone()).unwrap();
+        let win = Window::new(&opts.frame_opts);
+        let total = num_frames(n, &opts.frame_opts, true);
+        let mut window_buf = vec![0.0; opts.frame_opts.padded_window_size()];
+        let mut offline = vec![vec![0.0; comp.dim()]; total];
+        for (frame, feat) in offline.iter_mut().enumerate() {
+            let raw_log_energy =
+                extract_window(0, &wave, frame, &opts.frame_opts, win.as_ref(), &mut window_buf)
+                    .unwrap();
+            comp.compute(raw_log_energy, 1.0, &mut window_buf, feat);
+        }
+
+        for &chunk in &[1usize, 77, 160, 1000] {
+            let comp = FbankComputer::new(opts.clone()).unwrap();
+            let mut online = OnlineFeature::new(comp);
+            for c in wave.chunks(chunk) {
+                online.accept_waveform(16000.0, c).unwrap();
+            }
+            online.input_finished();
+
+            assert_eq!(online.num_frames_ready(), total);
+            for t in 0..total {
+                assert_eq!(
+                    online.get_frame(t).unwrap(),
+                    &offline[t][..],
+                    "n {} chunk {} frame {}",
+                    n,
+                    chunk,
+                    t
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_fingerprint() {
+    let mut opts = FbankOptions::default();
+    opts.frame_opts.dither = 0.0;
+
+    let wave: Vec<f32> = (0..4000)
+        .map(|i| (2.0 * PI * 440.0 * (i as f32 / 16000.0)).sin())
+        .collect();
+    let extract = |opts: &FbankOptions| {
+        let mut online = OnlineFeature::new(FbankComputer::new(opts.clone()).unwrap());
+        online.accept_waveform(16000.0, &wave).unwrap();
+        online.input_finished();
+        online.features
+    };
+
+    let a = extract(&opts);
+    let b = extract(&opts);
+    let q = fingerprint::DEFAULT_QUANTIZATION;
+    assert_eq!(
+        fingerprint::features_fingerprint(&a, q),
+        fingerprint::features_fingerprint(&b, q)
+    );
+    assert_eq!(
+        fingerprint::utterance_fingerprint(&a, &opts, q),
+        fingerprint::utterance_fingerprint(&b, &opts, q)
+    );
+
+    let mut other = opts.clone();
+    other.mel_opts.num_bins = 40;
+    assert_ne!(
+        fingerprint::options_fingerprint(&opts),
+        fingerprint::options_fingerprint(&other)
+    );
+    assert_ne!(
+        fingerprint::utterance_fingerprint(&a, &opts, q),
+        fingerprint::utterance_fingerprint(&a, &other, q)
+    );
+
+    let mut c = a.clone();
+    c[3][5] += 0.5;
+    assert_ne!(
+        fingerprint::features_fingerprint(&a, q),
+        fingerprint::features_fingerprint(&c, q)
+    );
+    assert_ne!(
+        fingerprint::features_fingerprint(&a, q),
+        fingerprint::features_fingerprint(&a[..a.len() - 1], q)
+    );
+}
+
+#[test]
+fn test_whisper_online() {
+    let opts = WhisperOptions::default();
+    let samp_freq = opts.frame_opts.samp_freq;
+    let n = 4000;
+    let wave: Vec<f32> = (0..n)
+        .map(|i| (2.0 * PI * 300.0 * i as f32 / samp_freq).sin())
+        .collect();
+
+    let mut comp = WhisperComputer::new(opts.clone()).unwrap();
+    let win = Window::new(&opts.frame_opts);
+    let total = num_frames(n, &opts.frame_opts, true);
+    let mut window_buf = vec![0.0; opts.frame_opts.padded_window_size()];
+
+    let mut online = OnlineFeature::new(FeatureComputer::Whisper(
+        WhisperComputer::new(opts.clone()).unwrap(),
+    ));
+    for chunk in wave.chunks(333) {
+        online.accept_waveform(samp_freq, chunk).unwrap();
+    }
+    assert!(online.num_frames_ready() < total);
+    online.input_finished();
+    assert_eq!(online.num_frames_ready(), total);
+
+    for t in 0..total {
+        let mut expected = vec![0.0; opts.dim];
+        extract_window(0, &wave, t, &opts.frame_opts, win.as_ref(), &mut window_buf).unwrap();
+        comp.compute(0.0, 1.0, &mut window_buf, &mut expected);
+
+        let frame = online.get_frame(t).unwrap();
+        assert_eq!(frame.len(), opts.dim);
+        for (a, b) in frame.iter().zip(expected.iter()) {
+            assert!((a - b).abs() <= 1e-4 * b.abs().max(1.0));
+        }
+    }
+}
+
+#[test]
+fn test_raw_audio_online() {
+    let mut opts = RawAudioOptions::default();
+    opts.frame_opts.dither = 0.0;
+    opts.frame_opts.preemph_coeff = 0.0;
+    opts.frame_opts.remove_dc_offset = false;
+    opts.frame_opts.window_type = WindowType::Rectangular;
+
+    let wave: Vec<f32> = (0..2000).map(|i| i as f32).collect();
+    let mut online = OnlineFeature::new(FeatureComputer::Raw(RawAudioComputer::new(opts.clone())));
+    for chunk in wave.chunks(250) {
+        online.accept_waveform(16000.0, chunk).unwrap();
+    }
+    online.input_finished();
+
+    let shift = opts.frame_opts.window_shift();
+    let size = opts.frame_opts.window_size();
+    assert_eq!(online.num_frames_ready(), 1 + (2000 - size) / shift);
+    for t in 0..online.num_frames_ready() {
+        let frame = online.get_frame(t).unwrap();
+        assert_eq!(frame.len(), opts.frame_opts.padded_window_size());
+        assert_eq!(&frame[..size], &wave[t * shift..t * shift + size]);
+        assert!(frame[size..].iter().all(|&x| x == 0.0));
+    }
+}
+
+#[test]
+fn test_frame_aligner() {
+    let mut opts = FrameOptions::default();
+    opts.snip_edges = false;
+
+    let aligner = FrameAligner::new(opts.clone(), 1).unwrap();
+    assert_eq!(aligner.frame_span(0), 0..160);
+    assert_eq!(aligner.frame_span(3), 480..640);
+    assert_eq!(aligner.frame_window(0), -120..280);
+    assert_eq!(aligner.sample_to_frame(159), 0);
+    assert_eq!(aligner.sample_to_frame(160), 1);
+    assert_eq!(aligner.samples_to_frames(100..500), 0..4);
+    assert_eq!(aligner.time_to_frame(1.0), 100);
+    assert_eq!(aligner.frame_to_time(100), 1.0..1.01);
+    assert_eq!(aligner.times_to_frames(0.5..1.0), 50..100);
+    assert_eq!(aligner.num_frames(16000, true), 100);
+
+    let sub = FrameAligner::new(opts.clone(), 4).unwrap();
+    assert_eq!(sub.frame_shift(), 640);
+    assert_eq!(sub.frame_span(1), 640..1280);
+    assert_eq!(sub.frame_window(1), 520..1400);
+    assert_eq!(sub.sample_to_frame(1279), 1);
+    assert_eq!(sub.num_frames(16000, true), 25);
+    assert_eq!(sub.time_to_frame(0.16), 4);
+
+    assert!(matches!(
+        FrameAligner::new(opts.clone(), 0),
+        Err(KnfError::InvalidOptions(_))
+    ));
+
+    opts.snip_edges = true;
+    let snip = FrameAligner::new(opts, 1).unwrap();
+    assert_eq!(snip.frame_window(2), 320..720);
+    assert_eq!(snip.frame_span(0), 120..280);
+    assert_eq!(snip.sample_to_frame(0), 0);
+    assert_eq!(snip.sample_to_frame(280), 1);
+    for frame in 0..50 {
+        let span = snip.frame_span(frame);
+        assert_eq!(snip.sample_to_frame(span.start as usize), frame);
+        assert_eq!(snip.sample_to_frame(span.end as usize - 1), frame);
+    }
+}
+
+#[test]
+fn test_composed_computer() {
+    let mut fopts = FbankOptions::default();
+    fopts.frame_opts.dither = 0.0;
+    let mut mopts = MfccOptions::default();
+    mopts.frame_opts.dither = 0.0;
+
+    let composed = ComposedComputer::new(vec![
+        Box::new(FbankComputer::new(fopts.clone()).unwrap()),
+        Box::new(MfccComputer::new(mopts.clone()).unwrap()),
+    ])
+    .unwrap();
+    assert_eq!(composed.dim(), 26 + 13);
+
+    let wave: Vec<f32> = (0..3200)
+        .map(|i| (2.0 * PI * 440.0 * (i as f32 / 16000.0)).sin())
+        .collect();
+    let run = |c: Box<dyn Computer>| {
+        let mut online = OnlineFeature::new(c);
+        online.accept_waveform(16000.0, &wave).unwrap();
+        online.input_finished();
+        online.features
+    };
+
+    let both = run(Box::new(composed));
+    let fbank = run(Box::new(FbankComputer::new(fopts.clone()).unwrap()));
+    let mfcc = run(Box::new(MfccComputer::new(mopts).unwrap()));
+    assert_eq!(both.len(), fbank.len());
+    for t in 0..both.len() {
+        let expected: Vec<f32> = fbank[t].iter().chain(mfcc[t].iter()).copied().collect();
+        for (a, b) in both[t].iter().zip(expected.iter()) {
+            assert!((a - b).abs() < 1e-4);
+        }
+    }
+
+    let mut other = fopts.clone();
+    other.frame_opts.frame_shift_ms = 20.0;
+    assert!(ComposedComputer::new(vec![
+        Box::new(FbankComputer::new(fopts).unwrap()),
+        Box::new(FbankComputer::new(other).unwrap()),
+    ])
+    .is_err());
+    assert!(ComposedComputer::new(vec![]).is_err());
+}
+
+#[test]
+fn test_frame_taper() {
+    let mut opts = FrameTaperOptions::default();
+    opts.fade_in_frames = 3;
+    opts.fade_out_frames = 1;
+    let w = taper_weights(6, &opts);
+    assert_eq!(w, vec![0.25, 0.5, 0.75, 1.0, 1.0, 0.5]);
+
+    // Overlapping ramps take the smaller weight
+    let w = taper_weights(2, &opts);
+    assert_eq!(w, vec![0.25, 0.5]);
+
+    opts.shape = TaperShape::Hann;
+    let w = taper_weights(6, &opts);
+    assert!((w[1] - 0.5).abs() < 1e-6);
+    assert!(w[0] < w[1] && w[1] < w[2] && w[2] < 1.0);
+
+    let mut frames = vec![vec![2.0, 4.0]; 6];
+    opts.shape = TaperShape::Linear;
+    opts.coefficient = Some(0);
+    apply_frame_taper(&mut frames, &opts).unwrap();
+    assert_eq!(frames[0], vec![0.5, 4.0]);
+    assert_eq!(frames[5], vec![1.0, 4.0]);
+
+    // Bad sizes are rejected before any frame is modified
+    let mut frames = vec![vec![2.0, 4.0]; 3];
+    assert!(matches!(
+        apply_frame_weights(&mut frames, &[1.0, 0.5], None),
+        Err(KnfError::SizeMismatch { what: "frame weights", expected: 3, actual: 2 })
+    ));
+    frames[2].pop();
+    opts.coefficient = Some(1);
+    assert!(matches!(
+        apply_frame_taper(&mut frames, &opts),
+        Err(KnfError::SizeMismatch { what: "frame dim", expected: 2, actual: 1 })
+    ));
+    assert_eq!(frames, vec![vec![2.0, 4.0], vec![2.0, 4.0], vec![2.0]]);
+}
+
+#[test]
+fn test_librosa_slaney_mel_banks() {
+    // Whisper / librosa.filters.mel(sr=16000, n_fft=400, n_mels=80) edges, before normalization
+    let opts = WhisperOptions::default();
+    let mut mopts = MelOptions::default();
+    mopts.num_bins = 80;
+    mopts.low_freq = 0.0;
+    mopts.is_librosa = true;
+    mopts.use_slaney_mel_scale = true;
+    mopts.norm = MelNorm::None;
+
+    let banks = MelBanks::new(&mopts, &opts.frame_opts, 1.0).unwrap();
+    assert_eq!(banks.num_fft_bins, 200);
+    let w = |bin: usize, fft_bin: usize| banks.weight(bin, fft_bin);
+
+    // Slaney scale is linear below 1 kHz: first edges are 0, 37.24 and 74.48 Hz
+    assert!((w(0, 1) - 0.925_863_4).abs() < 1e-5);
+    assert_eq!(w(0, 2), 0.0);
+    assert!((w(79, 192) - 0.935_896_7).abs() < 1e-5);
+    assert!((w(79, 199) - 0.132_711).abs() < 1e-5);
+
+    // HTK-style (Kaldi default) banks differ from the librosa ones
+    mopts.is_librosa = false;
+    let kaldi = MelBanks::new(&mopts, &opts.frame_opts, 1.0).unwrap();
+    assert_ne!(kaldi.to_dense(), banks.to_dense());
+}
+
+#[test]
+fn test_whisper_window() {
+    let mut opts = WhisperWindowOptions::default();
+    opts.overlap_s = 5.0;
+    let mut win = WhisperWindow::new(opts).unwrap();
+    assert_eq!(win.window_samples(), 480000);
+    assert_eq!(win.num_frames(), 3000);
+
+    let tone: Vec<f32> = (0..16000 * 10)
+        .map(|i| 0.5 * (2.0 * PI * 440.0 * i as f32 / 16000.0).sin())
+        .collect();
+    win.accept_waveform(&tone);
+    assert!(!win.is_full());
+
+    let mel = win.log_mel();
+    assert_eq!(mel.len(), 80 * 3000);
+    let max = mel.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    for &x in &mel {
+        assert!(x.is_finite() && x >= max - 2.0 - 1e-6);
+    }
+    // The zero-padded tail (after 10 s) is clamped to the floor
+    let at = |m: usize, t: usize| mel[m * 3000 + t];
+    assert!((at(10, 2500) - (max - 2.0)).abs() < 1e-6);
+    assert!(at(11, 500) > at(60, 500) + 1.0);
+
+    for _ in 0..3 {
+        win.accept_waveform(&tone);
+    }
+    assert!(win.is_full());
+    win.advance();
+    assert_eq!(win.window_offset(), 25 * 16000);
+    assert_eq!(win.num_samples_buffered(), 15 * 16000);
+}
+
+#[test]
+fn test_fbank_mean_log_energy_subtraction() {
+    let mut opts = FbankOptions::default();
+    opts.frame_opts.dither = 0.0;
+    opts.subtract_mean_log_energy = true;
+
+    let mut rng = rand::thread_rng();
+    let wave: Vec<f32> = (0..8000)
+        .map(|i| (rng.gen::<f32>() - 0.5) * (1.0 + i as f32 / 8000.0))
+        .collect();
+    let loud: Vec<f32> = wave.iter().map(|x| x * 10.0).collect();
+
+    for window in [0, 5] {
+        opts.log_energy_norm_window = window;
+        let extract = |w: &[f32]| {
+            let mut online = OnlineFeature::new(FbankComputer::new(opts.clone()).unwrap());
+            online.accept_waveform(16000.0, w).unwrap();
+            online.input_finished();
+            online.features
+        };
+        let a = extract(&wave);
+        let b = extract(&loud);
+        for (fa, fb) in a.iter().zip(b.iter()) {
+            for (x, y) in fa.iter().zip(fb.iter()) {
+                assert!((x - y).abs() < 1e-3, "{} vs {}", x, y);
+            }
+        }
+    }
+
+    // Without normalization the gain shows up as a constant offset
+    opts.subtract_mean_log_energy = false;
+    let mut comp = FbankComputer::new(opts.clone()).unwrap();
+    let padded = opts.frame_opts.padded_window_size();
+    let (mut fa, mut fb) = (vec![0.0; comp.dim()], vec![0.0; comp.dim()]);
+    comp.compute(0.0, 1.0, &mut wave[..padded].to_vec(), &mut fa);
+    comp.compute(0.0, 1.0, &mut loud[..padded].to_vec(), &mut fb);
+    assert!((fb[5] - fa[5] - 2.0 * 10f32.ln()).abs() < 1e-3);
+}
+
+#[test]
+fn test_librosa_slaney_norm() {
+    let opts = WhisperOptions::default();
+    let mut mopts = MelOptions::default();
+    mopts.num_bins = 80;
+    mopts.low_freq = 0.0;
+    mopts.is_librosa = true;
+    mopts.norm = MelNorm::Slaney;
+
+    let banks = MelBanks::new(&mopts, &opts.frame_opts, 1.0).unwrap();
+    // 0.9258634 * 2 / (74.478 Hz - 0 Hz), the first entry of Whisper's mel_80 filters
+    assert!((banks.weight(0, 1) - 0.024_862_3).abs() < 1e-6);
+
+    mopts.norm = MelNorm::None;
+    let plain = MelBanks::new(&mopts, &opts.frame_opts, 1.0).unwrap();
+    assert!((plain.weight(0, 1) - 0.925_863_4).abs() < 1e-5);
+}
+
+#[test]
+fn test_explicit_n_fft() {
+    let mut opts = FbankOptions::default();
+    opts.frame_opts.dither = 0.0;
+    opts.frame_opts.n_fft = 1024;
+    assert_eq!(opts.frame_opts.window_size(), 400);
+    assert_eq!(opts.frame_opts.padded_window_size(), 1024);
+
+    let banks = MelBanks::new(&opts.mel_opts, &opts.frame_opts, 1.0).unwrap();
+    assert_eq!(banks.num_fft_bins, 512);
+
+    let mut online = OnlineFeature::new(FbankComputer::new(opts.clone()).unwrap());
+    online.accept_waveform(16000.0, &vec![0.1; 3200]).unwrap();
+    online.input_finished();
+    assert_eq!(online.num_frames_ready(), 18);
+
+    let mut mopts = MfccOptions::default();
+    mopts.frame_opts.n_fft = 600; // not a power of two
+    mopts.frame_opts.dither = 0.0;
+    let mut mfcc = MfccComputer::new(mopts.clone()).unwrap();
+    let mut frame = vec![0.0; 600];
+    frame[..400].fill(0.3);
+    let mut feat = vec![0.0; mfcc.dim()];
+    mfcc.compute(0.0, 1.0, &mut frame, &mut feat);
+    assert!(feat.iter().all(|x| x.is_finite()));
+
+    opts.frame_opts.n_fft = 256;
+    assert!(FbankComputer::new(opts).is_err());
+}
+
+#[test]
+fn test_htk_mel_formula() {
+    let fopts = FrameOptions::default();
+    let mut mopts = MelOptions::default();
+    mopts.num_bins = 23;
+    let kaldi = MelBanks::new(&mopts, &fopts, 1.0).unwrap();
+
+    mopts.use_htk_mel_formula = true;
+    let htk = MelBanks::new(&mopts, &fopts, 1.0).unwrap();
+    let warped = MelBanks::new(&mopts, &fopts, 0.9).unwrap();
+
+    // 2595 * log10(x) and 1127 * ln(x) differ only by ~1e-5 relative
+    let max_diff = kaldi
+        .to_dense()
+        .iter()
+        .zip(htk.to_dense().iter())
+        .map(|(a, b)| (a - b).abs())
+        .fold(0.0f32, f32::max);
+    assert!(max_diff > 0.0 && max_diff < 1e-2, "max diff {}", max_diff);
+    assert_ne!(htk.to_dense(), warped.to_dense());
+}
+
+#[test]
+fn test_feature_channel_backpressure() {
+    let mut opts = FbankOptions::default();
+    opts.frame_opts.dither = 0.0;
+    let wave: Vec<f32> = (0..16000).map(|i| (i as f32 * 0.05).sin()).collect();
+
+    let mut offline = OnlineFeature::new(FbankComputer::new(opts.clone()).unwrap());
+    offline.accept_waveform(16000.0, &wave).unwrap();
+    offline.input_finished();
+
+    let online = OnlineFeature::new(FbankComputer::new(opts).unwrap());
+    let (mut producer, consumer) = feature_channel(online, 4);
+
+    // Without a consumer, only `max_pending` frames are computed.
+    assert_eq!(producer.accept_waveform(16000.0, &wave[..8000]).unwrap(), 4);
+    assert!(producer.is_backpressured());
+    assert_eq!(producer.feature().num_frames_ready(), 4);
+    assert!(producer.feature().num_frames_pending() > 0);
+
+    let handle = std::thread::spawn(move || {
+        let mut frames = Vec::new();
+        while let Some(frame) = consumer.recv() {
+            frames.push(frame);
+        }
+        frames
+    });
+
+    producer.accept_waveform(16000.0, &wave[8000..]).unwrap();
+    producer.input_finished();
+    while producer.feature().num_frames_pending() > 0 || !producer.feature().is_input_finished() {
+        producer.wait_for_capacity();
+    }
+    drop(producer);
+
+    let frames = handle.join().unwrap();
+    assert_eq!(frames.len(), offline.num_frames_ready());
+    for (t, frame) in frames.iter().enumerate() {
+        assert_eq!(frame.as_slice(), offline.get_frame(t).unwrap());
+    }
+}
+
+#[test]
+fn test_feature_channel_consumer_dropped() {
+    let mut opts = FbankOptions::default();
+    opts.frame_opts.dither = 0.0;
+    let wave: Vec<f32> = (0..16000).map(|i| (i as f32 * 0.05).sin()).collect();
+
+    let online = OnlineFeature::new(FbankComputer::new(opts).unwrap());
+    let (mut producer, consumer) = feature_channel(online, 2);
+    assert_eq!(producer.accept_waveform(16000.0, &wave).unwrap(), 2);
+    // Sent frames are released from the inner feature.
+    assert_eq!(producer.feature().first_frame(), 2);
+    assert!(producer.feature().get_frame(0).is_none());
+
+    let handle = std::thread::spawn(move || {
+        let sent = producer.wait_for_capacity();
+        (sent, producer.is_consumer_gone())
+    });
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    drop(consumer);
+    assert_eq!(handle.join().unwrap(), (0, true));
+}
+
+#[test]
+fn test_mel_htk_mode() {
+    let frame_opts = FrameOptions::default();
+    let mut mel_opts = MelOptions::default();
+    let plain = MelBanks::new(&mel_opts, &frame_opts, 1.0).unwrap();
+    mel_opts.htk_mode = true;
+    let htk = MelBanks::new(&mel_opts, &frame_opts, 1.0).unwrap();
+
+    // Only the lowest nonzero weight of the first bin differs.
+    let n = plain.num_fft_bins;
+    let (plain_dense, htk_dense) = (plain.to_dense(), htk.to_dense());
+    let first = plain_dense[..n].iter().position(|&w| w > 0.0).unwrap();
+    assert_eq!(htk_dense[first], 0.0);
+    for (i, (a, b)) in plain_dense.iter().zip(&htk_dense).enumerate() {
+        if i != first {
+            assert_eq!(a, b);
+        }
+    }
+
+    // Mel energies are floored at 1.0, so log-mels are non-negative.
+    let silence = vec![0.0; n + 1];
+    let mut out = vec![0.0; mel_opts.num_bins];
+    htk.compute(&silence, &mut out);
+    assert!(out.iter().all(|&x| x == 1.0));
+
+    // The frame energy is left alone, as in Kaldi.
+    let mut opts = FbankOptions::default();
+    opts.frame_opts.dither = 0.0;
+    opts.mel_opts.htk_mode = true;
+    opts.use_energy = true;
+    let mut fbank = FbankComputer::new(opts.clone()).unwrap();
+    let mut frame = vec![0.0; opts.frame_opts.padded_window_size()];
+    let mut feature = vec![1.0; fbank.dim()];
+    fbank.compute(-50.0, 1.0, &mut frame, &mut feature);
+    assert_eq!(feature[0], -50.0);
+    assert!(feature[1..].iter().all(|&x| x == 0.0));
+}
+
+#[test]
+fn test_sparse_mel_banks() {
+    let mut fopts = FrameOptions::default();
+    fopts.dither = 0.0;
+    let mut mopts = MelOptions::default();
+    mopts.num_bins = 80;
+    let banks = MelBanks::new(&mopts, &fopts, 1.0).unwrap();
+
+    let dense = banks.to_dense();
+    let mut stored = 0;
+    for (bin, (first, weights)) in banks.bins.iter().enumerate() {
+        assert!(weights[0] > 0.0 && *weights.last().unwrap() > 0.0);
+        assert!(first + weights.len() <= banks.num_fft_bins);
+        for fft_bin in 0..banks.num_fft_bins {
+            assert_eq!(banks.weight(bin, fft_bin), dense[bin * banks.num_fft_bins + fft_bin]);
+        }
+        stored += weights.len();
+    }
+    assert!(stored * 10 < dense.len());
+
+    // Sparse compute matches a dense matrix-vector product
+    let mut rng = rand::thread_rng();
+    let power: Vec<f32> = (0..=banks.num_fft_bins).map(|_| rng.gen::<f32>()).collect();
+    let mut out = vec![0.0; mopts.num_bins];
+    banks.compute(&power, &mut out);
+    for (bin, &x) in out.iter().enumerate() {
+        let row = &dense[bin * banks.num_fft_bins..(bin + 1) * banks.num_fft_bins];
+        let expected: f32 = row.iter().zip(&power).map(|(w, p)| w * p).sum();
+        assert!((x - expected).abs() <= 1e-5 * expected.abs().max(1.0));
+    }
+}
+
+#[test]
+fn test_mel_bin_edges() {
+    let fopts = FrameOptions::default();
+    let mut mopts = MelOptions::default();
+    mopts.num_bins = 23;
+    let banks = MelBanks::new(&mopts, &fopts, 1.0).unwrap();
+
+    assert_eq!(banks.edges_hz.len(), 23);
+    assert!((banks.edges_hz[0][0] - 20.0).abs() < 1e-2);
+    assert!((banks.edges_hz[22][2] - 8000.0).abs() < 1e-1);
+    let centers = banks.center_freqs();
+    for bin in 0..23 {
+        let [l, c, r] = banks.edges_hz[bin];
+        assert!(l < c && c < r);
+        assert_eq!(centers[bin], c);
+        // Neighbouring triangles share edges, evenly spaced in mel
+        if bin > 0 {
+            assert_eq!(banks.edges_mel[bin][0], banks.edges_mel[bin - 1][1]);
+        }
+        let [ml, mc, mr] = banks.edges_mel[bin];
+        assert!(((mc - ml) - (mr - mc)).abs() < 1e-3);
+        // The peak of each triangle is at the FFT bin nearest its center
+        let peak = (0..banks.num_fft_bins)
+            .max_by(|&a, &b| banks.weight(bin, a).total_cmp(&banks.weight(bin, b)))
+            .unwrap();
+        let bin_width = fopts.samp_freq / fopts.padded_window_size() as f32;
+        assert!((peak as f32 * bin_width - c).abs() <= bin_width);
+    }
+
+    mopts.is_librosa = true;
+    mopts.low_freq = 0.0;
+    let librosa = MelBanks::new(&mopts, &fopts, 1.0).unwrap();
+    assert_eq!(librosa.edges_hz[0][0], 0.0);
+    // Slaney mel is linear (3 mel per 200 Hz) below 1 kHz
+    assert!((librosa.edges_mel[0][1] - librosa.edges_hz[0][1] * 3.0 / 200.0).abs() < 1e-4);
+}
+
+#[test]
+fn test_mel_banks_from_weights() {
+    let opts = WhisperOptions::default();
+    let mut mopts = MelOptions::default();
+    mopts.num_bins = 80;
+    mopts.low_freq = 0.0;
+    mopts.is_librosa = true;
+    let banks = MelBanks::new(&mopts, &opts.frame_opts, 1.0).unwrap();
+
+    // Whisper's filters have n_fft / 2 + 1 columns, with a zero Nyquist column
+    let cols = banks.num_fft_bins + 1;
+    let mut weights = vec![0.0; 80 * cols];
+    for bin in 0..80 {
+        for k in 0..banks.num_fft_bins {
+            weights[bin * cols + k] = banks.weight(bin, k);
+        }
+    }
+    let loaded = MelBanks::from_weights(&weights, 80, &opts.frame_opts).unwrap();
+    assert_eq!(loaded.to_dense(), banks.to_dense());
+    assert!(loaded.edges_hz.is_empty());
+
+    let wave: Vec<f32> = (0..4000).map(|i| (i as f32 * 0.3).sin()).collect();
+    let features = |computer: WhisperComputer| {
+        let mut online = OnlineFeature::new(computer);
+        online.accept_waveform(16000.0, &wave).unwrap();
+        online.input_finished();
+        online.features.clone()
+    };
+    let reference = features(WhisperComputer::new(opts.clone()).unwrap());
+    let custom = features(WhisperComputer::with_mel_banks(opts.clone(), loaded).unwrap());
+    assert_eq!(reference, custom);
+
+    weights[cols - 1] = 1.0;
+    assert!(MelBanks::from_weights(&weights, 80, &opts.frame_opts).is_err());
+    assert!(MelBanks::from_weights(&weights[..80 * 100], 80, &opts.frame_opts).is_err());
+    let mut opts128 = opts.clone();
+    opts128.dim = 128;
+    assert!(WhisperComputer::with_mel_banks(opts128, banks).is_err());
+}
+
+#[cfg(feature = "npz")]
+#[test]
+fn test_mel_banks_from_npz() {
+    let opts = WhisperOptions::default();
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/mel_filters.npz");
+    let loaded = MelBanks::from_npz(path, "mel_80", &opts.frame_opts).unwrap();
+    assert_eq!(loaded.num_bins, 80);
+    assert!(MelBanks::from_npz(path, "mel_128", &opts.frame_opts).is_err());
+
+    let mut mopts = MelOptions::default();
+    mopts.num_bins = 80;
+    mopts.low_freq = 0.0;
+    mopts.is_librosa = true;
+    let computed = MelBanks::new(&mopts, &opts.frame_opts, 1.0).unwrap();
+    for (a, b) in loaded.to_dense().iter().zip(computed.to_dense()) {
+        assert!((a - b).abs() < 1e-7, "{} vs {}", a, b);
+    }
+}
+
+#[test]
+fn test_inverse_mel_scale() {
+    let fopts = FrameOptions::default();
+    let mut mopts = MelOptions::default();
+    mopts.num_bins = 40;
+    let banks = MelBanks::new(&mopts, &fopts, 1.0).unwrap();
+    let inverse = InverseMelScale::new(&banks, 0.0).unwrap();
+
+    use rand::{rngs::StdRng, SeedableRng};
+    let mut rng = StdRng::seed_from_u64(7);
+    let power: Vec<f32> = (0..=banks.num_fft_bins).map(|_| rng.gen::<f32>()).collect();
+    let mut mel = vec![0.0; 40];
+    banks.compute(&power, &mut mel);
+
+    let mut linear = vec![0.0; banks.num_fft_bins + 1];
+    inverse.compute(&mel, &mut linear);
+    assert!(linear.iter().all(|&x| x >= 0.0));
+    assert_eq!(linear[banks.num_fft_bins], 0.0);
+
+    // Projecting the reconstruction back gives (nearly) the same mel energies
+    let mut remel = vec![0.0; 40];
+    banks.compute(&linear, &mut remel);
+    for (a, b) in mel.iter().zip(&remel) {
+        assert!((a - b).abs() < 0.05 * a, "{} vs {}", a, b);
+    }
+
+    // Too many bins for a 512-point FFT leaves empty filters
+    mopts.num_bins = 200;
+    let sparse = MelBanks::new(&mopts, &fopts, 1.0).unwrap();
+    assert!(InverseMelScale::new(&sparse, 0.0).is_err());
+    assert!(InverseMelScale::new(&sparse, 1e-3).is_ok());
+}
+
+#[test]
+fn test_mel_to_audio() {
+    let mut stft_opts = StftOptions::default();
+    stft_opts.window_type = WindowType::Hann;
+    stft_opts.n_fft = 512;
+    stft_opts.win_length = 512;
+    stft_opts.hop_length = 128;
+    let bins = 257;
+
+    let wave: Vec<f32> = (0..8000)
+        .map(|i| (2.0 * PI * 440.0 * i as f32 / 16000.0).sin())
+        .collect();
+    let stft = stft_compute(&stft_opts, &wave).unwrap();
+    let magnitudes: Vec<Vec<f32>> = (0..stft.num_frames)
+        .map(|t| {
+            (0..bins)
+                .map(|k| stft.real[t * bins + k].hypot(stft.imag[t * bins + k]))
+                .collect()
+        })
+        .collect();
+
+    let spectral_error = |audio: &[f32]| {
+        let rebuilt = stft_compute(&stft_opts, audio).unwrap();
+        assert_eq!(rebuilt.num_frames, stft.num_frames);
+        let (mut err, mut total) = (0.0f32, 0.0f32);
+        for t in 0..stft.num_frames {
+            for k in 0..bins {
+                let m = rebuilt.real[t * bins + k].hypot(rebuilt.imag[t * bins + k]);
+                err += (m - magnitudes[t][k]).powi(2);
+                total += magnitudes[t][k].powi(2);
+            }
+        }
+        (err / total).sqrt()
+    };
+
+    let mut gl_opts = GriffinLimOptions::default();
+    gl_opts.random_init = false;
+    let audio = griffin_lim(&magnitudes, &stft_opts, &gl_opts).unwrap();
+    assert_eq!(audio.len(), (stft.num_frames - 1) * 128);
+    let error = spectral_error(&audio);
+    gl_opts.n_iter = 0;
+    let initial = spectral_error(&griffin_lim(&magnitudes, &stft_opts, &gl_opts).unwrap());
+    assert!(error < 0.2 && error < 0.5 * initial, "{} vs {}", error, initial);
+
+    // Power mel spectrogram -> audio keeps the tone
+    let mut mel_opts = MelOptions::default();
+    mel_opts.num_bins = 128;
+    mel_opts.low_freq = 0.0;
+    mel_opts.is_librosa = true;
+    let frame_opts = FrameOptions {
+        n_fft: 512,
+        ..Default::default()
+    };
+    let banks = MelBanks::new(&mel_opts, &frame_opts, 1.0).unwrap();
+    let mel: Vec<Vec<f32>> = magnitudes
+        .iter()
+        .map(|m| {
+            let power: Vec<f32> = m.iter().map(|x| x * x).collect();
+            let mut out = vec![0.0; 128];
+            banks.compute(&power, &mut out);
+            out
+        })
+        .collect();
+    let audio = mel_to_audio(&mel, &mel_opts, &stft_opts).unwrap();
+    let rebuilt = stft_compute(&stft_opts, &audio).unwrap();
+    let t = rebuilt.num_frames / 2;
+    let peak = (0..bins)
+        .max_by(|&a, &b| {
+            let m = |k: usize| rebuilt.real[t * bins + k].hypot(rebuilt.imag[t * bins + k]);
+            m(a).total_cmp(&m(b))
+        })
+        .unwrap();
+    assert!((peak as f32 * 16000.0 / 512.0 - 440.0).abs() < 40.0, "peak bin {}", peak);
+}
+
+#[test]
+fn test_whisper_feature_extractor() {
+    let mut extractor = WhisperFeatureExtractor::new(WhisperOptions::default()).unwrap();
+    let wave: Vec<f32> = (0..16000)
+        .map(|i| 0.5 * (2.0 * PI * 1000.0 * i as f32 / 16000.0).sin())
+        .collect();
+
+    let log_mel = extractor.compute(&wave);
+    let num_frames = extractor.num_frames(wave.len());
+    assert_eq!(num_frames, 100);
+    assert_eq!(log_mel.len(), 80 * num_frames);
+
+    // Dynamic range is clamped to 8 (log10) before scaling, so values span at most 2
+    let max = log_mel.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let min = log_mel.iter().copied().fold(f32::INFINITY, f32::min);
+    assert!(max - min <= 2.0 + 1e-6);
+
+    // Post-processing of the linear energies reproduces `compute`
+    let mut mel = extractor.mel_energies(&wave);
+    let peak = mel.iter().copied().fold(0.0f32, f32::max);
+    whisper_log_mel_normalize(&mut mel);
+    assert_eq!(mel, log_mel);
+    assert_eq!(max, (peak.log10() + 4.0) / 4.0);
+
+    let mut window = WhisperWindow::new(WhisperWindowOptions::default()).unwrap();
+    window.accept_waveform(&wave);
+    let mut padded = wave.clone();
+    padded.resize(480000, 0.0);
+    assert_eq!(window.log_mel(), extractor.compute(&padded));
+}
+
+#[test]
+fn test_whisper_large_v3_preset() {
+    let opts = WhisperOptions::large_v3();
+    assert_eq!(opts.dim, 128);
+    assert_eq!(opts.frame_opts.samp_freq, 16000.0);
+    assert_eq!(opts.frame_opts.window_size(), 400);
+    assert_eq!(opts.frame_opts.window_shift(), 160);
+    assert_eq!(opts.frame_opts.padded_window_size(), 400);
+
+    // Reference values of Whisper's mel_128 filters (librosa, n_fft=400)
+    let mut mopts = MelOptions::default();
+    mopts.num_bins = 128;
+    mopts.low_freq = 0.0;
+    mopts.is_librosa = true;
+    let banks = MelBanks::new(&mopts, &opts.frame_opts, 1.0).unwrap();
+    assert!((banks.weight(0, 1) - 0.012_373_986).abs() < 1e-7);
+    assert!((banks.weight(1, 1) - 0.030_392_565).abs() < 1e-7);
+    assert_eq!(banks.weight(1, 2), 0.0);
+    assert!((banks.weight(127, 198) - 0.002_228_560_4).abs() < 1e-8);
+    assert!((banks.weight(127, 199) - 0.001_114_280_2).abs() < 1e-8);
+
+    let mut extractor = WhisperFeatureExtractor::new(opts).unwrap();
+    let wave: Vec<f32> = (0..3200).map(|i| (i as f32 * 0.1).sin()).collect();
+    assert_eq!(extractor.compute(&wave).len(), 128 * 20);
+}
+
+#[test]
+fn test_whisper_pad_or_trim() {
+    let wave: Vec<f32> = (0..1000).map(|i| i as f32).collect();
+    let padded = whisper_pad_or_trim(&wave, 1500);
+    assert_eq!(&padded[..1000], &wave[..]);
+    assert!(padded[1000..].iter().all(|&x| x == 0.0));
+    assert_eq!(whisper_pad_or_trim(&wave, 10), &wave[..10]);
+
+    // 2 mels x 3 frames
+    let mel = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+    assert_eq!(
+        whisper_pad_or_trim_frames(&mel, 2, 5, 0.0),
+        [1.0, 2.0, 3.0, 0.0, 0.0, 4.0, 5.0, 6.0, 0.0, 0.0]
+    );
+    assert_eq!(whisper_pad_or_trim_frames(&mel, 2, 2, 0.0), [1.0, 2.0, 4.0, 5.0]);
+
+    let mut extractor = WhisperFeatureExtractor::new(WhisperOptions::default()).unwrap();
+    let short: Vec<f32> = (0..16000).map(|i| (i as f32 * 0.05).sin()).collect();
+    let chunk = extractor.compute_chunk(&short);
+    assert_eq!(chunk.len(), 80 * WHISPER_N_FRAMES);
+    let long = whisper_pad_or_trim(&short, 2 * WHISPER_N_SAMPLES);
+    assert_eq!(extractor.compute_chunk(&long), chunk);
+}
+
+#[test]
+fn test_whisper_streaming_extractor() {
+    let opts = WhisperOptions::default();
+    let mut rng = rand::thread_rng();
+    for &len in &[150usize, 500, 16000 + 37] {
+        let wave: Vec<f32> = (0..len).map(|_| rng.gen::<f32>() * 2.0 - 1.0).collect();
+        let mut extractor = WhisperFeatureExtractor::new(opts.clone()).unwrap();
+        let reference = extractor.mel_energies(&wave);
+        let num_frames = extractor.num_frames(len);
+
+        let mut stream = WhisperStreamingExtractor::new(opts.clone()).unwrap();
+        let mut pos = 0;
+        while pos < len {
+            let end = (pos + rng.gen_range(1..700)).min(len);
+            stream.accept_waveform(&wave[pos..end]).unwrap();
+            pos = end;
+            // Frames are emitted before the end of input
+            assert!(stream.num_frames_ready() + 3 >= pos / 160 || pos < 400);
+        }
+        stream.input_finished();
+        assert!(matches!(
+            stream.accept_waveform(&wave[..1]),
+            Err(KnfError::InvalidInput(_))
+        ));
+
+        assert_eq!(stream.num_frames_ready(), num_frames);
+        for t in 0..num_frames {
+            let frame = stream.get_frame(t).unwrap();
+            for m in 0..80 {
+                assert_eq!(frame[m], reference[m * num_frames + t], "len {} frame {}", len, t);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_linear_resample() {
+    let tone = |rate: f32, n: usize| -> Vec<f32> {
+        (0..n)
+            .map(|i| (2.0 * PI * 1000.0 * i as f32 / rate).sin())
+            .collect()
+    };
+    let wave = tone(48000.0, 48000);
+    let out = resample_waveform(&wave, 48000, 16000).unwrap();
+    assert_eq!(out.len(), 16000);
+    let expected = tone(16000.0, 16000);
+    for i in 100..15900 {
+        assert!((out[i] - expected[i]).abs() < 1e-2, "{}: {} vs {}", i, out[i], expected[i]);
+    }
+
+    // 44.1 kHz -> 16 kHz in chunks matches the one-shot result
+    let wave = tone(44100.0, 44100);
+    let reference = resample_waveform(&wave, 44100, 16000).unwrap();
+    let cutoff = 0.99 * 0.5 * 16000.0;
+    let mut resampler = LinearResample::new(44100, 16000, cutoff, 6).unwrap();
+    let mut streamed = Vec::new();
+    for chunk in wave.chunks(1234) {
+        streamed.extend(resampler.resample(chunk));
+    }
+    assert!(streamed.len() < reference.len());
+    streamed.extend(resampler.flush());
+    assert_eq!(streamed.len(), reference.len());
+    for (a, b) in streamed.iter().zip(&reference) {
+        assert!((a - b).abs() < 1e-6);
+    }
+
+    // Upsampling keeps the tone too
+    let up = resample_waveform(&tone(8000.0, 8000), 8000, 16000).unwrap();
+    assert_eq!(up.len(), 16000);
+    let expected = tone(16000.0, 16000);
+    for i in 100..15900 {
+        assert!((up[i] - expected[i]).abs() < 1e-2);
+    }
+
+    assert!(LinearResample::new(16000, 8000, 5000.0, 6).is_err());
+}
+
+#[test]
+fn test_arbitrary_resample() {
+    let rate = 16000.0;
+    let wave: Vec<f32> = (0..8000)
+        .map(|i| (2.0 * PI * 300.0 * i as f32 / rate).sin())
+        .collect();
+    let value_at = |t: f64| (2.0 * std::f64::consts::PI * 300.0 * t).sin() as f32;
+
+    // Batch evaluation at non-grid times
+    let times: Vec<f64> = (0..200).map(|i| 0.1 + i as f64 * 0.00123).collect();
+    let resampler = ArbitraryResample::new(wave.len(), rate, 0.99 * 8000.0, &times, 6).unwrap();
+    let out = resampler.resample(&wave);
+    assert_eq!(out.len(), times.len());
+    for (&t, &y) in times.iter().zip(&out) {
+        assert!((y - value_at(t)).abs() < 1e-2, "t {}: {} vs {}", t, y, value_at(t));
+    }
+
+    // Streaming with explicit times gives the same values
+    let mut stream = StreamingArbitraryResample::new(rate, 0.99 * 8000.0, 6).unwrap();
+    stream.push_times(&times);
+    let mut streamed = Vec::new();
+    for chunk in wave.chunks(333) {
+        streamed.extend(stream.accept_waveform(chunk));
+    }
+    streamed.extend(stream.input_finished());
+    assert_eq!(streamed.len(), out.len());
+    for (a, b) in streamed.iter().zip(&out) {
+        assert!((a - b).abs() < 1e-6);
+    }
+
+    // Speed perturbation by 1.25: a 300 Hz tone becomes 375 Hz, 4/5 as long
+    let mut stream = StreamingArbitraryResample::with_ratio(rate, 1.25, 6).unwrap();
+    let mut fast = Vec::new();
+    for chunk in wave.chunks(1000) {
+        fast.extend(stream.accept_waveform(chunk));
+    }
+    fast.extend(stream.input_finished());
+    assert_eq!(fast.len(), 6400);
+    for i in 200..6200 {
+        let expected = (2.0 * PI * 375.0 * i as f32 / rate).sin();
+        assert!((fast[i] - expected).abs() < 2e-2, "{}: {} vs {}", i, fast[i], expected);
+    }
+}
+
+#[test]
+fn test_accept_waveform_i16() {
+    let pcm: Vec<i16> = (0..8000)
+        .map(|i| (10000.0 * (i as f32 * 0.07).sin()) as i16)
+        .collect();
+    assert_eq!(i16_to_f32(&[i16::MIN, 0, i16::MAX], PcmScale::Kaldi), [-32768.0, 0.0, 32767.0]);
+    assert_eq!(i16_to_f32(&[i16::MIN, 16384], PcmScale::Normalized), [-1.0, 0.5]);
+
+    let mut opts = FbankOptions::default();
+    opts.frame_opts.dither = 0.0;
+    for scale in [PcmScale::Kaldi, PcmScale::Normalized] {
+        let floats = i16_to_f32(&pcm, scale);
+        let mut reference = OnlineFeature::new(FbankComputer::new(opts.clone()).unwrap());
+        reference.accept_waveform(16000.0, &floats).unwrap();
+        reference.input_finished();
+
+        let mut online = OnlineFeature::new(FbankComputer::new(opts.clone()).unwrap());
+        for chunk in pcm.chunks(1000) {
+            online.accept_waveform_i16(16000.0, chunk, scale).unwrap();
+        }
+        online.input_finished();
+        assert_eq!(online.features, reference.features);
+
+        let offline = kaldi_native_fbank::compute_features_i16(&pcm, scale, &opts).unwrap();
+        assert_eq!(offline, kaldi_native_fbank::compute_features(&floats, &opts).unwrap());
+        assert_eq!(offline.to_rows(), reference.features);
+    }
+
+    // Normalized input shifts log-mel energies by 2 * ln(32768)
+    let mut pipeline_opts = OnlinePipelineOptions::default();
+    pipeline_opts.fbank_opts.frame_opts.dither = 0.0;
+    let mut kaldi = OnlineFeaturePipeline::new(pipeline_opts.clone()).unwrap();
+    let mut normalized = OnlineFeaturePipeline::new(pipeline_opts).unwrap();
+    kaldi.accept_waveform_i16(16000.0, &pcm, PcmScale::Kaldi).unwrap();
+    normalized.accept_waveform_i16(16000.0, &pcm, PcmScale::Normalized).unwrap();
+    let shift = 2.0 * 32768.0f32.ln();
+    let (a, b) = (kaldi.get_frame(10).unwrap(), normalized.get_frame(10).unwrap());
+    for (x, y) in a.iter().zip(b) {
+        assert!((x - y - shift).abs() < 1e-2, "{} vs {}", x, y);
+    }
+}
+
+#[test]
+fn test_multi_channel() {
+    let left: Vec<f32> = (0..4000).map(|i| (i as f32 * 0.05).sin()).collect();
+    let right: Vec<f32> = (0..4000).map(|i| 0.5 * (i as f32 * 0.11).cos()).collect();
+    let stereo: Vec<f32> = left.iter().zip(&right).flat_map(|(&l, &r)| [l, r]).collect();
+
+    assert_eq!(deinterleave(&stereo, 2).unwrap(), vec![left.clone(), right.clone()]);
+    assert_eq!(
+        apply_channel_policy(&stereo, 2, ChannelPolicy::Select(1)).unwrap(),
+        vec![right.clone()]
+    );
+    let mono = apply_channel_policy(&stereo, 2, ChannelPolicy::Average).unwrap();
+    assert_eq!(mono.len(), 1);
+    assert_eq!(mono[0][3], (left[3] + right[3]) / 2.0);
+    assert!(apply_channel_policy(&stereo[..3], 2, ChannelPolicy::Average).is_err());
+    assert!(apply_channel_policy(&stereo, 2, ChannelPolicy::Select(2)).is_err());
+
+    let mut opts = FbankOptions::default();
+    opts.frame_opts.dither = 0.0;
+    let single = |wave: &[f32]| {
+        let mut online = OnlineFeature::new(FbankComputer::new(opts.clone()).unwrap());
+        online.accept_waveform(16000.0, wave).unwrap();
+        online.input_finished();
+        online.features
+    };
+
+    let mut multi = MultiChannelFeature::new(2, ChannelPolicy::PerChannel, || {
+        FbankComputer::new(opts.clone())
+    })
+    .unwrap();
+    for chunk in stereo.chunks(500) {
+        multi.accept_waveform(16000.0, chunk).unwrap();
+    }
+    multi.input_finished();
+    assert_eq!(multi.num_streams(), 2);
+    assert_eq!(multi.stream(0).features, single(&left));
+    assert_eq!(multi.stream(1).features, single(&right));
+    assert_eq!(multi.num_frames_ready(), single(&left).len());
+
+    let mut averaged = MultiChannelFeature::new(2, ChannelPolicy::Average, || {
+        FbankComputer::new(opts.clone())
+    })
+    .unwrap();
+    averaged.accept_waveform(16000.0, &stereo).unwrap();
+    averaged.input_finished();
+    assert_eq!(averaged.num_streams(), 1);
+    assert_eq!(averaged.stream(0).features, single(&mono[0]));
+    assert!(averaged.accept_waveform(16000.0, &stereo[..1]).is_err());
+}
+
+#[cfg(feature = "wav")]
+#[test]
+fn test_read_wave() {
+    use kaldi_native_fbank::wav::read_wave_channels;
+
+    let path = std::env::temp_dir().join(format!("knf_read_wave_{}.wav", std::process::id()));
+    let spec = hound::WavSpec {
+        channels: 2,
+        sample_rate: 8000,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+    for (l, r) in [(1000i16, 3000i16), (-32768, 32767), (0, -2)] {
+        writer.write_sample(l).unwrap();
+        writer.write_sample(r).unwrap();
+    }
+    writer.finalize().unwrap();
+
+    let (mono, rate) = kaldi_native_fbank::read_wave(&path).unwrap();
+    assert_eq!(rate, 8000);
+    assert_eq!(mono, [2000.0, -0.5, -1.0]);
+
+    let (channels, _) =
+        read_wave_channels(&path, ChannelPolicy::PerChannel, PcmScale::Normalized).unwrap();
+    assert_eq!(channels[0], [1000.0 / 32768.0, -1.0, 0.0]);
+    assert_eq!(channels[1][1], 32767.0 / 32768.0);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(kaldi_native_fbank::read_wave("/nonexistent.wav").is_err());
+}
+
+#[cfg(feature = "decode")]
+#[test]
+fn test_extract_features_from_file() {
+    use kaldi_native_fbank::decode::decode_audio_file;
+    use kaldi_native_fbank::extract_features_from_file;
+
+    // 8 kHz stereo 16-bit PCM wav
+    let frames: Vec<(i16, i16)> = (0..4000)
+        .map(|i| {
+            let x = (8000.0 * (i as f32 * 0.1).sin()) as i16;
+            (x, x / 2)
+        })
+        .collect();
+    let data_len = (frames.len() * 4) as u32;
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    bytes.extend_from_slice(&2u16.to_le_bytes()); // channels
+    bytes.extend_from_slice(&8000u32.to_le_bytes());
+    bytes.extend_from_slice(&32000u32.to_le_bytes()); // byte rate
+    bytes.extend_from_slice(&4u16.to_le_bytes()); // block align
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_len.to_le_bytes());
+    for &(l, r) in &frames {
+        bytes.extend_from_slice(&l.to_le_bytes());
+        bytes.extend_from_slice(&r.to_le_bytes());
+    }
+    let path = std::env::temp_dir().join(format!("knf_decode_{}.wav", std::process::id()));
+    std::fs::write(&path, bytes).unwrap();
+
+    let (mono, rate) = decode_audio_file(&path).unwrap();
+    assert_eq!(rate, 8000);
+    assert_eq!(mono.len(), 4000);
+    let expected = (frames[7].0 as f32 + frames[7].1 as f32) / 2.0 / 32768.0;
+    assert!((mono[7] - expected).abs() < 1e-6);
+
+    let mut opts = OnlinePipelineOptions::default();
+    opts.fbank_opts.frame_opts.dither = 0.0;
+    let features = extract_features_from_file(&path, &opts).unwrap();
+
+    let scaled: Vec<f32> = mono.iter().map(|x| x * 32768.0).collect();
+    let resampled = resample_waveform(&scaled, 8000, 16000).unwrap();
+    let mut pipeline = OnlineFeaturePipeline::new(opts).unwrap();
+    pipeline.accept_waveform(16000.0, &resampled).unwrap();
+    pipeline.input_finished();
+    assert_eq!(features.len(), 48);
+    assert_eq!(features, pipeline.features);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "safetensors")]
+#[test]
+fn test_safetensors_export() {
+    use kaldi_native_fbank::export::{
+        features_from_safetensors, features_to_safetensors, save_safetensors,
+    };
+    use std::collections::HashMap;
+
+    let fbank: Vec<Vec<f32>> = (0..5)
+        .map(|t| (0..3).map(|d| t as f32 * 0.5 - d as f32).collect())
+        .collect();
+    let mfcc = vec![vec![1.0f32, -2.0], vec![3.5, 4.25]];
+    let metadata = HashMap::from([("feature_type".to_string(), "fbank".to_string())]);
+
+    let bytes = features_to_safetensors(
+        &[("fbank", fbank.as_slice()), ("mfcc", mfcc.as_slice())],
+        Some(metadata.clone()),
+    )
+    .unwrap();
+    assert_eq!(features_from_safetensors(&bytes, "fbank").unwrap(), fbank);
+    assert_eq!(features_from_safetensors(&bytes, "mfcc").unwrap(), mfcc);
+    assert!(features_from_safetensors(&bytes, "missing").is_err());
+
+    let tensors = safetensors::SafeTensors::deserialize(&bytes).unwrap();
+    let view = tensors.tensor("fbank").unwrap();
+    assert_eq!(view.dtype(), safetensors::Dtype::F32);
+    assert_eq!(view.shape(), &[5, 3]);
+    let (_, header) = safetensors::SafeTensors::read_metadata(&bytes).unwrap();
+    assert_eq!(header.metadata().as_ref(), Some(&metadata));
+
+    let ragged = vec![vec![1.0f32, 2.0], vec![3.0]];
+    assert!(features_to_safetensors(&[("x", ragged.as_slice())], None).is_err());
+
+    let path = std::env::temp_dir().join(format!("knf_{}.safetensors", std::process::id()));
+    save_safetensors(&path, &[("fbank", fbank.as_slice())], None).unwrap();
+    let loaded = std::fs::read(&path).unwrap();
+    assert_eq!(features_from_safetensors(&loaded, "fbank").unwrap(), fbank);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_knf_cli() {
+    use std::process::Command;
+
+    let dir = std::env::temp_dir().join(format!("knf_cli_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let wav_path = dir.join("a.wav");
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: 16000,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let samples: Vec<i16> = (0..8000)
+        .map(|i| ((i as f32 * 0.05).sin() * 8000.0) as i16)
+        .collect();
+    let mut writer = hound::WavWriter::create(&wav_path, spec).unwrap();
+    for &s in &samples {
+        writer.write_sample(s).unwrap();
+    }
+    writer.finalize().unwrap();
+    let scp = dir.join("wav.scp");
+    std::fs::write(&scp, format!("utt1 {}\nutt2 {}\n", wav_path.display(), wav_path.display())).unwrap();
+
+    let mut opts = FbankOptions::default();
+    opts.frame_opts.dither = 0.0;
+    opts.mel_opts.num_bins = 40;
+    let mut online = OnlineFeature::new(FeatureComputer::Fbank(FbankComputer::new(opts).unwrap()));
+    let wave: Vec<f32> = samples.iter().map(|&s| s as f32).collect();
+    online.accept_waveform(16000.0, &wave).unwrap();
+    online.input_finished();
+    let expected = online.features;
+
+    let knf = env!("CARGO_BIN_EXE_knf");
+    let ark = dir.join("feats.ark");
+    let status = Command::new(knf)
+        .args(["compute-fbank-feats", "--dither=0", "--num-mel-bins=40"])
+        .arg(format!("scp:{}", scp.display()))
+        .arg(format!("ark:{}", ark.display()))
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    // Two binary Kaldi matrices
+    let bytes = std::fs::read(&ark).unwrap();
+    let header = b"utt1 \0BFM \x04";
+    assert_eq!(&bytes[..header.len()], header);
+    let rows = i32::from_le_bytes(bytes[11..15].try_into().unwrap()) as usize;
+    let cols = i32::from_le_bytes(bytes[16..20].try_into().unwrap()) as usize;
+    assert_eq!((rows, cols), (expected.len(), 41)); // with energy
+    let matrix_len = 20 + rows * cols * 4;
+    assert_eq!(bytes.len(), 2 * matrix_len);
+    let offset = 20 + 4 * (cols + 1);
+    let value = f32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+    assert_eq!(value, expected[1][1]);
+
+    let npy = dir.join("a.npy");
+    let status = Command::new(knf)
+        .args(["compute-mfcc-feats", "--dither=0", "--num-ceps=10"])
+        .arg(&wav_path)
+        .arg(&npy)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let array = npyz::NpyFile::new(std::fs::File::open(&npy).unwrap()).unwrap();
+    assert_eq!(array.shape(), &[expected.len() as u64, 10]);
+
+    let status = Command::new(knf)
+        .args(["compute-fbank-feats", "--sample-frequency=8000"])
+        .arg(&wav_path)
+        .arg(format!("ark,t:{}", dir.join("t.ark").display()))
+        .status()
+        .unwrap();
+    assert!(!status.success());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "ffi")]
+#[test]
+fn test_ffi() {
+    use kaldi_native_fbank::ffi::*;
+
+    let wave: Vec<f32> = (0..4000).map(|i| (i as f32 * 0.1).sin() * 1000.0).collect();
+    let mut opts = knf_fbank_options_default();
+    opts.frame_opts.dither = 0.0;
+    opts.mel_opts.num_bins = 40;
+
+    let mut fbank = FbankOptions::default();
+    fbank.frame_opts.dither = 0.0;
+    fbank.mel_opts.num_bins = 40;
+    let mut online = OnlineFeature::new(FeatureComputer::Fbank(FbankComputer::new(fbank).unwrap()));
+    online.accept_waveform(16000.0, &wave).unwrap();
+    online.input_finished();
+
+    unsafe {
+        let feature = knf_online_fbank_create(&opts);
+        assert!(!feature.is_null());
+        assert_eq!(knf_online_feature_dim(feature), 41);
+        assert_eq!(knf_online_feature_accept_waveform(feature, 16000.0, wave.as_ptr(), 2000), 0);
+        assert_eq!(knf_online_feature_accept_waveform(feature, 16000.0, wave[2000..].as_ptr(), 2000), 0);
+        assert_eq!(knf_online_feature_accept_waveform(feature, 8000.0, wave.as_ptr(), 10), -1);
+        let message = std::ffi::CStr::from_ptr(knf_last_error()).to_str().unwrap();
+        assert!(message.contains("mismatch"));
+        knf_online_feature_input_finished(feature);
+
+        let n = knf_online_feature_num_frames_ready(feature);
+        assert_eq!(n as usize, online.features.len());
+        assert!(knf_online_feature_is_last_frame(feature, n - 1));
+        assert!(!knf_online_feature_is_last_frame(feature, 0));
+        let frame = std::slice::from_raw_parts(knf_online_feature_get_frame(feature, 3), 41);
+        assert_eq!(frame, online.features[3].as_slice());
+        assert!(knf_online_feature_get_frame(feature, n).is_null());
+        knf_online_feature_destroy(feature);
+
+        let (mut out, mut num_frames, mut dim) = (std::ptr::null_mut(), 0, 0);
+        let status = knf_compute_fbank(&opts, 16000.0, wave.as_ptr(), 4000, &mut out, &mut num_frames, &mut dim);
+        assert_eq!(status, 0);
+        assert_eq!((num_frames, dim), (n, 41));
+        let matrix = std::slice::from_raw_parts(out, (n * dim) as usize);
+        assert_eq!(&matrix[41 * 3..41 * 4], online.features[3].as_slice());
+        knf_features_free(out, num_frames, dim);
+
+        let mut mfcc = knf_mfcc_options_default();
+        mfcc.num_ceps = -1;
+        assert!(knf_online_mfcc_create(&mfcc).is_null());
+
+        let whisper = knf_whisper_options_default();
+        let feature = knf_online_whisper_fbank_create(&whisper);
+        assert_eq!(knf_online_feature_dim(feature), 80);
+        knf_online_feature_destroy(feature);
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[test]
+fn test_wasm_online_feature() {
+    use kaldi_native_fbank::wasm::WasmOnlineFeature;
+
+    let wave: Vec<f32> = (0..3200).map(|i| (i as f32 * 0.2).sin() * 500.0).collect();
+    let Ok(mut feature) = WasmOnlineFeature::fbank(16000.0, 40, 0.0, true) else {
+        panic!("failed to create fbank extractor");
+    };
+    assert_eq!(feature.dim(), 41);
+    assert!(feature.accept_waveform(16000.0, &wave).is_ok());
+    feature.input_finished();
+
+    let mut opts = FbankOptions::default();
+    opts.frame_opts.dither = 0.0;
+    opts.mel_opts.num_bins = 40;
+    let mut online = OnlineFeature::new(FeatureComputer::Fbank(FbankComputer::new(opts).unwrap()));
+    online.accept_waveform(16000.0, &wave).unwrap();
+    online.input_finished();
+
+    let n = feature.num_frames_ready();
+    assert_eq!(n, online.features.len());
+    assert!(feature.is_last_frame(n - 1));
+    assert_eq!(feature.get_frame(2).unwrap(), online.features[2]);
+    assert!(feature.get_frame(n).is_none());
+    let frames = feature.get_frames(n - 2, 10);
+    assert_eq!(frames.len(), 2 * 41);
+    assert_eq!(&frames[41..], online.features[n - 1].as_slice());
+}
+
+#[test]
+fn test_error_variants() {
+    let mut opts = FbankOptions::default();
+    opts.mel_opts.num_bins = 0;
+    assert!(matches!(
+        FbankComputer::new(opts),
+        Err(KnfError::InvalidOptions(_))
+    ));
+
+    let frame_opts = FrameOptions::default();
+    let wave = vec![0.0f32; 500];
+    let mut window_buf = vec![0.0; frame_opts.padded_window_size()];
+    let err = extract_window(0, &wave, 3, &frame_opts, None, &mut window_buf).unwrap_err();
+    assert_eq!(err, KnfError::FrameOutOfRange { frame: 3 });
+
+    let stft_opts = StftOptions {
+        n_fft: 16,
+        ..Default::default()
+    };
+    let err = griffin_lim(&[vec![0.0; 8]], &stft_opts, &GriffinLimOptions::default()).unwrap_err();
+    assert_eq!(
+        err,
+        KnfError::SizeMismatch {
+            what: "magnitude bins per frame",
+            expected: 9,
+            actual: 8,
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "size mismatch for magnitude bins per frame: expected 9, got 8"
+    );
+
+    assert!(matches!(
+        deinterleave(&[0.0; 3], 2),
+        Err(KnfError::InvalidInput(_))
+    ));
+    let boxed: Box<dyn std::error::Error> = Box::new(err);
+    assert!(boxed.to_string().starts_with("size mismatch"));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_options_serde() {
+    // Partial configs fall back to the defaults
+    let json = r#"{
+        "frame_opts": { "dither": 0.0, "snip_edges": false },
+        "mel_opts": { "num_bins": 80, "high_freq": -400.0 },
+        "use_energy": false
+    }"#;
+    let opts: FbankOptions = serde_json::from_str(json).unwrap();
+    assert_eq!(opts.frame_opts.dither, 0.0);
+    assert!(!opts.frame_opts.snip_edges);
+    assert_eq!(opts.frame_opts.window_type, WindowType::Povey);
+    assert_eq!(opts.mel_opts.num_bins, 80);
+    assert_eq!(opts.mel_opts.high_freq, -400.0);
+    assert!(!opts.use_energy);
+    assert_eq!(opts.mel_floor_mode, MelFloorMode::Clamp);
+
+    let mut pipeline = OnlinePipelineOptions::default();
+    pipeline.feature_type = "mfcc".to_string();
+    pipeline.mfcc_opts.num_ceps = 20;
+    pipeline.delta = Some(DeltaOptions::default());
+    let value = serde_json::to_value(&pipeline).unwrap();
+    assert_eq!(value["mfcc_opts"]["num_ceps"], 20);
+    assert!(value["cmvn"].is_null());
+    let back: OnlinePipelineOptions = serde_json::from_value(value).unwrap();
+    assert_eq!(back.mfcc_opts.num_ceps, 20);
+    assert_eq!(back.delta.unwrap().order, DeltaOptions::default().order);
+
+    let whisper: WhisperOptions = serde_json::from_str(r#"{"dim": 128}"#).unwrap();
+    assert_eq!(whisper.dim, 128);
+    assert_eq!(whisper.frame_opts.window_type, WindowType::Hann);
+    let stft: StftOptions = serde_json::from_str(r#"{"n_fft": 512}"#).unwrap();
+    assert_eq!(stft.n_fft, 512);
+    assert!(serde_json::from_str::<MfccOptions>(r#"{"num_ceps": "x"}"#).is_err());
+
+    // Window types use their Kaldi names
+    let frame: FrameOptions = serde_json::from_str(r#"{"window_type": "hamming"}"#).unwrap();
+    assert_eq!(frame.window_type, WindowType::Hamming);
+    assert_eq!(serde_json::to_value(&frame).unwrap()["window_type"], "hamming");
+    assert!(serde_json::from_str::<FrameOptions>(r#"{"window_type": "kaiser"}"#).is_err());
+}
+
+#[test]
+fn test_option_presets() {
+    let zipformer = FbankOptions::sherpa_onnx_zipformer();
+    assert_eq!(zipformer.mel_opts.high_freq, -400.0);
+    assert!(!zipformer.frame_opts.snip_edges);
+    assert_eq!(zipformer.frame_opts.dither, 0.0);
+    assert_eq!(FbankComputer::new(zipformer).unwrap().dim(), 80);
+
+    let wenet = FbankOptions::wenet_80bin();
+    assert!(wenet.frame_opts.snip_edges);
+    assert_eq!(wenet.mel_opts.high_freq, 0.0);
+    assert_eq!(FbankComputer::new(wenet).unwrap().dim(), 80);
+
+    let mfcc = MfccOptions::kaldi_default();
+    assert_eq!(mfcc.mel_opts.num_bins, 23);
+    assert_eq!(mfcc.frame_opts.dither, 1.0);
+    assert_eq!(mfcc.frame_opts.dither_mode, DitherMode::Gaussian);
+    assert_eq!(MfccComputer::new(mfcc).unwrap().dim(), 13);
+
+    assert_eq!(WhisperOptions::tiny_en().dim, 80);
+    assert_eq!(WhisperOptions::large_v3().dim, 128);
+}
+
+#[test]
+fn test_options_validate() {
+    assert!(FbankOptions::default().validate().is_ok());
+    assert!(MfccOptions::default().validate().is_ok());
+    assert!(StftOptions::default().validate().is_ok());
+    assert!(WhisperOptions::default().validate().is_ok());
+
+    let mut mfcc = MfccOptions::default();
+    mfcc.num_ceps = mfcc.mel_opts.num_bins + 1;
+    assert!(matches!(mfcc.validate(), Err(KnfError::InvalidOptions(_))));
+    assert!(MfccComputer::new(mfcc).is_err());
+
+    let stft = StftOptions {
+        win_length: 512,
+        n_fft: 400,
+        ..Default::default()
+    };
+    assert!(stft.validate().is_err());
+    assert!(stft_compute(&stft, &[0.0; 1000]).is_err());
+
+    let mut fbank = FbankOptions::default();
+    fbank.mel_opts.low_freq = 4000.0;
+    fbank.mel_opts.high_freq = 3000.0;
+    assert!(fbank.validate().is_err());
+
+    let mut fbank = FbankOptions::default();
+    fbank.frame_opts.frame_shift_ms = 0.0;
+    assert!(fbank.validate().is_err());
+}
+
+#[test]
+fn test_window_type_parse() {
+    for w in WindowType::ALL {
+        assert_eq!(w.to_string().parse::<WindowType>().unwrap(), w);
+    }
+    assert_eq!(WindowType::default(), WindowType::Povey);
+    assert_eq!("hamming".parse::<WindowType>(), Ok(WindowType::Hamming));
+
+    let err = "kaiser".parse::<WindowType>().unwrap_err();
+    assert!(matches!(err, KnfError::InvalidOptions(_)));
+    let msg = err.to_string();
+    assert!(msg.contains("kaiser") && msg.contains("povey"), "{}", msg);
+}
+
+#[test]
+fn test_custom_window() {
+    let wave: Vec<f32> = (0..8000).map(|i| (i as f32 * 0.05).sin() * 1000.0).collect();
+
+    let mut opts = FbankOptions::default();
+    opts.frame_opts.dither = 0.0;
+    opts.frame_opts.window_type = WindowType::Hamming;
+    let hamming = Window::new(&opts.frame_opts).unwrap().data;
+
+    let mut custom = opts.clone();
+    custom.frame_opts.window_type = WindowType::Rectangular;
+    custom.frame_opts.custom_window = Some(hamming.clone());
+
+    let run = |opts: FbankOptions| {
+        let comp = FbankComputer::new(opts).unwrap();
+        let mut online = OnlineFeature::new(FeatureComputer::Fbank(comp));
+        online.accept_waveform(16000.0, &wave).unwrap();
+        online.input_finished();
+        online.features
+    };
+    assert_eq!(run(opts), run(custom.clone()));
+
+    // The length has to match the window size
+    custom.frame_opts.custom_window = Some(vec![1.0; 100]);
+    assert!(matches!(
+        custom.validate().unwrap_err(),
+        KnfError::SizeMismatch {
+            expected: 400,
+            actual: 100,
+            ..
+        }
+    ));
+    assert!(FbankComputer::new(custom).is_err());
+
+    // STFT/ISTFT take a custom window too
+    let mut stft = StftOptions::default();
+    stft.window_type = WindowType::Hamming;
+    let expected = stft_compute(&stft, &wave).unwrap();
+    stft.window_type = WindowType::Rectangular;
+    stft.custom_window = Some(Window::from_data(hamming).data);
+    let res = stft_compute(&stft, &wave).unwrap();
+    assert_eq!(res.real, expected.real);
+    assert_eq!(res.imag, expected.imag);
+
+    let istft = IstftOptions::from(&stft);
+    let recon = istft_compute(&istft, &res).unwrap();
+    let max_err = recon
+        .iter()
+        .zip(&wave)
+        .map(|(a, b)| (a - b).abs())
+        .fold(0.0f32, f32::max);
+    assert!(max_err < 1e-2, "max_err = {}", max_err);
+
+    stft.win_length = 200;
+    assert!(stft_compute(&stft, &wave).is_err());
+}
+
+#[test]
+fn test_dither_mode() {
+    let mut opts = FrameOptions::default();
+    opts.frame_length_ms = 1000.0;
+    opts.dither = 1.0;
+    opts.remove_dc_offset = false;
+    opts.preemph_coeff = 0.0;
+    let size = opts.window_size();
+    let silence = vec![0.0; size];
+
+    let mut variance = |mode| {
+        opts.dither_mode = mode;
+        let mut out = vec![0.0; opts.padded_window_size()];
+        extract_window(0, &silence, 0, &opts, None, &mut out).unwrap();
+        out[..size].iter().map(|x| x * x).sum::<f32>() / size as f32
+    };
+    // Uniform noise in [-0.5, 0.5) has variance 1/12, Kaldi's RandGauss 1
+    let uniform = variance(DitherMode::Uniform);
+    assert!((uniform - 1.0 / 12.0).abs() < 0.01, "{}", uniform);
+    let gaussian = variance(DitherMode::Gaussian);
+    assert!((gaussian - 1.0).abs() < 0.1, "{}", gaussian);
+}
+
+#[test]
+fn test_frame_size_in_samples() {
+    let mut opts = FrameOptions::default();
+    opts.samp_freq = 22050.0;
+    // 25ms/10ms truncate to 551/220 samples
+    assert_eq!((opts.window_size(), opts.window_shift()), (551, 220));
+
+    opts.frame_length_samples = 1024;
+    opts.frame_shift_samples = 256;
+    assert_eq!((opts.window_size(), opts.window_shift()), (1024, 256));
+    assert_eq!(opts.padded_window_size(), 1024);
+    assert_eq!(num_frames(22050, &opts, false), 1 + (22050 - 1024) / 256);
+
+    let mut fbank = FbankOptions::default();
+    fbank.frame_opts = opts;
+    fbank.frame_opts.dither = 0.0;
+    let comp = FbankComputer::new(fbank).unwrap();
+    let mut online = OnlineFeature::new(FeatureComputer::Fbank(comp));
+    online.accept_waveform(22050.0, &vec![100.0; 22050]).unwrap();
+    assert!(matches!(
+        online.accept_waveform(16000.0, &[0.0; 100]),
+        Err(KnfError::SamplingRateMismatch { .. })
+    ));
+    online.input_finished();
+    assert_eq!(online.num_frames_ready(), 83);
+}
+
+#[test]
+fn test_inner_product_lengths() {
+    let mut rng = rand::thread_rng();
+    let a: Vec<f32> = (0..67).map(|_| rng.gen::<f32>() - 0.5).collect();
+    let b: Vec<f32> = (0..67).map(|_| rng.gen::<f32>() - 0.5).collect();
+    // Covers the vector bodies and scalar tails of the SIMD kernels
+    for n in 0..a.len() {
+        let expected: f64 = a[..n].iter().zip(&b[..n]).map(|(x, y)| (x * y) as f64).sum();
+        let got = inner_product(&a[..n], &b[..n]);
+        assert!((got as f64 - expected).abs() < 1e-5, "n = {}: {} vs {}", n, got, expected);
+    }
+}
+
+#[test]
+fn test_mfcc_fast_dct() {
+    let wave: Vec<f32> = (0..16000)
+        .map(|i| (i as f32 * 0.021).sin() * 3000.0 + (i as f32 * 0.17).cos() * 500.0)
+        .collect();
+    let mut opts = MfccOptions::default();
+    opts.frame_opts.dither = 0.0;
+    opts.mel_opts.num_bins = 80;
+    opts.num_ceps = 40;
+
+    let run = |opts: MfccOptions| {
+        let comp = MfccComputer::new(opts).unwrap();
+        let mut online = OnlineFeature::new(FeatureComputer::Mfcc(comp));
+        online.accept_waveform(16000.0, &wave).unwrap();
+        online.input_finished();
+        online.features
+    };
+    let matrix = run(opts.clone());
+    opts.fast_dct = true;
+    let fast = run(opts);
+
+    assert_eq!(matrix.len(), fast.len());
+    for (a, b) in matrix.iter().flatten().zip(fast.iter().flatten()) {
+        assert!((a - b).abs() < 1e-3 * a.abs().max(1.0), "{} vs {}", a, b);
+    }
+}
+
+#[test]
+fn test_compute_features() {
+    let wave: Vec<f32> = (0..12345).map(|i| (i as f32 * 0.013).sin() * 2000.0).collect();
+    for snip_edges in [true, false] {
+        let mut opts = FbankOptions::default();
+        opts.frame_opts.dither = 0.0;
+        opts.frame_opts.snip_edges = snip_edges;
+
+        let comp = FbankComputer::new(opts.clone()).unwrap();
+        let mut online = OnlineFeature::new(FeatureComputer::Fbank(comp));
+        online.accept_waveform(16000.0, &wave).unwrap();
+        online.input_finished();
+
+        let feats = kaldi_native_fbank::compute_features(&wave, &opts).unwrap();
+        assert_eq!(feats.dim, 26);
+        assert_eq!(feats.num_frames, online.num_frames_ready());
+        assert_eq!(feats.data.len(), feats.num_frames * feats.dim);
+        assert_eq!(feats.to_rows(), online.features);
+        assert_eq!(feats.row(3), online.get_frame(3).unwrap());
+    }
+
+    let mut mfcc = MfccComputer::new(MfccOptions::default()).unwrap();
+    let feats = kaldi_native_fbank::compute_features_with(&mut mfcc, &wave).unwrap();
+    assert_eq!(feats.dim, 13);
+    assert_eq!(feats.rows().count(), feats.num_frames);
+
+    let empty = kaldi_native_fbank::compute_features(&[], &FbankOptions::default()).unwrap();
+    assert!(empty.is_empty());
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_compute_features_par() {
+    use kaldi_native_fbank::{compute_features, compute_features_par, compute_features_par_with};
+
+    // 20 s spans several parallel chunks
+    let wave: Vec<f32> = (0..320_000).map(|i| (i as f32 * 0.011).sin() * 1000.0).collect();
+    let mut opts = FbankOptions::default();
+    opts.frame_opts.dither = 0.0;
+    let seq = compute_features(&wave, &opts).unwrap();
+    assert_eq!(compute_features_par(&wave, &opts).unwrap(), seq);
+
+    opts.subtract_mean_log_energy = true;
+    assert_eq!(
+        compute_features_par(&wave, &opts).unwrap(),
+        compute_features(&wave, &opts).unwrap()
+    );
+
+    let mut mfcc_opts = MfccOptions::default();
+    mfcc_opts.frame_opts.dither = 0.0;
+    let par = compute_features_par_with(|| MfccComputer::new(mfcc_opts.clone()), &wave).unwrap();
+    let mut mfcc = MfccComputer::new(mfcc_opts.clone()).unwrap();
+    assert_eq!(par, kaldi_native_fbank::compute_features_with(&mut mfcc, &wave).unwrap());
+
+    mfcc_opts.num_ceps = 0;
+    assert!(compute_features_par_with(|| MfccComputer::new(mfcc_opts.clone()), &wave).is_err());
+}
+
+#[test]
+fn test_compute_features_batch() {
+    use kaldi_native_fbank::{compute_features, compute_features_batch, compute_features_batch_with};
+
+    let waves: Vec<Vec<f32>> = (1..=6)
+        .map(|k| (0..4000 * k).map(|i| (i as f32 * 0.01 * k as f32).sin() * 800.0).collect())
+        .collect();
+    let mut opts = FbankOptions::default();
+    opts.frame_opts.dither = 0.0;
+    // Mean log-energy state must not leak between utterances
+    opts.subtract_mean_log_energy = true;
+
+    let batch = compute_features_batch(&waves, &opts).unwrap();
+    assert_eq!(batch.len(), waves.len());
+    for (feats, wave) in batch.iter().zip(&waves) {
+        assert_eq!(feats, &compute_features(wave, &opts).unwrap());
+    }
+
+    let slices: Vec<&[f32]> = waves.iter().map(|w| w.as_slice()).collect();
+    let mfcc = compute_features_batch_with(|| MfccComputer::new(MfccOptions::default()), &slices)
+        .unwrap();
+    assert!(mfcc.iter().all(|m| m.dim == 13));
+    assert!(mfcc.windows(2).all(|w| w[0].num_frames < w[1].num_frames));
+
+    opts.mel_opts.num_bins = 0;
+    assert!(compute_features_batch(&waves, &opts).is_err());
+}
+
+#[cfg(feature = "gpu")]
+#[test]
+fn test_compute_features_gpu() {
+    use kaldi_native_fbank::gpu::compute_features_with_stage;
+    use kaldi_native_fbank::{compute_features, compute_features_gpu, GpuMelStage};
+
+    let wave: Vec<f32> = (0..16000).map(|i| (i as f32 * 0.031).sin() * 1200.0).collect();
+    let mut opts = FbankOptions::default();
+    opts.frame_opts.dither = 0.0;
+    let cpu = compute_features(&wave, &opts).unwrap();
+
+    // Uses the CPU when no adapter is available
+    let gpu = compute_features_gpu(&wave, &opts).unwrap();
+    assert_eq!((gpu.num_frames, gpu.dim), (cpu.num_frames, cpu.dim));
+    for (a, b) in gpu.data.iter().zip(&cpu.data) {
+        assert!((a - b).abs() < 1e-3 * b.abs().max(1.0), "{} vs {}", a, b);
+    }
+
+    match GpuMelStage::new(&opts) {
+        Ok(stage) => {
+            opts.mel_opts.num_bins = 40;
+            assert!(compute_features_with_stage(&stage, &wave, &opts).is_err());
+        }
+        Err(e) => assert!(matches!(e, KnfError::Gpu(_)), "{}", e),
+    }
+}
+
+#[test]
+fn test_online_pop_frames() {
+    let wave: Vec<f32> = (0..16000).map(|i| (i as f32 * 0.02).sin() * 1000.0).collect();
+    let mut opts = FbankOptions::default();
+    opts.frame_opts.dither = 0.0;
+    let reference = kaldi_native_fbank::compute_features(&wave, &opts).unwrap();
+
+    let comp = FbankComputer::new(opts).unwrap();
+    let mut online = OnlineFeature::new(FeatureComputer::Fbank(comp));
+    let mut popped = Vec::new();
+    for chunk in wave.chunks(1000) {
+        online.accept_waveform(16000.0, chunk).unwrap();
+        // Release all but the last two ready frames
+        let ready = online.num_frames_ready();
+        assert_eq!(online.first_frame(), popped.len());
+        let n = ready.saturating_sub(online.first_frame() + 2);
+        popped.extend(online.pop_frames(n));
+        assert!(online.features.len() <= 2);
+        assert_eq!(online.num_frames_ready(), ready);
+    }
+    online.input_finished();
+    let total = online.num_frames_ready();
+    assert_eq!(total, reference.num_frames);
+
+    online.discard_frames_before(total - 1);
+    assert_eq!(online.first_frame(), total - 1);
+    assert!(online.get_frame(total - 2).is_none());
+    assert_eq!(online.get_frame(total - 1).unwrap(), reference.row(total - 1));
+    // Discarding never goes backwards
+    online.discard_frames_before(0);
+    assert_eq!(online.first_frame(), total - 1);
+
+    for (frame, feats) in popped.iter().enumerate() {
+        assert_eq!(feats.as_slice(), reference.row(frame));
+    }
+}
+
+#[test]
+fn test_online_get_frames() {
+    let wave: Vec<f32> = (0..8000).map(|i| (i as f32 * 0.03).sin() * 1000.0).collect();
+    let mut opts = FbankOptions::default();
+    opts.frame_opts.dither = 0.0;
+    let reference = kaldi_native_fbank::compute_features(&wave, &opts).unwrap();
+    let dim = reference.dim;
+
+    let comp = FbankComputer::new(opts).unwrap();
+    let mut online = OnlineFeature::new(FeatureComputer::Fbank(comp));
+    online.accept_waveform(16000.0, &wave).unwrap();
+    online.input_finished();
+
+    let mut out = vec![0.0; 10 * dim];
+    online.get_frames(5..15, &mut out).unwrap();
+    assert_eq!(out, reference.data[5 * dim..15 * dim]);
+
+    // Indices stay absolute after releasing frames
+    online.pop_frames(8);
+    online.get_frames(8..18, &mut out).unwrap();
+    assert_eq!(out, reference.data[8 * dim..18 * dim]);
+    assert!(matches!(
+        online.get_frames(5..15, &mut out),
+        Err(KnfError::FrameOutOfRange { frame: 5 })
+    ));
+    let total = online.num_frames_ready();
+    assert!(matches!(
+        online.get_frames(total - 5..total + 5, &mut out),
+        Err(KnfError::FrameOutOfRange { .. })
+    ));
+    assert!(matches!(
+        online.get_frames(8..10, &mut out),
+        Err(KnfError::SizeMismatch { .. })
+    ));
+    online.get_frames(9..9, &mut []).unwrap();
+}
+
+#[test]
+fn test_online_reset() {
+    let wave: Vec<f32> = (0..8000).map(|i| (i as f32 * 0.05).sin() * 1000.0).collect();
+    let other: Vec<f32> = (0..4000).map(|i| (i as f32 * 0.11).cos() * 500.0).collect();
+    let mut opts = FbankOptions::default();
+    opts.frame_opts.dither = 0.0;
+    opts.subtract_mean_log_energy = true;
+    let reference = kaldi_native_fbank::compute_features(&wave, &opts).unwrap();
+
+    let comp = FbankComputer::new(opts).unwrap();
+    let mut online = OnlineFeature::new(FeatureComputer::Fbank(comp));
+    online.accept_waveform(16000.0, &other).unwrap();
+    online.input_finished();
+    online.pop_frames(3);
+
+    online.reset();
+    assert_eq!(online.num_frames_ready(), 0);
+    assert_eq!(online.first_frame(), 0);
+    assert!(!online.is_input_finished());
+
+    online.accept_waveform(16000.0, &wave).unwrap();
+    online.input_finished();
+    assert_eq!(online.num_frames_ready(), reference.num_frames);
+    for frame in 0..reference.num_frames {
+        assert_eq!(online.get_frame(frame).unwrap(), reference.row(frame));
+    }
+}
+
+#[test]
+fn test_online_checkpoint_rollback() {
+    let wave: Vec<f32> = (0..12000).map(|i| (i as f32 * 0.04).sin() * 1000.0).collect();
+    let noise: Vec<f32> = (0..3000).map(|i| (i as f32 * 0.13).cos() * 3000.0).collect();
+    let mut opts = FbankOptions::default();
+    opts.frame_opts.dither = 0.0;
+    opts.subtract_mean_log_energy = true;
+    let reference = kaldi_native_fbank::compute_features(&wave, &opts).unwrap();
+
+    let comp = FbankComputer::new(opts).unwrap();
+    let mut online = OnlineFeature::new(FeatureComputer::Fbank(comp));
+    online.accept_waveform(16000.0, &wave[..5000]).unwrap();
+    let checkpoint = online.checkpoint();
+    assert_eq!(checkpoint.num_frames(), online.num_frames_ready());
+
+    // Feed audio that is later rejected, releasing some frames on the way
+    online.accept_waveform(16000.0, &noise).unwrap();
+    online.pop_frames(online.num_frames_ready() - 2);
+    online.input_finished();
+
+    online.rollback(&checkpoint);
+    assert!(!online.is_input_finished());
+    assert_eq!(online.num_frames_ready(), checkpoint.num_frames());
+    online.accept_waveform(16000.0, &wave[5000..]).unwrap();
+    online.input_finished();
+    assert_eq!(online.num_frames_ready(), reference.num_frames);
+    for frame in online.first_frame()..reference.num_frames {
+        assert_eq!(online.get_frame(frame).unwrap(), reference.row(frame));
+    }
+
+    // Frames held at the checkpoint survive the rollback
+    let mut online = OnlineFeature::new(FeatureComputer::Fbank(
+        FbankComputer::new(FbankOptions {
+            frame_opts: FrameOptions {
+                dither: 0.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .unwrap(),
+    ));
+    online.accept_waveform(16000.0, &wave[..5000]).unwrap();
+    let checkpoint = online.checkpoint();
+    online.accept_waveform(16000.0, &noise).unwrap();
+    online.rollback(&checkpoint);
+    assert_eq!(online.first_frame(), 0);
+    assert_eq!(online.features.len(), checkpoint.num_frames());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_online_checkpoint_serde() {
+    let wave: Vec<f32> = (0..12000).map(|i| (i as f32 * 0.04).sin() * 1000.0).collect();
+    let mut opts = FbankOptions::default();
+    opts.frame_opts.dither = 0.0;
+    opts.subtract_mean_log_energy = true;
+    let reference = kaldi_native_fbank::compute_features(&wave, &opts).unwrap();
+
+    let mut online = OnlineFeature::new(FeatureComputer::Fbank(
+        FbankComputer::new(opts.clone()).unwrap(),
+    ));
+    online.accept_waveform(16000.0, &wave[..7001]).unwrap();
+    let json = serde_json::to_string(&online.checkpoint()).unwrap();
+
+    // Resume in a "new process"
+    let checkpoint: kaldi_native_fbank::OnlineCheckpoint = serde_json::from_str(&json).unwrap();
+    let comp = FeatureComputer::Fbank(FbankComputer::new(opts.clone()).unwrap());
+    let mut resumed = OnlineFeature::resume(comp, &checkpoint).unwrap();
+    assert_eq!(resumed.first_frame(), online.num_frames_ready());
+    resumed.accept_waveform(16000.0, &wave[7001..]).unwrap();
+    resumed.input_finished();
+    assert_eq!(resumed.num_frames_ready(), reference.num_frames);
+    for frame in resumed.first_frame()..reference.num_frames {
+        assert_eq!(resumed.get_frame(frame).unwrap(), reference.row(frame));
+    }
+
+    opts.frame_opts.frame_shift_ms = 20.0;
+    let comp = FeatureComputer::Fbank(FbankComputer::new(opts).unwrap());
+    assert!(matches!(
+        OnlineFeature::resume(comp, &checkpoint),
+        Err(KnfError::InvalidOptions(_))
+    ));
+}
+
+#[test]
+fn test_stream_pool() {
+    use kaldi_native_fbank::StreamPool;
+
+    let mut opts = FbankOptions::default();
+    opts.frame_opts.dither = 0.0;
+    let waves: Vec<Vec<f32>> = (1..=3)
+        .map(|k| (0..6000).map(|i| (i as f32 * 0.01 * k as f32).sin() * 1000.0).collect())
+        .collect();
+    let references: Vec<_> = waves
+        .iter()
+        .map(|w| kaldi_native_fbank::compute_features(w, &opts).unwrap())
+        .collect();
+
+    let made = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let counter = made.clone();
+    let mut pool = StreamPool::new(move || {
+        counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        FbankComputer::new(opts.clone()).map(FeatureComputer::Fbank)
+    });
+    for id in 0..2 {
+        pool.open(id).unwrap();
+    }
+    assert!(pool.open(0).is_err());
+    assert!(pool.accept_waveform(&7, 16000.0, &waves[0]).is_err());
+    assert!(matches!(
+        pool.accept_waveform(&0, 8000.0, &waves[0]),
+        Err(KnfError::SamplingRateMismatch { .. })
+    ));
+
+    let mut collected = vec![Vec::new(); 2];
+    for (c0, c1) in waves[0].chunks(1000).zip(waves[1].chunks(1000)) {
+        pool.accept_waveform(&0, 16000.0, c0).unwrap();
+        pool.accept_waveform(&1, 16000.0, c1).unwrap();
+        for ready in pool.collect_ready() {
+            assert_eq!(ready.first_frame * ready.frames.dim, collected[ready.id].len());
+            assert!(!ready.is_final);
+            collected[ready.id].extend_from_slice(&ready.frames.data);
+        }
+    }
+    for id in 0..2 {
+        pool.input_finished(&id).unwrap();
+    }
+    for ready in pool.collect_ready() {
+        assert!(ready.is_final);
+        collected[ready.id].extend_from_slice(&ready.frames.data);
+    }
+    assert_eq!(collected[0], references[0].data);
+    assert_eq!(collected[1], references[1].data);
+
+    // Closed streams are recycled for new ones
+    assert!(pool.close(&0));
+    assert!(!pool.close(&0));
+    pool.open(2).unwrap();
+    pool.accept_waveform(&2, 16000.0, &waves[2]).unwrap();
+    pool.input_finished(&2).unwrap();
+    let ready = pool.collect_ready();
+    assert_eq!(ready.len(), 1);
+    assert_eq!(ready[0].first_frame, 0);
+    assert_eq!(ready[0].frames, references[2]);
+    assert_eq!(made.load(std::sync::atomic::Ordering::SeqCst), 2);
+    assert_eq!(pool.len(), 2);
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn test_feature_stream() {
+    use futures::StreamExt;
+    use kaldi_native_fbank::FeatureStream;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let wave: Vec<f32> = (0..16000).map(|i| (i as f32 * 0.02).sin() * 1000.0).collect();
+    let mut opts = FbankOptions::default();
+    opts.frame_opts.dither = 0.0;
+    opts.frame_opts.snip_edges = false;
+    let reference = kaldi_native_fbank::compute_features(&wave, &opts).unwrap();
+
+    let chunks = futures::stream::iter(wave.chunks(1000).map(|c| c.to_vec()));
+    let comp = FeatureComputer::Fbank(FbankComputer::new(opts.clone()).unwrap());
+    let frames: Vec<Vec<f32>> =
+        futures::executor::block_on(FeatureStream::new(chunks, comp).collect());
+    assert_eq!(frames, reference.to_rows());
+
+    // Chunks are only pulled when the consumer wants more frames
+    let pulled = AtomicUsize::new(0);
+    let chunks = futures::stream::iter(wave.chunks(1000)).inspect(|_| {
+        pulled.fetch_add(1, Ordering::SeqCst);
+    });
+    let comp = FeatureComputer::Fbank(FbankComputer::new(opts).unwrap());
+    let mut stream = FeatureStream::new(chunks, comp);
+    let first = futures::executor::block_on(stream.next()).unwrap();
+    assert_eq!(first, reference.row(0));
+    assert_eq!(pulled.load(Ordering::SeqCst), 1);
+}
+
+#[cfg(feature = "capture")]
+#[test]
+fn test_capture_converter() {
+    use kaldi_native_fbank::capture::CaptureConverter;
+    use kaldi_native_fbank::resample::resample_waveform;
+
+    let left: Vec<f32> = (0..4800).map(|i| (i as f32 * 0.01).sin() * 0.5).collect();
+    let right: Vec<f32> = (0..4800).map(|i| (i as f32 * 0.03).cos() * 0.25).collect();
+    let interleaved: Vec<f32> = left.iter().zip(&right).flat_map(|(&l, &r)| [l, r]).collect();
+    let mono: Vec<f32> = left
+        .iter()
+        .zip(&right)
+        .map(|(l, r)| (l + r) * 0.5 * 32768.0)
+        .collect();
+    let expected = resample_waveform(&mono, 48000, 16000).unwrap();
+
+    let mut converter = CaptureConverter::new(48000, 2, 16000.0, PcmScale::Kaldi).unwrap();
+    let mut out = Vec::new();
+    for chunk in interleaved.chunks(960) {
+        out.extend(converter.process(chunk).unwrap());
+    }
+    out.extend(converter.flush());
+    assert_eq!(out.len(), expected.len());
+    for (a, b) in out.iter().zip(&expected) {
+        assert!((a - b).abs() <= 1e-3 * b.abs().max(1.0), "{} vs {}", a, b);
+    }
+
+    assert!(converter.process(&[0.0; 3]).is_err());
+    assert!(CaptureConverter::new(48000, 2, 16000.5, PcmScale::Kaldi).is_err());
+    let mut same_rate = CaptureConverter::new(16000, 1, 16000.0, PcmScale::Normalized).unwrap();
+    assert_eq!(same_rate.process(&[0.5, -0.25]).unwrap(), vec![0.5, -0.25]);
+}
+
+#[test]
+fn test_frame_iterator() {
+    let wave: Vec<f32> = (0..5000).map(|i| (i as f32 * 0.07).sin() * 100.0).collect();
+    let opts = FrameOptions {
+        dither: 0.0,
+        snip_edges: false,
+        ..Default::default()
+    };
+    let window = Window::new(&opts);
+    let mut expected = vec![0.0; opts.padded_window_size()];
+
+    let mut frames = FrameIterator::new(&wave, &opts);
+    assert_eq!(frames.num_frames(), num_frames(wave.len(), &opts, true));
+    assert_eq!(frames.len(), frames.num_frames());
+    let mut count = 0;
+    while let Some((frame, data, raw_log_energy)) = frames.next_frame() {
+        let energy =
+            extract_window(0, &wave, frame, &opts, window.as_ref(), &mut expected).unwrap();
+        assert_eq!(frame, count);
+        assert_eq!(data, expected.as_slice());
+        assert_eq!(raw_log_energy, energy);
+        count += 1;
+    }
+    assert_eq!(count, num_frames(wave.len(), &opts, true));
+
+    let owned: Vec<_> = FrameIterator::new(&wave, &opts).skip(3).collect();
+    assert_eq!(owned.len(), count - 3);
+    assert_eq!(owned[0].0, 3);
+    assert!(FrameIterator::new(&wave[..100], &FrameOptions::default())
+        .next()
+        .is_none());
+}
+
+#[test]
+fn test_sample_source() {
+    use kaldi_native_fbank::source::from_fn;
+    use kaldi_native_fbank::{ReadSource, SampleSource};
+    use std::collections::VecDeque;
+
+    let wave: Vec<f32> = (0..9000).map(|i| ((i as f32 * 0.03).sin() * 8000.0).round()).collect();
+    let mut opts = FbankOptions::default();
+    opts.frame_opts.dither = 0.0;
+    let reference = kaldi_native_fbank::compute_features(&wave, &opts).unwrap();
+    let new_online = || {
+        OnlineFeature::new(FeatureComputer::Fbank(
+            FbankComputer::new(opts.clone()).unwrap(),
+        ))
+    };
+
+    // Slices are consumed until exhausted, which finishes the input
+    let mut online = new_online();
+    let mut source = wave.as_slice();
+    while !online.is_input_finished() {
+        online.pull_from(&mut source, 777).unwrap();
+    }
+    assert_eq!(online.num_frames_ready(), reference.num_frames);
+    assert_eq!(online.features, reference.to_rows());
+
+    // 16-bit PCM bytes from a reader, delivered a few odd-sized bytes at a time
+    struct Trickle(Vec<u8>, usize);
+    impl std::io::Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = buf.len().min(self.0.len()).min(self.1);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0.drain(..n);
+            self.1 = if self.1 == 301 { 1 } else { 301 };
+            Ok(n)
+        }
+    }
+    let bytes: Vec<u8> = wave.iter().flat_map(|&x| (x as i16).to_le_bytes()).collect();
+    let mut source = ReadSource::new(Trickle(bytes, 301), PcmScale::Kaldi);
+    let mut online = new_online();
+    while !online.is_input_finished() {
+        online.pull_from(&mut source, 500).unwrap();
+    }
+    assert_eq!(online.features, reference.to_rows());
+
+    // Ring buffers report "nothing yet" rather than the end
+    let mut ring: VecDeque<f32> = wave[..100].iter().copied().collect();
+    let mut online = new_online();
+    assert_eq!(online.pull_from(&mut ring, 1000).unwrap(), 100);
+    assert_eq!(online.pull_from(&mut ring, 1000).unwrap(), 0);
+    assert!(!online.is_input_finished());
+
+    let mut failing = from_fn(|_: &mut [f32]| Err(KnfError::Io("device lost".into())));
+    assert!(failing.read_samples(&mut [0.0; 4]).is_err());
+    assert!(online.pull_from(&mut failing, 10).is_err());
+    assert_eq!(online.pull_from(&mut ring, 10).unwrap(), 0);
+}
+
+#[test]
+fn test_vtln_warp() {
+    use kaldi_native_fbank::mel::MelBanksCache;
+
+    let wave: Vec<f32> = (0..8000).map(|i| (i as f32 * 0.05).sin() * 1000.0).collect();
+    let mut opts = FbankOptions::default();
+    opts.frame_opts.dither = 0.0;
+    let unwarped = kaldi_native_fbank::compute_features(&wave, &opts).unwrap();
+
+    let mut cache = MelBanksCache::new(&opts.mel_opts, &opts.frame_opts).unwrap();
+    let direct = MelBanks::new(&opts.mel_opts, &opts.frame_opts, 0.9).unwrap();
+    assert_eq!(cache.get(0.9).edges_hz, cache.unwarped().edges_hz);
+    assert_eq!(cache.prepare(0.9).unwrap().edges_hz, direct.edges_hz);
+    assert_eq!(cache.get(0.9).edges_hz, direct.edges_hz);
+    assert_ne!(cache.unwarped().edges_hz, direct.edges_hz);
+    assert!(cache.prepare(-1.0).is_err());
+
+    let mut bad = opts.mel_opts.clone();
+    bad.vtln_low = 10.0;
+    assert!(MelBanks::new(&bad, &opts.frame_opts, 1.0).is_ok());
+    assert!(matches!(
+        MelBanks::new(&bad, &opts.frame_opts, 0.9),
+        Err(KnfError::InvalidOptions(_))
+    ));
+
+    // Warp factors can change between calls; each one gets its own banks
+    let run = |warps: &[f32]| {
+        let comp = FbankComputer::new(opts.clone()).unwrap();
+        let mut online = OnlineFeature::new(FeatureComputer::Fbank(comp));
+        for (chunk, &warp) in wave.chunks(2000).zip(warps) {
+            online.set_vtln_warp(warp).unwrap();
+            online.accept_waveform(16000.0, chunk).unwrap();
+        }
+        online.input_finished();
+        online.features
+    };
+    let warped = run(&[0.9; 4]);
+    assert_eq!(run(&[1.0; 4]), unwarped.to_rows());
+    assert_eq!(warped.len(), unwarped.num_frames);
+    assert_ne!(warped[0], unwarped.row(0));
+    let mixed = run(&[0.9, 1.0, 0.9, 1.0]);
+    assert_eq!(mixed[0], warped[0]);
+
+    // Unusable warps are rejected up front instead of failing mid-stream
+    let mut online = OnlineFeature::new(FeatureComputer::Fbank(
+        FbankComputer::new(opts.clone()).unwrap(),
+    ));
+    for warp in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+        assert!(matches!(
+            online.set_vtln_warp(warp),
+            Err(KnfError::InvalidOptions(_))
+        ));
+    }
+    let mut online = OnlineFeature::new(FeatureComputer::Fbank(
+        FbankComputer::new(FbankOptions {
+            mel_opts: bad.clone(),
+            ..opts.clone()
+        })
+        .unwrap(),
+    ));
+    assert!(online.set_vtln_warp(0.9).is_err());
+    assert_eq!(online.vtln_warp(), 1.0);
+
+    let mut mfcc = MfccComputer::new(MfccOptions::default()).unwrap();
+    let mut frame = vec![0.0; mfcc.opts.frame_opts.padded_window_size()];
+    frame[..400].copy_from_slice(&wave[..400]);
+    let (mut a, mut b) = (vec![0.0; mfcc.dim()], vec![0.0; mfcc.dim()]);
+    mfcc.prepare_vtln_warp(1.1).unwrap();
+    mfcc.compute(0.0, 1.1, &mut frame.clone(), &mut a);
+    mfcc.compute(0.0, 1.0, &mut frame, &mut b);
+    assert_ne!(a, b);
+}
+
+#[test]
+fn test_stft_window_centering() {
+    let wave: Vec<f32> = (0..4000).map(|i| (i as f32 * 0.05).sin() + (i as f32 * 0.31).cos()).collect();
+    let short = StftOptions {
+        n_fft: 512,
+        win_length: 300,
+        hop_length: 100,
+        window_type: WindowType::Hann,
+        ..Default::default()
+    };
+    // Same window given explicitly as a zero-padded full-length window
+    let hann = Window::from_type(WindowType::Hann, 300, 0.42).data;
+    let mut padded = vec![0.0; 106];
+    padded.extend(hann);
+    padded.resize(512, 0.0);
+    let full = StftOptions {
+        win_length: 512,
+        custom_window: Some(padded),
+        ..short.clone()
+    };
+    let a = stft_compute(&short, &wave).unwrap();
+    let b = stft_compute(&full, &wave).unwrap();
+    assert_eq!(a.real, b.real);
+    assert_eq!(a.imag, b.imag);
+
+    let recon = istft_compute(&IstftOptions::from(&short), &a).unwrap();
+    for (x, y) in recon.iter().zip(&wave).skip(300).take(3400) {
+        assert!((x - y).abs() < 1e-3, "{} vs {}", x, y);
+    }
+}
+
+#[test]
+fn test_stft_result_accessors() {
+    let wave: Vec<f32> = (0..2000).map(|i| (i as f32 * 0.2).sin()).collect();
+    let res = stft_compute(&StftOptions::default(), &wave).unwrap();
+    let bins = res.num_bins();
+    assert_eq!(bins, 201);
+
+    let (re, im) = res.frame(3);
+    assert_eq!(re, &res.real[3 * bins..4 * bins]);
+    assert_eq!(im, &res.imag[3 * bins..4 * bins]);
+
+    let mag = res.magnitude();
+    let power = res.power();
+    assert_eq!((mag.num_frames, mag.dim), (res.num_frames, bins));
+    assert_eq!((power.num_frames, power.dim), (res.num_frames, bins));
+    let k = 7;
+    assert!((mag.row(3)[k] - re[k].hypot(im[k])).abs() < 1e-6);
+    assert!((power.row(3)[k] - mag.row(3)[k].powi(2)).abs() < 1e-3 * power.row(3)[k].max(1.0));
+
+    let db = res.to_db(None);
+    assert!((db.row(3)[k] - 10.0 * power.row(3)[k].log10()).abs() < 1e-4);
+    let top = res.to_db(Some(40.0));
+    let max = top.data.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    assert!(top.data.iter().all(|&x| x >= max - 40.0));
+    assert_eq!(max, db.data.iter().copied().fold(f32::NEG_INFINITY, f32::max));
+}
+
+#[test]
+fn test_online_istft() {
+    use kaldi_native_fbank::OnlineIstft;
+
+    let wave: Vec<f32> = (0..3000).map(|i| (i as f32 * 0.07).sin() * (i as f32 * 0.001).cos()).collect();
+    for (n_fft, hop, center) in [(400, 160, true), (256, 64, false), (255, 100, true), (64, 100, false)] {
+        let stft_opts = StftOptions {
+            n_fft,
+            win_length: n_fft,
+            hop_length: hop,
+            center,
+            window_type: WindowType::Hann,
+            ..Default::default()
+        };
+        let res = stft_compute(&stft_opts, &wave).unwrap();
+        let istft_opts = IstftOptions::from(&stft_opts);
+        let expected = istft_compute(&istft_opts, &res).unwrap();
+
+        let mut online = OnlineIstft::new(istft_opts).unwrap();
+        assert_eq!(online.latency(), n_fft.saturating_sub(hop));
+        let mut out = Vec::new();
+        for i in 0..res.num_frames {
+            let (re, im) = res.frame(i);
+            let chunk = online.accept_frame(re, im).unwrap();
+            // Everything but the overlap still to come is emitted right away
+            let trim = if center { n_fft / 2 } else { 0 };
+            let ready = ((i + 1) * hop).min(i * hop + n_fft - trim);
+            out.extend(chunk);
+            assert_eq!(out.len(), ready.saturating_sub(trim));
+        }
+        out.extend(online.finish());
+        assert_eq!(out, expected, "n_fft {} hop {}", n_fft, hop);
+        assert_eq!(online.num_frames(), 0);
+    }
+
+    let mut online = OnlineIstft::new(IstftOptions::default()).unwrap();
+    assert!(matches!(
+        online.accept_frame(&[0.0; 10], &[0.0; 10]),
+        Err(KnfError::SizeMismatch { .. })
+    ));
+    assert!(online.finish().is_empty());
+}
+
+#[test]
+fn test_stft_multi_resolution() {
+    use kaldi_native_fbank::stft_multi_resolution;
+
+    let wave: Vec<f32> = (0..4000).map(|i| (i as f32 * 0.05).sin()).collect();
+    let configs: Vec<StftOptions> = [(512, 128, 512), (1024, 256, 1024), (256, 64, 200)]
+        .into_iter()
+        .map(|(n_fft, hop_length, win_length)| StftOptions {
+            n_fft,
+            hop_length,
+            win_length,
+            window_type: WindowType::Hann,
+            ..Default::default()
+        })
+        .collect();
+    let results = stft_multi_resolution(&configs, &wave).unwrap();
+    assert_eq!(results.len(), 3);
+    for (opts, res) in configs.iter().zip(&results) {
+        let single = stft_compute(opts, &wave).unwrap();
+        assert_eq!(res.n_fft, opts.n_fft);
+        assert_eq!(res.num_frames, single.num_frames);
+        assert_eq!(res.real, single.real);
+        assert_eq!(res.imag, single.imag);
+    }
+
+    let mut bad = configs.clone();
+    bad[1].win_length = 2048;
+    assert!(stft_multi_resolution(&bad, &wave).is_err());
+}
+
+#[test]
+fn test_time_stretch() {
+    use kaldi_native_fbank::{time_stretch, TimeStretchOptions};
+
+    let sr = 16000.0;
+    let wave: Vec<f32> = (0..16000)
+        .map(|i| 0.5 * (2.0 * std::f32::consts::PI * 440.0 * i as f32 / sr).sin())
+        .collect();
+    // Rising zero crossings per second, away from the edges
+    let crossings = |x: &[f32]| {
+        let mid = &x[2048..x.len() - 2048];
+        let n = mid.windows(2).filter(|w| w[0] < 0.0 && w[1] >= 0.0).count();
+        n as f32 * sr / mid.len() as f32
+    };
+
+    let mut opts = TimeStretchOptions::default();
+    let same = time_stretch(&wave, 1.0, &opts).unwrap();
+    assert_eq!(same.len(), wave.len());
+    for (a, b) in same[2048..14000].iter().zip(&wave[2048..14000]) {
+        assert!((a - b).abs() < 1e-3, "{} vs {}", a, b);
+    }
+
+    for phase_locking in [false, true] {
+        opts.phase_locking = phase_locking;
+        for rate in [0.5, 1.5, 2.0] {
+            let out = time_stretch(&wave, rate, &opts).unwrap();
+            assert_eq!(out.len(), (16000.0 / rate).round() as usize);
+            assert!((crossings(&out) - 440.0).abs() < 5.0, "rate {}", rate);
+        }
+    }
+
+    assert!(time_stretch(&[], 2.0, &opts).unwrap().is_empty());
+    assert!(time_stretch(&wave, 0.0, &opts).is_err());
+    assert!(time_stretch(&wave, f32::NAN, &opts).is_err());
+}
+
+#[test]
+fn test_pitch_shift() {
+    use kaldi_native_fbank::{pitch_shift, TimeStretchOptions};
+
+    let sr = 16000.0;
+    let wave: Vec<f32> = (0..16000)
+        .map(|i| 0.5 * (2.0 * std::f32::consts::PI * 440.0 * i as f32 / sr).sin())
+        .collect();
+    let crossings = |x: &[f32]| {
+        let mid = &x[2048..x.len() - 2048];
+        let n = mid.windows(2).filter(|w| w[0] < 0.0 && w[1] >= 0.0).count();
+        n as f32 * sr / mid.len() as f32
+    };
+
+    let opts = TimeStretchOptions::default();
+    for semitones in [12.0f32, -12.0, 7.0] {
+        let out = pitch_shift(&wave, semitones, &opts).unwrap();
+        assert_eq!(out.len(), wave.len());
+        let expected = 440.0 * 2f32.powf(semitones / 12.0);
+        assert!(
+            (crossings(&out) - expected).abs() < expected * 0.02,
+            "{} semitones: {} Hz",
+            semitones,
+            crossings(&out)
+        );
+    }
+    assert!(pitch_shift(&wave, f32::INFINITY, &opts).is_err());
+}
+
+#[test]
+fn test_wsola() {
+    use kaldi_native_fbank::{wsola, OnlineWsola, WsolaOptions};
+
+    let sr = 16000.0;
+    let wave: Vec<f32> = (0..16000)
+        .map(|i| {
+            let t = i as f32 / sr;
+            0.5 * (2.0 * std::f32::consts::PI * 220.0 * t).sin()
+                + 0.2 * (2.0 * std::f32::consts::PI * 660.0 * t).sin()
+        })
+        .collect();
+    let crossings = |x: &[f32]| {
+        let mid = &x[1000..x.len() - 1000];
+        let n = mid.windows(2).filter(|w| w[0] < 0.0 && w[1] >= 0.0).count();
+        n as f32 * sr / mid.len() as f32
+    };
+
+    let opts = WsolaOptions::default();
+    let same = wsola(&wave, 1.0, &opts).unwrap();
+    assert_eq!(same.len(), wave.len());
+    for (a, b) in same[400..15000].iter().zip(&wave[400..15000]) {
+        assert!((a - b).abs() < 1e-4);
+    }
+
+    for rate in [0.6f32, 1.3, 2.5] {
+        let out = wsola(&wave, rate, &opts).unwrap();
+        assert_eq!(out.len(), (16000.0 / rate).round() as usize);
+        assert!((crossings(&out) - 220.0).abs() < 5.0, "rate {}", rate);
+
+        // Streaming in uneven chunks gives the same samples
+        let mut online = OnlineWsola::new(opts.clone(), rate).unwrap();
+        let mut streamed = Vec::new();
+        for chunk in wave.chunks(777) {
+            streamed.extend(online.accept_waveform(chunk));
+        }
+        streamed.extend(online.finish());
+        assert_eq!(streamed, out);
+    }
+
+    assert!(wsola(&wave, -1.0, &opts).is_err());
+    let bad = WsolaOptions {
+        frame_length_ms: 0.0,
+        ..Default::default()
+    };
+    assert!(wsola(&wave, 1.0, &bad).is_err());
+}
+
+#[test]
+fn test_wiener_denoise() {
+    use kaldi_native_fbank::{wiener_denoise, WienerFilter, WienerOptions};
+
+    // Deterministic white noise
+    let mut state = 12345u32;
+    let mut noise = || {
+        state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+        (state >> 8) as f32 / (1u32 << 24) as f32 - 0.5
+    };
+    let sr = 16000.0;
+    let clean: Vec<f32> = (0..16000)
+        .map(|i| {
+            if i < 3200 {
+                0.0
+            } else {
+                (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / sr).sin()
+            }
+        })
+        .collect();
+    let noisy: Vec<f32> = clean.iter().map(|x| x + 0.3 * noise()).collect();
+
+    let opts = WienerOptions::default();
+    let out = wiener_denoise(&noisy, &opts).unwrap();
+    assert_eq!(out.len(), noisy.len());
+    let err = |x: &[f32], range: std::ops::Range<usize>| -> f32 {
+        x[range.clone()]
+            .iter()
+            .zip(&clean[range])
+            .map(|(a, b)| (a - b) * (a - b))
+            .sum()
+    };
+    // Noise-only lead-in and noisy tone both get closer to the clean signal
+    assert!(err(&out, 1000..3000) < 0.1 * err(&noisy, 1000..3000));
+    assert!(err(&out, 5000..15000) < 0.5 * err(&noisy, 5000..15000));
+
+    // A given noise spectrum skips the estimation
+    let filter = WienerFilter::with_noise_psd(opts.clone(), vec![1.0; 257]).unwrap();
+    assert_eq!(filter.noise_psd(), &[1.0; 257][..]);
+    assert!(WienerFilter::with_noise_psd(opts.clone(), vec![1.0; 10]).is_err());
+    let bad = WienerOptions {
+        alpha: 1.0,
+        ..Default::default()
+    };
+    assert!(wiener_denoise(&noisy, &bad).is_err());
+}
+
+#[test]
+fn test_spectral_gate() {
+    use kaldi_native_fbank::{spectral_gate, SpectralGate, SpectralGateOptions};
+
+    let mut state = 54321u32;
+    let mut noise = || {
+        state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+        (state >> 8) as f32 / (1u32 << 24) as f32 - 0.5
+    };
+    let sr = 16000.0;
+    let clean: Vec<f32> = (0..16000)
+        .map(|i| {
+            if (3200..12800).contains(&i) {
+                (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / sr).sin()
+            } else {
+                0.0
+            }
+        })
+        .collect();
+    let noisy: Vec<f32> = clean.iter().map(|x| x + 0.05 * noise()).collect();
+
+    let opts = SpectralGateOptions::default();
+    let out = spectral_gate(&noisy, &opts).unwrap();
+    assert_eq!(out.len(), noisy.len());
+    let energy = |x: &[f32]| x.iter().map(|v| v * v).sum::<f32>();
+    // Noise after the tone is gated, the tone itself passes
+    assert!(energy(&out[13500..15500]) < 0.1 * energy(&noisy[13500..15500]));
+    let tone = energy(&clean[5000..11000]);
+    let residual: Vec<f32> = out[5000..11000]
+        .iter()
+        .zip(&clean[5000..11000])
+        .map(|(a, b)| a - b)
+        .collect();
+    assert!(energy(&residual) < 0.01 * tone);
+
+    let gate = SpectralGate::new(opts.clone()).unwrap();
+    assert_eq!(gate.threshold().len(), 257);
+    let bad = SpectralGateOptions {
+        prop_decrease: 1.5,
+        ..Default::default()
+    };
+    assert!(spectral_gate(&noisy, &bad).is_err());
+}
+
+#[test]
+fn test_gain_perturbation() {
+    use kaldi_native_fbank::{
+        apply_gain_curve, apply_gain_db, ClipMode, GainOptions, GainPerturbation, PcmScale,
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let wave = vec![1000.0f32, -2000.0, 500.0, 0.0];
+    let opts = GainOptions::default();
+
+    let mut out = wave.clone();
+    apply_gain_db(&mut out, 20.0, &opts);
+    for (a, b) in out.iter().zip(&wave) {
+        assert!((a - 10.0 * b).abs() < 1e-2);
+    }
+
+    // Clipping-safe: Limit keeps the shape, Clip clamps, None overflows
+    let mut limited = wave.clone();
+    apply_gain_db(&mut limited, 40.0, &opts);
+    assert!((limited[1] + 32767.0).abs() < 1e-2);
+    assert!((limited[0] - 32767.0 / 2.0).abs() < 1e-1);
+    let mut clipped = wave.clone();
+    let clip = GainOptions {
+        clip: ClipMode::Clip,
+        ..opts.clone()
+    };
+    apply_gain_db(&mut clipped, 40.0, &clip);
+    assert_eq!(clipped[..2], [32767.0, -32767.0]);
+    let mut raw = wave.clone();
+    let none = GainOptions {
+        clip: ClipMode::None,
+        ..opts.clone()
+    };
+    apply_gain_db(&mut raw, 40.0, &none);
+    assert!(raw[1] < -32767.0);
+    assert_eq!(
+        GainOptions::for_scale(PcmScale::Normalized).max_amplitude,
+        32767.0 / 32768.0
+    );
+
+    // A ramp from 0 dB to -20 dB
+    let mut ramp = vec![1.0f32; 5];
+    apply_gain_curve(&mut ramp, &[0.0, -20.0], &opts);
+    assert!((ramp[0] - 1.0).abs() < 1e-6);
+    assert!((ramp[2] - 10f32.powf(-0.5)).abs() < 1e-6);
+    assert!((ramp[4] - 0.1).abs() < 1e-6);
+
+    // Random gains are reproducible with a seeded generator and stay in range
+    let perturbation = GainPerturbation::RandomRamp {
+        min_db: -6.0,
+        max_db: 6.0,
+        num_points: 4,
+    };
+    let (mut a, mut b) = (vec![100.0f32; 64], vec![100.0f32; 64]);
+    let points = perturbation
+        .apply(&mut a, &opts, &mut StdRng::seed_from_u64(7))
+        .unwrap();
+    perturbation
+        .apply(&mut b, &opts, &mut StdRng::seed_from_u64(7))
+        .unwrap();
+    assert_eq!(a, b);
+    assert_eq!(points.len(), 4);
+    assert!(points.iter().all(|db| (-6.0..=6.0).contains(db)));
+
+    let uniform = GainPerturbation::Uniform {
+        min_db: 3.0,
+        max_db: -3.0,
+    };
+    assert!(uniform
+        .apply(&mut a, &opts, &mut StdRng::seed_from_u64(7))
+        .is_err());
+}
+
+#[test]
+fn test_convolve() {
+    use kaldi_native_fbank::{convolve, reverberate, Convolver};
+
+    let direct = |x: &[f32], h: &[f32]| {
+        let mut y = vec![0.0f32; x.len() + h.len() - 1];
+        for (i, a) in x.iter().enumerate() {
+            for (j, b) in h.iter().enumerate() {
+                y[i + j] += a * b;
+            }
+        }
+        y
+    };
+    let signal: Vec<f32> = (0..3000).map(|i| ((i * 7919) % 101) as f32 / 50.0 - 1.0).collect();
+    // Decaying "room" response, longer than one block
+    let ir: Vec<f32> = (0..1500)
+        .map(|i| (-(i as f32) / 300.0).exp() * if i % 3 == 0 { 1.0 } else { -0.5 })
+        .collect();
+
+    let expected = direct(&signal, &ir);
+    let out = convolve(&signal, &ir).unwrap();
+    assert_eq!(out.len(), expected.len());
+    for (a, b) in out.iter().zip(&expected) {
+        assert!((a - b).abs() < 1e-3, "{} vs {}", a, b);
+    }
+
+    // Streaming with small partitions and uneven chunks
+    let mut convolver = Convolver::new(&ir, 128).unwrap();
+    let mut streamed = Vec::new();
+    for chunk in signal.chunks(333) {
+        streamed.extend(convolver.accept_waveform(chunk));
+    }
+    streamed.extend(convolver.flush());
+    assert_eq!(streamed.len(), expected.len());
+    for (a, b) in streamed.iter().zip(&expected) {
+        assert!((a - b).abs() < 1e-3);
+    }
+
+    // A delayed unit impulse leaves the signal untouched after alignment
+    let mut rir = vec![0.0f32; 50];
+    rir[10] = 2.0;
+    let dry = reverberate(&signal, &rir).unwrap();
+    assert_eq!(dry.len(), signal.len());
+    for (a, b) in dry.iter().zip(&signal) {
+        assert!((a - b).abs() < 1e-5);
+    }
+
+    assert!(convolve(&signal, &[]).is_err());
+    assert!(reverberate(&signal, &[0.0; 4]).is_err());
+}
+
+#[test]
+fn test_fir_filter() {
+    use kaldi_native_fbank::{fir_filter, FirFilter};
+
+    let lfilter = |b: &[f32], x: &[f32]| -> Vec<f32> {
+        (0..x.len())
+            .map(|i| {
+                b.iter()
+                    .enumerate()
+                    .filter(|(k, _)| *k <= i)
+                    .map(|(k, t)| t * x[i - k])
+                    .sum()
+            })
+            .collect()
+    };
+    let signal: Vec<f32> = (0..2000).map(|i| ((i * 37) % 29) as f32 - 14.0).collect();
+    let taps: Vec<f32> = (0..63).map(|k| ((k as f32 - 31.0) * 0.2).cos() / 63.0).collect();
+
+    let expected = lfilter(&taps, &signal);
+    let out = fir_filter(&taps, &signal).unwrap();
+    assert_eq!(out.len(), signal.len());
+    for (a, b) in out.iter().zip(&expected) {
+        assert!((a - b).abs() < 1e-3, "{} vs {}", a, b);
+    }
+
+    // Chunk sizes smaller and larger than the FFT block give the same output
+    for chunk_size in [1, 7, 100, 1500] {
+        let mut filter = FirFilter::new(&taps).unwrap();
+        let streamed: Vec<f32> = signal
+            .chunks(chunk_size)
+            .flat_map(|c| filter.process(c))
+            .collect();
+        for (a, b) in streamed.iter().zip(&expected) {
+            assert!((a - b).abs() < 1e-3);
+        }
+    }
+
+    // A single tap is a plain gain
+    let gained = fir_filter(&[2.0], &[1.0, -3.0]).unwrap();
+    assert!((gained[0] - 2.0).abs() < 1e-5 && (gained[1] + 6.0).abs() < 1e-5);
+    assert!(FirFilter::new(&[]).is_err());
+}
+
+#[test]
+fn test_biquad_filters() {
+    use kaldi_native_fbank::{Biquad, BiquadCascade, BiquadType};
+
+    let sr = 16000.0;
+    // Steady-state gain for a sine at `freq`, skipping the transient
+    let gain = |process: &mut dyn FnMut(&[f32]) -> Vec<f32>, freq: f32| {
+        let x: Vec<f32> = (0..8000)
+            .map(|i| (2.0 * std::f32::consts::PI * freq * i as f32 / sr).sin())
+            .collect();
+        let y = process(&x);
+        let rms = |v: &[f32]| (v.iter().map(|a| a * a).sum::<f32>() / v.len() as f32).sqrt();
+        rms(&y[4000..]) / rms(&x[4000..])
+    };
+    let q = std::f32::consts::FRAC_1_SQRT_2;
+    let biquad = |kind| Biquad::new(kind, sr, 1000.0, q).unwrap();
+
+    let mut lp = biquad(BiquadType::LowPass);
+    assert!((gain(&mut |x| lp.process(x), 100.0) - 1.0).abs() < 0.01);
+    assert!((gain(&mut |x| lp.process(x), 1000.0) - q).abs() < 0.01);
+    assert!(gain(&mut |x| lp.process(x), 6000.0) < 0.05);
+
+    let mut hp = biquad(BiquadType::HighPass);
+    assert!(gain(&mut |x| hp.process(x), 100.0) < 0.02);
+    assert!((gain(&mut |x| hp.process(x), 6000.0) - 1.0).abs() < 0.01);
+
+    let mut bp = biquad(BiquadType::BandPass);
+    assert!((gain(&mut |x| bp.process(x), 1000.0) - 1.0).abs() < 0.01);
+    let mut notch = biquad(BiquadType::Notch);
+    assert!(gain(&mut |x| notch.process(x), 1000.0) < 0.01);
+
+    let mut shelf = biquad(BiquadType::LowShelf { gain_db: 6.0 });
+    assert!((gain(&mut |x| shelf.process(x), 50.0) - 10f32.powf(0.3)).abs() < 0.02);
+    assert!((gain(&mut |x| shelf.process(x), 7000.0) - 1.0).abs() < 0.02);
+    let mut shelf = biquad(BiquadType::HighShelf { gain_db: -6.0 });
+    assert!((gain(&mut |x| shelf.process(x), 7000.0) - 10f32.powf(-0.3)).abs() < 0.02);
+
+    // Telephone band keeps 1 kHz and removes rumble and high frequencies
+    let mut phone = BiquadCascade::telephone_band(sr).unwrap();
+    assert_eq!(phone.stages.len(), 4);
+    assert!((gain(&mut |x| phone.process(x), 1000.0) - 1.0).abs() < 0.02);
+    assert!(gain(&mut |x| phone.process(x), 60.0) < 0.01);
+    assert!(gain(&mut |x| phone.process(x), 7000.0) < 0.01);
+
+    // Streaming in chunks matches one call
+    let x: Vec<f32> = (0..1000).map(|i| ((i * 13) % 17) as f32).collect();
+    let mut a = BiquadCascade::butterworth(BiquadType::HighPass, 4, sr, 80.0).unwrap();
+    let whole = a.process(&x);
+    a.reset();
+    let chunked: Vec<f32> = x.chunks(37).flat_map(|c| a.process(c)).collect();
+    assert_eq!(whole, chunked);
+
+    assert!(Biquad::new(BiquadType::LowPass, sr, 9000.0, q).is_err());
+    assert!(BiquadCascade::butterworth(BiquadType::LowPass, 3, sr, 1000.0).is_err());
+    assert!(BiquadCascade::butterworth(BiquadType::Notch, 2, sr, 1000.0).is_err());
+}
+
+#[test]
+fn test_highpass_prefilter() {
+    use kaldi_native_fbank::{compute_features, FirstOrderHighPass};
+
+    let sr = 16000.0;
+    let wave: Vec<f32> = (0..8000)
+        .map(|i| {
+            let t = i as f32 / sr;
+            1000.0 * (2.0 * std::f32::consts::PI * 1000.0 * t).sin()
+                + 20000.0 * (2.0 * std::f32::consts::PI * 30.0 * t).sin()
+        })
+        .collect();
+
+    // The filter removes most of the rumble and keeps the tone
+    let mut highpass = FirstOrderHighPass::new(sr, 200.0).unwrap();
+    let prefiltered = highpass.process(&wave);
+    let rms = |x: &[f32]| (x.iter().map(|v| v * v).sum::<f32>() / x.len() as f32).sqrt();
+    assert!(rms(&prefiltered[4000..]) < 0.25 * rms(&wave[4000..]));
+    assert!(rms(&prefiltered[4000..]) > 0.9 * 1000.0 / 2f32.sqrt());
+
+    // With `highpass_cutoff` set, features are those of the filtered audio
+    let mut opts = FbankOptions::default();
+    opts.frame_opts.dither = 0.0;
+    let expected = compute_features(&prefiltered, &opts).unwrap();
+    opts.frame_opts.highpass_cutoff = 200.0;
+    let filtered = compute_features(&wave, &opts).unwrap();
+    assert_eq!(filtered.data, expected.data);
+
+    // The online path filters across chunk boundaries like the offline one
+    opts.frame_opts.highpass_cutoff = 200.0;
+    let mut online = OnlineFeature::new(FeatureComputer::Fbank(
+        FbankComputer::new(opts.clone()).unwrap(),
+    ));
+    for chunk in wave.chunks(333) {
+        online.accept_waveform(sr, chunk).unwrap();
+    }
+    online.input_finished();
+    assert_eq!(online.num_frames_ready(), filtered.num_frames);
+    for (i, row) in filtered.data.chunks(filtered.dim).enumerate() {
+        for (a, b) in online.get_frame(i).unwrap().iter().zip(row) {
+            assert!((a - b).abs() < 1e-3);
+        }
+    }
+
+    opts.frame_opts.highpass_cutoff = 9000.0;
+    assert!(FbankComputer::new(opts).is_err());
+}
+
+#[test]
+fn test_pre_de_emphasis() {
+    use kaldi_native_fbank::{DeEmphasis, PreEmphasis};
+
+    let wave: Vec<f32> = (0..1000).map(|i| ((i * 31) % 23) as f32 - 11.0).collect();
+
+    // One frame matches the in-frame pre-emphasis of extract_window
+    let opts = FrameOptions {
+        dither: 0.0,
+        remove_dc_offset: false,
+        preemph_coeff: 0.97,
+        ..Default::default()
+    };
+    let frame = &wave[..opts.window_size()];
+    let mut buf = vec![0.0; opts.padded_window_size()];
+    extract_window(0, frame, 0, &opts, None, &mut buf).unwrap();
+    let emphasized = PreEmphasis::new(0.97).unwrap().process(frame);
+    for (a, b) in emphasized.iter().zip(&buf) {
+        assert!((a - b).abs() < 1e-4);
+    }
+
+    // Streaming in chunks carries the last sample, and de-emphasis undoes it
+    let mut pre = PreEmphasis::new(0.97).unwrap();
+    let mut de = DeEmphasis::new(0.97).unwrap();
+    let whole = PreEmphasis::new(0.97).unwrap().process(&wave);
+    let mut streamed = Vec::new();
+    let mut restored = Vec::new();
+    for chunk in wave.chunks(77) {
+        let y = pre.process(chunk);
+        restored.extend(de.process(&y));
+        streamed.extend(y);
+    }
+    assert_eq!(streamed, whole);
+    for (a, b) in restored.iter().zip(&wave) {
+        assert!((a - b).abs() < 1e-2, "{} vs {}", a, b);
+    }
+
+    assert!(PreEmphasis::new(1.5).is_err());
+    assert!(DeEmphasis::new(1.0).is_err());
+}
+
+#[test]
+fn test_agc() {
+    use kaldi_native_fbank::{Agc, AgcOptions};
+
+    let sr = 16000.0;
+    let sine = |amp: f32, n: usize| -> Vec<f32> {
+        (0..n)
+            .map(|i| amp * (2.0 * std::f32::consts::PI * 440.0 * i as f32 / sr).sin())
+            .collect()
+    };
+    let rms = |x: &[f32]| (x.iter().map(|v| v * v).sum::<f32>() / x.len() as f32).sqrt();
+    let opts = AgcOptions::default();
+
+    // Quiet and loud passages both end up near the target level
+    let mut wave = sine(300.0, 48000);
+    wave.extend(sine(20000.0, 48000));
+    let out = Agc::new(opts.clone()).unwrap().process(&wave);
+    let target = opts.target_rms;
+    assert!((rms(&out[40000..48000]) - target).abs() < 0.15 * target);
+    assert!((rms(&out[88000..96000]) - target).abs() < 0.15 * target);
+
+    // The gain is capped, and silence below the gate keeps unit gain
+    let mut agc = Agc::new(opts.clone()).unwrap();
+    agc.process(&sine(50.0, 48000));
+    assert!((agc.gain() - 10f32.powf(1.5)).abs() < 0.5);
+    agc.reset();
+    agc.process(&sine(10.0, 16000));
+    assert_eq!(agc.gain(), 1.0);
+
+    // Streaming in chunks matches one call
+    let mut agc = Agc::new(opts.clone()).unwrap();
+    let chunked: Vec<f32> = wave.chunks(1000).flat_map(|c| agc.process(c)).collect();
+    assert_eq!(chunked, out);
+
+    let bad = AgcOptions {
+        attack_ms: 0.0,
+        ..Default::default()
+    };
+    assert!(Agc::new(bad).is_err());
+}
+
+#[test]
+fn test_compressor() {
+    use kaldi_native_fbank::{Compressor, CompressorOptions};
+
+    // Square waves keep a constant level, so the settled gain is the static one
+    let square = |amp: f32, n: usize| -> Vec<f32> {
+        (0..n)
+            .map(|i| if (i / 20) % 2 == 0 { amp } else { -amp })
+            .collect()
+    };
+    let db = |x: f32| 20.0 * (x.abs() / 32768.0).log10();
+    let hard = CompressorOptions {
+        knee_db: 0.0,
+        ..Default::default()
+    };
+
+    // -6 dBFS in, 4:1 above -20 dBFS: -20 + 14 / 4 = -16.5 dBFS out
+    let mut comp = Compressor::new(hard.clone()).unwrap();
+    let out = comp.process(&square(32768.0 * 10f32.powf(-6.0 / 20.0), 8000));
+    assert!((db(out[7999]) + 16.5).abs() < 0.1);
+    assert!((comp.gain_reduction_db() - 10.5).abs() < 0.1);
+
+    // Below the threshold nothing changes; makeup gain is added on top
+    let mut comp = Compressor::new(CompressorOptions {
+        makeup_db: 6.0,
+        ..hard.clone()
+    })
+    .unwrap();
+    let quiet = square(32768.0 * 10f32.powf(-30.0 / 20.0), 8000);
+    let out = comp.process(&quiet);
+    assert!((db(out[7999]) + 24.0).abs() < 0.1);
+
+    // A limiter holds the level at the threshold
+    let mut limiter = Compressor::new(CompressorOptions {
+        ratio: f32::INFINITY,
+        ..hard.clone()
+    })
+    .unwrap();
+    let out = limiter.process(&square(30000.0, 8000));
+    assert!((db(out[7999]) + 20.0).abs() < 0.1);
+
+    // The soft knee is continuous and meets the hard curve outside the knee
+    let soft = Compressor::new(CompressorOptions::default()).unwrap();
+    assert_eq!(soft.static_curve(-30.0), -30.0);
+    assert!((soft.static_curve(-10.0) + 17.5).abs() < 1e-4);
+    assert!((soft.static_curve(-23.0) + 23.0).abs() < 1e-4);
+    assert!((soft.static_curve(-17.0) + 19.25).abs() < 1e-4);
+
+    let bad = CompressorOptions {
+        ratio: 0.5,
+        ..Default::default()
+    };
+    assert!(Compressor::new(bad).is_err());
+}
+
+#[test]
+fn test_endpointing() {
+    use kaldi_native_fbank::{EndpointOptions, Endpointer, EnergyVad};
+
+    // 0.5 s of speech then silence: rule 1 (1.2 s after speech) fires
+    let mut ep = Endpointer::new(EndpointOptions::default()).unwrap();
+    let mut events = Vec::new();
+    for t in 0..500 {
+        if let Some(e) = ep.accept_frame(t < 50) {
+            events.push(e);
+        }
+    }
+    assert_eq!(events[0].rule, 1);
+    assert_eq!(events[0].start_frame, 0);
+    assert_eq!(events[0].end_frame, 170);
+    assert_eq!(events[0].trailing_silence_frames, 120);
+    // Then 2.4 s of pure silence fires rule 0
+    assert_eq!(events[1].rule, 0);
+    assert_eq!((events[1].start_frame, events[1].end_frame), (170, 410));
+    assert_eq!(events.len(), 2);
+
+    // Continuous speech is cut every 20 s
+    ep.reset();
+    let cuts: Vec<_> = (0..4500).filter_map(|_| ep.accept_frame(true)).collect();
+    assert_eq!(cuts.len(), 2);
+    assert!(cuts.iter().all(|e| e.rule == 2));
+    assert_eq!(cuts[1].end_frame, 4000);
+
+    // Driven by MFCC C0 of an online stream, chunk by chunk
+    let sr = 16000.0;
+    let mut wave: Vec<f32> = (0..8000)
+        .map(|i| 1000.0 * (2.0 * std::f32::consts::PI * 440.0 * i as f32 / sr).sin())
+        .collect();
+    wave.resize(48000, 0.0);
+    let mut online = OnlineFeature::new(FeatureComputer::Mfcc(
+        MfccComputer::new(MfccOptions::default()).unwrap(),
+    ));
+    let vad = EnergyVad {
+        threshold: 5.0,
+        index: Some(0),
+    };
+    let mut ep = Endpointer::new(EndpointOptions::default()).unwrap();
+    let mut found = None;
+    for chunk in wave.chunks(1600) {
+        online.accept_waveform(sr, chunk).unwrap();
+        if let Some(e) = ep.update(&online, |f| vad.is_speech(f)) {
+            found = Some(e);
+            break;
+        }
+    }
+    let e = found.unwrap();
+    assert_eq!(e.rule, 1);
+    assert_eq!(e.trailing_silence_frames, 120);
+    // Speech ends around frame 50 (0.5 s)
+    assert!((e.end_frame as i64 - 170).abs() <= 3, "{:?}", e);
+
+    let bad = EndpointOptions {
+        frame_shift_ms: 0.0,
+        ..Default::default()
+    };
+    assert!(Endpointer::new(bad).is_err());
+}
+
+#[test]
+fn test_signal_stats() {
+    use kaldi_native_fbank::SignalStats;
+
+    let mut wave: Vec<f32> = (0..16000)
+        .map(|i| 100.0 + 1000.0 * (2.0 * std::f32::consts::PI * 50.0 * i as f32 / 16000.0).sin())
+        .collect();
+    wave[10] = 32767.0;
+    wave[20] = -32768.0;
+
+    let stats = SignalStats::compute(&wave, 16000.0, PcmScale::Kaldi).unwrap();
+    assert_eq!(stats.num_samples, 16000);
+    assert_eq!(stats.num_clipped, 2);
+    assert_eq!(stats.peak, 32768.0);
+    assert!((stats.duration() - 1.0).abs() < 1e-6);
+    let mean = wave.iter().map(|&x| x as f64).sum::<f64>() / 16000.0;
+    let rms = (wave.iter().map(|&x| x as f64 * x as f64).sum::<f64>() / 16000.0).sqrt();
+    assert!((stats.dc_offset() as f64 - mean).abs() < 1e-3);
+    assert!((stats.rms() as f64 - rms).abs() < 1e-2);
+
+    // Streaming in chunks, or merging partial statistics, gives the same result
+    let mut streamed = SignalStats::for_scale(16000.0, PcmScale::Kaldi).unwrap();
+    for chunk in wave.chunks(777) {
+        streamed.accept_waveform(chunk);
+    }
+    assert_eq!(streamed, stats);
+    let mut merged = SignalStats::compute(&wave[..5000], 16000.0, PcmScale::Kaldi).unwrap();
+    merged.merge(&SignalStats::compute(&wave[5000..], 16000.0, PcmScale::Kaldi).unwrap());
+    assert_eq!(merged.num_clipped, 2);
+    assert!((merged.rms() - stats.rms()).abs() < 1e-3);
+
+    // Normalized samples clip at full scale too
+    let stats = SignalStats::compute(&[0.5, -1.0, 0.99999], 16000.0, PcmScale::Normalized).unwrap();
+    assert_eq!(stats.num_clipped, 2);
+
+    let empty = SignalStats::for_scale(16000.0, PcmScale::Kaldi).unwrap();
+    assert_eq!((empty.rms(), empty.dc_offset(), empty.clipped_fraction()), (0.0, 0.0, 0.0));
+    assert!(SignalStats::new(0.0, 1.0).is_err());
+}
+
+#[test]
+fn test_loudness() {
+    use kaldi_native_fbank::{integrated_loudness, normalize_loudness, LoudnessMeter, LoudnessOptions};
+
+    let sr = 48000.0;
+    let tone = |amp: f32, secs: f32| -> Vec<f32> {
+        (0..(sr * secs) as usize)
+            .map(|i| amp * (2.0 * std::f32::consts::PI * 997.0 * i as f32 / sr).sin())
+            .collect()
+    };
+    let opts = LoudnessOptions {
+        samp_freq: sr,
+        full_scale: 1.0,
+        ..Default::default()
+    };
+
+    // A full-scale 997 Hz sine reads -3.01 LUFS
+    let wave = tone(1.0, 3.0);
+    let lufs = integrated_loudness(&wave, &opts).unwrap();
+    assert!((lufs + 3.01).abs() < 0.05, "{}", lufs);
+
+    // Silence is gated out
+    let mut padded = wave.clone();
+    padded.resize(wave.len() * 2, 0.0);
+    let gated = integrated_loudness(&padded, &opts).unwrap();
+    assert!((gated - lufs).abs() < 0.3, "{} vs {}", gated, lufs);
+    assert_eq!(integrated_loudness(&vec![0.0; 48000], &opts).unwrap(), f32::NEG_INFINITY);
+
+    // Streaming matches, and stereo with identical channels adds 3 dB
+    let mut meter = LoudnessMeter::new(opts.clone()).unwrap();
+    for chunk in wave.chunks(1001) {
+        meter.accept_waveform(chunk);
+    }
+    assert_eq!(meter.integrated(), lufs);
+    assert_eq!(meter.num_blocks(), 27);
+    let stereo: Vec<f32> = wave.iter().flat_map(|&x| [x, x]).collect();
+    let stereo_opts = LoudnessOptions {
+        num_channels: 2,
+        ..opts.clone()
+    };
+    let lufs2 = integrated_loudness(&stereo, &stereo_opts).unwrap();
+    assert!((lufs2 - lufs - 3.0103).abs() < 0.01);
+
+    // Normalizing in Kaldi scale at 16 kHz hits the target
+    let mut speechlike: Vec<f32> = (0..32000)
+        .map(|i| {
+            let t = i as f32 / 16000.0;
+            3000.0 * (2.0 * std::f32::consts::PI * 300.0 * t).sin() * (1.0 + (6.0 * t).sin())
+        })
+        .collect();
+    let kaldi = LoudnessOptions::default();
+    let gain = normalize_loudness(&mut speechlike, &kaldi, -23.0).unwrap();
+    assert!(gain.is_finite());
+    let after = integrated_loudness(&speechlike, &kaldi).unwrap();
+    assert!((after + 23.0).abs() < 0.05, "{}", after);
+
+    assert!(normalize_loudness(&mut [0.0; 100], &kaldi, -23.0).is_err());
+    let bad = LoudnessOptions {
+        channel_weights: vec![1.0],
+        num_channels: 2,
+        ..Default::default()
+    };
+    assert!(LoudnessMeter::new(bad).is_err());
+}
+
+#[test]
+fn test_a_weighting() {
+    use kaldi_native_fbank::{a_weight, BiquadCascade};
+
+    // IEC 61672-1 table values
+    let a = BiquadCascade::a_weighting(48000.0).unwrap();
+    for (freq, expected, tol) in [
+        (31.5, -39.4, 0.2),
+        (100.0, -19.1, 0.1),
+        (1000.0, 0.0, 1e-4),
+        (2000.0, 1.2, 0.1),
+        (4000.0, 1.0, 0.2),
+        // The bilinear transform bends the response down near Nyquist
+        (8000.0, -1.1, 0.7),
+    ] {
+        let db = a.magnitude_db(freq, 48000.0);
+        assert!((db - expected).abs() < tol, "{} Hz: {} dB", freq, db);
+    }
+
+    // Filtering passes 1 kHz and attenuates 100 Hz by about 19 dB
+    let sr = 16000.0;
+    let rms_db = |freq: f32| {
+        let wave: Vec<f32> = (0..16000)
+            .map(|i| (2.0 * std::f32::consts::PI * freq * i as f32 / sr).sin())
+            .collect();
+        let out = a_weight(&wave, sr).unwrap();
+        let tail = &out[8000..];
+        10.0 * (tail.iter().map(|x| x * x).sum::<f32>() / tail.len() as f32 * 2.0).log10()
+    };
+    assert!(rms_db(1000.0).abs() < 0.05);
+    assert!((rms_db(100.0) + 19.1).abs() < 0.2);
+
+    assert!(BiquadCascade::a_weighting(1000.0).is_err());
+}
+
+#[test]
+fn test_decibel_conversions() {
+    use kaldi_native_fbank::{
+        amplitude_to_db, db_to_amplitude, db_to_power, power_to_db, DbOptions, DbReference,
+    };
+
+    let no_top = DbOptions {
+        top_db: None,
+        ..Default::default()
+    };
+    let mut power = vec![1.0, 10.0, 100.0, 0.0, -1e-3];
+    power_to_db(&mut power, &no_top).unwrap();
+    assert_eq!(power[..3], [0.0, 10.0, 20.0]);
+    assert!((power[3] + 100.0).abs() < 1e-4);
+    assert!((power[4] + 30.0).abs() < 1e-4);
+
+    // librosa defaults: top_db = 80, amplitude amin = 1e-5
+    let mut amp = vec![1.0, 0.1, 0.0];
+    amplitude_to_db(&mut amp, &DbOptions::default()).unwrap();
+    assert!((amp[1] + 20.0).abs() < 1e-4);
+    assert!((amp[2] + 80.0).abs() < 1e-4);
+
+    // ref=np.max puts the peak at 0 dB
+    let mut amp = vec![2.0, 1.0, 0.5];
+    let opts = DbOptions {
+        reference: DbReference::Max,
+        ..Default::default()
+    };
+    amplitude_to_db(&mut amp, &opts).unwrap();
+    for (x, e) in amp.iter().zip([0.0, -6.0206, -12.0412]) {
+        assert!((x - e).abs() < 1e-3);
+    }
+
+    // Round trips with a reference
+    let orig = vec![0.5f32, 3.0, 42.0];
+    let with_ref = DbOptions {
+        reference: DbReference::Value(2.0),
+        top_db: None,
+        ..Default::default()
+    };
+    let mut x = orig.clone();
+    power_to_db(&mut x, &with_ref).unwrap();
+    db_to_power(&mut x, 2.0);
+    let mut y = orig.clone();
+    amplitude_to_db(&mut y, &with_ref).unwrap();
+    db_to_amplitude(&mut y, 2.0);
+    for ((a, b), o) in x.iter().zip(&y).zip(&orig) {
+        assert!((a - o).abs() < 1e-4 * o && (b - o).abs() < 1e-4 * o);
+    }
+
+    let bad = DbOptions {
+        top_db: Some(-1.0),
+        ..Default::default()
+    };
+    assert!(power_to_db(&mut [1.0], &bad).is_err());
+}
+
+#[test]
+fn test_gammatone_gfcc() {
+    use kaldi_native_fbank::offline::compute_features_with;
+    use kaldi_native_fbank::{
+        erb_rate_to_hz, hz_to_erb_rate, Compression, GammatoneComputer, GammatoneFbankOptions,
+        GfccComputer, GfccOptions,
+    };
+
+    assert!((hz_to_erb_rate(1000.0) - 15.62).abs() < 0.01);
+    assert!((erb_rate_to_hz(hz_to_erb_rate(3000.0)) - 3000.0).abs() < 0.1);
+
+    let fbank_opts = GammatoneFbankOptions::default();
+    let mut fbank = GammatoneComputer::new(fbank_opts.clone()).unwrap();
+    let banks = fbank.banks().clone();
+    let centers = banks.center_freqs();
+    assert_eq!(centers.len(), 64);
+    assert!((centers[0] - 50.0).abs() < 1e-2);
+    assert!((centers[63] - 8000.0).abs() < 1.0);
+    // Centers are evenly spaced in ERB rate
+    let erbs: Vec<f32> = centers.iter().map(|&f| hz_to_erb_rate(f)).collect();
+    let step = erbs[1] - erbs[0];
+    assert!(erbs.windows(2).all(|w| (w[1] - w[0] - step).abs() < 1e-3));
+    // 512-point FFT at 16 kHz: each filter peaks at the bin nearest its center
+    for (bin, &fc) in centers.iter().enumerate().skip(10) {
+        let nearest = (fc / 31.25).round() as usize;
+        let peak = (0..256)
+            .max_by(|&a, &b| banks.weight(bin, a).total_cmp(&banks.weight(bin, b)))
+            .unwrap();
+        assert_eq!(peak, nearest.min(255));
+    }
+
+    // A 1 kHz tone excites the filter centred nearest 1 kHz most
+    let wave: Vec<f32> = (0..16000)
+        .map(|i| 1000.0 * (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / 16000.0).sin())
+        .collect();
+    let feats = compute_features_with(&mut fbank, &wave).unwrap();
+    let row = feats.row(10);
+    let loudest = (0..64).max_by(|&a, &b| row[a].total_cmp(&row[b])).unwrap();
+    let nearest = (0..64)
+        .min_by(|&a, &b| (centers[a] - 1000.0).abs().total_cmp(&(centers[b] - 1000.0).abs()))
+        .unwrap();
+    assert_eq!(loudest, nearest);
+
+    // Log compression is the floored log of the linear energies
+    let mut log_opts = fbank_opts;
+    log_opts.frame_opts.dither = 0.0;
+    let logs = compute_features_with(&mut GammatoneComputer::new(log_opts.clone()).unwrap(), &wave);
+    log_opts.compression = Compression::None;
+    let linear = compute_features_with(&mut GammatoneComputer::new(log_opts).unwrap(), &wave);
+    for (l, e) in logs.unwrap().data.iter().zip(&linear.unwrap().data) {
+        assert!((l - e.max(1e-20).ln()).abs() < 1e-4);
+    }
+
+    // GFCC online matches offline
+    let opts = GfccOptions::default();
+    let mut gfcc = GfccComputer::new(opts.clone()).unwrap();
+    let offline = compute_features_with(&mut gfcc, &wave).unwrap();
+    assert_eq!(offline.dim, 23);
+    let mut online = OnlineFeature::new(GfccComputer::new(opts).unwrap());
+    for chunk in wave.chunks(1234) {
+        online.accept_waveform(16000.0, chunk).unwrap();
+    }
+    online.input_finished();
+    assert_eq!(online.num_frames_ready(), offline.num_frames);
+    for t in 0..offline.num_frames {
+        for (a, b) in online.get_frame(t).unwrap().iter().zip(offline.row(t)) {
+            assert!((a - b).abs() <= 1e-3 * b.abs().max(1.0));
+        }
+    }
+
+    let bad = GfccOptions {
+        num_ceps: 0,
+        ..Default::default()
+    };
+    assert!(GfccComputer::new(bad).is_err());
+}
+
+#[test]
+fn test_erb_scale_banks() {
+    use kaldi_native_fbank::hz_to_erb_rate;
+
+    let fopts = FrameOptions::default();
+    let mut mopts = MelOptions::default();
+    mopts.num_bins = 40;
+    mopts.frequency_scale = FrequencyScale::Erb;
+    for is_librosa in [false, true] {
+        mopts.is_librosa = is_librosa;
+        let banks = MelBanks::new(&mopts, &fopts, 1.0).unwrap();
+        // Triangle edges are evenly spaced in ERB rate from low_freq to Nyquist
+        let step = (hz_to_erb_rate(8000.0) - hz_to_erb_rate(20.0)) / 41.0;
+        for (bin, edges) in banks.edges_hz.iter().enumerate() {
+            let expected = hz_to_erb_rate(20.0) + (bin + 1) as f32 * step;
+            assert!((hz_to_erb_rate(edges[1]) - expected).abs() < 1e-3);
+            assert!((banks.edges_mel[bin][1] - expected).abs() < 1e-3);
+        }
+    }
+
+    // ERB spacing puts more low-frequency bins than the mel scale
+    mopts.is_librosa = false;
+    let erb = MelBanks::new(&mopts, &fopts, 1.0).unwrap().center_freqs();
+    mopts.frequency_scale = FrequencyScale::Mel;
+    let mel = MelBanks::new(&mopts, &fopts, 1.0).unwrap().center_freqs();
+    let below = |c: &[f32]| c.iter().filter(|&&f| f < 1000.0).count();
+    assert!(below(&erb) > below(&mel));
+
+    // VTLN warping works on ERB-spaced banks too
+    mopts.frequency_scale = FrequencyScale::Erb;
+    let warped = MelBanks::new(&mopts, &fopts, 0.9).unwrap();
+    assert_ne!(warped.to_dense(), MelBanks::new(&mopts, &fopts, 1.0).unwrap().to_dense());
+}
+
+#[test]
+fn test_linear_banks_lfcc() {
+    use kaldi_native_fbank::offline::compute_features_with;
+    use kaldi_native_fbank::{LfccComputer, LfccOptions};
+
+    let fopts = FrameOptions::default();
+    let mut mopts = MelOptions::default();
+    mopts.num_bins = 20;
+    mopts.low_freq = 0.0;
+    mopts.frequency_scale = FrequencyScale::Linear;
+    for is_librosa in [false, true] {
+        mopts.is_librosa = is_librosa;
+        let banks = MelBanks::new(&mopts, &fopts, 1.0).unwrap();
+        for (bin, edges) in banks.edges_hz.iter().enumerate() {
+            let expected = [bin, bin + 1, bin + 2].map(|k| k as f32 * 8000.0 / 21.0);
+            for (e, x) in edges.iter().zip(expected) {
+                assert!((e - x).abs() < 1e-2, "{:?} vs {:?}", edges, expected);
+            }
+        }
+    }
+    let mut opts = LfccOptions::default();
+    opts.frame_opts.dither = 0.0;
+    let mut lfcc = LfccComputer::new(opts.clone()).unwrap();
+    assert_eq!(lfcc.dim(), 20);
+    let wave: Vec<f32> = (0..8000)
+        .map(|i| 1000.0 * (i as f32 * 0.3).sin() + 300.0 * (i as f32 * 1.7).cos())
+        .collect();
+    let feats = compute_features_with(&mut lfcc, &wave).unwrap();
+    let mut mfcc = MfccComputer::new(opts.to_mfcc_options()).unwrap();
+    assert_eq!(feats, compute_features_with(&mut mfcc, &wave).unwrap());
+    // Linear and mel banks give different cepstra
+    let mut mel_opts = opts.to_mfcc_options();
+    mel_opts.mel_opts.frequency_scale = FrequencyScale::Mel;
+    let mut mel = MfccComputer::new(mel_opts).unwrap();
+    assert_ne!(feats, compute_features_with(&mut mel, &wave).unwrap());
+
+    opts.num_ceps = 21;
+    assert!(LfccComputer::new(opts).is_err());
+}
+
+#[test]
+fn test_onset_detection() {
+    use kaldi_native_fbank::offline::compute_features;
+    use kaldi_native_fbank::{
+        onset_detect, onset_strength, OnsetAggregate, OnsetDetectOptions, OnsetStrengthOptions,
+    };
+
+    // Decaying 0.2 s tone bursts starting every 0.5 s, on a quiet noise floor
+    let sr = 16000.0;
+    let mut rng = 12345u32;
+    let wave: Vec<f32> = (0..48000)
+        .map(|i| {
+            rng = rng.wrapping_mul(1664525).wrapping_add(1013904223);
+            let noise = (rng >> 8) as f32 / (1 << 24) as f32 - 0.5;
+            let t = (i % 8000) as f32 / sr;
+            let burst = if i >= 4000 && t < 0.2 {
+                8000.0 * (-t * 50.0).exp() * (2.0 * std::f32::consts::PI * 660.0 * i as f32 / sr).sin()
+            } else {
+                0.0
+            };
+            burst + 10.0 * noise
+        })
+        .collect();
+    let mut opts = FbankOptions::default();
+    opts.frame_opts.dither = 0.0;
+    opts.use_energy = false;
+    opts.mel_opts.num_bins = 40;
+    let feats = compute_features(&wave, &opts).unwrap();
+
+    let strength = onset_strength(&feats, &OnsetStrengthOptions::default()).unwrap();
+    assert_eq!(strength.len(), feats.num_frames);
+    assert_eq!(strength[0], 0.0);
+    assert!(strength.iter().all(|&x| x >= 0.0));
+
+    // Bursts start at 0.5, 1.0, 1.5, 2.0 and 2.5 s; frames are 10 ms apart
+    // and span 25 ms, so an onset shows up in the first frame reaching it
+    let onsets = onset_detect(&strength, &OnsetDetectOptions::default()).unwrap();
+    assert_eq!(onsets.len(), 5, "{:?}", onsets);
+    for (k, &frame) in onsets.iter().enumerate() {
+        let expected = 50 * (k + 1) - 2;
+        assert!((frame as i64 - expected as i64).abs() <= 2, "{:?}", onsets);
+    }
+
+    // SuperFlux-style maximum filtering and median aggregation still find them
+    let superflux = OnsetStrengthOptions {
+        max_size: 3,
+        aggregate: OnsetAggregate::Median,
+        ..Default::default()
+    };
+    let strength = onset_strength(&feats, &superflux).unwrap();
+    assert_eq!(onset_detect(&strength, &OnsetDetectOptions::default()).unwrap().len(), 5);
+
+    let bad = OnsetStrengthOptions {
+        lag: 0,
+        ..Default::default()
+    };
+    assert!(onset_strength(&feats, &bad).is_err());
+}
+
+#[test]
+fn test_tuning_estimation() {
+    use kaldi_native_fbank::{estimate_tuning, pitch_tuning, TuningOptions};
+
+    // Deviations from A440 in fractions of a semitone
+    let cents = |c: f32| 440.0 * 2f32.powf(c / 1200.0);
+    assert!((pitch_tuning(&[cents(-10.0)], 0.01, 12).unwrap() + 0.1).abs() < 0.011);
+    let mixed = [cents(20.0), cents(1220.0), cents(-380.0), cents(-45.0), 0.0];
+    assert!((pitch_tuning(&mixed, 0.01, 12).unwrap() - 0.2).abs() < 0.011);
+    assert_eq!(pitch_tuning(&[], 0.01, 12).unwrap(), 0.0);
+
+    // A harmonic tone on A3 tuned 25 cents sharp
+    let f0 = 220.0 * 2f32.powf(25.0 / 1200.0);
+    let sr = 16000.0;
+    let wave: Vec<f32> = (0..32000)
+        .map(|i| {
+            let t = i as f32 / sr;
+            (1..=6)
+                .map(|h| (2.0 * std::f32::consts::PI * f0 * h as f32 * t).sin() / h as f32)
+                .sum::<f32>()
+        })
+        .collect();
+    let tuning = estimate_tuning(&wave, &TuningOptions::default()).unwrap();
+    assert!((tuning - 0.25).abs() <= 0.02, "{}", tuning);
+
+    let bad = TuningOptions {
+        resolution: 0.0,
+        ..Default::default()
+    };
+    assert!(estimate_tuning(&wave, &bad).is_err());
+}
+
+#[test]
+fn test_lpc_analysis() {
+    use kaldi_native_fbank::offline::compute_features_with;
+    use kaldi_native_fbank::{levinson_durbin, lpc, LpcComputer, LpcOptions};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    // AR(2) process x[n] = 1.3 x[n-1] - 0.6 x[n-2] + e[n], i.e. A(z) = 1 - 1.3 z^-1 + 0.6 z^-2
+    let mut rng = StdRng::seed_from_u64(3);
+    let mut wave = vec![0.0f32; 16000];
+    for n in 2..wave.len() {
+        let e: f32 = rng.gen_range(-1.0..1.0);
+        wave[n] = 1.3 * wave[n - 1] - 0.6 * wave[n - 2] + e;
+    }
+    let result = lpc(&wave, 2).unwrap();
+    assert_eq!(result.order(), 2);
+    assert_eq!(result.coeffs[0], 1.0);
+    assert!((result.coeffs[1] + 1.3).abs() < 0.03, "{:?}", result);
+    assert!((result.coeffs[2] - 0.6).abs() < 0.03, "{:?}", result);
+    assert_eq!(result.reflection[1], result.coeffs[2]);
+    // Uniform noise in [-1, 1) has variance 1/3
+    let per_sample = result.error / wave.len() as f32;
+    assert!((per_sample - 1.0 / 3.0).abs() < 0.02, "{}", per_sample);
+
+    // Higher orders leave the extra coefficients near 0, and the residual
+    // energy is r[0] times the product of (1 - k^2)
+    let r: Vec<f32> = (0..=4)
+        .map(|lag| wave.iter().zip(&wave[lag..]).map(|(a, b)| a * b).sum())
+        .collect();
+    let high = levinson_durbin(&r, 4).unwrap();
+    assert!(high.coeffs[3..].iter().all(|a| a.abs() < 0.05), "{:?}", high);
+    let expected = high
+        .reflection
+        .iter()
+        .fold(r[0], |e, k| e * (1.0 - k * k));
+    assert!((high.error - expected).abs() / expected < 1e-3);
+    assert!(levinson_durbin(&r[..3], 4).is_err());
+
+    // Silent frames give the trivial predictor
+    let silent = lpc(&[0.0; 100], 4).unwrap();
+    assert_eq!(silent.coeffs, vec![1.0, 0.0, 0.0, 0.0, 0.0]);
+    assert_eq!(silent.error, 0.0);
+
+    let mut opts = LpcOptions::default();
+    opts.frame_opts.dither = 0.0;
+    opts.frame_opts.preemph_coeff = 0.0;
+    opts.frame_opts.window_type = WindowType::Rectangular;
+    opts.order = 2;
+    let mut computer = LpcComputer::new(opts.clone()).unwrap();
+    let feats = compute_features_with(&mut computer, &wave).unwrap();
+    assert_eq!(feats.dim, 3);
+    let mean_a1 = feats.rows().map(|row| row[0]).sum::<f32>() / feats.num_frames as f32;
+    assert!((mean_a1 + 1.3).abs() < 0.05, "{}", mean_a1);
+    assert!(feats.rows().all(|row| row[2] > 0.0));
+
+    opts.order = 0;
+    assert!(LpcComputer::new(opts).is_err());
+}
+
+#[test]
+fn test_lpcc() {
+    use kaldi_native_fbank::offline::compute_features_with;
+    use kaldi_native_fbank::{lpc_to_cepstrum, Lpc, LpccComputer, LpccOptions};
+
+    // 1 / (1 - 0.9 z^-1) has cepstrum 0.9^n / n
+    let first = Lpc {
+        coeffs: vec![1.0, -0.9],
+        reflection: vec![-0.9],
+        error: 4.0,
+    };
+    let c = lpc_to_cepstrum(&first, 8);
+    assert!((c[0] - 2f32.ln()).abs() < 1e-6);
+    for (n, &cn) in c.iter().enumerate().skip(1) {
+        assert!((cn - 0.9f32.powi(n as i32) / n as f32).abs() < 1e-5, "{:?}", c);
+    }
+
+    // For a minimum-phase model, c_n is twice the real cepstrum of |H|
+    let second = Lpc {
+        coeffs: vec![1.0, -1.3, 0.6],
+        reflection: vec![-0.8125, 0.6],
+        error: 1.0,
+    };
+    let c = lpc_to_cepstrum(&second, 10);
+    let num_points = 4096;
+    for (n, &cn) in c.iter().enumerate().skip(1) {
+        let real_cepstrum = (0..num_points)
+            .map(|k| {
+                let w = 2.0 * std::f64::consts::PI * k as f64 / num_points as f64;
+                let re = 1.0 - 1.3 * w.cos() + 0.6 * (2.0 * w).cos();
+                let im = 1.3 * w.sin() - 0.6 * (2.0 * w).sin();
+                -0.5 * (re * re + im * im).ln() * (n as f64 * w).cos()
+            })
+            .sum::<f64>()
+            / num_points as f64;
+        assert!((cn as f64 - 2.0 * real_cepstrum).abs() < 1e-4, "{} {:?}", n, c);
+    }
+
+    let wave: Vec<f32> = (0..16000)
+        .map(|i| (i as f32 * 0.05).sin() + 0.3 * (i as f32 * 0.31).sin())
+        .collect();
+    let mut opts = LpccOptions::default();
+    opts.frame_opts.dither = 0.0;
+    opts.num_ceps = 20;
+    let mut computer = LpccComputer::new(opts.clone()).unwrap();
+    let feats = compute_features_with(&mut computer, &wave).unwrap();
+    assert_eq!(feats.dim, 20);
+    assert!(feats.data.iter().all(|x| x.is_finite()));
+
+    // Liftering scales each coefficient by a fixed factor
+    opts.cepstral_lifter = 22.0;
+    let mut liftered = LpccComputer::new(opts.clone()).unwrap();
+    let lifted = compute_features_with(&mut liftered, &wave).unwrap();
+    let scale = 1.0 + 11.0 * (std::f32::consts::PI * 3.0 / 22.0).sin();
+    assert!((lifted.row(5)[3] - scale * feats.row(5)[3]).abs() < 1e-3 * scale.abs().max(1.0));
+    assert_eq!(lifted.row(5)[0], feats.row(5)[0]);
+
+    opts.num_ceps = 0;
+    assert!(LpccComputer::new(opts).is_err());
+}
+
+#[test]
+fn test_lsf_conversion() {
+    use kaldi_native_fbank::{lpc, lpc_to_lsf, lsf_to_lpc};
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::f32::consts::PI;
+
+    // A(z) = 1 has LSFs evenly spaced at k pi / (p + 1)
+    for order in [4, 5] {
+        let mut trivial = vec![0.0f32; order + 1];
+        trivial[0] = 1.0;
+        let lsf = lpc_to_lsf(&trivial).unwrap();
+        assert_eq!(lsf.len(), order);
+        for (k, &w) in lsf.iter().enumerate() {
+            assert!((w - (k + 1) as f32 * PI / (order + 1) as f32).abs() < 1e-5, "{:?}", lsf);
+        }
+        let back = lsf_to_lpc(&lsf).unwrap();
+        assert!(back.iter().zip(&trivial).all(|(a, b)| (a - b).abs() < 1e-5));
+    }
+
+    // Round trip of the LPC of a noisy harmonic frame, for even and odd orders
+    let mut rng = StdRng::seed_from_u64(11);
+    let frame: Vec<f32> = (0..400)
+        .map(|i| {
+            let hann = 0.5 - 0.5 * (2.0 * PI * i as f32 / 399.0).cos();
+            let x = (i as f32 * 0.12).sin() + 0.5 * (i as f32 * 0.7).sin();
+            hann * (x + 0.05 * rng.gen_range(-1.0..1.0))
+        })
+        .collect();
+    for order in [10, 13] {
+        let coeffs = lpc(&frame, order).unwrap().coeffs;
+        let lsf = lpc_to_lsf(&coeffs).unwrap();
+        assert_eq!(lsf.len(), order);
+        assert!(lsf[0] > 0.0 && lsf[order - 1] < PI);
+        assert!(lsf.windows(2).all(|w| w[0] < w[1]), "{:?}", lsf);
+        let back = lsf_to_lpc(&lsf).unwrap();
+        for (a, b) in back.iter().zip(&coeffs) {
+            assert!((a - b).abs() < 1e-3, "{:?} vs {:?}", back, coeffs);
+        }
+    }
+
+    // Zeros outside the unit circle and malformed inputs are rejected
+    assert!(lpc_to_lsf(&[1.0, -3.0, 2.2]).is_err());
+    assert!(lpc_to_lsf(&[0.5, 0.1]).is_err());
+    assert!(lsf_to_lpc(&[0.5, 0.4]).is_err());
+    assert!(lsf_to_lpc(&[0.5, PI]).is_err());
+}
+
+#[test]
+fn test_yin_pyin() {
+    use kaldi_native_fbank::{pyin, yin, PyinOptions, YinOptions};
+    use std::f32::consts::PI;
+
+    // 0.8 s at 220 Hz, 0.5 s of silence, then 0.8 s at 150 Hz
+    let sr = 16000.0;
+    let tone = |f0: f32, n: usize| -> Vec<f32> {
+        (0..n)
+            .map(|i| {
+                let t = i as f32 / sr;
+                (1..=4)
+                    .map(|h| (2.0 * PI * f0 * h as f32 * t).sin() / h as f32)
+                    .sum::<f32>()
+            })
+            .collect()
+    };
+    let mut wave = tone(220.0, 12800);
+    wave.extend(vec![0.0; 8000]);
+    wave.extend(tone(150.0, 12800));
+
+    // Frames are 64 ms long and 10 ms apart; skip those straddling a boundary
+    let segments = [(0..70, Some(220.0)), (88..118, None), (138..200, Some(150.0))];
+    let check = |track: &kaldi_native_fbank::PitchTrack, tolerance: f32| {
+        assert!(track.num_frames() >= 200);
+        for (frames, f0) in segments.clone() {
+            for t in frames {
+                match f0 {
+                    Some(f0) => {
+                        assert!(track.voiced[t], "frame {}", t);
+                        let error = (track.f0[t] / f0 - 1.0).abs();
+                        assert!(error < tolerance, "frame {}: {}", t, track.f0[t]);
+                        assert!(track.voiced_prob[t] > 0.5, "frame {}", t);
+                    }
+                    None => {
+                        assert!(!track.voiced[t], "frame {}", t);
+                        assert!(track.voiced_prob[t] < 0.1, "frame {}", t);
+                    }
+                }
+            }
+        }
+    };
+
+    check(&yin(&wave, &YinOptions::default()).unwrap(), 0.005);
+    // pYIN quantizes to 0.1 semitone (0.6 %)
+    check(&pyin(&wave, &PyinOptions::default()).unwrap(), 0.004);
+
+    let bad = YinOptions {
+        fmin: 20.0,
+        ..Default::default()
+    };
+    assert!(yin(&wave, &bad).is_err());
+    let bad = PyinOptions {
+        switch_prob: 1.5,
+        ..Default::default()
+    };
+    assert!(pyin(&wave, &bad).is_err());
+}
+
+#[test]
+fn test_xcorr() {
+    use kaldi_native_fbank::{autocorr, xcorr, XcorrMode, XcorrNormalization, XcorrOptions};
+
+    let direct = |a: &[f32], b: &[f32], lag: isize| -> f32 {
+        (0..b.len() as isize)
+            .filter(|&i| i + lag >= 0 && i + lag < a.len() as isize)
+            .map(|i| a[(i + lag) as usize] * b[i as usize])
+            .sum()
+    };
+    let a: Vec<f32> = (0..37).map(|i| ((i * 7 % 11) as f32 - 5.0) * 0.1).collect();
+    let b: Vec<f32> = (0..12).map(|i| ((i * 5 % 7) as f32 - 3.0) * 0.2).collect();
+
+    // Full mode covers lags 1 - len(b) ..= len(a) - 1
+    let full = xcorr(&a, &b, &XcorrOptions::default()).unwrap();
+    assert_eq!(full.values.len(), a.len() + b.len() - 1);
+    assert_eq!(full.first_lag, -11);
+    for (i, &c) in full.values.iter().enumerate() {
+        assert!((c - direct(&a, &b, full.lag(i))).abs() < 1e-4);
+    }
+
+    // Valid mode keeps the fully overlapping lags, in either argument order
+    let valid = XcorrOptions {
+        mode: XcorrMode::Valid,
+        ..Default::default()
+    };
+    let c = xcorr(&a, &b, &valid).unwrap();
+    assert_eq!((c.first_lag, c.values.len()), (0, 26));
+    let c = xcorr(&b, &a, &valid).unwrap();
+    assert_eq!((c.first_lag, c.values.len()), (-25, 26));
+    assert!((c.values[3] - direct(&b, &a, -22)).abs() < 1e-4);
+
+    // A delayed copy peaks at its delay, also within a restricted lag range
+    let delayed: Vec<f32> = std::iter::repeat_n(0.0, 5).chain(a.iter().copied()).collect();
+    let limited = XcorrOptions {
+        max_lag: Some(8),
+        ..Default::default()
+    };
+    let c = xcorr(&delayed, &a, &limited).unwrap();
+    assert_eq!((c.first_lag, c.values.len()), (-8, 17));
+    assert_eq!(c.peak_lag(), Some(5));
+
+    // Normalizations
+    let opts = |normalization| XcorrOptions {
+        normalization,
+        ..Default::default()
+    };
+    let raw = autocorr(&a, &XcorrOptions::default()).unwrap();
+    let coeff = autocorr(&a, &opts(XcorrNormalization::Coeff)).unwrap();
+    assert!((coeff.values[36] - 1.0).abs() < 1e-5);
+    assert!(coeff.values.iter().all(|c| c.abs() <= 1.0 + 1e-5));
+    let biased = autocorr(&a, &opts(XcorrNormalization::Biased)).unwrap();
+    let unbiased = autocorr(&a, &opts(XcorrNormalization::Unbiased)).unwrap();
+    assert!((biased.values[40] - raw.values[40] / 37.0).abs() < 1e-5);
+    assert!((unbiased.values[40] - raw.values[40] / 33.0).abs() < 1e-5);
+
+    assert!(xcorr(&a, &[], &XcorrOptions::default()).is_err());
+}
+
+#[test]
+fn test_cepstrum() {
+    use kaldi_native_fbank::{
+        complex_cepstrum, inverse_complex_cepstrum, lifter_high, lifter_low, real_cepstrum,
+        spectral_envelope, unwrap_phase,
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+
+    // The minimum-phase sequence 1 + 0.5 z^-1 has complex cepstrum
+    // (-1)^(n+1) 0.5^n / n and real cepstrum half of that at +-n
+    let mut x = vec![0.0f32; 64];
+    x[0] = 1.0;
+    x[1] = 0.5;
+    let cc = complex_cepstrum(&x).unwrap();
+    assert_eq!(cc.delay, 0);
+    assert!(cc.values[0].abs() < 1e-6);
+    for n in 1..6 {
+        let expected = -(-0.5f32).powi(n as i32) / n as f32;
+        assert!((cc.values[n] - expected).abs() < 1e-5, "{:?}", &cc.values[..6]);
+        assert!(cc.values[64 - n].abs() < 1e-5);
+    }
+    let rc = real_cepstrum(&x).unwrap();
+    assert!((rc[1] - 0.25).abs() < 1e-5 && (rc[63] - 0.25).abs() < 1e-5);
+
+    // A delay and a sign flip are factored out
+    x.rotate_right(3);
+    x.iter_mut().for_each(|v| *v = -*v);
+    let shifted = complex_cepstrum(&x).unwrap();
+    assert_eq!((shifted.delay, shifted.negated), (3, true));
+    assert!(shifted.values.iter().zip(&cc.values).all(|(a, b)| (a - b).abs() < 1e-5));
+    let back = inverse_complex_cepstrum(&shifted);
+    assert!(back.iter().zip(&x).all(|(a, b)| (a - b).abs() < 1e-5));
+
+    // The complex cepstrum inverts mixed-phase signals of any length
+    let mut rng = StdRng::seed_from_u64(5);
+    let signal: Vec<f32> = (0..301).map(|_| rng.gen_range(-1.0..1.0)).collect();
+    let cc = complex_cepstrum(&signal).unwrap();
+    let back = inverse_complex_cepstrum(&cc);
+    assert!(signal.iter().zip(&back).all(|(a, b)| (a - b).abs() < 1e-4));
+
+    // An echo at 0.5 gain and 100 samples shows up as a real cepstrum peak
+    let source: Vec<f32> = (0..4096).map(|_| rng.gen_range(-1.0..1.0)).collect();
+    let echoed: Vec<f32> = (0..4096)
+        .map(|i| source[i] + if i >= 100 { 0.5 * source[i - 100] } else { 0.0 })
+        .collect();
+    let mut rc = real_cepstrum(&echoed).unwrap();
+    lifter_high(&mut rc, 20);
+    assert!(rc[..20].iter().all(|&c| c == 0.0));
+    let peak = (0..2048).fold(0, |best, q| if rc[q] > rc[best] { q } else { best });
+    assert_eq!(peak, 100);
+
+    // Low-quefrency liftering keeps both ends; the envelope is smooth
+    let mut rc = real_cepstrum(&echoed).unwrap();
+    lifter_low(&mut rc, 30);
+    assert!(rc[30..4067].iter().all(|&c| c == 0.0));
+    let envelope = spectral_envelope(&echoed, 30).unwrap();
+    assert_eq!(envelope.len(), 2049);
+    assert!(envelope.windows(2).all(|w| (w[1] - w[0]).abs() < 0.1));
+
+    let mut phase = vec![3.0f32, -3.0, 3.1, -3.1];
+    unwrap_phase(&mut phase);
+    assert!(phase.windows(2).all(|w| (w[1] - w[0]).abs() <= std::f32::consts::PI));
+    assert!((phase[3] - (-3.1 + 2.0 * std::f32::consts::PI)).abs() < 1e-5);
+
+    assert!(real_cepstrum(&[]).is_err());
+}
+
+#[test]
+fn test_modified_group_delay() {
+    use kaldi_native_fbank::offline::compute_features_with;
+    use kaldi_native_fbank::{group_delay, ModGdfComputer, ModGdfOptions};
+
+    // A resonance at 0.1 of the sampling rate peaks the group delay there
+    let (r, theta) = (0.95f32, 2.0 * std::f32::consts::PI * 0.1);
+    let mut h = vec![0.0f32; 512];
+    for n in 0..h.len() {
+        let x = if n == 0 { 1.0 } else { 0.0 };
+        let y1 = if n >= 1 { h[n - 1] } else { 0.0 };
+        let y2 = if n >= 2 { h[n - 2] } else { 0.0 };
+        h[n] = x + 2.0 * r * theta.cos() * y1 - r * r * y2;
+    }
+    let gd = group_delay(&h).unwrap();
+    assert_eq!(gd.len(), 257);
+    let peak = (0..gd.len()).fold(0, |best, k| if gd[k] > gd[best] { k } else { best });
+    assert!((peak as i32 - 51).abs() <= 1, "{}", peak);
+
+    // A delayed impulse has a flat group delay equal to its delay, so its
+    // MODGDF is delay^alpha in every bin and only C0 of the DCT is non-zero
+    let mut opts = ModGdfOptions::default();
+    opts.frame_opts.frame_length_samples = 512;
+    opts.num_ceps = 0;
+    let mut impulse = vec![0.0f32; 512];
+    impulse[5] = 1.0;
+    assert!(group_delay(&impulse).unwrap().iter().all(|d| (d - 5.0).abs() < 1e-3));
+    let mut spectrum_computer = ModGdfComputer::new(opts.clone()).unwrap();
+    assert_eq!(spectrum_computer.dim(), 257);
+    let mut feature = vec![0.0; 257];
+    spectrum_computer.compute(&mut impulse.clone(), &mut feature);
+    let expected = 5f32.powf(0.4);
+    assert!(feature.iter().all(|v| (v - expected).abs() < 1e-3), "{:?}", &feature[..4]);
+
+    opts.num_ceps = 13;
+    let mut cepstral = ModGdfComputer::new(opts.clone()).unwrap();
+    let mut ceps = vec![0.0; 13];
+    cepstral.compute(&mut impulse.clone(), &mut ceps);
+    assert!((ceps[0] - expected * 257f32.sqrt()).abs() < 1e-2, "{:?}", ceps);
+    assert!(ceps[1..].iter().all(|c| c.abs() < 1e-2), "{:?}", ceps);
+
+    // Framed extraction like the other computers
+    let wave: Vec<f32> = (0..16000)
+        .map(|i| (i as f32 * 0.07).sin() + 0.5 * (i as f32 * 0.23).sin())
+        .collect();
+    let mut computer = ModGdfComputer::new(ModGdfOptions::default()).unwrap();
+    let feats = compute_features_with(&mut computer, &wave).unwrap();
+    assert_eq!(feats.dim, 13);
+    assert!(feats.data.iter().all(|x| x.is_finite()));
+
+    opts.lifter_cutoff = 0;
+    assert!(ModGdfComputer::new(opts).is_err());
+}